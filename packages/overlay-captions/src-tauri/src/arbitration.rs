@@ -0,0 +1,273 @@
+// Other caption apps on screen. Customers running Zoom's or the system's live captions next
+// to ours get two always-on-top windows taking turns to be on top, which flickers. A thread
+// polls for windows whose title or class matches `arbitration.windows` (see desktop.rs) and
+// applies `arbitration.policy` while one is open: "warn" tells the operator, "yield" lets
+// the other window stay on top by dropping our overlays' always-on-top, and "share" moves
+// the public overlay out of its way, above or below it, and back once it has closed. The
+// main window hears about it with a `caption-app` event under every policy.
+
+use crate::desktop::{self, Bounds, Desktop, Window};
+use crate::i18n::{self, tr};
+use crate::settings::ArbitrationSettings;
+use crate::window_manager::{ManagedWindow, OverlayId};
+use crate::{diagnostics, presentation, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Manager, PhysicalPosition};
+use tauri_plugin_notification::NotificationExt;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct ArbitrationState {
+    // Bumped to stop the running watcher
+    generation: u64,
+    // Our overlays are kept off the top while another caption window is open
+    yielding: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionApp {
+    // Title of the other caption window, or its class without one; None once it has closed
+    pub window: Option<String>,
+    pub policy: String,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .arbitration
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn is_caption_app(settings: &ArbitrationSettings, window: &Window) -> bool {
+    let title = window.title.to_lowercase();
+    let class = window.class.to_lowercase();
+    settings
+        .windows
+        .iter()
+        .map(|pattern| pattern.trim().to_lowercase())
+        .any(|pattern| {
+            !pattern.is_empty() && (title.contains(&pattern) || class.contains(&pattern))
+        })
+}
+
+// Whether the overlays stay off the top for another caption app
+pub fn is_yielding(app: &AppHandle) -> bool {
+    app.state::<AppState>()
+        .arbitration
+        .lock()
+        .map(|s| s.yielding)
+        .unwrap_or(false)
+}
+
+fn set_yielding(app: &AppHandle, yielding: bool) {
+    if let Ok(mut arbitration) = app.state::<AppState>().arbitration.lock() {
+        arbitration.yielding = yielding;
+    }
+    for id in [OverlayId::Public, OverlayId::Operator] {
+        let managed = ManagedWindow::Overlay(id);
+        if let Some(window) = managed.get(app) {
+            if let Err(e) = window.set_always_on_top(!yielding) {
+                log::error!(
+                    "Failed to change the {} window's z-order: {}",
+                    managed.label(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+// Stop the running watcher and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.arbitration.clone(),
+        Err(_) => return,
+    };
+    let (generation, yielding) = match state.arbitration.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            (s.generation, s.yielding)
+        }
+        Err(_) => return,
+    };
+    if yielding {
+        set_yielding(app, false);
+    }
+
+    if settings.policy == "off" {
+        return;
+    }
+    if !desktop::SUPPORTED {
+        log::warn!("Detecting other caption apps is not supported on this platform");
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn announce(app: &AppHandle, settings: &ArbitrationSettings, window: Option<String>) {
+    let payload = CaptionApp {
+        window,
+        policy: settings.policy.clone(),
+    };
+    if let Err(e) = ManagedWindow::Main.emit(app, "caption-app", payload) {
+        log::error!("Failed to emit caption app: {}", e);
+    }
+}
+
+fn appeared(app: &AppHandle, settings: &ArbitrationSettings, name: &str) {
+    log::info!("Another caption app's window appeared: {}", name);
+    let _ = diagnostics::record(app, "caption app appeared", Some(name.to_string()));
+    match settings.policy.as_str() {
+        "yield" => set_yielding(app, true),
+        // Nothing may pop up over the projector output in presentation mode; the main
+        // window's banner still says so
+        "warn" if presentation::is_active(app) => {}
+        "warn" => {
+            let lang = i18n::current_language(app);
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(tr(&lang, "notification.caption_app_title"))
+                .body(tr(&lang, "notification.caption_app_body"))
+                .show()
+            {
+                log::error!("Failed to show caption app notification: {}", e);
+            }
+        }
+        _ => {}
+    }
+    announce(app, settings, Some(name.to_string()));
+}
+
+fn closed(app: &AppHandle, settings: &ArbitrationSettings) {
+    log::info!("The other caption app's window closed");
+    let _ = diagnostics::record(app, "caption app closed", None);
+    if is_yielding(app) {
+        set_yielding(app, false);
+    }
+    announce(app, settings, None);
+}
+
+fn overlaps(a: Bounds, b: Bounds) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+// Move the public overlay above or below `other` where they overlap, whichever side has
+// room on its monitor; `moved_from` keeps where it was before the first move
+fn make_room(
+    app: &AppHandle,
+    settings: &ArbitrationSettings,
+    other: Bounds,
+    moved_from: &mut Option<PhysicalPosition<i32>>,
+) {
+    let window = match ManagedWindow::OVERLAY.get(app) {
+        Some(window) => window,
+        None => return,
+    };
+    let (position, size) = match (window.outer_position(), window.outer_size()) {
+        (Ok(position), Ok(size)) => (position, size),
+        _ => return,
+    };
+    let ours = Bounds {
+        x: position.x,
+        y: position.y,
+        width: size.width as i32,
+        height: size.height as i32,
+    };
+    if !overlaps(ours, other) {
+        return;
+    }
+    let (top, bottom) = match window.current_monitor() {
+        Ok(Some(monitor)) => (
+            monitor.position().y,
+            monitor.position().y + monitor.size().height as i32,
+        ),
+        _ => (i32::MIN, i32::MAX),
+    };
+    let gap = settings.gap_px as i32;
+    let above = other.y - gap - ours.height;
+    let below = other.y + other.height + gap;
+    let y = if above >= top {
+        above
+    } else if below <= bottom - ours.height {
+        below
+    } else {
+        // Nowhere to go; the two share the screen as they are
+        return;
+    };
+
+    match window.set_position(tauri::Position::Physical(PhysicalPosition { x: ours.x, y })) {
+        Ok(()) => {
+            moved_from.get_or_insert(position);
+        }
+        Err(e) => log::error!("Failed to move the overlay out of the way: {}", e),
+    }
+}
+
+// Put the public overlay back where it was before making room
+fn move_back(app: &AppHandle, moved_from: &mut Option<PhysicalPosition<i32>>) {
+    let position = match moved_from.take() {
+        Some(position) => position,
+        None => return,
+    };
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
+        if let Err(e) = window.set_position(tauri::Position::Physical(position)) {
+            log::error!("Failed to move the overlay back: {}", e);
+        }
+    }
+}
+
+fn run(app: &AppHandle, settings: &ArbitrationSettings, generation: u64) {
+    let desktop = match Desktop::open() {
+        Ok(desktop) => desktop,
+        Err(e) => {
+            log::warn!("Detecting other caption apps unavailable: {}", e);
+            return;
+        }
+    };
+    // Whether another caption window is open
+    let mut open = false;
+    let mut moved_from: Option<PhysicalPosition<i32>> = None;
+
+    while is_current(app, generation) {
+        let other = desktop
+            .windows()
+            .into_iter()
+            .find(|window| is_caption_app(settings, window));
+        match (&other, open) {
+            (Some(window), false) => {
+                let name = if window.title.trim().is_empty() {
+                    &window.class
+                } else {
+                    &window.title
+                };
+                appeared(app, settings, name);
+                open = true;
+            }
+            (None, true) => {
+                move_back(app, &mut moved_from);
+                closed(app, settings);
+                open = false;
+            }
+            _ => {}
+        }
+        if let Some(window) = &other {
+            match settings.policy.as_str() {
+                // The overlays are put back on top now and then, e.g. after a resume
+                "yield" => set_yielding(app, true),
+                // Again whenever either window has moved into the other
+                "share" => make_room(app, settings, window.bounds, &mut moved_from),
+                _ => {}
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    move_back(app, &mut moved_from);
+}
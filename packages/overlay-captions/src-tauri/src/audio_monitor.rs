@@ -0,0 +1,329 @@
+// Level meter for the audio the captions are made from, so operators can tell "no captions"
+// caused by no audio reaching speech recognition from a recognition problem. The monitored
+// device is a capture device, e.g. the feed into the ASR computer or, on Linux, the PulseAudio
+// or PipeWire monitor source of what the computer plays. On Windows `loopback` captures what
+// an output device plays instead.
+//
+// Every REPORT_INTERVAL an `audio-level` event carries the level and, once captions are
+// connected, a warning:
+//   "silence"    - the audio has stayed below the threshold for `warn_after_secs`
+//   "noCaptions" - there is audio, but no caption came in for `warn_after_secs`
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::{AppSettings, AudioMonitorSettings};
+use crate::{diagnostics, AppHandle, AppState};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+// Wait before opening a device again after it failed or went away
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+// Reported for digital silence instead of minus infinity
+const FLOOR_DB: f32 = -96.0;
+
+#[derive(Default)]
+pub struct AudioMonitorState {
+    // Bumped to stop the running monitor, e.g. when the device changes
+    generation: u64,
+    last_caption: Option<Instant>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioDevice {
+    pub name: String,
+    // An output device, captured in loopback mode
+    pub loopback: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioLevel {
+    // RMS and peak over the last report interval, in dBFS
+    pub level_db: f32,
+    pub peak_db: f32,
+    pub silent_secs: u64,
+    pub warning: Option<&'static str>,
+}
+
+// Samples seen since the last report
+#[derive(Default)]
+struct Meter {
+    sum_squares: f64,
+    count: u64,
+    peak: f32,
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(FLOOR_DB)
+    } else {
+        FLOOR_DB
+    }
+}
+
+// Devices offered in the audio monitor settings
+pub fn list_devices() -> Result<Vec<AudioDevice>, String> {
+    let host = cpal::default_host();
+    let mut devices = Vec::new();
+    for device in host.input_devices().map_err(|e| e.to_string())? {
+        if let Ok(name) = device.name() {
+            devices.push(AudioDevice {
+                name,
+                loopback: false,
+            });
+        }
+    }
+    // Only WASAPI records what an output device plays
+    if cfg!(windows) {
+        for device in host.output_devices().map_err(|e| e.to_string())? {
+            if let Ok(name) = device.name() {
+                devices.push(AudioDevice {
+                    name,
+                    loopback: true,
+                });
+            }
+        }
+    }
+    Ok(devices)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .audio_monitor
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running monitor and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.audio_monitor.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.audio_monitor.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut reported_failure = false;
+        while is_current(&app, generation) {
+            // Returns once the device fails or the monitor is replaced
+            if let Err(e) = run(&app, &settings, generation) {
+                if !reported_failure {
+                    log::warn!("Audio monitor stopped: {}", e);
+                    let _ = diagnostics::record(&app, "audio monitor failed", Some(e));
+                    reported_failure = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            } else {
+                reported_failure = false;
+            }
+        }
+    });
+}
+
+// Device by the name in settings, the default one for an empty name; also used by
+// audio_uplink.rs
+pub fn find_device(name: &str, loopback: bool) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    let name = name.trim();
+    let device = match (name.is_empty(), loopback) {
+        (true, false) => host.default_input_device(),
+        (true, true) => host.default_output_device(),
+        (false, false) => host
+            .input_devices()
+            .map_err(|e| e.to_string())?
+            .find(|d| d.name().is_ok_and(|n| n == name)),
+        (false, true) => host
+            .output_devices()
+            .map_err(|e| e.to_string())?
+            .find(|d| d.name().is_ok_and(|n| n == name)),
+    };
+    device.ok_or_else(|| format!("Audio device not found: {:?}", name))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    meter: Arc<Mutex<Meter>>,
+    failure: Arc<Mutex<Option<String>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                if let Ok(mut meter) = meter.lock() {
+                    for &sample in data {
+                        let value: f32 = sample.to_sample();
+                        meter.sum_squares += (value * value) as f64;
+                        meter.peak = meter.peak.max(value.abs());
+                    }
+                    meter.count += data.len() as u64;
+                }
+            },
+            move |e| {
+                if let Ok(mut failure) = failure.lock() {
+                    *failure = Some(e.to_string());
+                }
+            },
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+fn run(app: &AppHandle, settings: &AudioMonitorSettings, generation: u64) -> Result<(), String> {
+    let device = find_device(&settings.device, settings.loopback)?;
+    let supported = if settings.loopback {
+        device.default_output_config()
+    } else {
+        device.default_input_config()
+    }
+    .map_err(|e| e.to_string())?;
+
+    let meter = Arc::new(Mutex::new(Meter::default()));
+    let failure = Arc::new(Mutex::new(None));
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, meter.clone(), failure.clone()),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, meter.clone(), failure.clone()),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, meter.clone(), failure.clone()),
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, meter.clone(), failure.clone()),
+        format => Err(format!("Unsupported sample format {}", format)),
+    }?;
+    stream.play().map_err(|e| e.to_string())?;
+    log::info!(
+        "Monitoring audio from {}",
+        device.name().unwrap_or_default()
+    );
+
+    let warn_after = Duration::from_secs(settings.warn_after_secs.max(1));
+    let started = Instant::now();
+    let mut last_sound = Instant::now();
+    let mut warning: Option<&'static str> = None;
+
+    while is_current(app, generation) {
+        std::thread::sleep(REPORT_INTERVAL);
+        if let Some(e) = failure.lock().map_err(|e| e.to_string())?.take() {
+            return Err(e);
+        }
+
+        let (level_db, peak_db) = {
+            let mut meter = meter.lock().map_err(|e| e.to_string())?;
+            let rms = if meter.count > 0 {
+                (meter.sum_squares / meter.count as f64).sqrt() as f32
+            } else {
+                0.0
+            };
+            let levels = (to_db(rms), to_db(meter.peak));
+            *meter = Meter::default();
+            levels
+        };
+        if level_db > settings.silence_threshold_db {
+            last_sound = Instant::now();
+        }
+
+        let state = app.state::<AppState>();
+        let connected = state.connected.lock().map(|c| *c).unwrap_or(false);
+        let last_caption = state
+            .audio_monitor
+            .lock()
+            .map_err(|e| e.to_string())?
+            .last_caption;
+        // Counted from when monitoring started if no caption came in since
+        let caption_idle = last_caption.unwrap_or(started).max(started).elapsed();
+
+        let current = if last_sound.elapsed() >= warn_after {
+            Some("silence")
+        } else if caption_idle >= warn_after {
+            Some("noCaptions")
+        } else {
+            None
+        };
+        if !connected {
+            // Nothing to warn about between sessions
+            warning = None;
+        } else if current != warning {
+            warning_changed(app, warning, current);
+            warning = current;
+        }
+
+        let _ = app.emit(
+            "audio-level",
+            &AudioLevel {
+                level_db,
+                peak_db,
+                silent_secs: last_sound.elapsed().as_secs(),
+                warning,
+            },
+        );
+    }
+    Ok(())
+}
+
+fn warning_changed(app: &AppHandle, previous: Option<&str>, current: Option<&str>) {
+    let (kind, detail) = match current {
+        Some("silence") => ("audio silent", "no audio above the silence threshold"),
+        Some(_) => (
+            "captions missing",
+            "audio is present but no captions come in",
+        ),
+        None if previous == Some("silence") => ("audio back", "audio above the silence threshold"),
+        None => ("captions back", "captions come in again"),
+    };
+    log::warn!("Audio monitor: {}", detail);
+    let _ = diagnostics::record(app, kind, Some(detail.to_string()));
+}
+
+// Notes when the last caption came in
+pub struct AudioMonitorOutput;
+
+impl Output for AudioMonitorOutput {
+    fn id(&self) -> &str {
+        "audio-monitor"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.audio_monitor.enabled {
+            return Ok(());
+        }
+        let caption = payload.get("text").and_then(Value::as_str).unwrap_or("");
+        if caption.trim().is_empty() {
+            return Ok(());
+        }
+        let state = app.state::<AppState>();
+        let mut monitor = state.audio_monitor.lock().map_err(|e| e.to_string())?;
+        monitor.last_caption = Some(Instant::now());
+        Ok(())
+    }
+}
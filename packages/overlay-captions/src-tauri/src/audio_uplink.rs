@@ -0,0 +1,310 @@
+// Venue audio streamed to the speech recognition server, so the room needs only this app
+// instead of a browser tab sending the microphone. The selected input device is mixed down
+// to mono, resampled to 16 kHz and encoded as 20 ms Opus frames. Over a WebSocket to
+// `settings.audio_uplink.url` goes first a text message describing the stream
+//   {"type":"start","codec":"opus","sampleRate":16000,"channels":1,"frameMs":20}
+// followed by every Opus frame as a binary message. The captions come back through the
+// session as usual, so anything the server sends on this connection is ignored.
+
+use crate::settings::AudioUplinkSettings;
+use crate::{audio_monitor, diagnostics, network, AppHandle, AppState};
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::Serialize;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+const SAMPLE_RATE: u32 = 16000;
+// 20 ms at SAMPLE_RATE
+const FRAME_SAMPLES: usize = 320;
+// Upper bound for an encoded frame recommended by libopus
+const MAX_PACKET: usize = 4000;
+// Captured chunks waiting for the encoder; more are dropped while the network stalls
+const QUEUE_CHUNKS: usize = 100;
+// Wait between attempts to reach the server or open the device
+const RETRY_INTERVAL: Duration = Duration::from_secs(3);
+// A server that takes no audio for this long is considered gone
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+// Incoming messages are only looked for, never waited for
+const READ_TIMEOUT: Duration = Duration::from_millis(1);
+
+#[derive(Default)]
+pub struct AudioUplinkState {
+    // Bumped to stop the running uplink, e.g. when the server URL changes
+    generation: u64,
+    streaming: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioUplinkStatus {
+    pub streaming: bool,
+}
+
+pub fn status(app: &AppHandle) -> Result<AudioUplinkStatus, String> {
+    let state = app.state::<AppState>();
+    let uplink = state.audio_uplink.lock().map_err(|e| e.to_string())?;
+    Ok(AudioUplinkStatus {
+        streaming: uplink.streaming,
+    })
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .audio_uplink
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn set_streaming(app: &AppHandle, generation: u64, streaming: bool) {
+    let changed = match app.state::<AppState>().audio_uplink.lock() {
+        Ok(mut s) if s.generation == generation && s.streaming != streaming => {
+            s.streaming = streaming;
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        let _ = app.emit("audio-uplink-status", AudioUplinkStatus { streaming });
+    }
+}
+
+// Stop the running uplink and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.audio_uplink.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.audio_uplink.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.streaming = false;
+            s.generation
+        }
+        Err(_) => return,
+    };
+    let _ = app.emit(
+        "audio-uplink-status",
+        AudioUplinkStatus { streaming: false },
+    );
+
+    if !settings.enabled || settings.url.trim().is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut reported_failure = false;
+        while is_current(&app, generation) {
+            // Returns once the device or the connection fails, or the uplink is replaced
+            if let Err(e) = run(&app, &settings, generation) {
+                set_streaming(&app, generation, false);
+                if !reported_failure {
+                    log::warn!("Audio uplink stopped: {}", e);
+                    let _ = diagnostics::record(&app, "audio uplink failed", Some(e));
+                    reported_failure = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            } else {
+                reported_failure = false;
+            }
+        }
+    });
+}
+
+// Linear interpolation from the device rate to SAMPLE_RATE, plenty for speech
+struct Resampler {
+    // Input samples per output sample
+    step: f64,
+    // Where the next output sample falls in `pending`
+    position: f64,
+    pending: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(input_rate: u32) -> Self {
+        Self {
+            step: input_rate as f64 / SAMPLE_RATE as f64,
+            position: 0.0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.pending.extend_from_slice(input);
+        while self.position + 1.0 < self.pending.len() as f64 {
+            let i = self.position as usize;
+            let t = (self.position - i as f64) as f32;
+            output.push(self.pending[i] + (self.pending[i + 1] - self.pending[i]) * t);
+            self.position += self.step;
+        }
+        let consumed = (self.position as usize).min(self.pending.len());
+        self.pending.drain(..consumed);
+        self.position -= consumed as f64;
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: SyncSender<Vec<f32>>,
+    failure: Arc<Mutex<Option<String>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mono = data
+                    .chunks(channels)
+                    .map(|frame| {
+                        frame.iter().map(|&s| s.to_sample::<f32>()).sum::<f32>() / channels as f32
+                    })
+                    .collect();
+                // Dropped rather than holding up the audio thread
+                let _ = queue.try_send(mono);
+            },
+            move |e| {
+                if let Ok(mut failure) = failure.lock() {
+                    *failure = Some(e.to_string());
+                }
+            },
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+// Starts capturing into `queue`; returns the stream, which captures until dropped, and the
+// device's sample rate
+fn capture(
+    device_name: &str,
+    queue: SyncSender<Vec<f32>>,
+    failure: Arc<Mutex<Option<String>>>,
+) -> Result<(cpal::Stream, u32), String> {
+    let device = audio_monitor::find_device(device_name, false)?;
+    let supported = device.default_input_config().map_err(|e| e.to_string())?;
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, queue, failure),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, queue, failure),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, queue, failure),
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, queue, failure),
+        format => Err(format!("Unsupported sample format {}", format)),
+    }?;
+    stream.play().map_err(|e| e.to_string())?;
+    log::info!(
+        "Capturing audio for the uplink from {}",
+        device.name().unwrap_or_default()
+    );
+    Ok((stream, config.sample_rate.0))
+}
+
+fn connect(url: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+    let socket = network::connect(url)?;
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream,
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref(),
+        _ => return Err("Unsupported connection type".to_string()),
+    };
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    stream
+        .set_write_timeout(Some(WRITE_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+// Reads whatever the server sent, which also answers its pings
+fn drain_incoming(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<(), String> {
+    loop {
+        match socket.read() {
+            Ok(Message::Close(_)) => return Err("The server closed the connection".to_string()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(())
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn run(app: &AppHandle, settings: &AudioUplinkSettings, generation: u64) -> Result<(), String> {
+    let url = settings.url.trim();
+    if !url.starts_with("ws://") && !url.starts_with("wss://") {
+        return Err(format!("Not a WebSocket URL: {}", url));
+    }
+    network::check(app, url)?;
+
+    let mut encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip)
+        .map_err(|e| e.to_string())?;
+    encoder
+        .set_bitrate(Bitrate::BitsPerSecond(settings.bitrate as i32))
+        .map_err(|e| e.to_string())?;
+
+    let mut socket = connect(url)?;
+    let start = serde_json::json!({
+        "type": "start",
+        "codec": "opus",
+        "sampleRate": SAMPLE_RATE,
+        "channels": 1,
+        "frameMs": 20,
+    });
+    socket
+        .send(Message::Text(start.to_string()))
+        .map_err(|e| e.to_string())?;
+
+    // Capture starts only once the server takes audio, so nothing stale is sent
+    let (queue, captured) = mpsc::sync_channel(QUEUE_CHUNKS);
+    let failure = Arc::new(Mutex::new(None));
+    let (_stream, device_rate) = capture(&settings.device, queue, failure.clone())?;
+    log::info!("Streaming audio to {}", url);
+    set_streaming(app, generation, true);
+
+    let mut resampler = Resampler::new(device_rate);
+    let mut samples: Vec<f32> = Vec::with_capacity(FRAME_SAMPLES * 2);
+    let mut packet = [0u8; MAX_PACKET];
+
+    while is_current(app, generation) {
+        match captured.recv_timeout(Duration::from_millis(100)) {
+            Ok(chunk) => resampler.process(&chunk, &mut samples),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err("Audio capture stopped".to_string()),
+        }
+        if let Some(e) = failure.lock().map_err(|e| e.to_string())?.take() {
+            return Err(e);
+        }
+
+        while samples.len() >= FRAME_SAMPLES {
+            let length = encoder
+                .encode_float(&samples[..FRAME_SAMPLES], &mut packet)
+                .map_err(|e| e.to_string())?;
+            samples.drain(..FRAME_SAMPLES);
+            socket
+                .send(Message::Binary(packet[..length].to_vec()))
+                .map_err(|e| e.to_string())?;
+        }
+        drain_incoming(&mut socket)?;
+    }
+
+    let _ = socket.close(None);
+    Ok(())
+}
@@ -0,0 +1,410 @@
+// Experimental caption output for subtitle glasses and their companion apps over Bluetooth LE.
+// The app acts as a GATT peripheral advertising SERVICE_UUID under `settings.ble_broadcast.name`
+// with one characteristic, CAPTION_UUID, that can be read and subscribed to. Its value is the
+// UTF-8 tail of the latest caption, at most `max_bytes` long and starting at a word, notified
+// again whenever the caption changes.
+//
+// Only Linux is supported, through BlueZ over D-Bus. Auracast-style broadcasts carry audio, not
+// text, so captions go out over GATT only.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::{AppSettings, BleBroadcastSettings};
+use crate::{text, AppHandle, AppState};
+use serde_json::Value;
+use std::time::Duration;
+use tauri::Manager;
+
+// 128-bit UUIDs of our own, outside the Bluetooth SIG base range
+const SERVICE_UUID: &str = "4a75746b-7576-6100-8000-00000000c0de";
+const CAPTION_UUID: &str = "4a75746b-7576-6100-8000-00000000c0df";
+// How often the broadcaster looks for a new caption
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Wait between attempts when there is no usable adapter, e.g. Bluetooth is off
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct BleBroadcastState {
+    // Bumped to stop the running broadcaster
+    generation: u64,
+    // Plain text of the latest caption
+    caption: String,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .ble_broadcast
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn caption(app: &AppHandle) -> String {
+    app.state::<AppState>()
+        .ble_broadcast
+        .lock()
+        .map(|s| s.caption.clone())
+        .unwrap_or_default()
+}
+
+// Stop the running broadcaster and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.ble_broadcast.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.ble_broadcast.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+    if !peripheral::SUPPORTED {
+        log::warn!("Bluetooth LE caption broadcast is not supported on this platform");
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &BleBroadcastSettings, generation: u64) {
+    let mut reported_missing = false;
+
+    while is_current(app, generation) {
+        match peripheral::Peripheral::start(&settings.name) {
+            Ok(peripheral) => {
+                log::info!(
+                    "Broadcasting captions over Bluetooth LE as {}",
+                    settings.name
+                );
+                reported_missing = false;
+                if let Err(e) = broadcast(app, &peripheral, settings, generation) {
+                    log::warn!("Bluetooth LE caption broadcast stopped: {}", e);
+                }
+                peripheral.stop();
+            }
+            Err(e) => {
+                if !reported_missing {
+                    log::warn!("Bluetooth LE caption broadcast not available: {}", e);
+                    reported_missing = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+fn broadcast(
+    app: &AppHandle,
+    peripheral: &peripheral::Peripheral,
+    settings: &BleBroadcastSettings,
+    generation: u64,
+) -> Result<(), String> {
+    let mut sent: Option<String> = None;
+
+    while is_current(app, generation) {
+        let current = caption(app);
+        let value = tail(&current, settings.max_bytes.max(1));
+        if sent.as_deref() != Some(value) {
+            peripheral.set_value(value.as_bytes().to_vec())?;
+            sent = Some(value.to_string());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+// The end of the caption within `max_bytes`, from the first whole word
+fn tail(caption: &str, max_bytes: usize) -> &str {
+    if caption.len() <= max_bytes {
+        return caption;
+    }
+    let mut start = caption.len() - max_bytes;
+    while !caption.is_char_boundary(start) {
+        start += 1;
+    }
+    let rest = &caption[start..];
+    match rest.find(' ') {
+        Some(space) if space + 1 < rest.len() => &rest[space + 1..],
+        _ => rest,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod peripheral {
+    use super::{CAPTION_UUID, SERVICE_UUID};
+    use std::collections::HashMap;
+    use zbus::blocking::Connection;
+    use zbus::fdo::{ManagedObjects, ObjectManager};
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+    pub const SUPPORTED: bool = true;
+
+    const BLUEZ: &str = "org.bluez";
+    // GATT objects go under the application path, which BlueZ reads through ObjectManager
+    const APPLICATION_PATH: &str = "/org/jutukuva/ble";
+    const SERVICE_PATH: &str = "/org/jutukuva/ble/service0";
+    const CHARACTERISTIC_PATH: &str = "/org/jutukuva/ble/service0/char0";
+    const ADVERTISEMENT_PATH: &str = "/org/jutukuva/advertisement0";
+
+    struct Service;
+
+    #[zbus::interface(name = "org.bluez.GattService1")]
+    impl Service {
+        #[zbus(property, name = "UUID")]
+        fn uuid(&self) -> String {
+            SERVICE_UUID.to_string()
+        }
+
+        #[zbus(property)]
+        fn primary(&self) -> bool {
+            true
+        }
+    }
+
+    struct Characteristic {
+        value: Vec<u8>,
+    }
+
+    #[zbus::interface(name = "org.bluez.GattCharacteristic1")]
+    impl Characteristic {
+        // Values longer than the MTU are read in parts from an offset
+        fn read_value(&self, options: HashMap<String, OwnedValue>) -> Vec<u8> {
+            let offset = options
+                .get("offset")
+                .and_then(|v| v.downcast_ref::<u16>().ok())
+                .unwrap_or(0);
+            self.value
+                .get(usize::from(offset)..)
+                .unwrap_or_default()
+                .to_vec()
+        }
+
+        // BlueZ notifies subscribers of every change of Value
+        fn start_notify(&self) {}
+
+        fn stop_notify(&self) {}
+
+        #[zbus(property, name = "UUID")]
+        fn uuid(&self) -> String {
+            CAPTION_UUID.to_string()
+        }
+
+        #[zbus(property)]
+        fn service(&self) -> OwnedObjectPath {
+            ObjectPath::from_static_str_unchecked(SERVICE_PATH).into()
+        }
+
+        #[zbus(property)]
+        fn flags(&self) -> Vec<String> {
+            vec!["read".to_string(), "notify".to_string()]
+        }
+
+        #[zbus(property)]
+        fn value(&self) -> Vec<u8> {
+            self.value.clone()
+        }
+    }
+
+    struct Advertisement {
+        name: String,
+    }
+
+    #[zbus::interface(name = "org.bluez.LEAdvertisement1")]
+    impl Advertisement {
+        fn release(&self) {}
+
+        #[zbus(property, name = "Type")]
+        fn kind(&self) -> String {
+            "peripheral".to_string()
+        }
+
+        #[zbus(property, name = "ServiceUUIDs")]
+        fn service_uuids(&self) -> Vec<String> {
+            vec![SERVICE_UUID.to_string()]
+        }
+
+        #[zbus(property)]
+        fn local_name(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    pub struct Peripheral {
+        connection: Connection,
+        adapter: OwnedObjectPath,
+    }
+
+    impl Peripheral {
+        // Publish the caption service and advertise it on the first adapter able to
+        pub fn start(name: &str) -> Result<Self, String> {
+            let connection = Connection::system().map_err(|e| e.to_string())?;
+            let adapter = find_adapter(&connection)?;
+
+            {
+                let server = connection.object_server();
+                server
+                    .at(SERVICE_PATH, Service)
+                    .map_err(|e| e.to_string())?;
+                server
+                    .at(CHARACTERISTIC_PATH, Characteristic { value: Vec::new() })
+                    .map_err(|e| e.to_string())?;
+                server
+                    .at(APPLICATION_PATH, ObjectManager)
+                    .map_err(|e| e.to_string())?;
+                server
+                    .at(
+                        ADVERTISEMENT_PATH,
+                        Advertisement {
+                            name: name.to_string(),
+                        },
+                    )
+                    .map_err(|e| e.to_string())?;
+            }
+
+            let peripheral = Self {
+                connection,
+                adapter,
+            };
+            peripheral.call_adapter(
+                "org.bluez.GattManager1",
+                "RegisterApplication",
+                APPLICATION_PATH,
+            )?;
+            peripheral.call_adapter(
+                "org.bluez.LEAdvertisingManager1",
+                "RegisterAdvertisement",
+                ADVERTISEMENT_PATH,
+            )?;
+            Ok(peripheral)
+        }
+
+        pub fn set_value(&self, value: Vec<u8>) -> Result<(), String> {
+            let characteristic = self
+                .connection
+                .object_server()
+                .interface::<_, Characteristic>(CHARACTERISTIC_PATH)
+                .map_err(|e| e.to_string())?;
+            let mut current = characteristic.get_mut();
+            current.value = value;
+            zbus::block_on(current.value_changed(characteristic.signal_emitter()))
+                .map_err(|e| e.to_string())
+        }
+
+        // BlueZ also drops both registrations when the connection closes
+        pub fn stop(&self) {
+            let _ = self.call_adapter(
+                "org.bluez.LEAdvertisingManager1",
+                "UnregisterAdvertisement",
+                ADVERTISEMENT_PATH,
+            );
+            let _ = self.call_adapter(
+                "org.bluez.GattManager1",
+                "UnregisterApplication",
+                APPLICATION_PATH,
+            );
+        }
+
+        // Register*(object, options) and Unregister*(object) on the adapter
+        fn call_adapter(&self, interface: &str, method: &str, path: &str) -> Result<(), String> {
+            let path = ObjectPath::try_from(path).map_err(|e| e.to_string())?;
+            let reply = if method.starts_with("Register") {
+                let options: HashMap<&str, OwnedValue> = HashMap::new();
+                self.connection.call_method(
+                    Some(BLUEZ),
+                    self.adapter.as_str(),
+                    Some(interface),
+                    method,
+                    &(path, options),
+                )
+            } else {
+                self.connection.call_method(
+                    Some(BLUEZ),
+                    self.adapter.as_str(),
+                    Some(interface),
+                    method,
+                    &(path,),
+                )
+            };
+            reply.map(|_| ()).map_err(|e| format!("{}: {}", method, e))
+        }
+    }
+
+    // The first adapter that can both serve GATT and advertise, e.g. /org/bluez/hci0
+    fn find_adapter(connection: &Connection) -> Result<OwnedObjectPath, String> {
+        let reply = connection
+            .call_method(
+                Some(BLUEZ),
+                "/",
+                Some("org.freedesktop.DBus.ObjectManager"),
+                "GetManagedObjects",
+                &(),
+            )
+            .map_err(|e| e.to_string())?;
+        let objects: ManagedObjects = reply.body().deserialize().map_err(|e| e.to_string())?;
+        objects
+            .into_iter()
+            .filter(|(_, interfaces)| {
+                let has = |name: &str| interfaces.keys().any(|i| i.as_str() == name);
+                has("org.bluez.GattManager1") && has("org.bluez.LEAdvertisingManager1")
+            })
+            .map(|(path, _)| path)
+            .min_by(|a, b| a.as_str().cmp(b.as_str()))
+            .ok_or_else(|| "no Bluetooth adapter that can advertise".to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod peripheral {
+    pub const SUPPORTED: bool = false;
+
+    pub struct Peripheral;
+
+    impl Peripheral {
+        pub fn start(_name: &str) -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+
+        pub fn set_value(&self, _value: Vec<u8>) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn stop(&self) {}
+    }
+}
+
+// Keeps the latest caption for the broadcaster
+pub struct BleBroadcastOutput;
+
+impl Output for BleBroadcastOutput {
+    fn id(&self) -> &str {
+        "ble-broadcast"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.ble_broadcast.enabled {
+            return Ok(());
+        }
+        let caption = payload.get("text").and_then(Value::as_str).unwrap_or("");
+        let state = app.state::<AppState>();
+        let mut broadcast = state.ble_broadcast.lock().map_err(|e| e.to_string())?;
+        broadcast.caption = text::plain(caption);
+        Ok(())
+    }
+}
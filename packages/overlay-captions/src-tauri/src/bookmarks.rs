@@ -0,0 +1,132 @@
+// Bookmarks: timestamped markers the operator or a meeting secretary drops during a session
+// to find decisions and action items again. One is added with a note and a tag from the
+// review window or, without either, with the global shortcut in `bookmarks`. They belong
+// to the session: kept with the archived session, listed in `bookmarks-changed` events, and
+// put into exported transcripts where they happened (see export.rs). Tagged ones also make
+// the meeting minutes export (see minutes.rs).
+
+use crate::settings::BookmarkSettings;
+use crate::{clock, AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+// Longer notes are cut
+const MAX_NOTE_CHARS: usize = 200;
+// What a bookmark can be tagged as, in the order the minutes list them; untagged is ""
+pub const TAGS: [&str; 3] = ["decision", "action", "question"];
+
+#[derive(Default)]
+pub struct BookmarkState {
+    // This session's, oldest first
+    bookmarks: Vec<Bookmark>,
+    // Global shortcut registered for adding one
+    shortcut: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    // Milliseconds since the Unix epoch
+    pub at: u64,
+    // May be empty
+    pub note: String,
+    // One of TAGS, or empty for a plain bookmark
+    #[serde(default)]
+    pub tag: String,
+}
+
+// What goes in front of the note in exports; the PDF fonts have no symbols for these
+pub fn label(tag: &str) -> &'static str {
+    match tag {
+        "decision" => "Decision:",
+        "action" => "Action:",
+        "question" => "Question:",
+        _ => "Bookmark:",
+    }
+}
+
+fn settings(app: &AppHandle) -> Result<BookmarkSettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.bookmarks.clone())
+}
+
+pub fn list(app: &AppHandle) -> Result<Vec<Bookmark>, String> {
+    let state = app.state::<AppState>();
+    let bookmarks = state.bookmarks.lock().map_err(|e| e.to_string())?;
+    Ok(bookmarks.bookmarks.clone())
+}
+
+pub fn add(app: &AppHandle, note: &str, tag: &str) -> Result<Bookmark, String> {
+    if !tag.is_empty() && !TAGS.contains(&tag) {
+        return Err(format!("Unknown bookmark tag: {}", tag));
+    }
+    let bookmark = Bookmark {
+        at: clock::now_millis(),
+        note: note.trim().chars().take(MAX_NOTE_CHARS).collect(),
+        tag: tag.to_string(),
+    };
+    let state = app.state::<AppState>();
+    state
+        .bookmarks
+        .lock()
+        .map_err(|e| e.to_string())?
+        .bookmarks
+        .push(bookmark.clone());
+    state
+        .storage
+        .lock()
+        .map_err(|e| e.to_string())?
+        .add_bookmark(&bookmark)?;
+    log::info!("Bookmark added: {}", bookmark.note);
+
+    if let Ok(bookmarks) = list(app) {
+        let _ = app.emit("bookmarks-changed", bookmarks);
+    }
+    Ok(bookmark)
+}
+
+// A new session starts without bookmarks
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut state) = app.state::<AppState>().bookmarks.lock() {
+        state.bookmarks.clear();
+    }
+    let _ = app.emit("bookmarks-changed", Vec::<Bookmark>::new());
+}
+
+// (Re-)register the shortcut for adding a bookmark from settings
+pub fn register_shortcut(app: &AppHandle) {
+    let shortcut = match settings(app) {
+        Ok(s) => s.shortcut.trim().to_string(),
+        Err(_) => return,
+    };
+    let state = app.state::<AppState>();
+    let mut bookmarks = match state.bookmarks.lock() {
+        Ok(b) => b,
+        Err(_) => return,
+    };
+
+    if let Some(previous) = bookmarks.shortcut.take() {
+        if let Err(e) = app.global_shortcut().unregister(previous.as_str()) {
+            log::warn!("Failed to unregister bookmark shortcut {}: {}", previous, e);
+        }
+    }
+    if shortcut.is_empty() {
+        return;
+    }
+
+    let result = app
+        .global_shortcut()
+        .on_shortcut(shortcut.as_str(), |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                if let Err(e) = add(app, "", "") {
+                    log::error!("Failed to add bookmark: {}", e);
+                }
+            }
+        });
+    match result {
+        Ok(()) => bookmarks.shortcut = Some(shortcut),
+        Err(e) => log::error!("Failed to register bookmark shortcut {}: {}", shortcut, e),
+    }
+}
@@ -0,0 +1,371 @@
+// Refreshable braille displays, so deafblind attendees can follow the captions straight from
+// the caption machine. The display is driven through BrlAPI, the client interface of the
+// BRLTTY screen reader (Linux, and its Windows port), which has to be running and handles
+// the braille translation. libbrlapi is loaded at runtime, so installs without BRLTTY
+// don't need it.
+//
+// The display shows one window of the latest caption: its last lines while following along,
+// or earlier ones after paging back with the display's own window keys or braille_page.
+// Paging forward past the end follows along again.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::AppSettings;
+use crate::{text, AppHandle, AppState};
+use libloading::Library;
+use serde_json::Value;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_uint};
+use std::time::Duration;
+use tauri::Manager;
+
+#[cfg(windows)]
+const LIBRARY_NAMES: &[&str] = &["brlapi-0.8.dll", "brlapi.dll"];
+#[cfg(not(windows))]
+const LIBRARY_NAMES: &[&str] = &["libbrlapi.so.0.8", "libbrlapi.so"];
+
+// How often the display is updated and its keys are read
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Wait between attempts to reach BRLTTY
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+// From brlapi.h and brlapi_constants.h
+const TTY_DEFAULT: c_int = -1;
+const CURSOR_OFF: c_int = -1;
+const KEY_TYPE_CMD: u64 = 0x2000_0000;
+const KEY_CMD_LNUP: u64 = 1;
+const KEY_CMD_LNDN: u64 = 2;
+const KEY_CMD_TOP: u64 = 9;
+const KEY_CMD_BOT: u64 = 10;
+const KEY_CMD_TOP_LEFT: u64 = 11;
+const KEY_CMD_BOT_LEFT: u64 = 12;
+const KEY_CMD_FWINLT: u64 = 23;
+const KEY_CMD_FWINRT: u64 = 24;
+
+// wchar_t
+#[cfg(windows)]
+type WChar = u16;
+#[cfg(not(windows))]
+type WChar = u32;
+
+#[repr(C)]
+struct ConnectionSettings {
+    auth: *mut c_char,
+    host: *mut c_char,
+}
+
+type OpenConnectionFn =
+    unsafe extern "C" fn(*const ConnectionSettings, *mut ConnectionSettings) -> c_int;
+type CloseConnectionFn = unsafe extern "C" fn();
+type GetDisplaySizeFn = unsafe extern "C" fn(*mut c_uint, *mut c_uint) -> c_int;
+type EnterTtyModeFn = unsafe extern "C" fn(c_int, *const c_char) -> c_int;
+type LeaveTtyModeFn = unsafe extern "C" fn() -> c_int;
+type WriteWTextFn = unsafe extern "C" fn(c_int, *const WChar) -> c_int;
+type ReadKeyFn = unsafe extern "C" fn(c_int, *mut u64) -> c_int;
+
+#[derive(Default)]
+pub struct BrailleState {
+    // Bumped to stop the running writer, e.g. when the host changes
+    generation: u64,
+    // Plain text of the latest caption
+    caption: String,
+    // Window shown, counted from the start of the caption; None follows along
+    window: Option<usize>,
+    // Windows in the caption as last shown, for paging forward
+    windows: usize,
+}
+
+struct BrlApi {
+    open_connection: OpenConnectionFn,
+    close_connection: CloseConnectionFn,
+    get_display_size: GetDisplaySizeFn,
+    enter_tty_mode: EnterTtyModeFn,
+    leave_tty_mode: LeaveTtyModeFn,
+    write_wtext: WriteWTextFn,
+    read_key: ReadKeyFn,
+    // Keeps the function pointers above valid
+    _library: Library,
+}
+
+impl BrlApi {
+    fn load() -> Result<Self, String> {
+        let mut last_error = String::new();
+        for name in LIBRARY_NAMES {
+            // SAFETY: libbrlapi comes with BRLTTY; the signatures above are from its headers
+            unsafe {
+                let library = match Library::new(name) {
+                    Ok(library) => library,
+                    Err(e) => {
+                        last_error = e.to_string();
+                        continue;
+                    }
+                };
+                return Ok(Self {
+                    open_connection: *library
+                        .get::<OpenConnectionFn>(b"brlapi_openConnection\0")
+                        .map_err(|e| e.to_string())?,
+                    close_connection: *library
+                        .get::<CloseConnectionFn>(b"brlapi_closeConnection\0")
+                        .map_err(|e| e.to_string())?,
+                    get_display_size: *library
+                        .get::<GetDisplaySizeFn>(b"brlapi_getDisplaySize\0")
+                        .map_err(|e| e.to_string())?,
+                    enter_tty_mode: *library
+                        .get::<EnterTtyModeFn>(b"brlapi_enterTtyMode\0")
+                        .map_err(|e| e.to_string())?,
+                    leave_tty_mode: *library
+                        .get::<LeaveTtyModeFn>(b"brlapi_leaveTtyMode\0")
+                        .map_err(|e| e.to_string())?,
+                    write_wtext: *library
+                        .get::<WriteWTextFn>(b"brlapi_writeWText\0")
+                        .map_err(|e| e.to_string())?,
+                    read_key: *library
+                        .get::<ReadKeyFn>(b"brlapi_readKey\0")
+                        .map_err(|e| e.to_string())?,
+                    _library: library,
+                });
+            }
+        }
+        Err(format!("BrlAPI library not found: {}", last_error))
+    }
+
+    // `host` is "host:port" of a BRLTTY elsewhere, empty for the local one
+    fn connect(&self, host: &str) -> Result<Connection<'_>, String> {
+        let host = CString::new(host.trim()).map_err(|e| e.to_string())?;
+        let settings = ConnectionSettings {
+            auth: std::ptr::null_mut(),
+            host: if host.as_bytes().is_empty() {
+                std::ptr::null_mut()
+            } else {
+                host.as_ptr() as *mut c_char
+            },
+        };
+        let (mut width, mut rows) = (0, 0);
+        // SAFETY: BrlAPI only reads the settings, and copies the host string
+        unsafe {
+            if (self.open_connection)(&settings, std::ptr::null_mut()) < 0 {
+                return Err("BRLTTY not reachable".to_string());
+            }
+            // From here on the connection is closed when dropped
+            let mut connection = Connection {
+                api: self,
+                width: 0,
+                rows: 0,
+            };
+            if (self.get_display_size)(&mut width, &mut rows) < 0 || width == 0 {
+                return Err("No braille display".to_string());
+            }
+            if (self.enter_tty_mode)(TTY_DEFAULT, std::ptr::null()) < 0 {
+                return Err("Braille display is in use".to_string());
+            }
+            connection.width = width as usize;
+            connection.rows = rows.max(1) as usize;
+            Ok(connection)
+        }
+    }
+}
+
+struct Connection<'a> {
+    api: &'a BrlApi,
+    // Cells per row and rows of the display
+    width: usize,
+    rows: usize,
+}
+
+impl Connection<'_> {
+    fn write(&self, cells: &str) -> Result<(), String> {
+        #[cfg(windows)]
+        let mut wide: Vec<WChar> = cells.encode_utf16().collect();
+        #[cfg(not(windows))]
+        let mut wide: Vec<WChar> = cells.chars().map(|c| c as WChar).collect();
+        wide.push(0);
+        // SAFETY: the text is NUL-terminated and outlives the call
+        if unsafe { (self.api.write_wtext)(CURSOR_OFF, wide.as_ptr()) } < 0 {
+            return Err("Writing to the braille display failed".to_string());
+        }
+        Ok(())
+    }
+
+    // Next key pressed on the display, without waiting
+    fn read_key(&self) -> Result<Option<u64>, String> {
+        let mut code = 0u64;
+        // SAFETY: BrlAPI writes one key code
+        match unsafe { (self.api.read_key)(0, &mut code) } {
+            1 => Ok(Some(code)),
+            0 => Ok(None),
+            _ => Err("Lost connection to BRLTTY".to_string()),
+        }
+    }
+}
+
+impl Drop for Connection<'_> {
+    fn drop(&mut self) {
+        // SAFETY: matches the successful openConnection in BrlApi::connect; leaving a tty
+        // mode that was never entered is a harmless error
+        unsafe {
+            (self.api.leave_tty_mode)();
+            (self.api.close_connection)();
+        }
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .braille
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running writer and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, host) = match state.settings.lock() {
+        Ok(s) => (s.braille.enabled, s.braille.host.clone()),
+        Err(_) => return,
+    };
+    let generation = match state.braille.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &host, generation));
+}
+
+fn run(app: &AppHandle, host: &str, generation: u64) {
+    let api = match BrlApi::load() {
+        Ok(api) => api,
+        Err(e) => {
+            log::error!("Braille output unavailable: {}", e);
+            return;
+        }
+    };
+    let mut reported_missing = false;
+
+    while is_current(app, generation) {
+        match api.connect(host) {
+            Ok(connection) => {
+                log::info!(
+                    "Braille display connected, {}x{} cells",
+                    connection.width,
+                    connection.rows
+                );
+                reported_missing = false;
+                if let Err(e) = show_captions(app, &connection, generation) {
+                    log::warn!("Braille display disconnected: {}", e);
+                }
+            }
+            Err(e) => {
+                if !reported_missing {
+                    log::warn!("Braille display not available: {}", e);
+                    reported_missing = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+// Move the window shown: "back", "forward", "start" or "live"
+pub fn page(app: &AppHandle, direction: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut braille = state.braille.lock().map_err(|e| e.to_string())?;
+    let last = braille.windows.saturating_sub(1);
+    let current = braille.window.unwrap_or(last).min(last);
+    braille.window = match direction {
+        "back" => Some(current.saturating_sub(1)),
+        "forward" if current + 1 < last => Some(current + 1),
+        "forward" | "live" => None,
+        "start" => Some(0),
+        _ => return Err(format!("Unknown paging direction: {}", direction)),
+    };
+    Ok(())
+}
+
+fn handle_key(app: &AppHandle, code: u64) -> Result<(), String> {
+    // Flags are in the upper half
+    let direction = match code & 0xFFFF_FFFF {
+        c if c == KEY_TYPE_CMD | KEY_CMD_FWINLT || c == KEY_TYPE_CMD | KEY_CMD_LNUP => "back",
+        c if c == KEY_TYPE_CMD | KEY_CMD_FWINRT || c == KEY_TYPE_CMD | KEY_CMD_LNDN => "forward",
+        c if c == KEY_TYPE_CMD | KEY_CMD_TOP || c == KEY_TYPE_CMD | KEY_CMD_TOP_LEFT => "start",
+        c if c == KEY_TYPE_CMD | KEY_CMD_BOT || c == KEY_TYPE_CMD | KEY_CMD_BOT_LEFT => "live",
+        _ => return Ok(()),
+    };
+    page(app, direction)
+}
+
+fn show_captions(app: &AppHandle, connection: &Connection, generation: u64) -> Result<(), String> {
+    let mut shown: Option<String> = None;
+
+    while is_current(app, generation) {
+        while let Some(code) = connection.read_key()? {
+            handle_key(app, code)?;
+        }
+
+        let cells = {
+            let state = app.state::<AppState>();
+            let mut braille = state.braille.lock().map_err(|e| e.to_string())?;
+            let lines = text::wrap(&braille.caption, connection.width);
+            let windows: Vec<&[String]> = lines.chunks(connection.rows).collect();
+            braille.windows = windows.len().max(1);
+            let last = braille.windows - 1;
+            let index = braille.window.unwrap_or(last).min(last);
+
+            let mut cells = String::new();
+            for row in 0..connection.rows {
+                let line = windows
+                    .get(index)
+                    .and_then(|window| window.get(row))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                cells.push_str(line);
+                let padding = connection.width.saturating_sub(text::grapheme_count(line));
+                cells.extend(std::iter::repeat_n(' ', padding));
+            }
+            cells
+        };
+        if shown.as_ref() != Some(&cells) {
+            connection.write(&cells)?;
+            shown = Some(cells);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+// Keeps the latest caption for the braille display
+pub struct BrailleOutput;
+
+impl Output for BrailleOutput {
+    fn id(&self) -> &str {
+        "braille"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.braille.enabled {
+            return Ok(());
+        }
+        let caption = payload.get("text").and_then(Value::as_str).unwrap_or("");
+        let state = app.state::<AppState>();
+        let mut braille = state.braille.lock().map_err(|e| e.to_string())?;
+        braille.caption = text::plain(caption);
+        Ok(())
+    }
+}
@@ -0,0 +1,478 @@
+// Captions from the companion browser extension, e.g. a web meeting's own captions, so that
+// every caption source ends up on the one overlay. Browsers only let an extension talk to
+// a "native messaging host" they start themselves, over stdin and stdout, so the same
+// executable has a host mode: started by the browser (with the extension's origin, or for
+// Firefox the host manifest, as argument) it opens no windows and forwards each message to
+// the running app over 127.0.0.1:`browser_bridge.port`, one JSON line each, after a line
+// with the token the app keeps in `browser-bridge.token` in the config directory. The
+// extension sends
+//
+//   {"type": "caption", "text": "…", "source": "meet.google.com"}
+//   {"type": "clear"}
+//
+// and gets back the app's answer to each, {"ok": true} or {"ok": false, "error": "…"}, a
+// line per message. Captions and clears from the browser are unsigned, so
+// `caption_signing.mode` decides what happens to them (see `signatures::unsigned`). The host
+// manifests naming the allowed extensions are written when the bridge is enabled.
+
+use crate::settings::{get_config_dir, load_settings, BrowserBridgeSettings};
+use crate::{commands, diagnostics, signatures, viewer, AppHandle, AppState};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::Manager;
+
+// Lower case letters, digits, dots and underscores only, as Chrome requires
+pub const HOST_NAME: &str = "ee.jutukuva.subtiitrid";
+const TOKEN_FILE: &str = "browser-bridge.token";
+const TOKEN_LENGTH: usize = 32;
+// How often connections and the listener check for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Browsers allow 4 GB; a caption is far smaller
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+// How long the host waits for the app to answer a message
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct BrowserBridgeState {
+    // Bumped to stop the running server, e.g. when the port changes
+    generation: u64,
+    token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Message {
+    Caption {
+        text: String,
+        source: Option<String>,
+    },
+    Clear,
+}
+
+#[derive(Serialize)]
+struct HostManifest<'a> {
+    name: &'a str,
+    description: &'a str,
+    path: &'a str,
+    #[serde(rename = "type")]
+    kind: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_origins: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_extensions: Option<&'a [String]>,
+}
+
+fn token_path() -> PathBuf {
+    get_config_dir().join(TOKEN_FILE)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .browser_bridge
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running server and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.browser_bridge.clone(),
+        Err(_) => return,
+    };
+    let token: String = {
+        let mut rng = rand::thread_rng();
+        (0..TOKEN_LENGTH)
+            .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0'))
+            .collect()
+    };
+    let generation = match state.browser_bridge.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.token = token.clone();
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        let _ = std::fs::remove_file(token_path());
+        return;
+    }
+    if let Err(e) = std::fs::write(token_path(), &token) {
+        log::error!("Browser bridge unavailable, token not written: {}", e);
+        return;
+    }
+    if let Err(e) = install_manifests(&settings) {
+        log::warn!("Failed to register the browser extension host: {}", e);
+    }
+
+    let app = app.clone();
+    let port = settings.port;
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&app, port, generation) {
+            log::error!("Browser bridge on port {} failed: {}", port, e);
+        }
+    });
+}
+
+fn serve(app: &AppHandle, port: u16, generation: u64) -> Result<(), String> {
+    // A server being replaced may hold the port until its next poll
+    let mut attempts = 0;
+    let listener = loop {
+        match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(listener) => break listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts < 5 => {
+                attempts += 1;
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    log::info!("Browser bridge listening on 127.0.0.1:{}", port);
+
+    while is_current(app, generation) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_client(&app, stream, generation) {
+                        log::warn!("Browser extension disconnected: {}", e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    log::info!("Browser bridge on port {} stopped", port);
+    Ok(())
+}
+
+fn handle_client(app: &AppHandle, stream: TcpStream, generation: u64) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    // Reads time out so the loop notices when the server stops
+    stream
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    let mut authorized = false;
+    let mut source: Option<String> = None;
+
+    while is_current(app, generation) {
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            // A partial line stays in `line` for the next read
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+        if line.len() > MAX_MESSAGE_BYTES {
+            return Err("message too long".to_string());
+        }
+        if !line.ends_with(b"\n") {
+            continue;
+        }
+        let message = String::from_utf8_lossy(&std::mem::take(&mut line)).into_owned();
+
+        if !authorized {
+            let token = app
+                .state::<AppState>()
+                .browser_bridge
+                .lock()
+                .map(|s| s.token.clone())
+                .map_err(|e| e.to_string())?;
+            if message.trim() != token {
+                return Err("wrong token".to_string());
+            }
+            authorized = true;
+            continue;
+        }
+        let result = match serde_json::from_str::<Message>(&message) {
+            Ok(message) => handle_message(app, message, &mut source),
+            Err(e) => {
+                log::warn!("Invalid browser extension message: {}", e);
+                Err(e.to_string())
+            }
+        };
+        let reply = match result {
+            Ok(()) => json!({ "ok": true }),
+            Err(e) => json!({ "ok": false, "error": e }),
+        };
+        writeln!(reader.get_mut(), "{}", reply).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn handle_message(
+    app: &AppHandle,
+    message: Message,
+    source: &mut Option<String>,
+) -> Result<(), String> {
+    // Viewers get their captions from the primary
+    if viewer::is_active(app) {
+        return Ok(());
+    }
+    match message {
+        Message::Caption { text, source: from } => {
+            if from.is_some() && from != *source {
+                log::info!(
+                    "Captions from the browser: {}",
+                    from.as_deref().unwrap_or("")
+                );
+                let _ = diagnostics::record(app, "browser captions", from.clone());
+                *source = from;
+            }
+            commands::submit_caption(app, text, signatures::unsigned(app))
+        }
+        Message::Clear => commands::submit_caption(app, String::new(), signatures::unsigned(app)),
+    }
+}
+
+// Host mode: the exit code when the browser started us, None to start the app
+pub fn run_host() -> Option<i32> {
+    let started_by_browser = std::env::args().skip(1).any(|arg| {
+        arg.starts_with("chrome-extension://") || arg.ends_with(&format!("{}.json", HOST_NAME))
+    });
+    if !started_by_browser {
+        return None;
+    }
+    let port = tauri::async_runtime::block_on(load_settings())
+        .browser_bridge
+        .port;
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut app: Option<BufReader<TcpStream>> = None;
+
+    // Until the browser closes stdin, when the extension disconnects
+    while let Some(message) = read_message(&mut stdin) {
+        let result = match message {
+            Ok(message) => forward(&mut app, port, &message),
+            Err(e) => Err(e),
+        };
+        let reply = match result {
+            Ok(reply) => reply,
+            Err(e) => {
+                // Connect afresh for the next message, the app may have restarted
+                app = None;
+                json!({ "ok": false, "error": e })
+            }
+        };
+        if write_message(&mut stdout, &reply.to_string()).is_err() {
+            break;
+        }
+    }
+    Some(0)
+}
+
+// One message from the browser: its length in native byte order, then the JSON
+fn read_message(input: &mut impl Read) -> Option<Result<String, String>> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len).ok()?;
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_BYTES {
+        return None;
+    }
+    let mut message = vec![0u8; len];
+    input.read_exact(&mut message).ok()?;
+    Some(String::from_utf8(message).map_err(|e| e.to_string()))
+}
+
+fn write_message(output: &mut impl Write, message: &str) -> std::io::Result<()> {
+    output.write_all(&(message.len() as u32).to_ne_bytes())?;
+    output.write_all(message.as_bytes())?;
+    output.flush()
+}
+
+// Send a message to the app; returns the app's reply, Err when the app couldn't be reached
+fn forward(
+    app: &mut Option<BufReader<TcpStream>>,
+    port: u16,
+    message: &str,
+) -> Result<Value, String> {
+    // A line per message, so the JSON must not span several
+    let value: Value = serde_json::from_str(message).map_err(|e| e.to_string())?;
+    let stream = match app {
+        Some(stream) => stream,
+        None => {
+            let token = std::fs::read_to_string(token_path())
+                .map_err(|_| "The caption app is not running or the bridge is off".to_string())?;
+            let mut stream = TcpStream::connect(("127.0.0.1", port))
+                .map_err(|_| "The caption app is not running or the bridge is off".to_string())?;
+            stream
+                .set_read_timeout(Some(REPLY_TIMEOUT))
+                .map_err(|e| e.to_string())?;
+            writeln!(stream, "{}", token.trim()).map_err(|e| e.to_string())?;
+            app.insert(BufReader::new(stream))
+        }
+    };
+    writeln!(stream.get_mut(), "{}", value).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    match stream.read_line(&mut reply) {
+        Ok(0) => Err("The caption app closed the connection".to_string()),
+        Ok(_) => serde_json::from_str(&reply).map_err(|e| e.to_string()),
+        Err(e) => Err(format!("No answer from the caption app: {}", e)),
+    }
+}
+
+fn write_manifest(path: &Path, manifest: &HostManifest) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Tell the browsers where the host is and which extensions may start it
+fn install_manifests(settings: &BrowserBridgeSettings) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe = exe.to_string_lossy();
+    let chrome = HostManifest {
+        name: HOST_NAME,
+        description: "Jutukuva captions from the browser",
+        path: &exe,
+        kind: "stdio",
+        allowed_origins: Some(
+            settings
+                .chrome_extension_ids
+                .iter()
+                .map(|id| format!("chrome-extension://{}/", id.trim()))
+                .collect(),
+        ),
+        allowed_extensions: None,
+    };
+    let firefox = HostManifest {
+        allowed_origins: None,
+        allowed_extensions: Some(&settings.firefox_extension_ids),
+        ..chrome
+    };
+    let file = format!("{}.json", HOST_NAME);
+
+    for (browser, manifests_dir) in platform::MANIFEST_DIRS {
+        let path = platform::manifest_path(manifests_dir, &file);
+        let (manifest, ids) = match *browser {
+            "firefox" => (&firefox, settings.firefox_extension_ids.len()),
+            _ => (&chrome, settings.chrome_extension_ids.len()),
+        };
+        match path {
+            // Only for browsers that are installed and extensions that are named
+            Some(path) if ids > 0 => {
+                write_manifest(&path, manifest)?;
+                platform::register(browser, &path)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Host manifests go in per-user directories under each browser's own, which must exist
+#[cfg(not(windows))]
+mod platform {
+    use directories::BaseDirs;
+    use std::path::PathBuf;
+
+    // Relative to the home directory
+    #[cfg(target_os = "macos")]
+    pub const MANIFEST_DIRS: &[(&str, &str)] = &[
+        (
+            "chrome",
+            "Library/Application Support/Google/Chrome/NativeMessagingHosts",
+        ),
+        (
+            "chromium",
+            "Library/Application Support/Chromium/NativeMessagingHosts",
+        ),
+        (
+            "edge",
+            "Library/Application Support/Microsoft Edge/NativeMessagingHosts",
+        ),
+        (
+            "firefox",
+            "Library/Application Support/Mozilla/NativeMessagingHosts",
+        ),
+    ];
+
+    #[cfg(not(target_os = "macos"))]
+    pub const MANIFEST_DIRS: &[(&str, &str)] = &[
+        ("chrome", ".config/google-chrome/NativeMessagingHosts"),
+        ("chromium", ".config/chromium/NativeMessagingHosts"),
+        ("edge", ".config/microsoft-edge/NativeMessagingHosts"),
+        ("firefox", ".mozilla/native-messaging-hosts"),
+    ];
+
+    pub fn manifest_path(manifests_dir: &str, file: &str) -> Option<PathBuf> {
+        let dirs = BaseDirs::new()?;
+        let dir = dirs.home_dir().join(manifests_dir);
+        // The browser's own directory
+        dir.parent().filter(|browser| browser.is_dir())?;
+        Some(dir.join(file))
+    }
+
+    pub fn register(_browser: &str, _path: &std::path::Path) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// The manifests can live anywhere; a registry key per browser points at them
+#[cfg(windows)]
+mod platform {
+    use crate::settings::get_config_dir;
+    use std::path::{Path, PathBuf};
+    use windows_sys::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    pub const MANIFEST_DIRS: &[(&str, &str)] = &[
+        ("chrome", "Software\\Google\\Chrome\\NativeMessagingHosts"),
+        ("edge", "Software\\Microsoft\\Edge\\NativeMessagingHosts"),
+        ("firefox", "Software\\Mozilla\\NativeMessagingHosts"),
+    ];
+
+    pub fn manifest_path(_registry_key: &str, file: &str) -> Option<PathBuf> {
+        Some(get_config_dir().join("native-messaging").join(file))
+    }
+
+    pub fn register(browser: &str, path: &Path) -> Result<(), String> {
+        let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+        let key = MANIFEST_DIRS
+            .iter()
+            .find(|(name, _)| *name == browser)
+            .map(|(_, key)| format!("{}\\{}", key, super::HOST_NAME))
+            .ok_or_else(|| format!("unknown browser {}", browser))?;
+        let key = wide(&key);
+        let value = wide(&path.to_string_lossy());
+        // SAFETY: both strings are NUL-terminated and the data length is in bytes
+        let status = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                key.as_ptr(),
+                std::ptr::null(),
+                REG_SZ,
+                value.as_ptr() as *const _,
+                (value.len() * 2) as u32,
+            )
+        };
+        if status != 0 {
+            return Err(format!("registry error {} for {}", status, browser));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+// Sessions started from the calendar. The ICS feed at `calendar.ics_url` (an https or
+// webcal URL, or a local file exported from the calendar app) is read every
+// `refresh_minutes`; events whose description carries a session code, as a
+// jutukuva://join/CODE link or after "code:" / "kood:", are announced `lead_minutes` before
+// they start: the overlay is shown, the operator gets a notification, and the main window
+// is sent a `calendar-session` event to join with the code. Recurring events only count
+// their first occurrence, and times with a TZID are taken as local time.
+
+use crate::i18n::{self, tr};
+use crate::settings::CalendarSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{clock, diagnostics, network, presentation, AppHandle, AppState};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+// Start times are checked this often, well within a minute
+const POLL_INTERVAL: Duration = Duration::from_secs(20);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CODE_LENGTH: usize = 6;
+// Words a session code may follow in an event description
+const CODE_LABELS: [&str; 2] = ["code:", "kood:"];
+
+#[derive(Default)]
+pub struct CalendarState {
+    // Bumped to stop the running watcher
+    generation: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarSession {
+    pub title: String,
+    pub code: String,
+    // Milliseconds since the Unix epoch
+    pub starts_at: u64,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .calendar
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running watcher and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.calendar.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.calendar.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled || settings.ics_url.trim().is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &CalendarSettings, generation: u64) {
+    let agent = match network::agent(REQUEST_TIMEOUT) {
+        Ok(agent) => agent,
+        Err(e) => {
+            log::error!("Calendar unavailable: {}", e);
+            return;
+        }
+    };
+    let refresh_every = u64::from(settings.refresh_minutes.max(1)) * 60_000;
+    let lead = u64::from(settings.lead_minutes) * 60_000;
+    let mut sessions: Vec<CalendarSession> = Vec::new();
+    let mut fetched_at: Option<u64> = None;
+    // Events already announced, by start time and code
+    let mut announced: HashSet<(u64, String)> = HashSet::new();
+    let mut failing = false;
+
+    while is_current(app, generation) {
+        let now = clock::now_millis();
+        if fetched_at.is_none_or(|at| now >= at + refresh_every) {
+            fetched_at = Some(now);
+            match fetch(app, &agent, settings.ics_url.trim()) {
+                Ok(ics) => {
+                    sessions = parse_sessions(&ics);
+                    log::info!("Calendar read: {} session(s)", sessions.len());
+                    failing = false;
+                }
+                // Reported once until the calendar can be read again
+                Err(e) if !failing => {
+                    log::warn!("Failed to read the calendar: {}", e);
+                    let _ = diagnostics::record(app, "calendar failed", Some(e));
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+
+        for session in &sessions {
+            // Not for events long under way, e.g. when the app starts mid-afternoon
+            let due = now + lead >= session.starts_at && now < session.starts_at + lead.max(60_000);
+            if due && announced.insert((session.starts_at, session.code.clone())) {
+                announce(app, session);
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn fetch(app: &AppHandle, agent: &ureq::Agent, url: &str) -> Result<String, String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        let path = url.strip_prefix("file://").unwrap_or(&url);
+        return std::fs::read_to_string(path).map_err(|e| e.to_string());
+    }
+    network::check(app, &url)?;
+    match agent.get(&url).call() {
+        Ok(response) => response.into_string().map_err(|e| e.to_string()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("HTTP {}", code)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn announce(app: &AppHandle, session: &CalendarSession) {
+    log::info!("Calendar session due: {} ({})", session.title, session.code);
+    let _ = diagnostics::record(
+        app,
+        "calendar session",
+        Some(format!("{} {}", session.code, session.title)),
+    );
+
+    let visible = app
+        .state::<AppState>()
+        .overlay_visible
+        .lock()
+        .map(|v| *v)
+        .unwrap_or(false);
+    if !visible {
+        crate::spawn_show_overlay_window(app.clone());
+    }
+    if let Err(e) = ManagedWindow::Main.emit(app, "calendar-session", session.clone()) {
+        log::error!("Failed to emit calendar session: {}", e);
+    }
+
+    // Nothing may pop up over the projector output in presentation mode
+    if presentation::is_active(app) {
+        return;
+    }
+    let lang = i18n::current_language(app);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(tr(&lang, "notification.calendar_session_title"))
+        .body(format!("{} ({})", session.title, session.code))
+        .show()
+    {
+        log::error!("Failed to show calendar notification: {}", e);
+    }
+}
+
+// The parts of a VEVENT that matter here
+#[derive(Default)]
+struct Event {
+    starts_at: Option<u64>,
+    title: String,
+    description: String,
+}
+
+// Events with a session code, from the text of an ICS file
+fn parse_sessions(ics: &str) -> Vec<CalendarSession> {
+    let mut sessions = Vec::new();
+    let mut event: Option<Event> = None;
+
+    for line in unfold(ics) {
+        let (name, value) = match line.split_once(':') {
+            Some(split) => split,
+            None => continue,
+        };
+        let (name, params) = match name.split_once(';') {
+            Some((name, params)) => (name, params),
+            None => (name, ""),
+        };
+        let name = name.to_ascii_uppercase();
+        if name == "BEGIN" && value.eq_ignore_ascii_case("VEVENT") {
+            event = Some(Event::default());
+            continue;
+        }
+        let current = match event.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        match name.as_str() {
+            "DTSTART" => current.starts_at = parse_time(params, value),
+            "SUMMARY" => current.title = unescape(value),
+            "DESCRIPTION" => current.description = unescape(value),
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                let code = find_code(&current.description).or_else(|| find_code(&current.title));
+                if let (Some(starts_at), Some(code)) = (current.starts_at, code) {
+                    sessions.push(CalendarSession {
+                        title: std::mem::take(&mut current.title),
+                        code,
+                        starts_at,
+                    });
+                }
+                event = None;
+            }
+            _ => {}
+        }
+    }
+    sessions
+}
+
+// Long lines continue on lines starting with a space or tab (RFC 5545 section 3.1)
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+// "20240501T123000Z" in UTC, or without the Z in local time; all-day dates don't start sessions
+fn parse_time(params: &str, value: &str) -> Option<u64> {
+    if params.to_ascii_uppercase().contains("VALUE=DATE") && !params.contains("DATE-TIME") {
+        return None;
+    }
+    let value = value.trim();
+    let (local, value) = match value.strip_suffix('Z') {
+        Some(value) => (false, value),
+        None => (true, value),
+    };
+    let (date, time) = value.split_once('T')?;
+    if date.len() != 8
+        || time.len() != 6
+        || !date.chars().chain(time.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let iso = format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    );
+    let millis = clock::parse_iso_timestamp(&iso)?;
+    if !local {
+        return Some(millis);
+    }
+    let offset = clock::local_offset_secs(millis) * 1000;
+    u64::try_from(millis as i64 - offset).ok()
+}
+
+fn is_code(word: &str) -> bool {
+    word.len() == CODE_LENGTH
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+// A jutukuva://join/CODE link, or a code following one of CODE_LABELS
+fn find_code(text: &str) -> Option<String> {
+    if let Some((_, rest)) = text.split_once("jutukuva://join/") {
+        let code: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if is_code(&code) {
+            return Some(code);
+        }
+    }
+
+    // ASCII only, so the positions match `text`
+    let lower = text.to_ascii_lowercase();
+    CODE_LABELS.iter().find_map(|label| {
+        let start = lower.find(label)? + label.len();
+        let word = text
+            .get(start..)?
+            .split_whitespace()
+            .next()?
+            .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+            .to_ascii_uppercase();
+        is_code(&word).then_some(word)
+    })
+}
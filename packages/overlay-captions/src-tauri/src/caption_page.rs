@@ -0,0 +1,395 @@
+// Plain web page showing the current caption, for screens that can open a URL but can't run
+// the app: Chromecasts (see cast.rs), DLNA renderers (see dlna.rs) and smart TV browsers.
+// Served over HTTP on `settings.caption_page.port` once something needs it:
+//   GET /              the page, styled like the overlay; polls /caption.json
+//   GET /tv            the same without scripts, reloading itself, for older TV browsers
+//   GET /caption.json  the latest caption as sent to the overlay (see CaptionPayload)
+// Stages after processors/markup.rs (speaker names, learned corrections, review edits,
+// scripts and plugins) can put raw text in the caption, so neither page takes it as HTML:
+// only the backend's own styling tags are kept, as on the overlay (see CaptionDisplay.svelte),
+// and everything else is text.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::keywords;
+use crate::processors::markup;
+use crate::settings::AppSettings;
+use crate::AppHandle;
+use crate::AppState;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::time::Duration;
+use tauri::Manager;
+
+// How often the listener checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Requests are a single GET; anything longer isn't for us
+const MAX_REQUEST_LEN: usize = 8192;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Jutukuva</title>
+<style>
+html, body { margin: 0; height: 100%; overflow: hidden; background: {background}; }
+body { display: flex; align-items: flex-end; justify-content: center; }
+#caption { box-sizing: border-box; width: 100%; padding: 4vh 5vw; color: {color};
+  font-family: {family}, sans-serif; font-size: {size}px; font-weight: {weight};
+  line-height: {line_height}; text-align: {align}; white-space: pre-line; }
+span.em { text-decoration: underline; }
+span.low { opacity: 0.6; }
+span.b { font-weight: bold; }
+span.i { font-style: italic; }
+</style>
+</head>
+<body>
+<div id="caption"></div>
+<script>
+var caption = document.getElementById('caption');
+var last = null;
+var TAG = /(<\/?(?:em|low|b|i)>|<conf v="[\d.]+">|<\/conf>)/;
+function decode(text) {
+  return text.replace(/&lt;/g, '<').replace(/&gt;/g, '>').replace(/&amp;/g, '&');
+}
+// The backend's styling tags become classes on spans, everything else is text
+function render(text) {
+  var style = { em: false, low: false, b: false, i: false };
+  var parts = text.split(TAG);
+  caption.textContent = '';
+  for (var n = 0; n < parts.length; n++) {
+    var part = parts[n];
+    if (!part || part.indexOf('<conf') === 0 || part === '</conf>') continue;
+    if (TAG.test(part)) {
+      style[part.replace(/[<>\/]/g, '')] = part.charAt(1) !== '/';
+      continue;
+    }
+    var span = document.createElement('span');
+    span.textContent = decode(part);
+    span.className = ['em', 'low', 'b', 'i'].filter(function (name) { return style[name]; }).join(' ');
+    caption.appendChild(span);
+  }
+}
+function poll() {
+  var request = new XMLHttpRequest();
+  request.open('GET', '/caption.json?' + Date.now());
+  request.onload = function () {
+    try {
+      var payload = JSON.parse(request.responseText);
+      if (payload.text !== last) {
+        last = payload.text;
+        render(payload.text);
+        caption.lang = payload.language || '';
+        caption.dir = payload.direction || 'auto';
+      }
+    } catch (e) {}
+    setTimeout(poll, 500);
+  };
+  request.onerror = function () { setTimeout(poll, 2000); };
+  request.send();
+}
+poll();
+</script>
+</body>
+</html>
+"#;
+
+// Older TV browsers often have no working XMLHttpRequest; they get the caption in the page
+const TV_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="{refresh}">
+<title>Jutukuva</title>
+<style>
+html, body { margin: 0; height: 100%; overflow: hidden; background: {background}; }
+#caption { position: absolute; left: 0; right: 0; bottom: 0; padding: 4% 5%; color: {color};
+  font-family: {family}, sans-serif; font-size: {size}px; font-weight: {weight};
+  line-height: {line_height}; text-align: {align}; white-space: pre-line; }
+em { font-style: normal; text-decoration: underline; }
+</style>
+</head>
+<body>
+<div id="caption" lang="{language}" dir="{direction}">{text}</div>
+</body>
+</html>
+"#;
+
+// Seconds between reloads of the TV page; shorter makes old TVs flicker
+const TV_REFRESH_SECONDS: u32 = 2;
+// Tags the TV page keeps; <low> and <conf> aren't markup its CSS knows, and old browsers may
+// render unknown tags oddly
+const TV_TAGS: [&str; 6] = ["<em>", "</em>", "<b>", "</b>", "<i>", "</i>"];
+const TV_DROPPED_TAGS: [&str; 3] = ["<low>", "</low>", "</conf>"];
+
+#[derive(Default)]
+pub struct CaptionPageState {
+    // Bumped to stop the running server, e.g. when the port changes
+    generation: u64,
+    // Port being served on, None while stopped
+    port: Option<u16>,
+    caption: Option<Value>,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .caption_page
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Serve the page if it isn't served yet; returns the port
+pub fn start(app: &AppHandle) -> Result<u16, String> {
+    let state = app.state::<AppState>();
+    let port = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .caption_page
+        .port;
+    let mut page = state.caption_page.lock().map_err(|e| e.to_string())?;
+    if let Some(port) = page.port {
+        return Ok(port);
+    }
+
+    // Bound here so that a port in use is reported to whoever wanted the page
+    let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    page.generation += 1;
+    page.port = Some(port);
+    let generation = page.generation;
+    log::info!("Caption page served on port {}", port);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&app, listener, generation) {
+            log::error!("Caption page server on port {} failed: {}", port, e);
+        }
+    });
+    Ok(port)
+}
+
+// Move a running server to the port now in settings
+pub fn restart(app: &AppHandle) {
+    let was_running = match app.state::<AppState>().caption_page.lock() {
+        Ok(mut page) => {
+            page.generation += 1;
+            page.port.take().is_some()
+        }
+        Err(_) => return,
+    };
+    if was_running {
+        // The old listener lets go of its port on its next poll
+        std::thread::sleep(POLL_INTERVAL * 2);
+        if let Err(e) = start(app) {
+            log::error!("Failed to restart caption page server: {}", e);
+        }
+    }
+}
+
+fn serve(app: &AppHandle, listener: TcpListener, generation: u64) -> Result<(), String> {
+    while is_current(app, generation) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(e) = handle_request(app, stream) {
+                    log::debug!("Caption page request from {} failed: {}", peer, e);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+// Settings values end up in CSS; keep them from breaking out of their declaration
+fn css_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '<' | '>' | '{' | '}' | ';' | '"'))
+        .collect()
+}
+
+pub fn render_page(settings: &AppSettings) -> String {
+    render_page_template(PAGE, settings)
+}
+
+fn render_page_template(template: &str, settings: &AppSettings) -> String {
+    template
+        .replace(
+            "{background}",
+            &css_value(&settings.overlay.background_color),
+        )
+        .replace("{color}", &css_value(&settings.font.color))
+        .replace("{family}", &css_value(&settings.font.family))
+        .replace("{size}", &settings.font.size.to_string())
+        .replace("{weight}", &settings.font.weight.to_string())
+        .replace("{line_height}", &settings.font.line_height.to_string())
+        .replace("{align}", &css_value(&settings.font.align))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// Caption text as HTML with only `TV_TAGS` left as tags
+fn tv_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        html.push_str(&markup::escape(&unescape(&rest[..start])));
+        rest = &rest[start..];
+        if let Some(tag) = TV_TAGS.iter().find(|tag| rest.starts_with(*tag)) {
+            html.push_str(tag);
+            rest = &rest[tag.len()..];
+        } else if let Some(tag) = TV_DROPPED_TAGS.iter().find(|tag| rest.starts_with(*tag)) {
+            rest = &rest[tag.len()..];
+        } else if let (true, Some(end)) = (rest.starts_with("<conf v=\""), rest.find('>')) {
+            rest = &rest[end + 1..];
+        } else {
+            html.push_str("&lt;");
+            rest = &rest[1..];
+        }
+    }
+    html.push_str(&markup::escape(&unescape(rest)));
+    html
+}
+
+fn render_tv_page(settings: &AppSettings, caption: &Value) -> String {
+    let text = tv_html(caption["text"].as_str().unwrap_or(""));
+    render_page_template(TV_PAGE, settings)
+        .replace("{refresh}", &TV_REFRESH_SECONDS.to_string())
+        .replace(
+            "{language}",
+            &css_value(caption["language"].as_str().unwrap_or("")),
+        )
+        .replace(
+            "{direction}",
+            &css_value(caption["direction"].as_str().unwrap_or("auto")),
+        )
+        .replace("{text}", &text)
+}
+
+// Address of the page as seen from `device`, i.e. on the interface that reaches it
+pub fn url(device: IpAddr, port: u16, path: &str) -> Result<String, String> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+    socket.connect((device, 9)).map_err(|e| e.to_string())?;
+    let local = socket.local_addr().map_err(|e| e.to_string())?.ip();
+    Ok(match local {
+        IpAddr::V4(ip) => format!("http://{}:{}{}", ip, port, path),
+        IpAddr::V6(ip) => format!("http://[{}]:{}{}", ip, port, path),
+    })
+}
+
+// The latest caption, or an empty one before the first
+pub fn current_caption(app: &AppHandle) -> Value {
+    app.state::<AppState>()
+        .caption_page
+        .lock()
+        .ok()
+        .and_then(|page| page.caption.clone())
+        .unwrap_or_else(|| json!({ "text": "" }))
+}
+
+fn handle_request(app: &AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|e| e.to_string())?;
+
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 || request.len() > MAX_REQUEST_LEN {
+            return Err("incomplete request".to_string());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("GET "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or("");
+    let path = path.split('?').next().unwrap_or(path);
+
+    let (status, content_type, body) = match path {
+        "/" => {
+            let settings = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone();
+            ("200 OK", "text/html; charset=utf-8", render_page(&settings))
+        }
+        "/tv" => {
+            let settings = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone();
+            (
+                "200 OK",
+                "text/html; charset=utf-8",
+                render_tv_page(&settings, &current_caption(app)),
+            )
+        }
+        "/caption.json" => (
+            "200 OK",
+            "application/json",
+            current_caption(app).to_string(),
+        ),
+        // For a word cloud display (see keywords.rs)
+        "/keywords.json" => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&keywords::top(app)?).map_err(|e| e.to_string())?,
+        ),
+        _ => ("404 Not Found", "text/plain", "Not found".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Keeps the latest caption for the page
+pub struct CaptionPageOutput;
+
+impl Output for CaptionPageOutput {
+    fn id(&self) -> &str {
+        "caption-page"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        _settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        let state = app.state::<AppState>();
+        let mut page = state.caption_page.lock().map_err(|e| e.to_string())?;
+        page.caption = Some(payload.clone());
+        Ok(())
+    }
+}
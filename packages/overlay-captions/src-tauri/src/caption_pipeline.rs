@@ -0,0 +1,70 @@
+use crate::settings::AppSettings;
+use serde::Serialize;
+
+// What processors know about the caption being processed
+pub struct CaptionContext<'a> {
+    pub settings: &'a AppSettings,
+    // Primary language subtag of the session, e.g. "et"
+    pub language: &'a str,
+    // Words the source scored below the confidence threshold
+    pub low_confidence: &'a [String],
+}
+
+// A stage in the caption pipeline. Every caption passes through the enabled processors in
+// order before it is broadcast to the windows; a processor may rewrite the text or only
+// observe it for side effects.
+pub trait CaptionProcessor: Send {
+    fn name(&self) -> &str;
+
+    // Built-in processors are toggled by their own settings, plugins by `settings.plugins`
+    fn is_builtin(&self) -> bool {
+        true
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool;
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String;
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorInfo {
+    pub name: String,
+    pub builtin: bool,
+    pub enabled: bool,
+}
+
+#[derive(Default)]
+pub struct CaptionPipeline {
+    processors: Vec<Box<dyn CaptionProcessor>>,
+}
+
+impl CaptionPipeline {
+    pub fn add(&mut self, processor: Box<dyn CaptionProcessor>) {
+        log::info!("Registered caption processor: {}", processor.name());
+        self.processors.push(processor);
+    }
+
+    // Drop all plugin processors, keeping the built-in ones
+    pub fn remove_plugins(&mut self) {
+        self.processors.retain(|p| p.is_builtin());
+    }
+
+    pub fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        self.processors
+            .iter_mut()
+            .filter(|p| p.enabled(ctx))
+            .fold(caption, |text, p| p.process(text, ctx))
+    }
+
+    pub fn list(&self, ctx: &CaptionContext) -> Vec<ProcessorInfo> {
+        self.processors
+            .iter()
+            .map(|p| ProcessorInfo {
+                name: p.name().to_string(),
+                builtin: p.is_builtin(),
+                enabled: p.enabled(ctx),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,281 @@
+// Meeting and streaming platforms that take live captions over HTTP, so remote participants
+// of a hybrid meeting or viewers of a livestream get the captions in the platform itself
+// while the room follows the overlay:
+//   "zoom"    - Zoom's closed caption API, with the API token URL from the meeting's closed
+//               caption options
+//   "teams"   - the CART captions URL of a Microsoft Teams meeting
+//   "youtube" - the caption ingestion URL of a YouTube Live stream; each caption carries the
+//               time it was shown, which YouTube lines up with the video
+// The URL goes to the secrets store (see secrets.rs) because anyone holding it can caption
+// the meeting or stream. The latest caption is POSTed as plain text with a sequence number
+// the platform orders captions by, at most once per POST_INTERVAL.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::{AppSettings, CaptionServiceSettings};
+use crate::{clock, diagnostics, network, secrets, text, AppHandle, AppState};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::Manager;
+
+// The platforms drop captions that come in much faster than this
+const POST_INTERVAL: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Service {
+    Zoom,
+    Teams,
+    YouTube,
+}
+
+impl Service {
+    pub const ALL: [Service; 3] = [Service::Zoom, Service::Teams, Service::YouTube];
+
+    pub fn from_id(id: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|service| service.id() == id)
+            .ok_or_else(|| format!("Unknown caption service: {}", id))
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Service::Zoom => "zoom",
+            Service::Teams => "teams",
+            Service::YouTube => "youtube",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Service::Zoom => "Zoom",
+            Service::Teams => "Teams",
+            Service::YouTube => "YouTube",
+        }
+    }
+
+    fn settings(self, settings: &AppSettings) -> &CaptionServiceSettings {
+        match self {
+            Service::Zoom => &settings.zoom,
+            Service::Teams => &settings.teams,
+            Service::YouTube => &settings.youtube,
+        }
+    }
+
+    fn url_secret(self) -> &'static str {
+        match self {
+            Service::Zoom => "zoom.captionUrl",
+            Service::Teams => "teams.captionUrl",
+            Service::YouTube => "youtube.captionUrl",
+        }
+    }
+
+    // YouTube Studio hands out plain http:// ingestion URLs
+    fn accepts_url(self, url: &str) -> bool {
+        match self {
+            Service::YouTube => url.starts_with("https://") || url.starts_with("http://"),
+            _ => url.starts_with("https://"),
+        }
+    }
+
+    fn body(self, caption: &Pending) -> String {
+        match self {
+            Service::YouTube => format!(
+                "{}\n{}\n",
+                clock::iso_timestamp(caption.shown_at),
+                caption.text
+            ),
+            _ => caption.text.clone(),
+        }
+    }
+}
+
+struct Pending {
+    text: String,
+    // Milliseconds since the Unix epoch
+    shown_at: u64,
+}
+
+#[derive(Default)]
+struct Sender {
+    // Bumped to stop the running sender, e.g. when the URL changes
+    generation: u64,
+    // Latest caption not sent yet
+    pending: Option<Pending>,
+    // Last sequence number used with the current URL
+    seq: u64,
+}
+
+#[derive(Default)]
+pub struct CaptionServicesState {
+    senders: HashMap<&'static str, Sender>,
+}
+
+fn with_sender<T>(
+    app: &AppHandle,
+    service: Service,
+    f: impl FnOnce(&mut Sender) -> T,
+) -> Result<T, String> {
+    let state = app.state::<AppState>();
+    let mut services = state.caption_services.lock().map_err(|e| e.to_string())?;
+    Ok(f(services.senders.entry(service.id()).or_default()))
+}
+
+fn is_current(app: &AppHandle, service: Service, generation: u64) -> bool {
+    with_sender(app, service, |s| s.generation == generation).unwrap_or(false)
+}
+
+pub fn has_url(service: Service) -> bool {
+    secrets::get(service.url_secret()).is_some()
+}
+
+// Store the caption URL of a meeting or stream, or forget it with None
+pub fn set_url(app: &AppHandle, service: Service, url: Option<&str>) -> Result<(), String> {
+    let url = url.map(str::trim).filter(|url| !url.is_empty());
+    if let Some(url) = url {
+        if !service.accepts_url(url) {
+            return Err(format!("Not a {} caption URL: {}", service.name(), url));
+        }
+    }
+    secrets::set(service.url_secret(), url)?;
+    // Sequence numbers are per meeting or stream
+    with_sender(app, service, |sender| {
+        sender.seq = 0;
+        sender.pending = None;
+    })?;
+    restart(app, service);
+    Ok(())
+}
+
+pub fn restart_all(app: &AppHandle) {
+    for service in Service::ALL {
+        restart(app, service);
+    }
+}
+
+// Stop the running sender and start a new one if enabled and a URL is set
+pub fn restart(app: &AppHandle, service: Service) {
+    let settings = match app.state::<AppState>().settings.lock() {
+        Ok(s) => service.settings(&s).clone(),
+        Err(_) => return,
+    };
+    let generation = match with_sender(app, service, |s| {
+        s.generation += 1;
+        s.generation
+    }) {
+        Ok(generation) => generation,
+        Err(_) => return,
+    };
+
+    let url = match secrets::get(service.url_secret()) {
+        Some(url) if settings.enabled => url,
+        _ => return,
+    };
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = run(&app, service, &url, &settings.language, generation) {
+            log::error!("{} captions stopped: {}", service.name(), e);
+        }
+    });
+}
+
+fn run(
+    app: &AppHandle,
+    service: Service,
+    url: &str,
+    language: &str,
+    generation: u64,
+) -> Result<(), String> {
+    network::check(app, url)?;
+    let agent = network::agent(REQUEST_TIMEOUT)?;
+    let mut failing = false;
+
+    while is_current(app, service, generation) {
+        std::thread::sleep(POST_INTERVAL);
+        let next = with_sender(app, service, |sender| {
+            let caption = sender.pending.take()?;
+            sender.seq += 1;
+            Some((caption, sender.seq))
+        })?;
+        let (caption, seq) = match next {
+            Some(next) => next,
+            None => continue,
+        };
+
+        match post(&agent, url, seq, language, &service.body(&caption)) {
+            Ok(()) if failing => {
+                log::info!("{} accepts captions again", service.name());
+                failing = false;
+            }
+            Ok(()) => {}
+            // Reported once per outage, not for every caption
+            Err(e) if !failing => {
+                log::warn!("Sending captions to {} failed: {}", service.name(), e);
+                let kind = format!("{} captions failed", service.id());
+                let _ = diagnostics::record(app, &kind, Some(e));
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn post(
+    agent: &ureq::Agent,
+    url: &str,
+    seq: u64,
+    language: &str,
+    body: &str,
+) -> Result<(), String> {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let mut url = format!("{}{}seq={}", url, separator, seq);
+    if !language.is_empty() {
+        url.push_str(&format!("&lang={}", language));
+    }
+    match agent
+        .post(&url)
+        .set("Content-Type", "text/plain")
+        .send_string(body)
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("HTTP {}", code)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Queues the latest caption for a service's sender
+pub struct CaptionServiceOutput(pub Service);
+
+impl Output for CaptionServiceOutput {
+    fn id(&self) -> &str {
+        self.0.id()
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !self.0.settings(settings).enabled {
+            return Ok(());
+        }
+        let caption = text::plain(payload.get("text").and_then(Value::as_str).unwrap_or(""));
+        if caption.trim().is_empty() {
+            return Ok(());
+        }
+        let pending = Pending {
+            text: caption,
+            shown_at: clock::now_millis(),
+        };
+        with_sender(app, self.0, |sender| sender.pending = Some(pending))
+    }
+}
@@ -0,0 +1,465 @@
+// Casting the caption page (see caption_page.rs) to a Chromecast, so a TV in an overflow room
+// shows captions without a PC next to it. Targets are found over mDNS; casting launches the
+// DashCast receiver app on the device and points it at the page, then keeps the Cast
+// session alive until casting stops. A dropped session is picked up again.
+//
+// Only Cast devices are supported: AirPlay receivers play media streams, they can't be
+// told to show a web page.
+//
+// The Cast protocol is protobuf messages over TLS on port 8009; the one message type it
+// needs is encoded by hand below.
+
+use crate::{caption_page, diagnostics, discovery, AppHandle, AppState};
+use native_tls::{TlsConnector, TlsStream};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const SERVICE_TYPE: &str = "_googlecast._tcp.local.";
+
+// Third-party receiver app that shows any URL
+const DASHCAST_APP_ID: &str = "84912283";
+
+const NS_CONNECTION: &str = "urn:x-cast:com.google.cast.tp.connection";
+const NS_HEARTBEAT: &str = "urn:x-cast:com.google.cast.tp.heartbeat";
+const NS_RECEIVER: &str = "urn:x-cast:com.google.cast.receiver";
+const NS_DASHCAST: &str = "urn:x-cast:com.madmod.dashcast";
+const SENDER: &str = "sender-0";
+const RECEIVER: &str = "receiver-0";
+
+// The device drops senders that stay quiet for longer than a few of these
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// Launching the receiver app can take a while on older devices
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(20);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+// Largest message we accept from the device
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CastTarget {
+    pub id: String,
+    pub name: String,
+    pub model: Option<String>,
+    pub address: String,
+    pub port: u16,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CastStatus {
+    // Target being cast to, None when not casting
+    pub target: Option<String>,
+    pub connected: bool,
+}
+
+#[derive(Default)]
+pub struct CastState {
+    // Bumped to end the running session
+    generation: u64,
+    // From the last list_cast_targets, so start_casting can go by id
+    targets: Vec<CastTarget>,
+    target: Option<CastTarget>,
+    connected: bool,
+}
+
+struct CastMessage {
+    source: String,
+    destination: String,
+    namespace: String,
+    payload: String,
+}
+
+type CastStream = TlsStream<TcpStream>;
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_string(buf: &mut Vec<u8>, field: u64, value: &str) {
+    put_varint(buf, (field << 3) | 2);
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+impl CastMessage {
+    // CastMessage { protocol_version = 1: CASTV2_1_0, source_id = 2, destination_id = 3,
+    // namespace = 4, payload_type = 5: STRING, payload_utf8 = 6 }
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        put_varint(&mut body, 1 << 3);
+        put_varint(&mut body, 0);
+        put_string(&mut body, 2, &self.source);
+        put_string(&mut body, 3, &self.destination);
+        put_string(&mut body, 4, &self.namespace);
+        put_varint(&mut body, 5 << 3);
+        put_varint(&mut body, 0);
+        put_string(&mut body, 6, &self.payload);
+
+        let mut frame = (body.len() as u32).to_be_bytes().to_vec();
+        frame.extend(body);
+        frame
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let mut message = CastMessage {
+            source: String::new(),
+            destination: String::new(),
+            namespace: String::new(),
+            payload: String::new(),
+        };
+        let mut pos = 0;
+        while pos < data.len() {
+            let key = read_varint(data, &mut pos)?;
+            match key & 7 {
+                0 => {
+                    read_varint(data, &mut pos)?;
+                }
+                2 => {
+                    let len = read_varint(data, &mut pos)? as usize;
+                    let bytes = data.get(pos..pos.checked_add(len)?)?;
+                    pos += len;
+                    let value = String::from_utf8_lossy(bytes).into_owned();
+                    match key >> 3 {
+                        2 => message.source = value,
+                        3 => message.destination = value,
+                        4 => message.namespace = value,
+                        6 => message.payload = value,
+                        // Binary payloads are never sent to us
+                        _ => {}
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(message)
+    }
+
+    fn json(&self) -> Value {
+        serde_json::from_str(&self.payload).unwrap_or(Value::Null)
+    }
+}
+
+fn send(
+    stream: &mut CastStream,
+    namespace: &str,
+    destination: &str,
+    payload: Value,
+) -> Result<(), String> {
+    let message = CastMessage {
+        source: SENDER.to_string(),
+        destination: destination.to_string(),
+        namespace: namespace.to_string(),
+        payload: payload.to_string(),
+    };
+    stream
+        .write_all(&message.encode())
+        .map_err(|e| e.to_string())
+}
+
+// Next message from the device, None when nothing arrived before the read timeout
+fn receive(stream: &mut CastStream) -> Result<Option<CastMessage>, String> {
+    let mut header = [0u8; 4];
+    match stream.read(&mut header) {
+        Ok(0) => return Err("connection closed by the device".to_string()),
+        Ok(n) => stream
+            .read_exact(&mut header[n..])
+            .map_err(|e| e.to_string())?,
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(format!("message of {} bytes from the device", len));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).map_err(|e| e.to_string())?;
+    CastMessage::decode(&body)
+        .map(Some)
+        .ok_or_else(|| "malformed message from the device".to_string())
+}
+
+// Answer heartbeats; true if the message was one
+fn handle_heartbeat(stream: &mut CastStream, message: &CastMessage) -> Result<bool, String> {
+    if message.namespace != NS_HEARTBEAT {
+        return Ok(false);
+    }
+    if message.json()["type"] == "PING" {
+        send(
+            stream,
+            NS_HEARTBEAT,
+            &message.source,
+            json!({ "type": "PONG" }),
+        )?;
+    }
+    Ok(true)
+}
+
+// Transport and session id of the receiver app in a RECEIVER_STATUS message
+fn running_app(message: &CastMessage) -> Option<(String, String)> {
+    if message.namespace != NS_RECEIVER {
+        return None;
+    }
+    let status = message.json();
+    status["status"]["applications"]
+        .as_array()?
+        .iter()
+        .find(|app| app["appId"] == DASHCAST_APP_ID)
+        .and_then(|app| {
+            Some((
+                app["transportId"].as_str()?.to_string(),
+                app["sessionId"].as_str()?.to_string(),
+            ))
+        })
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .cast
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+pub fn status(app: &AppHandle) -> CastStatus {
+    app.try_state::<AppState>()
+        .and_then(|state| {
+            state.cast.lock().ok().map(|c| CastStatus {
+                target: c.target.as_ref().map(|t| t.name.clone()),
+                connected: c.connected,
+            })
+        })
+        .unwrap_or(CastStatus {
+            target: None,
+            connected: false,
+        })
+}
+
+fn set_connected(app: &AppHandle, generation: u64, connected: bool) {
+    if let Ok(mut cast) = app.state::<AppState>().cast.lock() {
+        if cast.generation != generation || cast.connected == connected {
+            return;
+        }
+        cast.connected = connected;
+    }
+    let _ = app.emit("cast-status-changed", status(app));
+}
+
+// Cast devices on the LAN; blocks while they answer
+pub fn list_targets(app: &AppHandle) -> Result<Vec<CastTarget>, String> {
+    let mut targets: Vec<CastTarget> =
+        discovery::browse(app, SERVICE_TYPE, discovery::BROWSE_TIMEOUT)?
+            .iter()
+            .filter_map(|info| {
+                let address = info.get_addresses_v4().into_iter().next()?.to_string();
+                let id = discovery::instance_name(info, SERVICE_TYPE).to_string();
+                Some(CastTarget {
+                    name: info
+                        .get_property_val_str("fn")
+                        .map(str::to_string)
+                        .unwrap_or_else(|| id.clone()),
+                    model: info.get_property_val_str("md").map(str::to_string),
+                    id,
+                    address,
+                    port: info.get_port(),
+                })
+            })
+            .collect();
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let state = app.state::<AppState>();
+    let mut cast = state.cast.lock().map_err(|e| e.to_string())?;
+    cast.targets = targets.clone();
+    Ok(targets)
+}
+
+pub fn start(app: &AppHandle, id: &str) -> Result<(), String> {
+    let port = caption_page::start(app)?;
+    let (target, generation) = {
+        let state = app.state::<AppState>();
+        let mut cast = state.cast.lock().map_err(|e| e.to_string())?;
+        let target = cast
+            .targets
+            .iter()
+            .find(|t| t.id == id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown cast target: {}", id))?;
+        cast.generation += 1;
+        cast.target = Some(target.clone());
+        cast.connected = false;
+        (target, cast.generation)
+    };
+    let _ = app.emit("cast-status-changed", status(app));
+    log::info!("Casting to {} ({})", target.name, target.address);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while is_current(&app, generation) {
+            if let Err(e) = cast(&app, &target, port, generation) {
+                log::warn!("Casting to {} failed: {}", target.name, e);
+                let _ = diagnostics::record(&app, "casting failed", Some(e));
+            }
+            set_connected(&app, generation, false);
+            if is_current(&app, generation) {
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    });
+    Ok(())
+}
+
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut cast = state.cast.lock().map_err(|e| e.to_string())?;
+        if cast.target.is_none() {
+            return Ok(());
+        }
+        cast.generation += 1;
+        cast.target = None;
+        cast.connected = false;
+    }
+    log::info!("Casting stopped");
+    app.emit("cast-status-changed", status(app))
+        .map_err(|e| e.to_string())
+}
+
+fn cast(app: &AppHandle, target: &CastTarget, port: u16, generation: u64) -> Result<(), String> {
+    let ip: IpAddr = target
+        .address
+        .parse()
+        .map_err(|_| "Invalid address".to_string())?;
+    let url = caption_page::url(ip, port, "/")?;
+
+    let tcp = TcpStream::connect_timeout(&SocketAddr::new(ip, target.port), CONNECT_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    tcp.set_read_timeout(Some(HEARTBEAT_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    // Cast devices present self-signed certificates
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut stream = connector
+        .connect(&target.address, tcp)
+        .map_err(|e| e.to_string())?;
+
+    send(
+        &mut stream,
+        NS_CONNECTION,
+        RECEIVER,
+        json!({ "type": "CONNECT" }),
+    )?;
+    send(
+        &mut stream,
+        NS_RECEIVER,
+        RECEIVER,
+        json!({ "type": "LAUNCH", "appId": DASHCAST_APP_ID, "requestId": 1 }),
+    )?;
+
+    let launched = Instant::now();
+    let (transport, session) = loop {
+        if launched.elapsed() > LAUNCH_TIMEOUT {
+            return Err("the receiver app did not start".to_string());
+        }
+        let message = match receive(&mut stream)? {
+            Some(message) => message,
+            None => continue,
+        };
+        if handle_heartbeat(&mut stream, &message)? {
+            continue;
+        }
+        if message.json()["type"] == "LAUNCH_ERROR" {
+            return Err(format!("launch failed: {}", message.payload));
+        }
+        if let Some(running) = running_app(&message) {
+            break running;
+        }
+    };
+
+    send(
+        &mut stream,
+        NS_CONNECTION,
+        &transport,
+        json!({ "type": "CONNECT" }),
+    )?;
+    send(
+        &mut stream,
+        NS_DASHCAST,
+        &transport,
+        json!({ "url": url, "force": true, "reload": false, "reload_time": 0 }),
+    )?;
+    log::info!("{} shows {}", target.name, url);
+    set_connected(app, generation, true);
+
+    let mut last_ping = Instant::now();
+    while is_current(app, generation) {
+        if last_ping.elapsed() >= HEARTBEAT_INTERVAL {
+            send(
+                &mut stream,
+                NS_HEARTBEAT,
+                RECEIVER,
+                json!({ "type": "PING" }),
+            )?;
+            last_ping = Instant::now();
+        }
+        let message = match receive(&mut stream)? {
+            Some(message) => message,
+            None => continue,
+        };
+        if handle_heartbeat(&mut stream, &message)? {
+            continue;
+        }
+        // Someone else cast to the device or the app was closed on it
+        if message.namespace == NS_RECEIVER
+            && message.json()["type"] == "RECEIVER_STATUS"
+            && running_app(&message).is_none()
+        {
+            return Err("the receiver app was closed".to_string());
+        }
+    }
+
+    let _ = send(
+        &mut stream,
+        NS_RECEIVER,
+        RECEIVER,
+        json!({ "type": "STOP", "sessionId": session, "requestId": 2 }),
+    );
+    let _ = send(
+        &mut stream,
+        NS_CONNECTION,
+        RECEIVER,
+        json!({ "type": "CLOSE" }),
+    );
+    Ok(())
+}
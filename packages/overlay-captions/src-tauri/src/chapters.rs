@@ -0,0 +1,118 @@
+// Chapters of long transcripts, for finding one's way around multi-hour conference
+// archives. A chapter starts at a caption beginning with the marker (`## Coffee break` with
+// the default marker, titled by the rest of it) or after a silence of at least
+// `chapter_silence_secs`, titled by the first words said after it. The exported documents
+// get a heading per chapter, and the chapter list can be pasted into a YouTube description.
+
+use crate::export::{split_speaker, TimedCaption};
+use crate::settings::ExportSettings;
+
+// A silence this soon after the previous chapter started doesn't start another
+const MIN_CHAPTER_MS: u64 = 60_000;
+// YouTube ignores chapter lists with shorter chapters
+const MIN_YOUTUBE_MS: u64 = 10_000;
+// Words of the first caption in a chapter that make its title
+const TITLE_WORDS: usize = 8;
+const MAX_TITLE_CHARS: usize = 60;
+
+pub struct Chapter {
+    // Index of the chapter's first caption
+    pub first: usize,
+    pub start: u64,
+    pub title: String,
+    // The first caption is the marker and only gives the title
+    pub marker: bool,
+}
+
+fn marker_title<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    if marker.trim().is_empty() {
+        return None;
+    }
+    text.trim()
+        .strip_prefix(marker.trim())
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+}
+
+fn words_title(text: &str) -> String {
+    let (_, text) = split_speaker(text);
+    let mut title = String::new();
+    let mut used = 0;
+    for word in text.split_whitespace().take(TITLE_WORDS) {
+        if title.chars().count() + word.chars().count() >= MAX_TITLE_CHARS {
+            break;
+        }
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+        used += 1;
+    }
+    if used < text.split_whitespace().count() {
+        format!("{}…", title.trim_end_matches([',', ';', ':']))
+    } else {
+        title
+    }
+}
+
+pub fn detect(captions: &[TimedCaption], settings: &ExportSettings) -> Vec<Chapter> {
+    let silence = u64::from(settings.chapter_silence_secs) * 1000;
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for (i, caption) in captions.iter().enumerate() {
+        if let Some(title) = marker_title(&caption.text, &settings.chapter_marker) {
+            chapters.push(Chapter {
+                first: i,
+                start: caption.start,
+                title: title.to_string(),
+                marker: true,
+            });
+            continue;
+        }
+        let starts_chapter = match (i.checked_sub(1), chapters.last()) {
+            (None, _) | (_, None) => true,
+            (Some(previous), Some(chapter)) => {
+                silence > 0
+                    && caption.start.saturating_sub(captions[previous].start) >= silence
+                    && caption.start.saturating_sub(chapter.start) >= MIN_CHAPTER_MS
+            }
+        };
+        if starts_chapter {
+            chapters.push(Chapter {
+                first: i,
+                start: caption.start,
+                title: words_title(&caption.text),
+                marker: false,
+            });
+        }
+    }
+    chapters
+}
+
+// "4:05" or "1:04:05"
+fn youtube_time(millis: u64) -> String {
+    let secs = millis / 1000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// One "0:00 Title" line per chapter. YouTube wants the first at 0:00 and each to last ten
+// seconds, so the first is moved to the start and shorter ones are merged into the next.
+pub fn youtube(chapters: &[Chapter]) -> String {
+    let mut lines: Vec<(u64, &str)> = Vec::new();
+    for chapter in chapters {
+        let start = if lines.is_empty() { 0 } else { chapter.start };
+        match lines.last_mut() {
+            Some(last) if start.saturating_sub(last.0) < MIN_YOUTUBE_MS => {
+                *last = (last.0, chapter.title.as_str())
+            }
+            _ => lines.push((start, chapter.title.as_str())),
+        }
+    }
+    lines
+        .iter()
+        .map(|(start, title)| format!("{} {}\n", youtube_time(*start), title))
+        .collect()
+}
@@ -0,0 +1,314 @@
+// Operator check-ins, for regulated events that must show the caption station was attended.
+// With `check_in` enabled, every `interval_mins` of a joined session the operator is asked
+// whether they are there: the tray icon blinks, the main window asks for attention and,
+// outside presentation mode, a notification says so. They answer from the main window or
+// the tray menu within `grace_secs`; otherwise the check-in is missed, which another
+// notification says and `check_in.webhook_url` hears about for the supervisors even in
+// presentation mode. Every prompt is archived with the session along with when it was
+// answered (see storage.rs).
+
+use crate::i18n::{self, tr};
+use crate::settings::CheckInSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{clock, network, presentation, tray, AppHandle, AppState};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::{Emitter, Manager, UserAttentionType};
+use tauri_plugin_notification::NotificationExt;
+
+// How often the tray icon blinks and the deadline is looked at
+const TICK: Duration = Duration::from_millis(700);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+// Dimmed tray icon for blinking, as a share of the full opacity
+const DIM_ALPHA: f32 = 0.25;
+
+#[derive(Default)]
+pub struct CheckInState {
+    // Bumped to stop the running worker
+    generation: u64,
+    // When the unanswered prompt was shown, in milliseconds since the Unix epoch
+    pending: Option<u64>,
+}
+
+// An archived prompt
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckIn {
+    // Milliseconds since the Unix epoch
+    pub prompted_at: u64,
+    // None when it was missed
+    pub answered_at: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckInStatus {
+    // When the unanswered prompt was shown, None when nothing is asked
+    pub pending_since: Option<u64>,
+    // When it will count as missed
+    pub deadline: Option<u64>,
+}
+
+fn settings(app: &AppHandle) -> Result<CheckInSettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.check_in.clone())
+}
+
+fn grace_ms(settings: &CheckInSettings) -> u64 {
+    u64::from(settings.grace_secs.max(10)) * 1000
+}
+
+pub fn status(app: &AppHandle) -> CheckInStatus {
+    let pending = app
+        .state::<AppState>()
+        .check_in
+        .lock()
+        .ok()
+        .and_then(|c| c.pending);
+    let grace = settings(app).map(|s| grace_ms(&s)).unwrap_or_default();
+    CheckInStatus {
+        pending_since: pending,
+        deadline: pending.map(|at| at + grace),
+    }
+}
+
+// Whether the tray menu offers to answer
+pub fn is_pending(app: &AppHandle) -> bool {
+    app.try_state::<AppState>()
+        .and_then(|state| state.check_in.lock().ok().map(|c| c.pending.is_some()))
+        .unwrap_or(false)
+}
+
+fn announce(app: &AppHandle) {
+    if let Err(e) = app.emit("check-in", status(app)) {
+        log::error!("Failed to emit check-in: {}", e);
+    }
+    tray::refresh(app);
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    // Nothing may pop up over the projector output in presentation mode
+    if presentation::is_active(app) {
+        return;
+    }
+    let lang = i18n::current_language(app);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(tr(&lang, title))
+        .body(tr(&lang, body))
+        .show()
+    {
+        log::error!("Failed to show check-in notification: {}", e);
+    }
+}
+
+fn archive(app: &AppHandle, check_in: &CheckIn) {
+    let result = app
+        .state::<AppState>()
+        .storage
+        .lock()
+        .map_err(|e| e.to_string())
+        .and_then(|storage| storage.add_check_in(check_in));
+    if let Err(e) = result {
+        log::error!("Failed to archive check-in: {}", e);
+    }
+}
+
+fn prompt(app: &AppHandle) {
+    let now = clock::now_millis();
+    if let Ok(mut check_in) = app.state::<AppState>().check_in.lock() {
+        check_in.pending = Some(now);
+    }
+    log::info!("Asking the operator to check in");
+    notify(
+        app,
+        "notification.check_in_title",
+        "notification.check_in_body",
+    );
+    if let Some(window) = ManagedWindow::Main.get(app) {
+        let _ = window.request_user_attention(Some(UserAttentionType::Critical));
+    }
+    announce(app);
+}
+
+// The operator is there; Ok without a prompt to answer too
+pub fn acknowledge(app: &AppHandle) -> Result<(), String> {
+    let prompted_at = {
+        let state = app.state::<AppState>();
+        let mut check_in = state.check_in.lock().map_err(|e| e.to_string())?;
+        check_in.pending.take()
+    };
+    let prompted_at = match prompted_at {
+        Some(at) => at,
+        None => return Ok(()),
+    };
+    let answered_at = clock::now_millis();
+    log::info!(
+        "Operator checked in after {} s",
+        answered_at.saturating_sub(prompted_at) / 1000
+    );
+    archive(
+        app,
+        &CheckIn {
+            prompted_at,
+            answered_at: Some(answered_at),
+        },
+    );
+    if let Some(window) = ManagedWindow::Main.get(app) {
+        let _ = window.request_user_attention(None);
+    }
+    announce(app);
+    Ok(())
+}
+
+fn post_webhook(app: &AppHandle, url: &str, prompted_at: u64) -> Result<(), String> {
+    network::check(app, url)?;
+    let session_code = app
+        .state::<AppState>()
+        .session_code
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let body = json!({
+        "event": "check_in_missed",
+        "sessionCode": session_code,
+        "promptedAt": prompted_at,
+        "missedAt": clock::now_millis(),
+    });
+    match network::agent(WEBHOOK_TIMEOUT)?
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("HTTP {}", code)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn missed(app: &AppHandle, settings: &CheckInSettings, prompted_at: u64) {
+    if let Ok(mut check_in) = app.state::<AppState>().check_in.lock() {
+        check_in.pending = None;
+    }
+    log::warn!("The operator missed a check-in");
+    archive(
+        app,
+        &CheckIn {
+            prompted_at,
+            answered_at: None,
+        },
+    );
+    notify(
+        app,
+        "notification.check_in_missed_title",
+        "notification.check_in_missed_body",
+    );
+    announce(app);
+
+    let url = settings.webhook_url.trim().to_string();
+    if url.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = post_webhook(&app, &url, prompted_at) {
+            log::error!("Failed to report the missed check-in: {}", e);
+        }
+    });
+}
+
+// The tray icon at full opacity and dimmed
+fn tray_icons() -> Option<(Image<'static>, Image<'static>)> {
+    let icon = Image::from_bytes(include_bytes!("../icons/32x32.png")).ok()?;
+    let mut rgba = icon.rgba().to_vec();
+    for alpha in rgba.iter_mut().skip(3).step_by(4) {
+        *alpha = (*alpha as f32 * DIM_ALPHA) as u8;
+    }
+    let dimmed = Image::new_owned(rgba, icon.width(), icon.height());
+    Some((icon.to_owned(), dimmed))
+}
+
+fn set_tray_icon(app: &AppHandle, icon: &Image<'static>) {
+    if let Some(tray) = app.tray_by_id(tray::TRAY_ID) {
+        let _ = tray.set_icon(Some(icon.clone()));
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .check_in
+        .lock()
+        .map(|c| c.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running worker and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let settings = match settings(app) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let generation = match app.state::<AppState>().check_in.lock() {
+        Ok(mut c) => {
+            c.generation += 1;
+            // A prompt from before is moot
+            c.pending = None;
+            c.generation
+        }
+        Err(_) => return,
+    };
+    announce(app);
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &CheckInSettings, generation: u64) {
+    let interval = u64::from(settings.interval_mins.max(1)) * 60_000;
+    let grace = grace_ms(settings);
+    let icons = tray_icons();
+    let mut next = clock::now_millis() + interval;
+    let mut dimmed = false;
+
+    while is_current(app, generation) {
+        let now = clock::now_millis();
+        let state = app.state::<AppState>();
+        let in_session = state.session_code.lock().is_ok_and(|code| code.is_some());
+        let pending = state.check_in.lock().ok().and_then(|c| c.pending);
+
+        match pending {
+            Some(prompted_at) if now >= prompted_at + grace => {
+                missed(app, settings, prompted_at);
+                next = now + interval;
+            }
+            // The first prompt comes an interval into the session
+            None if !in_session => next = now + interval,
+            None if now >= next => {
+                prompt(app);
+                next = now + interval;
+            }
+            _ => {}
+        }
+
+        // Blinks while a prompt waits for an answer
+        let dim = is_pending(app) && !dimmed;
+        if dim != dimmed {
+            if let Some((icon, faded)) = &icons {
+                set_tray_icon(app, if dim { faded } else { icon });
+            }
+            dimmed = dim;
+        }
+        std::thread::sleep(TICK);
+    }
+    if let (true, Some((icon, _))) = (dimmed, &icons) {
+        set_tray_icon(app, icon);
+    }
+}
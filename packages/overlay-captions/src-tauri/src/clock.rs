@@ -0,0 +1,184 @@
+// Wall clock times in UTC, for formats that need a calendar date (stream caption timestamps,
+// HLS playlists, S3 request signing) without pulling in a date library. The local time zone
+// is only asked for its offset, from the OS. With the test-hooks feature the clock can be
+// frozen at a fixed time (see test_hooks.rs).
+
+#[cfg(feature = "test-hooks")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Milliseconds the clock is frozen at, 0 while it runs
+#[cfg(feature = "test-hooks")]
+static FROZEN_AT: AtomicU64 = AtomicU64::new(0);
+
+pub struct UtcTime {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+    pub millis: u64,
+}
+
+pub fn now_millis() -> u64 {
+    #[cfg(feature = "test-hooks")]
+    if is_frozen() {
+        return FROZEN_AT.load(Ordering::SeqCst);
+    }
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Timers that wait for the clock hold off while it is frozen
+#[cfg(feature = "test-hooks")]
+pub fn is_frozen() -> bool {
+    FROZEN_AT.load(Ordering::SeqCst) != 0
+}
+
+#[cfg(not(feature = "test-hooks"))]
+pub fn is_frozen() -> bool {
+    false
+}
+
+// Stop the clock at `millis`, or let it run again with None. 0 means running, so the epoch
+// itself freezes a millisecond after it.
+#[cfg(feature = "test-hooks")]
+pub fn freeze(millis: Option<u64>) {
+    let frozen_at = millis.map(|m| m.max(1)).unwrap_or(0);
+    FROZEN_AT.store(frozen_at, Ordering::SeqCst);
+}
+
+// `millis` since the Unix epoch as a UTC date and time
+pub fn utc(millis: u64) -> UtcTime {
+    let secs = millis / 1000;
+    let time = secs % 86_400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    UtcTime {
+        year: yoe + era * 400 + i64::from(month <= 2),
+        month,
+        day: doy - (153 * mp + 2) / 5 + 1,
+        hour: time / 3600,
+        minute: time % 3600 / 60,
+        second: time % 60,
+        millis: millis % 1000,
+    }
+}
+
+// Seconds the local time zone is ahead of UTC at `millis`
+#[cfg(unix)]
+pub fn local_offset_secs(millis: u64) -> i64 {
+    let secs = (millis / 1000) as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it is given
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+// Windows only tells the offset in effect now
+#[cfg(windows)]
+pub fn local_offset_secs(_millis: u64) -> i64 {
+    use windows_sys::Win32::System::Time::{GetTimeZoneInformation, TIME_ZONE_INFORMATION};
+    // From winbase.h
+    const TIME_ZONE_ID_STANDARD: u32 = 1;
+    const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+    // SAFETY: GetTimeZoneInformation only writes the struct it is given
+    let (id, info) = unsafe {
+        let mut info: TIME_ZONE_INFORMATION = std::mem::zeroed();
+        (GetTimeZoneInformation(&mut info), info)
+    };
+    // Minutes behind UTC
+    let bias = match id {
+        TIME_ZONE_ID_STANDARD => info.Bias + info.StandardBias,
+        TIME_ZONE_ID_DAYLIGHT => info.Bias + info.DaylightBias,
+        _ => info.Bias,
+    };
+    -i64::from(bias) * 60
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn local_offset_secs(_millis: u64) -> i64 {
+    0
+}
+
+// Minutes since local midnight
+pub fn local_minute_of_day() -> u32 {
+    let millis = now_millis();
+    let local_secs = (millis / 1000) as i64 + local_offset_secs(millis);
+    (local_secs.rem_euclid(86_400) / 60) as u32
+}
+
+// "2024-05-01T12:30:05.250", without a zone designator
+pub fn iso_timestamp(millis: u64) -> String {
+    let t = utc(millis);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}",
+        t.year, t.month, t.day, t.hour, t.minute, t.second, t.millis
+    )
+}
+
+// Days since 1970-01-01 of a civil date, the inverse of the calculation in `utc`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468
+}
+
+// "2024-05-01T12:30:05.250+03:00" or "...Z" as milliseconds since the Unix epoch. Seconds
+// and their fraction may be left out; the zone may not, a bare time could be anywhere.
+pub fn parse_iso_timestamp(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (date, rest) = text.split_once(['T', ' '])?;
+    let (time, zone_minutes) = match rest.strip_suffix('Z') {
+        Some(time) => (time, 0),
+        None => {
+            let split = rest.rfind(['+', '-'])?;
+            let (hours, minutes) = rest[split + 1..].split_once(':')?;
+            let minutes = hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?;
+            let sign = if rest[split..].starts_with('-') {
+                -1
+            } else {
+                1
+            };
+            (&rest[..split], sign * minutes)
+        }
+    };
+
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut time = time.splitn(3, ':');
+    let hour = time.next()?.parse::<i64>().ok()?;
+    let minute = time.next()?.parse::<i64>().ok()?;
+    let second = match time.next() {
+        Some(second) => second.parse::<f64>().ok()?,
+        None => 0.0,
+    };
+    if hour > 23 || minute > 59 || !(0.0..60.0).contains(&second) {
+        return None;
+    }
+
+    let millis = (days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60
+        - zone_minutes * 60)
+        * 1000
+        + (second * 1000.0).round() as i64;
+    u64::try_from(millis).ok()
+}
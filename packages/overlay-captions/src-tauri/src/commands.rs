@@ -1,12 +1,113 @@
-use crate::settings::{self, AppSettings, OverlaySettings};
-use crate::window_manager;
+use crate::arbitration;
+use crate::audio_monitor::{self, AudioDevice};
+use crate::audio_uplink::{self, AudioUplinkStatus};
+use crate::ble_broadcast;
+use crate::bookmarks::{self, Bookmark};
+use crate::braille;
+use crate::browser_bridge;
+use crate::calendar;
+use crate::caption_page;
+use crate::caption_pipeline::{CaptionContext, ProcessorInfo};
+use crate::caption_services::{self, Service};
+use crate::cast::{self, CastStatus, CastTarget};
+use crate::check_in::{self, CheckIn, CheckInStatus};
+use crate::compact::{self, CompactPayload};
+use crate::compositor;
+use crate::context_menu;
+use crate::contrast;
+use crate::corrections::{self, CorrectionCandidates};
+use crate::diagnostics::{self, DiagnosticsSnapshot};
+use crate::discovery::{self, RelayInfo};
+use crate::display_delay;
+use crate::dlna::{self, DlnaRenderer};
+use crate::drag_region::{self, DragRect};
+use crate::event_router::{self, OutputInfo};
+use crate::exit_protection;
+use crate::export;
+use crate::failover::{self, ActiveServer};
+use crate::focus_follow;
+use crate::footswitch::{self, HidDeviceEntry};
+use crate::history::{self, HistoryEntry};
+use crate::hls_captions;
+use crate::hot_corner;
+use crate::i18n;
+use crate::info_line;
+use crate::keep_awake;
+use crate::keywords::{self, Keyword};
+use crate::kiosk::{self, KioskStatus};
+use crate::lan_session::{self, LanSession};
+use crate::language_detection;
+use crate::layout::{self, OverlayLayout};
+use crate::light_alert::{self, HueLight, Trigger};
+use crate::lower_third;
+use crate::macros;
+use crate::meetings;
+use crate::midi;
+use crate::network;
+use crate::network_quality;
+use crate::pause;
+use crate::plugins;
+use crate::power;
+use crate::presentation;
+use crate::processors::confidence::{self, ScoredCaption};
+use crate::processors::markup;
+use crate::processors::speaker_mute;
+use crate::reading_speed;
+use crate::relay;
+use crate::remote_control;
+use crate::remote_support::{self, RemoteSupportStatus};
+use crate::replay;
+use crate::review::{self, PendingCaption};
+use crate::s3;
+use crate::self_test::{self, SelfTestReport};
+use crate::serial_display;
+use crate::server_url;
+use crate::session_clock;
+use crate::settings::{AppSettings, ChannelStyle, LayoutRegion, OverlaySettings, SafeMargins};
+use crate::settings_writer;
+use crate::signatures::{self, SignedParagraph, Verdict};
+use crate::speaker_colors::{self, SpeakerColor};
+use crate::speaker_names::{self, SpeakerName};
+use crate::storage::{
+    self, ArchivedSession, DateRange, SearchHit, SessionTranscript, UsageStatistics,
+};
+use crate::summary;
+use crate::text;
+use crate::theme;
+use crate::touch::{self, TouchGesture};
+use crate::training::{self, TrainingStatus};
+use crate::tray;
+use crate::viewer::{self, ViewerStatus};
+use crate::watermark;
+use crate::window_manager::{self, ManagedWindow, OverlayId};
+use crate::AppHandle;
 use crate::AppState;
+use crate::WebviewWindow;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter, Manager, State};
+use std::path::Path;
+use tauri::{Emitter, Manager, State};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CaptionPayload {
     pub text: String,
+    // Lowest word confidence in the caption, when the source provides scores
+    pub confidence: Option<f32>,
+    // ISO 639-1 code detected from the caption text, when language detection is on
+    #[serde(default)]
+    pub language: Option<String>,
+    // "ltr" or "rtl" as set in the font settings or found in the text
+    #[serde(default)]
+    pub direction: Option<String>,
+    // Failed signature verification (see signatures.rs)
+    #[serde(default)]
+    pub unverified: bool,
+    // Colours of the speakers named in the caption (see speaker_colors.rs)
+    #[serde(default)]
+    pub speakers: Vec<SpeakerColor>,
+    // The caption with muted speakers left in, for the logs only; never serialized, so it
+    // can't reach a window or a public output
+    #[serde(skip)]
+    pub record: Option<String>,
 }
 
 // Settings commands
@@ -17,27 +118,450 @@ pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
 }
 
 #[tauri::command]
-pub fn save_settings(
+pub async fn save_settings(
+    app: AppHandle,
     state: State<'_, AppState>,
-    new_settings: AppSettings,
+    mut new_settings: AppSettings,
+    // The exit PIN, when `exit_protection` changes while it is in force
+    pin: Option<String>,
 ) -> Result<(), String> {
-    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
-    *settings = new_settings.clone();
-    settings::save_settings(&new_settings)
+    kiosk::check(&app)?;
+    let protection_changed = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .exit_protection
+        .enabled
+        != new_settings.exit_protection.enabled;
+    if protection_changed {
+        exit_protection::verify(&app, pin.as_deref())?;
+    }
+    let (
+        language_changed,
+        operator_tools_toggled,
+        macros_changed,
+        remote_control_changed,
+        midi_changed,
+        footswitch_changed,
+        network_quality_changed,
+        history_changed,
+        relay_changed,
+        caption_page_changed,
+        serial_display_changed,
+        braille_changed,
+        caption_services_changed,
+        hls_captions_changed,
+        audio_monitor_changed,
+        audio_uplink_changed,
+        lan_session_changed,
+        ble_broadcast_changed,
+        display_delay_shortcuts_changed,
+        clock_changed,
+        contrast_changed,
+        theme_changed,
+        power_changed,
+        keep_awake_changed,
+        hot_corner_changed,
+        compact_shortcut_changed,
+        focus_follow_changed,
+        meeting_detection_changed,
+        calendar_changed,
+        browser_bridge_changed,
+        replay_shortcut_changed,
+        bookmark_shortcut_changed,
+        keywords_changed,
+        offline_only_changed,
+        servers_changed,
+        dns_over_https_changed,
+        check_in_changed,
+        arbitration_changed,
+        plugins_changed,
+    ) = {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let changed = settings.ui_language != new_settings.ui_language;
+        let tools_toggled = settings.review.enabled != new_settings.review.enabled
+            || settings.operator_overlay.enabled != new_settings.operator_overlay.enabled;
+        let macros_changed = settings.macros != new_settings.macros;
+        let remote_control_changed = settings.remote_control != new_settings.remote_control;
+        let midi_changed = settings.midi != new_settings.midi;
+        let footswitch_changed = settings.footswitch != new_settings.footswitch;
+        let network_quality_changed = settings.network_quality != new_settings.network_quality;
+        let history_changed = settings.caption_history != new_settings.caption_history;
+        let relay_changed = settings.relay != new_settings.relay;
+        let caption_page_changed = settings.caption_page != new_settings.caption_page;
+        let serial_display_changed = settings.serial_display != new_settings.serial_display;
+        let braille_changed = settings.braille != new_settings.braille;
+        let caption_services_changed = settings.zoom != new_settings.zoom
+            || settings.teams != new_settings.teams
+            || settings.youtube != new_settings.youtube;
+        let hls_captions_changed = settings.hls_captions != new_settings.hls_captions;
+        let audio_monitor_changed = settings.audio_monitor != new_settings.audio_monitor;
+        let audio_uplink_changed = settings.audio_uplink != new_settings.audio_uplink;
+        let lan_session_changed = settings.lan_session != new_settings.lan_session;
+        let ble_broadcast_changed = settings.ble_broadcast != new_settings.ble_broadcast;
+        // The delay itself is read as captions pass
+        let display_delay_shortcuts_changed = settings.display_delay.later_shortcut
+            != new_settings.display_delay.later_shortcut
+            || settings.display_delay.sooner_shortcut != new_settings.display_delay.sooner_shortcut
+            || settings.display_delay.step_ms != new_settings.display_delay.step_ms;
+        let clock_changed = settings.clock != new_settings.clock;
+        let contrast_changed = settings.contrast != new_settings.contrast;
+        let theme_changed =
+            settings.theme != new_settings.theme || settings.themes != new_settings.themes;
+        let power_changed = settings.power != new_settings.power;
+        let keep_awake_changed = settings.keep_awake != new_settings.keep_awake;
+        let hot_corner_changed = settings.hot_corner != new_settings.hot_corner;
+        let compact_shortcut_changed = settings.compact.shortcut != new_settings.compact.shortcut;
+        let focus_follow_changed = settings.focus_follow != new_settings.focus_follow;
+        let meeting_detection_changed =
+            settings.meeting_detection != new_settings.meeting_detection;
+        let calendar_changed = settings.calendar != new_settings.calendar;
+        let browser_bridge_changed = settings.browser_bridge != new_settings.browser_bridge;
+        let replay_shortcut_changed = settings.replay.shortcut != new_settings.replay.shortcut
+            || settings.replay.slow_shortcut != new_settings.replay.slow_shortcut;
+        let bookmark_shortcut_changed =
+            settings.bookmarks.shortcut != new_settings.bookmarks.shortcut;
+        let keywords_changed = settings.keywords != new_settings.keywords;
+        let offline_only_changed = settings.offline_only != new_settings.offline_only;
+        let servers_changed =
+            failover::servers(&settings.connection) != failover::servers(&new_settings.connection);
+        // Servers already saved stay as they are, so other settings can still be changed
+        if servers_changed {
+            server_url::validate(&mut new_settings.connection)?;
+        }
+        let dns_over_https_changed = settings.dns_over_https != new_settings.dns_over_https;
+        let check_in_changed = settings.check_in != new_settings.check_in;
+        let arbitration_changed = settings.arbitration != new_settings.arbitration;
+        let plugins_changed = settings.plugins != new_settings.plugins;
+        *settings = new_settings;
+        (
+            changed,
+            tools_toggled,
+            macros_changed,
+            remote_control_changed,
+            midi_changed,
+            footswitch_changed,
+            network_quality_changed,
+            history_changed,
+            relay_changed,
+            caption_page_changed,
+            serial_display_changed,
+            braille_changed,
+            caption_services_changed,
+            hls_captions_changed,
+            audio_monitor_changed,
+            audio_uplink_changed,
+            lan_session_changed,
+            ble_broadcast_changed,
+            display_delay_shortcuts_changed,
+            clock_changed,
+            contrast_changed,
+            theme_changed,
+            power_changed,
+            keep_awake_changed,
+            hot_corner_changed,
+            compact_shortcut_changed,
+            focus_follow_changed,
+            meeting_detection_changed,
+            calendar_changed,
+            browser_bridge_changed,
+            replay_shortcut_changed,
+            bookmark_shortcut_changed,
+            keywords_changed,
+            offline_only_changed,
+            servers_changed,
+            dns_over_https_changed,
+            check_in_changed,
+            arbitration_changed,
+            plugins_changed,
+        )
+    };
+    settings_writer::mark_dirty(&app);
+
+    if macros_changed {
+        macros::register_shortcuts(&app);
+    }
+    if remote_control_changed {
+        remote_control::restart(&app);
+    }
+    if midi_changed {
+        midi::restart(&app);
+    }
+    if footswitch_changed {
+        footswitch::restart(&app);
+    }
+    if network_quality_changed {
+        network_quality::restart(&app);
+    }
+    if history_changed {
+        history::restart(&app);
+    }
+    if relay_changed {
+        relay::restart(&app);
+    }
+    if caption_page_changed {
+        caption_page::restart(&app);
+    }
+    if serial_display_changed {
+        serial_display::restart(&app);
+    }
+    if braille_changed {
+        braille::restart(&app);
+    }
+    if caption_services_changed || offline_only_changed {
+        caption_services::restart_all(&app);
+    }
+    if hls_captions_changed || offline_only_changed {
+        hls_captions::restart(&app);
+    }
+    if audio_monitor_changed {
+        audio_monitor::restart(&app);
+    }
+    if audio_uplink_changed || offline_only_changed {
+        audio_uplink::restart(&app);
+    }
+    if lan_session_changed {
+        lan_session::restart(&app);
+    }
+    if ble_broadcast_changed {
+        ble_broadcast::restart(&app);
+    }
+    if display_delay_shortcuts_changed {
+        display_delay::register_shortcuts(&app);
+    }
+    if clock_changed {
+        session_clock::restart(&app);
+    }
+    if contrast_changed {
+        contrast::restart(&app);
+    }
+    if theme_changed {
+        theme::restart(&app);
+    }
+    if power_changed {
+        power::restart(&app);
+    }
+    if keep_awake_changed {
+        keep_awake::restart(&app);
+    }
+    if hot_corner_changed {
+        hot_corner::restart(&app);
+    }
+    if compact_shortcut_changed {
+        compact::register_shortcut(&app);
+    }
+    if focus_follow_changed {
+        focus_follow::restart(&app);
+    }
+    if meeting_detection_changed {
+        meetings::restart(&app);
+    }
+    if calendar_changed || offline_only_changed {
+        calendar::restart(&app);
+    }
+    if browser_bridge_changed {
+        browser_bridge::restart(&app);
+    }
+    if replay_shortcut_changed {
+        replay::register_shortcuts(&app);
+    }
+    if bookmark_shortcut_changed {
+        bookmarks::register_shortcut(&app);
+    }
+    if keywords_changed {
+        keywords::restart(&app);
+    }
+    if servers_changed {
+        failover::reset(&app);
+    }
+    if dns_over_https_changed || offline_only_changed || servers_changed {
+        network::configure(&app);
+    }
+    if check_in_changed {
+        check_in::restart(&app);
+    }
+    if arbitration_changed {
+        arbitration::restart(&app);
+    }
+    if plugins_changed {
+        plugins::reload(&app)?;
+    }
+    // Integrations reconnect through the policy; remote support is started by hand
+    if offline_only_changed && state.settings.lock().is_ok_and(|s| s.offline_only) {
+        remote_support::stop(&app)?;
+    }
+
+    if language_changed {
+        ui_language_changed(&app);
+    } else if operator_tools_toggled {
+        // Operator tool entries are only in the tray menu while enabled
+        tray::refresh(&app);
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub fn reset_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
-    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
-    *settings = AppSettings::default();
-    settings::save_settings(&settings)?;
-    Ok(settings.clone())
+pub async fn reset_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    pin: Option<String>,
+) -> Result<AppSettings, String> {
+    kiosk::check(&app)?;
+    // The defaults have no exit protection
+    exit_protection::verify(&app, pin.as_deref())?;
+    let reset = {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        *settings = AppSettings::default();
+        settings.clone()
+    };
+    settings_writer::mark_dirty(&app);
+
+    ui_language_changed(&app);
+    macros::register_shortcuts(&app);
+    remote_control::restart(&app);
+    midi::restart(&app);
+    footswitch::restart(&app);
+    network_quality::restart(&app);
+    history::restart(&app);
+    relay::restart(&app);
+    caption_page::restart(&app);
+    serial_display::restart(&app);
+    braille::restart(&app);
+    caption_services::restart_all(&app);
+    hls_captions::restart(&app);
+    audio_monitor::restart(&app);
+    audio_uplink::restart(&app);
+    lan_session::restart(&app);
+    ble_broadcast::restart(&app);
+    display_delay::register_shortcuts(&app);
+    session_clock::restart(&app);
+    contrast::restart(&app);
+    theme::restart(&app);
+    power::restart(&app);
+    keep_awake::restart(&app);
+    hot_corner::restart(&app);
+    compact::register_shortcut(&app);
+    focus_follow::restart(&app);
+    meetings::restart(&app);
+    calendar::restart(&app);
+    browser_bridge::restart(&app);
+    replay::register_shortcuts(&app);
+    bookmarks::register_shortcut(&app);
+    keywords::restart(&app);
+    failover::reset(&app);
+    network::configure(&app);
+    check_in::restart(&app);
+    arbitration::restart(&app);
+    plugins::reload(&app)?;
+    Ok(reset)
+}
+
+// Session language declared in the session metadata, None when it is unknown
+#[tauri::command]
+pub fn set_session_language(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    language: Option<String>,
+) -> Result<(), String> {
+    {
+        let mut session_language = state.session_language.lock().map_err(|e| e.to_string())?;
+        if *session_language == language {
+            return Ok(());
+        }
+        *session_language = language.clone();
+    }
+    storage::set_language(&app, language.as_deref())?;
+
+    ui_language_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_ui_language(app: AppHandle) -> Result<String, String> {
+    Ok(i18n::current_language(&app))
+}
+
+// Refresh backend-produced strings and tell the windows which language is in effect
+fn ui_language_changed(app: &AppHandle) {
+    tray::refresh(app);
+    let _ = app.emit("ui-language-changed", i18n::current_language(app));
+}
+
+// Apply a backend-initiated settings change, persist it and notify all windows
+pub fn apply_settings_change<F>(app: &AppHandle, change: F) -> Result<AppSettings, String>
+where
+    F: FnOnce(&mut AppSettings),
+{
+    let state = app.state::<AppState>();
+    let updated = {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        change(&mut settings);
+        settings.clone()
+    };
+    settings_writer::mark_dirty(app);
+
+    app.emit("settings-changed", &updated)
+        .map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+pub const FONT_SIZE_STEP: i32 = 2;
+pub const FONT_SIZE_MIN: u32 = 16;
+pub const FONT_SIZE_MAX: u32 = 96;
+
+// Grow or shrink the caption font, staying within the range offered in the settings drawer
+pub fn step_font_size(app: &AppHandle, delta: i32) -> Result<(), String> {
+    apply_settings_change(app, |settings| {
+        let size = settings.font.size as i32 + delta;
+        settings.font.size = (size.max(0) as u32).clamp(FONT_SIZE_MIN, FONT_SIZE_MAX);
+    })?;
+    Ok(())
+}
+
+pub fn set_font_size(app: &AppHandle, size: u32) -> Result<(), String> {
+    apply_settings_change(app, |settings| {
+        settings.font.size = size.clamp(FONT_SIZE_MIN, FONT_SIZE_MAX);
+    })?;
+    Ok(())
+}
+
+// Secondary channels such as the info line may be smaller than captions ever are
+const CHANNEL_FONT_SIZE_MIN: u32 = 8;
+
+// Style a router channel apart from the overlay's font, or go back to it with None
+#[tauri::command]
+pub fn set_channel_style(
+    app: AppHandle,
+    channel: String,
+    style: Option<ChannelStyle>,
+) -> Result<(), String> {
+    kiosk::check(&app)?;
+    if !event_router::CHANNELS.contains(&channel.as_str()) {
+        return Err(format!("Unknown channel: {}", channel));
+    }
+    let style = style.map(|style| ChannelStyle {
+        size: style
+            .size
+            .map(|size| size.clamp(CHANNEL_FONT_SIZE_MIN, FONT_SIZE_MAX)),
+        ..style
+    });
+    apply_settings_change(&app, |settings| match style {
+        Some(style) => {
+            settings.channel_styles.insert(channel, style);
+        }
+        None => {
+            settings.channel_styles.remove(&channel);
+        }
+    })?;
+    Ok(())
 }
 
-// Helper function to create overlay window on a separate thread (avoids WebView2 deadlock)
+// Create the overlay window in a background task (avoids WebView2 deadlock)
 fn spawn_create_overlay(app: AppHandle, overlay_settings: OverlaySettings) {
-    std::thread::spawn(move || {
-        if let Err(e) = window_manager::create_overlay_window(&app, &overlay_settings) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) =
+            window_manager::create_overlay_window(&app, OverlayId::Public, &overlay_settings)
+        {
             log::error!("Failed to create overlay window: {}", e);
         }
     });
@@ -52,11 +576,11 @@ pub async fn show_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<
     };
 
     // Create or show the overlay window (keep main window open)
-    if app.get_webview_window("overlay").is_none() {
-        // Spawn window creation on separate thread to avoid WebView2 deadlock
+    if !ManagedWindow::OVERLAY.is_open(&app) {
+        // Spawn window creation in a task to avoid WebView2 deadlock
         spawn_create_overlay(app.clone(), overlay_settings);
     } else {
-        window_manager::show_overlay_window(&app)?;
+        ManagedWindow::OVERLAY.show(&app)?;
     }
 
     // Update state
@@ -69,8 +593,13 @@ pub async fn show_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<
 }
 
 #[tauri::command]
-pub fn hide_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    window_manager::hide_overlay_window(&app)?;
+pub async fn hide_overlay(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    exit_protection::check(&app, "hideOverlay", pin.as_deref())?;
+    ManagedWindow::OVERLAY.hide(&app)?;
 
     let mut overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
     *overlay_visible = false;
@@ -79,8 +608,13 @@ pub fn hide_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<(), St
 }
 
 #[tauri::command]
-pub fn close_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    window_manager::close_overlay_window(&app)?;
+pub async fn close_overlay(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    exit_protection::check(&app, "hideOverlay", pin.as_deref())?;
+    ManagedWindow::OVERLAY.close(&app)?;
 
     let mut overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
     *overlay_visible = false;
@@ -89,14 +623,18 @@ pub fn close_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<(), S
 }
 
 #[tauri::command]
-pub async fn toggle_overlay(app: AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn toggle_overlay(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    pin: Option<String>,
+) -> Result<bool, String> {
     let is_visible = {
         let overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
         *overlay_visible
     };
 
     if is_visible {
-        hide_overlay(app, state)?;
+        hide_overlay(app, state, pin).await?;
         Ok(false)
     } else {
         show_overlay(app, state).await?;
@@ -105,20 +643,82 @@ pub async fn toggle_overlay(app: AppHandle, state: State<'_, AppState>) -> Resul
 }
 
 #[tauri::command]
-pub fn set_overlay_position(app: AppHandle, x: i32, y: i32) -> Result<(), String> {
+pub async fn set_overlay_position(app: AppHandle, x: i32, y: i32) -> Result<(), String> {
+    kiosk::check(&app)?;
     window_manager::set_overlay_position(&app, x, y)
 }
 
 #[tauri::command]
-pub fn set_overlay_size(app: AppHandle, width: u32, height: u32) -> Result<(), String> {
+pub async fn set_overlay_size(app: AppHandle, width: u32, height: u32) -> Result<(), String> {
+    kiosk::check(&app)?;
     window_manager::set_overlay_size(&app, width, height)
 }
 
+// Turn the public overlay for a portrait-mounted display; returns the rotation applied
+#[tauri::command]
+pub async fn set_overlay_rotation(app: AppHandle, rotation: u32) -> Result<u32, String> {
+    kiosk::check(&app)?;
+    let rotation = window_manager::normalize_rotation(rotation);
+    let previous = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.overlay.rotation
+    };
+    window_manager::rotate_overlay(&app, previous, rotation)?;
+    apply_settings_change(&app, |settings| settings.overlay.rotation = rotation)?;
+    Ok(rotation)
+}
+
 #[tauri::command]
-pub fn set_click_through(app: AppHandle, enabled: bool) -> Result<(), String> {
+pub async fn set_click_through(app: AppHandle, enabled: bool) -> Result<(), String> {
+    kiosk::check(&app)?;
     window_manager::set_ignore_cursor_events(&app, enabled)
 }
 
+// The calling overlay's drag handle, in CSS pixels of its window; None to remove it
+#[tauri::command]
+pub fn set_drag_region(
+    app: AppHandle,
+    window: WebviewWindow,
+    region: Option<DragRect>,
+) -> Result<(), String> {
+    drag_region::set_region(&app, window.label(), region)
+}
+
+#[tauri::command]
+pub fn start_window_drag(app: AppHandle, window: WebviewWindow) -> Result<(), String> {
+    drag_region::start_dragging(&app, &window)
+}
+
+// A gesture the calling overlay recognised on its touchscreen
+#[tauri::command]
+pub fn overlay_touch(
+    app: AppHandle,
+    window: WebviewWindow,
+    gesture: TouchGesture,
+) -> Result<(), String> {
+    touch::handle(&app, &window, gesture)
+}
+
+#[tauri::command]
+pub fn set_presentation_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
+    kiosk::check(&app)?;
+    presentation::set_enabled(&app, enabled)
+}
+
+#[tauri::command]
+pub fn get_presentation_mode(state: State<'_, AppState>) -> Result<bool, String> {
+    let presentation_mode = state.presentation_mode.lock().map_err(|e| e.to_string())?;
+    Ok(*presentation_mode)
+}
+
+#[tauri::command]
+pub fn show_overlay_context_menu(app: AppHandle) -> Result<(), String> {
+    // Everything in the menu changes settings
+    kiosk::check(&app)?;
+    context_menu::popup_overlay_context_menu(&app)
+}
+
 #[tauri::command]
 pub fn get_overlay_visible(state: State<'_, AppState>) -> Result<bool, String> {
     let overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
@@ -127,13 +727,17 @@ pub fn get_overlay_visible(state: State<'_, AppState>) -> Result<bool, String> {
 
 // Session commands
 #[tauri::command]
-pub fn set_last_session_code(
+pub async fn set_last_session_code(
+    app: AppHandle,
     state: State<'_, AppState>,
     code: Option<String>,
 ) -> Result<(), String> {
-    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
-    settings.last_session_code = code;
-    settings::save_settings(&settings)
+    {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.last_session_code = code;
+    }
+    settings_writer::mark_dirty(&app);
+    Ok(())
 }
 
 #[tauri::command]
@@ -142,53 +746,1059 @@ pub fn get_last_session_code(state: State<'_, AppState>) -> Result<Option<String
     Ok(settings.last_session_code.clone())
 }
 
-// Caption broadcast command - emits to all windows via Rust backend
+// Reported by the frontend when the session connection goes up or down
 #[tauri::command]
-pub fn broadcast_caption(app: AppHandle, text: String) -> Result<(), String> {
-    log::info!("[broadcast_caption] Broadcasting: {}", if text.len() > 50 { &text[..50] } else { &text });
-    app.emit("caption-update", CaptionPayload { text: text.clone() })
-        .map_err(|e| {
-            log::error!("[broadcast_caption] Failed to emit: {}", e);
-            e.to_string()
-        })
-}
+pub fn set_connection_status(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    connected: bool,
+) -> Result<(), String> {
+    {
+        let mut current = state.connected.lock().map_err(|e| e.to_string())?;
+        if *current == connected {
+            return Ok(());
+        }
+        *current = connected;
+    }
+    if connected {
+        session_clock::session_started(&app);
+    } else {
+        light_alert::alert(&app, Trigger::Disconnect);
+    }
 
-// Show main window and open settings (keeps overlay visible)
-#[tauri::command]
-pub fn show_main_with_settings(app: AppHandle, _state: State<'_, AppState>) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.show().map_err(|e| e.to_string())?;
-        window.set_focus().map_err(|e| e.to_string())?;
-        // Emit event to open settings drawer
-        app.emit_to("main", "open-settings", ())
-            .map_err(|e| e.to_string())?;
+    let scripting_enabled = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.scripting.enabled
+    };
+    if scripting_enabled {
+        let mut scripts = state.scripts.lock().map_err(|e| e.to_string())?;
+        if connected {
+            scripts.on_connect();
+        } else {
+            scripts.on_disconnect();
+        }
     }
+
     Ok(())
 }
 
-// Close the entire application properly
+// Caption broadcast command - emits to all windows via Rust backend
 #[tauri::command]
-pub fn close_app(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    log::info!("close_app command called");
+pub fn broadcast_caption(
+    app: AppHandle,
+    text: String,
+    paragraphs: Option<Vec<SignedParagraph>>,
+) -> Result<(), String> {
+    // Viewers get their captions from the primary
+    if viewer::is_active(&app) {
+        return Ok(());
+    }
+    // Clearing the overlay needs no signature
+    let verdict = if text.trim().is_empty() {
+        Verdict::Trusted
+    } else {
+        signatures::verify(&app, &paragraphs.unwrap_or_default())
+    };
+    if training::delay(&app, &text, verdict)? {
+        return Ok(());
+    }
+    submit_caption(&app, text, verdict)
+}
 
-    // Close overlay window if it exists
-    if let Some(overlay) = app.get_webview_window("overlay") {
-        let _ = overlay.close();
+// Entry point for every caption, whether from the session or injected by the backend
+pub fn submit_caption(app: &AppHandle, text: String, verdict: Verdict) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let (threshold, review, markup_policy) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.confidence.threshold,
+            settings.review.clone(),
+            settings.markup.policy.clone(),
+        )
+    };
+    let mut scored = confidence::extract(&text, threshold);
+    // Nothing downstream, the operator included, gets to see the source's own markup
+    scored.text = markup::sanitize(&scored.text, &markup_policy);
+    // Speakers go by the names the operator gave them from here on
+    scored.text = speaker_names::apply(app, &scored.text);
+    // What the operator corrected earlier in the session stays corrected
+    scored.text = corrections::apply_learned(app, &scored.text);
+    corrections::note(app, &scored.low_confidence);
+    scored.unverified = verdict != Verdict::Trusted;
+
+    // The operator overlay sees every caption right away, low-confidence guesses included
+    let operator_caption = CaptionPayload {
+        text: confidence::mark_low(&scored.text, &scored.low_confidence),
+        confidence: scored.min_confidence,
+        language: None,
+        direction: None,
+        unverified: scored.unverified,
+        speakers: speaker_colors::for_caption(app, &scored.text),
+        record: None,
+    };
+    event_router::publish(app, event_router::OPERATOR_CAPTIONS, &operator_caption)?;
+
+    // Only the operator gets to see what a blocked caption said
+    if verdict == Verdict::Blocked {
+        return Ok(());
     }
 
-    // Update state
-    {
-        let mut overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
-        *overlay_visible = false;
+    // With review enabled the operator gets to see the caption before the public does
+    if review.enabled {
+        return review::enqueue(app, scored, review.delay_secs);
     }
+    publish_caption(app, scored)
+}
 
-    // Close main window
-    if let Some(main_window) = app.get_webview_window("main") {
-        main_window.close().map_err(|e| e.to_string())?;
+// Run a caption through the pipeline and send it on to the overlay
+pub fn publish_caption(app: &AppHandle, scored: ScoredCaption) -> Result<(), String> {
+    let scored = match pause::hold(app, scored)? {
+        Some(scored) => scored,
+        None => return Ok(()),
+    };
+    let state = app.state::<AppState>();
+    let detected_language = language_detection::detect(app, &scored.text);
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    // Before the pipeline, which adds markup the bidi rules would read as Latin text
+    let direction = match settings.font.direction.as_str() {
+        "auto" => text::base_direction(&scored.text),
+        fixed => Some(fixed),
     }
+    .map(str::to_string);
+    // Before the pipeline, whose markup could hide a speaker's name
+    let mut speakers = speaker_colors::for_caption(app, &scored.text);
+    // The logs keep what muted speakers said, as it came in
+    let record =
+        speaker_mute::filter(&settings.speaker_mute, &scored.text).map(|_| scored.text.clone());
+    speakers.retain(|s| !speaker_mute::is_muted(&settings.speaker_mute, &s.name));
 
-    // Exit the app
-    app.exit(0);
+    // Run the caption through the enabled processors first
+    let text = {
+        let language = detected_language
+            .clone()
+            .unwrap_or_else(|| i18n::caption_language(&state));
+        let ctx = CaptionContext {
+            settings: &settings,
+            language: &language,
+            low_confidence: &scored.low_confidence,
+        };
+        let mut pipeline = state.pipeline.lock().map_err(|e| e.to_string())?;
+        pipeline.process(scored.text, &ctx)
+    };
+
+    let text = if settings.confidence.low_confidence_display == "ellipsis" {
+        confidence::redact(&text)
+    } else {
+        text
+    };
+
+    reading_speed::submit(
+        app,
+        CaptionPayload {
+            text,
+            confidence: scored.min_confidence,
+            language: detected_language,
+            direction,
+            unverified: scored.unverified,
+            speakers,
+            record,
+        },
+    )
+}
+
+// Hand a processed caption to the outputs: overlay, log and whatever else is registered
+pub fn emit_caption(app: &AppHandle, payload: CaptionPayload) -> Result<(), String> {
+    if display_delay::hold(app, &payload)? {
+        return Ok(());
+    }
+    deliver_caption(app, &payload)
+}
+
+// Publish a caption to the public outputs, and to the logs with any muted speakers left in
+pub fn deliver_caption(app: &AppHandle, payload: &CaptionPayload) -> Result<(), String> {
+    event_router::publish(app, event_router::CAPTIONS, payload)?;
+    let record = CaptionPayload {
+        text: payload
+            .record
+            .clone()
+            .unwrap_or_else(|| payload.text.clone()),
+        ..payload.clone()
+    };
+    event_router::publish(app, event_router::RECORD, &record)
+}
+
+// Display delay commands; both return the delay now in effect
+#[tauri::command]
+pub fn set_display_delay(app: AppHandle, delay_ms: u64) -> Result<u64, String> {
+    kiosk::check(&app)?;
+    display_delay::set(&app, delay_ms)
+}
+
+#[tauri::command]
+pub fn step_display_delay(app: AppHandle, delta_ms: i64) -> Result<u64, String> {
+    kiosk::check(&app)?;
+    display_delay::step(&app, delta_ms)
+}
+
+// Session timer commands
+#[tauri::command]
+pub fn reset_session_timer(app: AppHandle) -> Result<(), String> {
+    session_clock::reset_timer(&app)
+}
+
+#[tauri::command]
+pub fn toggle_session_timer(app: AppHandle) -> Result<bool, String> {
+    session_clock::toggle_timer(&app)
+}
+
+// Overlay layout commands (see layout.rs)
+#[tauri::command]
+pub fn get_layout(state: State<'_, AppState>) -> Result<OverlayLayout, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(layout::overlay_layout(&settings))
+}
+
+#[tauri::command]
+pub fn set_layout(app: AppHandle, regions: Vec<LayoutRegion>) -> Result<OverlayLayout, String> {
+    kiosk::check(&app)?;
+    let regions = layout::validate(regions)?;
+    let updated = apply_settings_change(&app, |settings| settings.layout.regions = regions)?;
+    Ok(layout::overlay_layout(&updated))
+}
+
+#[tauri::command]
+pub fn set_safe_margins(app: AppHandle, margins: SafeMargins) -> Result<OverlayLayout, String> {
+    kiosk::check(&app)?;
+    let margins = layout::clamp_margins(&margins);
+    let updated = apply_settings_change(&app, |settings| settings.layout.safe_margins = margins)?;
+    Ok(layout::overlay_layout(&updated))
+}
+
+// Info line commands
+#[tauri::command]
+pub fn get_info_line(app: AppHandle) -> Result<String, String> {
+    info_line::current(&app)
+}
+
+#[tauri::command]
+pub fn set_info_line(app: AppHandle, text: String) -> Result<(), String> {
+    info_line::set(&app, &text)
+}
+
+// Lower third commands; without a duration the card stays up for the one in settings
+#[tauri::command]
+pub fn show_lower_third(
+    app: AppHandle,
+    name: String,
+    title: String,
+    duration: Option<u32>,
+) -> Result<(), String> {
+    lower_third::show(&app, &name, &title, duration)
+}
+
+#[tauri::command]
+pub fn hide_lower_third(app: AppHandle) -> Result<(), String> {
+    lower_third::hide(&app)
+}
+
+// Watermark commands
+#[tauri::command]
+pub fn set_watermark_image(app: AppHandle, path: String) -> Result<(), String> {
+    kiosk::check(&app)?;
+    watermark::import(&app, Path::new(path.trim()))
+}
+
+#[tauri::command]
+pub fn remove_watermark_image(app: AppHandle) -> Result<(), String> {
+    kiosk::check(&app)?;
+    watermark::remove(&app)
+}
+
+#[tauri::command]
+pub fn get_watermark_image(app: AppHandle) -> Result<Option<String>, String> {
+    watermark::data_url(&app)
+}
+
+// "light" or "dark", as decided from `settings.theme`
+#[tauri::command]
+pub fn get_caption_theme(app: AppHandle) -> Result<String, String> {
+    theme::current(&app).map(str::to_string)
+}
+
+// Why overlays are drawn opaque, None while they can be transparent
+#[tauri::command]
+pub fn get_compositor_fallback(app: AppHandle) -> Result<Option<String>, String> {
+    compositor::fallback_reason(&app)
+}
+
+// Caption processor commands
+#[tauri::command]
+pub fn list_caption_processors(state: State<'_, AppState>) -> Result<Vec<ProcessorInfo>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let language = i18n::caption_language(&state);
+    let ctx = CaptionContext {
+        settings: &settings,
+        language: &language,
+        low_confidence: &[],
+    };
+    let pipeline = state.pipeline.lock().map_err(|e| e.to_string())?;
+    Ok(pipeline.list(&ctx))
+}
+
+#[tauri::command]
+pub fn reload_plugins(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<ProcessorInfo>, String> {
+    kiosk::check(&app)?;
+    plugins::reload(&app)?;
+    list_caption_processors(state)
+}
+
+// Output commands
+#[tauri::command]
+pub fn list_outputs(state: State<'_, AppState>) -> Result<Vec<OutputInfo>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let router = state.router.lock().map_err(|e| e.to_string())?;
+    Ok(router.list(&settings))
+}
+
+#[tauri::command]
+pub fn enable_output(app: AppHandle, id: String, enabled: bool) -> Result<Vec<OutputInfo>, String> {
+    kiosk::check(&app)?;
+    {
+        let state = app.state::<AppState>();
+        let router = state.router.lock().map_err(|e| e.to_string())?;
+        if !router.contains(&id) {
+            return Err(format!("Unknown output: {}", id));
+        }
+    }
+    apply_settings_change(&app, |s| {
+        s.outputs.disabled.retain(|d| *d != id);
+        if !enabled {
+            s.outputs.disabled.push(id.clone());
+        }
+    })?;
+    list_outputs(app.state::<AppState>())
+}
 
+// Session archive commands
+#[tauri::command]
+pub fn set_active_session(
+    app: AppHandle,
+    code: Option<String>,
+    server_url: Option<String>,
+) -> Result<(), String> {
+    // A new session starts over with the declared language and no learned corrections
+    language_detection::reset(&app);
+    corrections::reset(&app);
+    speaker_colors::reset(&app);
+    speaker_names::reset(&app);
+    bookmarks::reset(&app);
+    keywords::reset(&app);
+    {
+        let state = app.state::<AppState>();
+        let mut session_code = state.session_code.lock().map_err(|e| e.to_string())?;
+        *session_code = code.clone();
+    }
+    // Summarized once it has ended
+    let ended = app
+        .state::<AppState>()
+        .storage
+        .lock()
+        .map_err(|e| e.to_string())?
+        .active_session();
+    storage::set_active_session(&app, code.zip(server_url))?;
+    if let Some(id) = ended {
+        summary::session_ended(&app, id);
+    }
     Ok(())
 }
+
+// Summarize an archived session with the endpoint in `summary`; returns where it was saved
+#[tauri::command]
+pub async fn summarize_session(app: AppHandle, id: i64) -> Result<String, String> {
+    summary::summarize(&app, id).map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn list_archived_sessions(state: State<'_, AppState>) -> Result<Vec<ArchivedSession>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.list_sessions()
+}
+
+#[tauri::command]
+pub fn get_session_transcript(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<SessionTranscript, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.transcript(id)
+}
+
+// Check-in prompts of an archived session and when they were answered
+#[tauri::command]
+pub fn list_check_ins(state: State<'_, AppState>, id: i64) -> Result<Vec<CheckIn>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.check_ins(id)
+}
+
+#[tauri::command]
+pub fn delete_session(app: AppHandle, state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    kiosk::check(&app)?;
+    let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.delete_session(id)
+}
+
+#[tauri::command]
+pub fn search_archive(
+    state: State<'_, AppState>,
+    query: String,
+    date_range: Option<DateRange>,
+) -> Result<Vec<SearchHit>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.search(&query, &date_range.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_usage_statistics(state: State<'_, AppState>) -> Result<UsageStatistics, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.usage_statistics()
+}
+
+// Caption history commands
+#[tauri::command]
+pub fn get_caption_history(state: State<'_, AppState>) -> Result<Vec<HistoryEntry>, String> {
+    let history = state.caption_history.lock().map_err(|e| e.to_string())?;
+    Ok(history.entries())
+}
+
+#[tauri::command]
+pub fn clear_caption_history(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    kiosk::check(&app)?;
+    let mut history = state.caption_history.lock().map_err(|e| e.to_string())?;
+    history.clear()
+}
+
+// Formatted transcript of the caption history, or of an archived session, written to the
+// documents folder; returns the file's path. Times are aligned to a separate recording by
+// when it started or by a marker caption and its position in the recording.
+#[tauri::command]
+pub fn export_transcript(
+    app: AppHandle,
+    format: String,
+    title: Option<String>,
+    session_id: Option<i64>,
+    recording_start: Option<u64>,
+    marker: Option<String>,
+    marker_at_ms: Option<u64>,
+) -> Result<String, String> {
+    let format = export::Format::parse(&format)?;
+    let alignment = match (recording_start, marker) {
+        (Some(at), _) => export::Alignment::RecordingStart(at),
+        (None, Some(text)) => export::Alignment::Marker {
+            text,
+            at: marker_at_ms.unwrap_or(0),
+        },
+        (None, None) => export::Alignment::FirstCaption,
+    };
+    let path = export::save(&app, format, title, session_id, &alignment)?;
+    Ok(path.display().to_string())
+}
+
+// Terminology commands
+#[tauri::command]
+pub fn list_terms(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.terminology.terms.clone())
+}
+
+#[tauri::command]
+pub fn add_term(app: AppHandle, term: String) -> Result<Vec<String>, String> {
+    kiosk::check(&app)?;
+    let term = term.trim().to_string();
+    if term.is_empty() {
+        return Err("Term is empty".to_string());
+    }
+
+    let updated = apply_settings_change(&app, |settings| {
+        let terms = &mut settings.terminology.terms;
+        if !terms.iter().any(|t| t.eq_ignore_ascii_case(&term)) {
+            terms.push(term);
+        }
+    })?;
+    Ok(updated.terminology.terms)
+}
+
+#[tauri::command]
+pub fn remove_term(app: AppHandle, term: String) -> Result<Vec<String>, String> {
+    kiosk::check(&app)?;
+    let updated = apply_settings_change(&app, |settings| {
+        settings.terminology.terms.retain(|t| t != term.trim());
+    })?;
+    Ok(updated.terminology.terms)
+}
+
+// Show main window and open settings (keeps overlay visible)
+#[tauri::command]
+pub fn show_main_with_settings(app: AppHandle, _state: State<'_, AppState>) -> Result<(), String> {
+    crate::show_main_with_settings_drawer(&app)
+}
+
+// Close the entire application properly
+#[tauri::command]
+pub fn close_app(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    log::info!("close_app command called");
+    kiosk::check(&app)?;
+    exit_protection::check(&app, "quit", pin.as_deref())?;
+
+    // Close overlay window if it exists
+    let _ = ManagedWindow::OVERLAY.close(&app);
+
+    // Update state
+    {
+        let mut overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
+        *overlay_visible = false;
+    }
+
+    // Close main window
+    ManagedWindow::Main.close(&app)?;
+
+    // Exit the app
+    app.exit(0);
+
+    Ok(())
+}
+
+// Operator review commands
+#[tauri::command]
+pub fn get_review_queue(app: AppHandle) -> Result<Vec<PendingCaption>, String> {
+    review::list(&app)
+}
+
+#[tauri::command]
+pub fn edit_pending_caption(app: AppHandle, id: u64, text: String) -> Result<(), String> {
+    review::edit(&app, id, text)
+}
+
+#[tauri::command]
+pub fn suppress_pending_caption(app: AppHandle, id: u64) -> Result<(), String> {
+    review::suppress(&app, id)
+}
+
+#[tauri::command]
+pub fn release_pending_caption(app: AppHandle, id: u64) -> Result<(), String> {
+    review::release(&app, id)
+}
+
+// Correction suggestions for the operator
+#[tauri::command]
+pub fn get_correction_candidates(app: AppHandle) -> Result<CorrectionCandidates, String> {
+    corrections::candidates(&app)
+}
+
+#[tauri::command]
+pub fn apply_correction(
+    app: AppHandle,
+    from: String,
+    to: String,
+) -> Result<CorrectionCandidates, String> {
+    corrections::apply(&app, &from, &to)
+}
+
+#[tauri::command]
+pub fn dismiss_correction(app: AppHandle, word: String) -> Result<CorrectionCandidates, String> {
+    corrections::dismiss(&app, &word)
+}
+
+// Speaker names for the session
+#[tauri::command]
+pub fn get_speaker_names(app: AppHandle) -> Result<Vec<SpeakerName>, String> {
+    speaker_names::list(&app)
+}
+
+#[tauri::command]
+pub fn set_speaker_name(
+    app: AppHandle,
+    label: String,
+    name: String,
+) -> Result<Vec<SpeakerName>, String> {
+    speaker_names::set(&app, &label, &name)
+}
+
+#[tauri::command]
+pub fn remove_speaker_name(app: AppHandle, label: String) -> Result<Vec<SpeakerName>, String> {
+    speaker_names::remove(&app, &label)
+}
+
+// MUST be async to avoid WebView2 deadlock on Windows
+#[tauri::command]
+pub async fn open_review_window(app: AppHandle) -> Result<(), String> {
+    crate::spawn_open_review_window(app);
+    Ok(())
+}
+
+// Operator overlay commands - MUST be async to avoid WebView2 deadlock on Windows
+#[tauri::command]
+pub async fn show_operator_overlay(app: AppHandle) -> Result<(), String> {
+    crate::spawn_show_operator_overlay_window(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_operator_overlay(app: AppHandle) -> Result<(), String> {
+    ManagedWindow::OPERATOR_OVERLAY.close(&app)
+}
+
+// Input ports offered in the MIDI settings
+#[tauri::command]
+pub fn list_midi_ports() -> Result<Vec<String>, String> {
+    midi::list_ports()
+}
+
+// HID devices offered in the footswitch settings
+#[tauri::command]
+pub fn list_hid_devices() -> Result<Vec<HidDeviceEntry>, String> {
+    footswitch::list_devices()
+}
+
+// Serial ports offered in the caption display settings
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<String>, String> {
+    serial_display::list_ports()
+}
+
+// Page the braille display: "back", "forward", "start" or "live"
+#[tauri::command]
+pub fn braille_page(app: AppHandle, direction: String) -> Result<(), String> {
+    braille::page(&app, &direction)
+}
+
+#[tauri::command]
+pub fn set_captions_paused(app: AppHandle, paused: bool) -> Result<(), String> {
+    pause::set_paused(&app, paused)
+}
+
+#[tauri::command]
+pub fn get_captions_paused(app: AppHandle) -> bool {
+    pause::is_paused(&app)
+}
+
+// Shrink the public overlay to a pill with the last few words, or expand it again;
+// returns whether it is compact now
+#[tauri::command]
+pub fn set_compact_mode(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    kiosk::check(&app)?;
+    compact::set_active(&app, enabled)
+}
+
+#[tauri::command]
+pub fn get_compact_mode(app: AppHandle) -> CompactPayload {
+    CompactPayload {
+        active: compact::is_active(&app),
+    }
+}
+
+// Show the captions of the last `seconds` on the overlay again; returns what was replayed
+#[tauri::command]
+pub fn replay_recent(app: AppHandle, seconds: u32) -> Result<String, String> {
+    replay::replay_recent(&app, seconds)
+}
+
+// Show the last paragraph again a word at a time; returns the paragraph
+#[tauri::command]
+pub fn slow_motion(app: AppHandle) -> Result<String, String> {
+    replay::slow_motion(&app)
+}
+
+// Bookmarks of the session, for exports and the archive
+#[tauri::command]
+pub fn add_bookmark(app: AppHandle, note: String, tag: Option<String>) -> Result<Bookmark, String> {
+    bookmarks::add(&app, &note, tag.as_deref().unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn list_bookmarks(app: AppHandle) -> Result<Vec<Bookmark>, String> {
+    bookmarks::list(&app)
+}
+
+// The most said words of the last `keywords.window_secs`, most first
+#[tauri::command]
+pub fn get_keywords(app: AppHandle) -> Result<Vec<Keyword>, String> {
+    keywords::top(&app)
+}
+
+// Whether the operator is being asked to check in (see check_in.rs)
+#[tauri::command]
+pub fn get_check_in_status(app: AppHandle) -> CheckInStatus {
+    check_in::status(&app)
+}
+
+// The operator answers the check-in prompt
+#[tauri::command]
+pub fn acknowledge_check_in(app: AppHandle) -> Result<(), String> {
+    check_in::acknowledge(&app)
+}
+
+// A button on the control strip: "pause", "hide", "fontLarger" or "fontSmaller"
+#[tauri::command]
+pub fn control_strip_action(app: AppHandle, action: String) -> Result<(), String> {
+    hot_corner::run_action(&app, &action)
+}
+
+// Connection diagnostics commands
+#[tauri::command]
+pub fn get_diagnostics(app: AppHandle) -> Result<DiagnosticsSnapshot, String> {
+    diagnostics::snapshot(&app)
+}
+
+// Reported by the main window: status changes ("connecting", "connected", "disconnected"),
+// connection errors and closes
+#[tauri::command]
+pub fn report_connection_event(
+    app: AppHandle,
+    kind: String,
+    detail: Option<String>,
+) -> Result<(), String> {
+    failover::connection_event(&app, &kind);
+    diagnostics::record(&app, &kind, detail)
+}
+
+#[tauri::command]
+pub fn force_reconnect(app: AppHandle) -> Result<(), String> {
+    diagnostics::force_reconnect(&app)
+}
+
+// The caption server the session connects to (see failover.rs)
+#[tauri::command]
+pub fn get_active_server(app: AppHandle) -> Result<ActiveServer, String> {
+    failover::active(&app)
+}
+
+// Move the session to another of the configured caption servers
+#[tauri::command]
+pub fn switch_server(app: AppHandle, index: usize) -> Result<ActiveServer, String> {
+    failover::switch_server(&app, index)
+}
+
+// A caption server URL checked before connecting, normalized; Err says what is wrong with it
+#[tauri::command]
+pub fn check_server_url(url: String) -> Result<String, String> {
+    server_url::parse(&url)
+}
+
+// The STUN and TURN servers of `webrtc.ice_servers` the offline only policy allows
+#[tauri::command]
+pub fn filter_ice_servers(app: AppHandle, urls: Vec<String>) -> Result<Vec<String>, String> {
+    network::allowed_ice_servers(&app, urls)
+}
+
+// Kiosk mode commands
+#[tauri::command]
+pub fn get_kiosk_status(app: AppHandle) -> Result<KioskStatus, String> {
+    Ok(kiosk::status(&app))
+}
+
+#[tauri::command]
+pub fn unlock_kiosk(app: AppHandle, pin: String) -> Result<(), String> {
+    kiosk::unlock(&app, &pin)
+}
+
+#[tauri::command]
+pub fn lock_kiosk(app: AppHandle) -> Result<(), String> {
+    kiosk::lock(&app)
+}
+
+#[tauri::command]
+pub fn get_viewer_status(app: AppHandle) -> Result<ViewerStatus, String> {
+    Ok(viewer::status(&app))
+}
+
+// Relays advertised on the LAN; waits a moment for them to answer
+#[tauri::command]
+pub async fn discover_relays(app: AppHandle) -> Result<Vec<RelayInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        discovery::discover(&app, discovery::BROWSE_TIMEOUT)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Chromecasts on the LAN; waits a moment for them to answer
+#[tauri::command]
+pub async fn list_cast_targets(app: AppHandle) -> Result<Vec<CastTarget>, String> {
+    tauri::async_runtime::spawn_blocking(move || cast::list_targets(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Show the caption page on `target`, an id from list_cast_targets
+#[tauri::command]
+pub fn start_casting(app: AppHandle, target: String) -> Result<(), String> {
+    kiosk::check(&app)?;
+    cast::start(&app, &target)
+}
+
+#[tauri::command]
+pub fn stop_casting(app: AppHandle) -> Result<(), String> {
+    kiosk::check(&app)?;
+    cast::stop(&app)
+}
+
+#[tauri::command]
+pub fn get_cast_status(app: AppHandle) -> Result<CastStatus, String> {
+    Ok(cast::status(&app))
+}
+
+// DLNA renderers on the LAN; waits a moment for them to answer
+#[tauri::command]
+pub async fn list_dlna_renderers(app: AppHandle) -> Result<Vec<DlnaRenderer>, String> {
+    tauri::async_runtime::spawn_blocking(move || dlna::list_renderers(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn push_to_renderer(app: AppHandle, renderer: String) -> Result<(), String> {
+    kiosk::check(&app)?;
+    tauri::async_runtime::spawn_blocking(move || dlna::push(&app, &renderer))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn stop_renderer(app: AppHandle, renderer: String) -> Result<(), String> {
+    kiosk::check(&app)?;
+    tauri::async_runtime::spawn_blocking(move || dlna::stop(&app, &renderer))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Caption page address for TVs whose own browser is used instead
+#[tauri::command]
+pub fn get_tv_page_url(app: AppHandle) -> Result<String, String> {
+    dlna::tv_page_url(&app)
+}
+
+// Pair with the smart light bridge; its link button has to be pressed first
+#[tauri::command]
+pub async fn pair_light_bridge(app: AppHandle, bridge: String) -> Result<(), String> {
+    kiosk::check(&app)?;
+    tauri::async_runtime::spawn_blocking(move || light_alert::pair(&bridge))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn unpair_light_bridge(app: AppHandle) -> Result<(), String> {
+    kiosk::check(&app)?;
+    light_alert::unpair()
+}
+
+#[tauri::command]
+pub fn is_light_bridge_paired() -> bool {
+    light_alert::is_paired()
+}
+
+#[tauri::command]
+pub async fn list_lights(app: AppHandle) -> Result<Vec<HueLight>, String> {
+    tauri::async_runtime::spawn_blocking(move || light_alert::list_lights(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Flash the selected lights now, whatever the alert triggers are set to
+#[tauri::command]
+pub async fn test_light_alert(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || light_alert::flash(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Caption URL of the meeting or stream on `service` ("zoom", "teams", "youtube"); None
+// forgets it
+#[tauri::command]
+pub fn set_caption_service_url(
+    app: AppHandle,
+    service: String,
+    url: Option<String>,
+) -> Result<(), String> {
+    kiosk::check(&app)?;
+    caption_services::set_url(&app, Service::from_id(&service)?, url.as_deref())
+}
+
+#[tauri::command]
+pub fn has_caption_service_url(service: String) -> Result<bool, String> {
+    Ok(caption_services::has_url(Service::from_id(&service)?))
+}
+
+// Access key for the S3 bucket HLS subtitles are written to; None forgets it
+#[tauri::command]
+pub fn set_s3_credentials(
+    app: AppHandle,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+) -> Result<(), String> {
+    kiosk::check(&app)?;
+    let keys = match (access_key_id.as_deref(), secret_access_key.as_deref()) {
+        (Some(id), Some(secret)) if !id.trim().is_empty() && !secret.trim().is_empty() => {
+            Some((id.trim(), secret.trim()))
+        }
+        (None, None) => None,
+        _ => return Err("Both the access key ID and the secret access key are needed".to_string()),
+    };
+    s3::set_credentials(keys)?;
+    hls_captions::restart(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn has_s3_credentials() -> bool {
+    s3::has_credentials()
+}
+
+// Key for the summary endpoint (see summary.rs); None forgets it
+#[tauri::command]
+pub fn set_summary_api_key(app: AppHandle, key: Option<String>) -> Result<(), String> {
+    kiosk::check(&app)?;
+    summary::set_api_key(key.as_deref())
+}
+
+#[tauri::command]
+pub fn has_summary_api_key() -> bool {
+    summary::has_api_key()
+}
+
+// Exit PIN (see exit_protection.rs); `current` is needed once one is set, None removes it
+#[tauri::command]
+pub fn set_exit_pin(
+    app: AppHandle,
+    current: Option<String>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    kiosk::check(&app)?;
+    exit_protection::set_pin(&app, current.as_deref(), pin.as_deref())
+}
+
+#[tauri::command]
+pub fn has_exit_pin() -> bool {
+    exit_protection::has_pin()
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
+    audio_monitor::list_devices()
+}
+
+#[tauri::command]
+pub fn get_audio_uplink_status(app: AppHandle) -> Result<AudioUplinkStatus, String> {
+    audio_uplink::status(&app)
+}
+
+// Session this instance hosts on the LAN, None when hosting is off
+#[tauri::command]
+pub fn get_hosted_lan_session(app: AppHandle) -> Result<Option<LanSession>, String> {
+    lan_session::hosted(&app)
+}
+
+#[tauri::command]
+pub async fn discover_lan_sessions(app: AppHandle) -> Result<Vec<LanSession>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        lan_session::discover(&app, discovery::BROWSE_TIMEOUT)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Streams logs to support staff for the configured window; the token comes from them
+#[tauri::command]
+pub fn start_remote_support(app: AppHandle, token: String) -> Result<RemoteSupportStatus, String> {
+    kiosk::check(&app)?;
+    remote_support::start(&app, &token)
+}
+
+#[tauri::command]
+pub fn stop_remote_support(app: AppHandle) -> Result<(), String> {
+    kiosk::check(&app)?;
+    remote_support::stop(&app)
+}
+
+#[tauri::command]
+pub fn get_remote_support_status(app: AppHandle) -> Result<RemoteSupportStatus, String> {
+    remote_support::status(&app)
+}
+
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    tauri::async_runtime::spawn_blocking(move || self_test::run(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_training_status(app: AppHandle) -> Result<TrainingStatus, String> {
+    training::status(&app)
+}
+
+// Failure simulations for operator training, only in training mode
+#[tauri::command]
+pub fn simulate_disconnect(app: AppHandle, seconds: Option<u64>) -> Result<(), String> {
+    training::simulate_disconnect(&app, seconds)
+}
+
+#[tauri::command]
+pub fn simulate_latency(app: AppHandle, ms: u64) -> Result<(), String> {
+    training::simulate_latency(&app, ms)
+}
+
+#[tauri::command]
+pub fn simulate_overlay_crash(app: AppHandle) -> Result<(), String> {
+    training::simulate_overlay_crash(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_app_state;
+
+    // The backend as the app sets it up, with default settings and no windows
+    fn mock_app() -> tauri::App<crate::AppRuntime> {
+        tauri::test::mock_builder()
+            .manage(new_app_state(AppSettings::default()))
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .expect("mock app")
+    }
+
+    fn history(app: &AppHandle) -> Vec<HistoryEntry> {
+        app.state::<AppState>()
+            .caption_history
+            .lock()
+            .unwrap()
+            .entries()
+    }
+
+    #[test]
+    fn trusted_caption_reaches_the_public() {
+        let app = mock_app();
+        submit_caption(
+            app.handle(),
+            "Tere <b>hommikust</b>".to_string(),
+            Verdict::Trusted,
+        )
+        .unwrap();
+        let history = history(app.handle());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].text, "Tere hommikust");
+    }
+
+    #[test]
+    fn blocked_caption_stays_with_the_operator() {
+        let app = mock_app();
+        submit_caption(app.handle(), "Tere".to_string(), Verdict::Blocked).unwrap();
+        assert!(history(app.handle()).is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+// Compact mode: the public overlay shrinks to a small pill showing only the last few words,
+// out of the way during normal work, and expands back to where and how big it was from the
+// pill, a command or the global shortcut in `compact`. The pill can be dragged around; the
+// full overlay grows back from wherever the pill was left, bottom edges lined up.
+
+use crate::settings::CompactSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use serde::Serialize;
+use tauri::{Emitter, Manager, PhysicalPosition, PhysicalSize};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+#[derive(Default)]
+pub struct CompactState {
+    active: bool,
+    // The full overlay's size, to go back to
+    expanded_size: Option<PhysicalSize<u32>>,
+    // Global shortcut registered for toggling
+    shortcut: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CompactPayload {
+    pub active: bool,
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    app.state::<AppState>()
+        .compact
+        .lock()
+        .map(|c| c.active)
+        .unwrap_or(false)
+}
+
+fn settings(app: &AppHandle) -> Result<CompactSettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.compact.clone())
+}
+
+// Resize the overlay to `size` keeping the middle of its bottom edge in place
+fn resize_anchored(window: &crate::WebviewWindow, size: PhysicalSize<u32>) -> Result<(), String> {
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let current = window.inner_size().map_err(|e| e.to_string())?;
+    window
+        .set_size(tauri::Size::Physical(size))
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(tauri::Position::Physical(PhysicalPosition {
+            x: position.x + (current.width as i32 - size.width as i32) / 2,
+            y: position.y + current.height as i32 - size.height as i32,
+        }))
+        .map_err(|e| e.to_string())
+}
+
+// Shrink the overlay to the pill or expand it again; returns whether it is compact now
+pub fn set_active(app: &AppHandle, active: bool) -> Result<bool, String> {
+    let settings = settings(app)?;
+    let window = ManagedWindow::OVERLAY
+        .get(app)
+        .ok_or_else(|| "The overlay is not open".to_string())?;
+
+    {
+        let state = app.state::<AppState>();
+        let mut compact = state.compact.lock().map_err(|e| e.to_string())?;
+        if compact.active == active {
+            return Ok(active);
+        }
+        if active {
+            compact.expanded_size = Some(window.inner_size().map_err(|e| e.to_string())?);
+            let scale = window.scale_factor().map_err(|e| e.to_string())?;
+            resize_anchored(
+                &window,
+                PhysicalSize {
+                    width: (f64::from(settings.width) * scale).round() as u32,
+                    height: (f64::from(settings.height) * scale).round() as u32,
+                },
+            )?;
+        } else if let Some(size) = compact.expanded_size.take() {
+            resize_anchored(&window, size)?;
+        }
+        compact.active = active;
+    }
+
+    log::info!("Compact overlay {}", if active { "on" } else { "off" });
+    app.emit("compact-mode-changed", &CompactPayload { active })
+        .map_err(|e| e.to_string())?;
+    Ok(active)
+}
+
+pub fn toggle(app: &AppHandle) -> Result<bool, String> {
+    set_active(app, !is_active(app))
+}
+
+// The overlay was closed, so it comes back full size
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut compact) = app.state::<AppState>().compact.lock() {
+        compact.active = false;
+        compact.expanded_size = None;
+    }
+}
+
+// (Re-)register the shortcut for toggling compact mode from settings
+pub fn register_shortcut(app: &AppHandle) {
+    let shortcut = match settings(app) {
+        Ok(s) => s.shortcut.trim().to_string(),
+        Err(_) => return,
+    };
+    let state = app.state::<AppState>();
+    let mut compact = match state.compact.lock() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+
+    if let Some(previous) = compact.shortcut.take() {
+        if let Err(e) = app.global_shortcut().unregister(previous.as_str()) {
+            log::warn!(
+                "Failed to unregister compact mode shortcut {}: {}",
+                previous,
+                e
+            );
+        }
+    }
+    if shortcut.is_empty() {
+        return;
+    }
+
+    let result = app
+        .global_shortcut()
+        .on_shortcut(shortcut.as_str(), |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                if let Err(e) = toggle(app) {
+                    log::error!("Failed to toggle compact mode: {}", e);
+                }
+            }
+        });
+    match result {
+        Ok(()) => compact.shortcut = Some(shortcut),
+        Err(e) => log::error!(
+            "Failed to register compact mode shortcut {}: {}",
+            shortcut,
+            e
+        ),
+    }
+}
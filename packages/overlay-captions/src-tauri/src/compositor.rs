@@ -0,0 +1,117 @@
+// Whether the overlay can be see-through. Without a compositor (a bare X11 window manager)
+// or over Remote Desktop, a transparent window shows up as a black rectangle, so overlays
+// are then created opaque and drawn on a solid background, and a `compositor-fallback`
+// event says why. Wayland and macOS always composite. The check runs each time an overlay
+// window is created, so starting a compositor later brings transparency back.
+
+use crate::{diagnostics, AppHandle, AppState};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositorFallback {
+    // None when transparency works again
+    pub reason: Option<String>,
+}
+
+// Why transparency is unavailable, None when it works or can't be told
+#[cfg(target_os = "linux")]
+fn unavailable_reason() -> Option<String> {
+    use libloading::Library;
+    use std::os::raw::{c_char, c_int, c_ulong, c_void};
+
+    type OpenDisplayFn = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+    type CloseDisplayFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type DefaultScreenFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type InternAtomFn = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_ulong;
+    type GetSelectionOwnerFn = unsafe extern "C" fn(*mut c_void, c_ulong) -> c_ulong;
+
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|d| !d.is_empty());
+    let forced_x11 = std::env::var("GDK_BACKEND").is_ok_and(|b| b == "x11");
+    if wayland && !forced_x11 {
+        return None;
+    }
+
+    // SAFETY: the signatures are from Xlib.h; the display is closed before returning
+    unsafe {
+        let library = Library::new("libX11.so.6").ok()?;
+        let open_display = *library.get::<OpenDisplayFn>(b"XOpenDisplay\0").ok()?;
+        let close_display = *library.get::<CloseDisplayFn>(b"XCloseDisplay\0").ok()?;
+        let default_screen = *library.get::<DefaultScreenFn>(b"XDefaultScreen\0").ok()?;
+        let intern_atom = *library.get::<InternAtomFn>(b"XInternAtom\0").ok()?;
+        let get_selection_owner = *library
+            .get::<GetSelectionOwnerFn>(b"XGetSelectionOwner\0")
+            .ok()?;
+
+        let display = open_display(std::ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        // A compositing manager owns _NET_WM_CM_S<screen> (EWMH)
+        let selection = format!("_NET_WM_CM_S{}\0", default_screen(display));
+        let atom = intern_atom(display, selection.as_ptr() as *const c_char, 0);
+        let owner = get_selection_owner(display, atom);
+        close_display(display);
+        (owner == 0).then(|| "no compositing window manager is running".to_string())
+    }
+}
+
+#[cfg(windows)]
+fn unavailable_reason() -> Option<String> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_REMOTESESSION};
+
+    // SAFETY: GetSystemMetrics only reads
+    let remote = unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0;
+    remote.then(|| "Remote Desktop session".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn unavailable_reason() -> Option<String> {
+    None
+}
+
+// Check again; returns whether overlays can be transparent
+pub fn check(app: &AppHandle) -> bool {
+    let reason = unavailable_reason();
+    let changed = {
+        let state = app.state::<AppState>();
+        let mut fallback = match state.compositor_fallback.lock() {
+            Ok(f) => f,
+            Err(_) => return reason.is_none(),
+        };
+        let changed = *fallback != reason;
+        *fallback = reason.clone();
+        changed
+    };
+
+    if changed {
+        match &reason {
+            Some(reason) => {
+                log::warn!(
+                    "Overlay transparency unavailable ({}), using a solid background",
+                    reason
+                );
+                let _ = diagnostics::record(app, "no transparency", Some(reason.clone()));
+            }
+            None => log::info!("Overlay transparency available again"),
+        }
+        let payload = CompositorFallback {
+            reason: reason.clone(),
+        };
+        if let Err(e) = app.emit("compositor-fallback", &payload) {
+            log::error!("Failed to emit compositor fallback: {}", e);
+        }
+    }
+    reason.is_none()
+}
+
+// Why overlays are opaque, None while they can be transparent
+pub fn fallback_reason(app: &AppHandle) -> Result<Option<String>, String> {
+    let state = app.state::<AppState>();
+    let fallback = state
+        .compositor_fallback
+        .lock()
+        .map_err(|e| e.to_string())?;
+    Ok(fallback.clone())
+}
@@ -0,0 +1,155 @@
+use crate::commands::{apply_settings_change, step_font_size, FONT_SIZE_STEP};
+use crate::compact;
+use crate::exit_protection;
+use crate::i18n::{self, tr};
+use crate::kiosk;
+use crate::window_manager::{self, ManagedWindow};
+use crate::AppHandle;
+use crate::AppState;
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    Manager,
+};
+
+// Context menu shown when the overlay is right-clicked. The transparent overlay has no
+// window chrome, so this is the only discoverable way to reach these actions from it.
+pub fn popup_overlay_context_menu(app: &AppHandle) -> Result<(), String> {
+    let window = match ManagedWindow::OVERLAY.get(app) {
+        Some(w) => w,
+        None => return Ok(()),
+    };
+
+    let lang = i18n::current_language(app);
+    let edit_mode = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        !settings.overlay.click_through
+    };
+
+    let hide_item = MenuItem::with_id(
+        app,
+        "overlay_ctx_hide",
+        tr(&lang, "context.hide"),
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let edit_mode_item = CheckMenuItem::with_id(
+        app,
+        "overlay_ctx_edit_mode",
+        tr(&lang, "context.edit_mode"),
+        true,
+        edit_mode,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let compact_item = CheckMenuItem::with_id(
+        app,
+        "overlay_ctx_compact",
+        tr(&lang, "context.compact"),
+        true,
+        compact::is_active(app),
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let font_larger_item = MenuItem::with_id(
+        app,
+        "overlay_ctx_font_larger",
+        tr(&lang, "context.font_larger"),
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let font_smaller_item = MenuItem::with_id(
+        app,
+        "overlay_ctx_font_smaller",
+        tr(&lang, "context.font_smaller"),
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let settings_item = MenuItem::with_id(
+        app,
+        "overlay_ctx_settings",
+        tr(&lang, "context.settings"),
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let quit_item = MenuItem::with_id(
+        app,
+        "overlay_ctx_quit",
+        tr(&lang, "context.quit"),
+        true,
+        None::<&str>,
+    )
+    .map_err(|e| e.to_string())?;
+    let separator = PredefinedMenuItem::separator(app).map_err(|e| e.to_string())?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &hide_item,
+            &edit_mode_item,
+            &compact_item,
+            &separator,
+            &font_larger_item,
+            &font_smaller_item,
+            &separator,
+            &settings_item,
+            &quit_item,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    window.popup_menu(&menu).map_err(|e| e.to_string())
+}
+
+// Global menu event handler; ignores events that don't belong to the overlay context menu
+pub fn handle_menu_event(app: &AppHandle, event: &MenuEvent) {
+    let result = match event.id.as_ref() {
+        "overlay_ctx_hide" if exit_protection::is_required(app) => {
+            exit_protection::request(app, "hideOverlay");
+            Ok(())
+        }
+        "overlay_ctx_hide" => crate::hide_overlay_window(app),
+        // Everything else changes settings or ends the app, which a locked kiosk keeps
+        "overlay_ctx_edit_mode" => kiosk::check(app).and_then(|_| toggle_edit_mode(app)),
+        "overlay_ctx_compact" => kiosk::check(app).and_then(|_| compact::toggle(app).map(|_| ())),
+        "overlay_ctx_font_larger" => {
+            kiosk::check(app).and_then(|_| step_font_size(app, FONT_SIZE_STEP))
+        }
+        "overlay_ctx_font_smaller" => {
+            kiosk::check(app).and_then(|_| step_font_size(app, -FONT_SIZE_STEP))
+        }
+        "overlay_ctx_settings" => {
+            kiosk::check(app).and_then(|_| crate::show_main_with_settings_drawer(app))
+        }
+        "overlay_ctx_quit" if kiosk::is_locked(app) => kiosk::check(app),
+        "overlay_ctx_quit" if exit_protection::is_required(app) => {
+            exit_protection::request(app, "quit");
+            Ok(())
+        }
+        "overlay_ctx_quit" => {
+            crate::quit_app(app);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        log::error!(
+            "Overlay context menu action '{}' failed: {}",
+            event.id.as_ref(),
+            e
+        );
+    }
+}
+
+// Edit mode means the overlay accepts mouse input (click-through disabled)
+fn toggle_edit_mode(app: &AppHandle) -> Result<(), String> {
+    let updated = apply_settings_change(app, |settings| {
+        settings.overlay.click_through = !settings.overlay.click_through;
+    })?;
+    window_manager::set_ignore_cursor_events(app, updated.overlay.click_through)
+}
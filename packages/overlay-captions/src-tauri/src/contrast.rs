@@ -0,0 +1,475 @@
+// Keeps the captions readable over bright slides: every few seconds the screen around the
+// overlay is sampled for its average luminance, and the caption background is made more
+// opaque, or the text flipped to black or white, until the text reaches the contrast ratio
+// in settings. The changes go out as `contrast-adjust` events over the overlay's own
+// settings, which are left alone.
+//
+// The overlay hides what is directly behind it, so the sampled pixels are in a band just
+// outside its window; slides rarely change colour right at its edge. The screen is read
+// through libX11 (X11 and XWayland, where other clients' windows read as black) or GDI,
+// both loaded at runtime.
+
+use crate::settings::AppSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{power, theme, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+// Width of the sampled band around the overlay
+const BAND_PX: i32 = 24;
+// Pixels read per sample, spread evenly around the band
+const SAMPLE_POINTS: i32 = 64;
+// The caption background is made more opaque in steps of this
+const OPACITY_STEP: f32 = 0.05;
+
+#[derive(Default)]
+pub struct ContrastState {
+    // Bumped to stop the running sampler
+    generation: u64,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastAdjustment {
+    // Average relative luminance around the overlay, 0 (black) to 1 (white); None while
+    // not sampling
+    pub luminance: Option<f32>,
+    // Caption background opacity and text colour over the settings'; None keeps theirs
+    pub opacity: Option<f32>,
+    pub color: Option<String>,
+}
+
+// WCAG 2 relative luminance of an sRGB colour
+fn luminance(r: u8, g: u8, b: u8) -> f32 {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+// Luminance of "#rrggbb"
+fn hex_luminance(color: &str) -> Option<f32> {
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    Some(luminance(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// Least change to the captions that reaches `settings.contrast.min_ratio` over a screen of
+// `screen` luminance in `theme`: a more opaque background first, then black or white text
+fn adjust(settings: &AppSettings, theme: &str, screen: f32) -> ContrastAdjustment {
+    let (text_color, background_color) = theme::caption_colors(settings, theme);
+    let opacity = settings.overlay.opacity.clamp(0.0, 1.0);
+    let min_ratio = settings.contrast.min_ratio.clamp(1.0, 21.0);
+    let background = hex_luminance(background_color).unwrap_or(0.0);
+    // What is seen behind the text: the caption background over the screen
+    let behind = |alpha: f32| alpha * background + (1.0 - alpha) * screen;
+
+    let flipped = if contrast_ratio(1.0, background) >= contrast_ratio(0.0, background) {
+        ("#ffffff", 1.0)
+    } else {
+        ("#000000", 0.0)
+    };
+    let candidates = [
+        (None, hex_luminance(text_color).unwrap_or(1.0)),
+        (Some(flipped.0), flipped.1),
+    ];
+    let luminance = Some((screen * 100.0).round() / 100.0);
+    for (color, text) in candidates {
+        let mut alpha = opacity;
+        loop {
+            if contrast_ratio(text, behind(alpha)) >= min_ratio {
+                return ContrastAdjustment {
+                    luminance,
+                    opacity: (alpha > opacity).then_some(alpha),
+                    color: color.map(str::to_string),
+                };
+            }
+            if alpha >= 1.0 {
+                break;
+            }
+            alpha = (alpha + OPACITY_STEP).min(1.0);
+        }
+    }
+    // Not reachable with this background colour; the most that can be had
+    ContrastAdjustment {
+        luminance,
+        opacity: (opacity < 1.0).then_some(1.0),
+        color: Some(flipped.0.to_string()),
+    }
+}
+
+// Points around the rectangle at `x`, `y`, in the middle of the band
+fn band_points(x: i32, y: i32, width: i32, height: i32) -> Vec<(i32, i32)> {
+    let half = BAND_PX / 2;
+    let (left, top) = (x - half, y - half);
+    let (width, height) = (width + BAND_PX, height + BAND_PX);
+    let perimeter = 2 * (width + height);
+    (0..SAMPLE_POINTS)
+        .map(|i| {
+            let d = i * perimeter / SAMPLE_POINTS;
+            if d < width {
+                (left + d, top)
+            } else if d < width + height {
+                (left + width, top + d - width)
+            } else if d < 2 * width + height {
+                (left + width - (d - width - height), top + height)
+            } else {
+                (left, top + height - (d - 2 * width - height))
+            }
+        })
+        .collect()
+}
+
+// Average luminance around the public overlay; None while it is hidden or off screen
+fn sample(app: &AppHandle, screen: &screen::Screen) -> Result<Option<f32>, String> {
+    let window = match ManagedWindow::OVERLAY.get(app) {
+        Some(window) => window,
+        None => return Ok(None),
+    };
+    if !window.is_visible().map_err(|e| e.to_string())? {
+        return Ok(None);
+    }
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    let points = band_points(
+        position.x,
+        position.y,
+        size.width as i32,
+        size.height as i32,
+    );
+    let values: Vec<f32> = points
+        .into_iter()
+        .filter_map(|(x, y)| screen.pixel(x, y))
+        .map(|(r, g, b)| luminance(r, g, b))
+        .collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(values.iter().sum::<f32>() / values.len() as f32))
+}
+
+fn emit(app: &AppHandle, adjustment: &ContrastAdjustment) {
+    if let Err(e) = app.emit("contrast-adjust", adjustment) {
+        log::error!("Failed to emit contrast adjustment: {}", e);
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .contrast
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn run(app: &AppHandle, generation: u64, interval: Duration) {
+    let screen = match screen::Screen::open() {
+        Ok(screen) => screen,
+        Err(e) => {
+            log::warn!("Contrast adjustment can't read the screen: {}", e);
+            return;
+        }
+    };
+    let mut last = ContrastAdjustment::default();
+    while is_current(app, generation) {
+        if power::is_saving(app) {
+            std::thread::sleep(interval);
+            continue;
+        }
+        match sample(app, &screen) {
+            Ok(Some(screen_luminance)) => {
+                let theme = match theme::current(app) {
+                    Ok(theme) => theme,
+                    Err(_) => return,
+                };
+                let adjustment = match app.state::<AppState>().settings.lock() {
+                    Ok(settings) => adjust(&settings, theme, screen_luminance),
+                    Err(_) => return,
+                };
+                if adjustment != last {
+                    emit(app, &adjustment);
+                    last = adjustment;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to sample the screen around the overlay: {}", e),
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+// Stop the running sampler and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.contrast.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.contrast.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    // Back to the overlay's own colours until the new sampler has something
+    emit(app, &ContrastAdjustment::default());
+    if !settings.enabled {
+        return;
+    }
+    if !screen::SUPPORTED {
+        log::warn!("Contrast adjustment is not supported on this platform");
+        return;
+    }
+
+    let interval = Duration::from_secs(u64::from(settings.interval_secs.max(1)));
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, generation, interval));
+}
+
+#[cfg(target_os = "linux")]
+mod screen {
+    use libloading::Library;
+    use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
+
+    pub const SUPPORTED: bool = true;
+
+    const LIBRARY_NAMES: &[&str] = &["libX11.so.6", "libX11.so"];
+    // From X.h
+    const ALL_PLANES: c_ulong = !0;
+    const Z_PIXMAP: c_int = 2;
+
+    type OpenDisplayFn = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+    type CloseDisplayFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type DefaultScreenFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type DisplaySizeFn = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+    type RootWindowFn = unsafe extern "C" fn(*mut c_void, c_int) -> c_ulong;
+    type GetImageFn = unsafe extern "C" fn(
+        *mut c_void,
+        c_ulong,
+        c_int,
+        c_int,
+        c_uint,
+        c_uint,
+        c_ulong,
+        c_int,
+    ) -> *mut c_void;
+    type GetPixelFn = unsafe extern "C" fn(*mut c_void, c_int, c_int) -> c_ulong;
+    type DestroyImageFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+    // A connection of our own to the X server
+    pub struct Screen {
+        display: *mut c_void,
+        root: c_ulong,
+        width: c_int,
+        height: c_int,
+        close_display: CloseDisplayFn,
+        get_image: GetImageFn,
+        get_pixel: GetPixelFn,
+        destroy_image: DestroyImageFn,
+        // Keeps the function pointers above valid
+        _library: Library,
+    }
+
+    impl Screen {
+        pub fn open() -> Result<Self, String> {
+            let mut last_error = String::new();
+            for name in LIBRARY_NAMES {
+                // SAFETY: the signatures above are from Xlib.h; the display is checked before
+                // use and closed on drop
+                unsafe {
+                    let library = match Library::new(name) {
+                        Ok(library) => library,
+                        Err(e) => {
+                            last_error = e.to_string();
+                            continue;
+                        }
+                    };
+                    let open_display = *library
+                        .get::<OpenDisplayFn>(b"XOpenDisplay\0")
+                        .map_err(|e| e.to_string())?;
+                    let default_screen = *library
+                        .get::<DefaultScreenFn>(b"XDefaultScreen\0")
+                        .map_err(|e| e.to_string())?;
+                    let display_width = *library
+                        .get::<DisplaySizeFn>(b"XDisplayWidth\0")
+                        .map_err(|e| e.to_string())?;
+                    let display_height = *library
+                        .get::<DisplaySizeFn>(b"XDisplayHeight\0")
+                        .map_err(|e| e.to_string())?;
+                    let root_window = *library
+                        .get::<RootWindowFn>(b"XRootWindow\0")
+                        .map_err(|e| e.to_string())?;
+                    let close_display = *library
+                        .get::<CloseDisplayFn>(b"XCloseDisplay\0")
+                        .map_err(|e| e.to_string())?;
+                    let get_image = *library
+                        .get::<GetImageFn>(b"XGetImage\0")
+                        .map_err(|e| e.to_string())?;
+                    let get_pixel = *library
+                        .get::<GetPixelFn>(b"XGetPixel\0")
+                        .map_err(|e| e.to_string())?;
+                    let destroy_image = *library
+                        .get::<DestroyImageFn>(b"XDestroyImage\0")
+                        .map_err(|e| e.to_string())?;
+
+                    let display = open_display(std::ptr::null());
+                    if display.is_null() {
+                        return Err("no X display".to_string());
+                    }
+                    let screen = default_screen(display);
+                    return Ok(Self {
+                        display,
+                        root: root_window(display, screen),
+                        width: display_width(display, screen),
+                        height: display_height(display, screen),
+                        close_display,
+                        get_image,
+                        get_pixel,
+                        destroy_image,
+                        _library: library,
+                    });
+                }
+            }
+            Err(format!("libX11 not found: {}", last_error))
+        }
+
+        // Colour of the pixel at `x`, `y`; None off screen. Assumes a 24-bit TrueColor
+        // visual, which every current X server has.
+        pub fn pixel(&self, x: i32, y: i32) -> Option<(u8, u8, u8)> {
+            // Reading outside the root window is a BadMatch, which Xlib's default handler
+            // answers by exiting
+            if x < 0 || y < 0 || x >= self.width || y >= self.height {
+                return None;
+            }
+            // SAFETY: the display is open and the image is freed before returning
+            unsafe {
+                let image =
+                    (self.get_image)(self.display, self.root, x, y, 1, 1, ALL_PLANES, Z_PIXMAP);
+                if image.is_null() {
+                    return None;
+                }
+                let value = (self.get_pixel)(image, 0, 0);
+                (self.destroy_image)(image);
+                Some(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+            }
+        }
+    }
+
+    impl Drop for Screen {
+        fn drop(&mut self) {
+            // SAFETY: opened in Screen::open and not used after this
+            unsafe {
+                (self.close_display)(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod screen {
+    use libloading::Library;
+    use std::os::raw::{c_int, c_void};
+
+    pub const SUPPORTED: bool = true;
+
+    // GetPixel's answer outside the screen
+    const CLR_INVALID: u32 = 0xFFFF_FFFF;
+
+    type GetDcFn = unsafe extern "system" fn(*mut c_void) -> *mut c_void;
+    type ReleaseDcFn = unsafe extern "system" fn(*mut c_void, *mut c_void) -> c_int;
+    type GetPixelFn = unsafe extern "system" fn(*mut c_void, c_int, c_int) -> u32;
+
+    // Device context of the whole screen
+    pub struct Screen {
+        dc: *mut c_void,
+        release_dc: ReleaseDcFn,
+        get_pixel: GetPixelFn,
+        // Keep the function pointers above valid
+        _user32: Library,
+        _gdi32: Library,
+    }
+
+    impl Screen {
+        pub fn open() -> Result<Self, String> {
+            // SAFETY: user32 and gdi32 come with Windows; the signatures are from WinUser.h
+            // and wingdi.h, and the device context is released on drop
+            unsafe {
+                let user32 = Library::new("user32.dll").map_err(|e| e.to_string())?;
+                let gdi32 = Library::new("gdi32.dll").map_err(|e| e.to_string())?;
+                let get_dc = *user32
+                    .get::<GetDcFn>(b"GetDC\0")
+                    .map_err(|e| e.to_string())?;
+                let release_dc = *user32
+                    .get::<ReleaseDcFn>(b"ReleaseDC\0")
+                    .map_err(|e| e.to_string())?;
+                let get_pixel = *gdi32
+                    .get::<GetPixelFn>(b"GetPixel\0")
+                    .map_err(|e| e.to_string())?;
+                let dc = get_dc(std::ptr::null_mut());
+                if dc.is_null() {
+                    return Err("no screen device context".to_string());
+                }
+                Ok(Self {
+                    dc,
+                    release_dc,
+                    get_pixel,
+                    _user32: user32,
+                    _gdi32: gdi32,
+                })
+            }
+        }
+
+        // Colour of the pixel at `x`, `y`; None off screen
+        pub fn pixel(&self, x: i32, y: i32) -> Option<(u8, u8, u8)> {
+            // SAFETY: the device context is valid until drop
+            let value = unsafe { (self.get_pixel)(self.dc, x, y) };
+            if value == CLR_INVALID {
+                return None;
+            }
+            // COLORREF is 0x00bbggrr
+            Some((value as u8, (value >> 8) as u8, (value >> 16) as u8))
+        }
+    }
+
+    impl Drop for Screen {
+        fn drop(&mut self) {
+            // SAFETY: obtained in Screen::open and not used after this
+            unsafe {
+                (self.release_dc)(std::ptr::null_mut(), self.dc);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod screen {
+    pub const SUPPORTED: bool = false;
+
+    pub struct Screen;
+
+    impl Screen {
+        pub fn open() -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+
+        pub fn pixel(&self, _x: i32, _y: i32) -> Option<(u8, u8, u8)> {
+            None
+        }
+    }
+}
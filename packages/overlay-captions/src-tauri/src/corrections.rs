@@ -0,0 +1,233 @@
+// One-keystroke corrections for the operator. Words the ASR wasn't sure of (below the
+// confidence threshold) are kept as suspects, each with the terminology terms that look
+// like it, so the review window can offer numbered fixes. Applying one rewrites the caption
+// history, asks the main window to correct the shared document (`correction-applied`), and
+// learns the substitution: later captions get it as well, until the session changes.
+
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use tauri::{Emitter, Manager};
+
+// Oldest suspects are forgotten beyond this
+const MAX_SUSPECTS: usize = 20;
+// One number key each
+const MAX_SUGGESTIONS: usize = 9;
+
+#[derive(Default)]
+pub struct CorrectionState {
+    // Low-confidence words, newest last
+    suspects: VecDeque<String>,
+    // Words the operator let stand
+    dismissed: HashSet<String>,
+    learned: Vec<LearnedCorrection>,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct LearnedCorrection {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SuspectWord {
+    pub word: String,
+    // Best first
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CorrectionCandidates {
+    pub terms: Vec<String>,
+    // Newest first
+    pub suspects: Vec<SuspectWord>,
+    pub learned: Vec<LearnedCorrection>,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '\''
+}
+
+// `text` with whole-word occurrences of `from` replaced by `to`
+fn replace_word(text: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(from) {
+        let end = start + from.len();
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+        result.push_str(&rest[..start]);
+        if before.is_some_and(is_word_char) || after.is_some_and(is_word_char) {
+            result.push_str(from);
+        } else {
+            result.push_str(to);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Terms close enough to `word` to be what was said, the closest first
+fn suggestions(word: &str, terms: &[String], learned: &[LearnedCorrection]) -> Vec<String> {
+    let limit = (word.chars().count() / 3).max(1);
+    let mut close: Vec<(usize, &String)> = terms
+        .iter()
+        .filter(|term| *term != word)
+        .map(|term| (edit_distance(word, term), term))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    close.sort_by_key(|(distance, _)| *distance);
+
+    // What it was corrected to before comes first
+    let mut suggestions: Vec<String> = learned
+        .iter()
+        .filter(|l| l.from == word)
+        .map(|l| l.to.clone())
+        .collect();
+    for (_, term) in close {
+        if !suggestions.contains(term) {
+            suggestions.push(term.clone());
+        }
+    }
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+pub fn candidates(app: &AppHandle) -> Result<CorrectionCandidates, String> {
+    let state = app.state::<AppState>();
+    let terms = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .terminology
+        .terms
+        .clone();
+    let corrections = state.corrections.lock().map_err(|e| e.to_string())?;
+    Ok(CorrectionCandidates {
+        suspects: corrections
+            .suspects
+            .iter()
+            .rev()
+            .map(|word| SuspectWord {
+                word: word.clone(),
+                suggestions: suggestions(word, &terms, &corrections.learned),
+            })
+            .collect(),
+        learned: corrections.learned.clone(),
+        terms,
+    })
+}
+
+fn notify_changed(app: &AppHandle) {
+    if let Ok(candidates) = candidates(app) {
+        let _ = app.emit("correction-candidates-changed", candidates);
+    }
+}
+
+// Remember the low-confidence words of a caption
+pub fn note(app: &AppHandle, words: &[String]) {
+    let added = match app.state::<AppState>().corrections.lock() {
+        Ok(mut state) => {
+            let mut added = false;
+            for word in words {
+                let known = state.suspects.contains(word)
+                    || state.dismissed.contains(word)
+                    || state.learned.iter().any(|l| l.from == *word);
+                if known {
+                    continue;
+                }
+                state.suspects.push_back(word.clone());
+                if state.suspects.len() > MAX_SUSPECTS {
+                    state.suspects.pop_front();
+                }
+                added = true;
+            }
+            added
+        }
+        Err(_) => false,
+    };
+    if added {
+        notify_changed(app);
+    }
+}
+
+// A caption with the corrections learned this session
+pub fn apply_learned(app: &AppHandle, text: &str) -> String {
+    match app.state::<AppState>().corrections.lock() {
+        Ok(state) => state.learned.iter().fold(text.to_string(), |text, l| {
+            replace_word(&text, &l.from, &l.to)
+        }),
+        Err(_) => text.to_string(),
+    }
+}
+
+pub fn apply(app: &AppHandle, from: &str, to: &str) -> Result<CorrectionCandidates, String> {
+    let (from, to) = (from.trim(), to.trim());
+    if from.is_empty() || from == to {
+        return Err("Nothing to correct".to_string());
+    }
+    let state = app.state::<AppState>();
+    {
+        let mut corrections = state.corrections.lock().map_err(|e| e.to_string())?;
+        corrections.suspects.retain(|word| word != from);
+        corrections.learned.retain(|l| l.from != from);
+        corrections.learned.push(LearnedCorrection {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+    state
+        .caption_history
+        .lock()
+        .map_err(|e| e.to_string())?
+        .rewrite(|text| replace_word(text, from, to))?;
+    log::info!("Correction learned: {} -> {}", from, to);
+
+    // The main window holds the shared document
+    let correction = LearnedCorrection {
+        from: from.to_string(),
+        to: to.to_string(),
+    };
+    if let Err(e) = ManagedWindow::Main.emit(app, "correction-applied", correction) {
+        log::error!("Failed to emit correction: {}", e);
+    }
+    notify_changed(app);
+    candidates(app)
+}
+
+// Let a suspect word stand; it isn't offered again this session
+pub fn dismiss(app: &AppHandle, word: &str) -> Result<CorrectionCandidates, String> {
+    {
+        let state = app.state::<AppState>();
+        let mut corrections = state.corrections.lock().map_err(|e| e.to_string())?;
+        corrections.suspects.retain(|w| w != word);
+        corrections.dismissed.insert(word.to_string());
+    }
+    notify_changed(app);
+    candidates(app)
+}
+
+// A new session starts over
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut state) = app.state::<AppState>().corrections.lock() {
+        *state = CorrectionState::default();
+    }
+    notify_changed(app);
+}
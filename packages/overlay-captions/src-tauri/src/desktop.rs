@@ -0,0 +1,532 @@
+// Other applications' windows: which one has the focus and where, the titles of all of
+// them and their classes and places, for focus-follow, meeting detection and making way
+// for other caption apps (see arbitration.rs). X11 is asked through libX11, Windows
+// through user32. Wayland doesn't tell clients about other windows, and macOS needs the
+// screen recording permission for their titles, so neither is supported.
+
+pub use platform::{Desktop, SUPPORTED};
+
+// A window's outer position and size in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+// A top-level window of another application
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub title: String,
+    // WM_CLASS on X11 (instance and class), the window class on Windows
+    pub class: String,
+    pub bounds: Bounds,
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{Bounds, Window};
+    use libloading::Library;
+    use std::os::raw::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong, c_void};
+
+    pub const SUPPORTED: bool = true;
+
+    const LIBRARY_NAMES: &[&str] = &["libX11.so.6", "libX11.so"];
+    // From X.h and Xatom.h
+    const SUCCESS: c_int = 0;
+    const XA_CARDINAL: c_ulong = 6;
+    const XA_STRING: c_ulong = 31;
+    const XA_WINDOW: c_ulong = 33;
+    const XA_WM_NAME: c_ulong = 39;
+    const XA_WM_CLASS: c_ulong = 67;
+    // More windows than anyone has open
+    const MAX_WINDOWS: c_long = 1024;
+    // In 32-bit units, so 1 KiB of title
+    const MAX_TITLE: c_long = 256;
+
+    type ErrorHandler = Option<unsafe extern "C" fn(*mut c_void, *mut c_void) -> c_int>;
+    type OpenDisplayFn = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+    type CloseDisplayFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type DefaultRootWindowFn = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    type InternAtomFn = unsafe extern "C" fn(*mut c_void, *const c_char, c_int) -> c_ulong;
+    type GetWindowPropertyFn = unsafe extern "C" fn(
+        *mut c_void,
+        c_ulong,
+        c_ulong,
+        c_long,
+        c_long,
+        c_int,
+        c_ulong,
+        *mut c_ulong,
+        *mut c_int,
+        *mut c_ulong,
+        *mut c_ulong,
+        *mut *mut c_uchar,
+    ) -> c_int;
+    type FreeFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type GetGeometryFn = unsafe extern "C" fn(
+        *mut c_void,
+        c_ulong,
+        *mut c_ulong,
+        *mut c_int,
+        *mut c_int,
+        *mut c_uint,
+        *mut c_uint,
+        *mut c_uint,
+        *mut c_uint,
+    ) -> c_int;
+    type TranslateCoordinatesFn = unsafe extern "C" fn(
+        *mut c_void,
+        c_ulong,
+        c_ulong,
+        c_int,
+        c_int,
+        *mut c_int,
+        *mut c_int,
+        *mut c_ulong,
+    ) -> c_int;
+    type SetErrorHandlerFn = unsafe extern "C" fn(ErrorHandler) -> ErrorHandler;
+    type SyncFn = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+
+    // The active window can close between two requests about it; Xlib's default handler
+    // would exit the app over the BadWindow
+    unsafe extern "C" fn ignore_error(_display: *mut c_void, _event: *mut c_void) -> c_int {
+        0
+    }
+
+    // A connection of our own to the X server
+    pub struct Desktop {
+        display: *mut c_void,
+        root: c_ulong,
+        active_atom: c_ulong,
+        pid_atom: c_ulong,
+        client_list_atom: c_ulong,
+        name_atom: c_ulong,
+        utf8_atom: c_ulong,
+        close_display: CloseDisplayFn,
+        get_window_property: GetWindowPropertyFn,
+        free: FreeFn,
+        get_geometry: GetGeometryFn,
+        translate_coordinates: TranslateCoordinatesFn,
+        set_error_handler: SetErrorHandlerFn,
+        sync: SyncFn,
+        // Keeps the function pointers above valid
+        _library: Library,
+    }
+
+    impl Desktop {
+        pub fn open() -> Result<Self, String> {
+            let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|d| !d.is_empty());
+            let forced_x11 = std::env::var("GDK_BACKEND").is_ok_and(|b| b == "x11");
+            if wayland && !forced_x11 {
+                return Err("Wayland doesn't tell where other windows are".to_string());
+            }
+
+            let mut last_error = String::new();
+            for name in LIBRARY_NAMES {
+                // SAFETY: the signatures above are from Xlib.h; the display is checked before
+                // use and closed on drop
+                unsafe {
+                    let library = match Library::new(name) {
+                        Ok(library) => library,
+                        Err(e) => {
+                            last_error = e.to_string();
+                            continue;
+                        }
+                    };
+                    let open_display = *library
+                        .get::<OpenDisplayFn>(b"XOpenDisplay\0")
+                        .map_err(|e| e.to_string())?;
+                    let default_root_window = *library
+                        .get::<DefaultRootWindowFn>(b"XDefaultRootWindow\0")
+                        .map_err(|e| e.to_string())?;
+                    let intern_atom = *library
+                        .get::<InternAtomFn>(b"XInternAtom\0")
+                        .map_err(|e| e.to_string())?;
+                    let close_display = *library
+                        .get::<CloseDisplayFn>(b"XCloseDisplay\0")
+                        .map_err(|e| e.to_string())?;
+                    let get_window_property = *library
+                        .get::<GetWindowPropertyFn>(b"XGetWindowProperty\0")
+                        .map_err(|e| e.to_string())?;
+                    let free = *library
+                        .get::<FreeFn>(b"XFree\0")
+                        .map_err(|e| e.to_string())?;
+                    let get_geometry = *library
+                        .get::<GetGeometryFn>(b"XGetGeometry\0")
+                        .map_err(|e| e.to_string())?;
+                    let translate_coordinates = *library
+                        .get::<TranslateCoordinatesFn>(b"XTranslateCoordinates\0")
+                        .map_err(|e| e.to_string())?;
+                    let set_error_handler = *library
+                        .get::<SetErrorHandlerFn>(b"XSetErrorHandler\0")
+                        .map_err(|e| e.to_string())?;
+                    let sync = *library
+                        .get::<SyncFn>(b"XSync\0")
+                        .map_err(|e| e.to_string())?;
+
+                    let display = open_display(std::ptr::null());
+                    if display.is_null() {
+                        return Err("no X display".to_string());
+                    }
+                    return Ok(Self {
+                        display,
+                        root: default_root_window(display),
+                        active_atom: intern_atom(display, c"_NET_ACTIVE_WINDOW".as_ptr(), 0),
+                        pid_atom: intern_atom(display, c"_NET_WM_PID".as_ptr(), 0),
+                        client_list_atom: intern_atom(display, c"_NET_CLIENT_LIST".as_ptr(), 0),
+                        name_atom: intern_atom(display, c"_NET_WM_NAME".as_ptr(), 0),
+                        utf8_atom: intern_atom(display, c"UTF8_STRING".as_ptr(), 0),
+                        close_display,
+                        get_window_property,
+                        free,
+                        get_geometry,
+                        translate_coordinates,
+                        set_error_handler,
+                        sync,
+                        _library: library,
+                    });
+                }
+            }
+            Err(format!("libX11 not found: {}", last_error))
+        }
+
+        // First 32-bit item of a window property
+        // Up to `max` 32-bit units of a window property: its format (8, 16 or 32), the
+        // number of items and the data, which the caller frees
+        unsafe fn raw_property(
+            &self,
+            window: c_ulong,
+            atom: c_ulong,
+            kind: c_ulong,
+            max: c_long,
+        ) -> Option<(c_int, usize, *mut c_uchar)> {
+            let mut actual_type: c_ulong = 0;
+            let mut actual_format: c_int = 0;
+            let mut items: c_ulong = 0;
+            let mut bytes_after: c_ulong = 0;
+            let mut data: *mut c_uchar = std::ptr::null_mut();
+            let status = (self.get_window_property)(
+                self.display,
+                window,
+                atom,
+                0,
+                max,
+                0,
+                kind,
+                &mut actual_type,
+                &mut actual_format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != SUCCESS || data.is_null() {
+                return None;
+            }
+            Some((actual_format, items as usize, data))
+        }
+
+        // The 32-bit items of a window property, which come as C longs
+        unsafe fn property(
+            &self,
+            window: c_ulong,
+            atom: c_ulong,
+            kind: c_ulong,
+            max: c_long,
+        ) -> Vec<c_ulong> {
+            let (format, items, data) = match self.raw_property(window, atom, kind, max) {
+                Some(property) => property,
+                None => return Vec::new(),
+            };
+            let values = if format == 32 {
+                std::slice::from_raw_parts(data as *const c_ulong, items).to_vec()
+            } else {
+                Vec::new()
+            };
+            (self.free)(data as *mut c_void);
+            values
+        }
+
+        // A text property such as a window's title
+        unsafe fn text_property(
+            &self,
+            window: c_ulong,
+            atom: c_ulong,
+            kind: c_ulong,
+        ) -> Option<String> {
+            let (format, items, data) = self.raw_property(window, atom, kind, MAX_TITLE)?;
+            let text = (format == 8).then(|| {
+                String::from_utf8_lossy(std::slice::from_raw_parts(data, items)).into_owned()
+            });
+            (self.free)(data as *mut c_void);
+            text.filter(|t| !t.is_empty())
+        }
+
+        unsafe fn bounds(&self, window: c_ulong) -> Option<Bounds> {
+            let (mut root, mut x, mut y) = (0, 0, 0);
+            let (mut width, mut height, mut border, mut depth) = (0, 0, 0, 0);
+            if (self.get_geometry)(
+                self.display,
+                window,
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut width,
+                &mut height,
+                &mut border,
+                &mut depth,
+            ) == 0
+            {
+                return None;
+            }
+            let mut child = 0;
+            if (self.translate_coordinates)(
+                self.display,
+                window,
+                self.root,
+                0,
+                0,
+                &mut x,
+                &mut y,
+                &mut child,
+            ) == 0
+            {
+                return None;
+            }
+            Some(Bounds {
+                x,
+                y,
+                width: width as i32,
+                height: height as i32,
+            })
+        }
+
+        // The focused window of another application
+        pub fn active_window(&self) -> Option<Bounds> {
+            // SAFETY: the display is open until drop; errors about a window that has gone
+            // away are ignored for the duration and flushed before the handler is restored
+            unsafe {
+                let previous = (self.set_error_handler)(Some(ignore_error));
+                let bounds = self
+                    .property(self.root, self.active_atom, XA_WINDOW, 1)
+                    .first()
+                    .copied()
+                    .filter(|&window| window != 0)
+                    .filter(|&window| {
+                        self.property(window, self.pid_atom, XA_CARDINAL, 1).first()
+                            != Some(&c_ulong::from(std::process::id()))
+                    })
+                    .and_then(|window| self.bounds(window));
+                (self.sync)(self.display, 0);
+                (self.set_error_handler)(previous);
+                bounds
+            }
+        }
+
+        // Titles of the top-level windows the window manager lists
+        pub fn window_titles(&self) -> Vec<String> {
+            // SAFETY: as in active_window
+            unsafe {
+                let previous = (self.set_error_handler)(Some(ignore_error));
+                let titles = self
+                    .property(self.root, self.client_list_atom, XA_WINDOW, MAX_WINDOWS)
+                    .into_iter()
+                    .filter_map(|window| {
+                        self.text_property(window, self.name_atom, self.utf8_atom)
+                            .or_else(|| self.text_property(window, XA_WM_NAME, XA_STRING))
+                    })
+                    .collect();
+                (self.sync)(self.display, 0);
+                (self.set_error_handler)(previous);
+                titles
+            }
+        }
+
+        // The top-level windows of other applications with their class and place
+        pub fn windows(&self) -> Vec<Window> {
+            // SAFETY: as in active_window
+            unsafe {
+                let previous = (self.set_error_handler)(Some(ignore_error));
+                let own_pid = c_ulong::from(std::process::id());
+                let windows = self
+                    .property(self.root, self.client_list_atom, XA_WINDOW, MAX_WINDOWS)
+                    .into_iter()
+                    .filter(|&window| {
+                        self.property(window, self.pid_atom, XA_CARDINAL, 1).first()
+                            != Some(&own_pid)
+                    })
+                    .filter_map(|window| {
+                        Some(Window {
+                            title: self
+                                .text_property(window, self.name_atom, self.utf8_atom)
+                                .or_else(|| self.text_property(window, XA_WM_NAME, XA_STRING))
+                                .unwrap_or_default(),
+                            // Instance and class, each NUL terminated
+                            class: self
+                                .text_property(window, XA_WM_CLASS, XA_STRING)
+                                .map(|class| class.replace('\0', " ").trim().to_string())
+                                .unwrap_or_default(),
+                            bounds: self.bounds(window)?,
+                        })
+                    })
+                    .collect();
+                (self.sync)(self.display, 0);
+                (self.set_error_handler)(previous);
+                windows
+            }
+        }
+    }
+
+    impl Drop for Desktop {
+        fn drop(&mut self) {
+            // SAFETY: opened in Desktop::open and not used after this
+            unsafe {
+                (self.close_display)(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{Bounds, Window};
+    use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetClassNameW, GetForegroundWindow, GetWindowRect, GetWindowTextW,
+        GetWindowThreadProcessId, IsIconic, IsWindowVisible,
+    };
+
+    pub const SUPPORTED: bool = true;
+
+    pub struct Desktop;
+
+    impl Desktop {
+        pub fn open() -> Result<Self, String> {
+            Ok(Self)
+        }
+
+        // The foreground window of another application
+        pub fn active_window(&self) -> Option<Bounds> {
+            // SAFETY: these only read window state; a window that has gone away makes them
+            // fail, which is checked
+            unsafe {
+                let window = GetForegroundWindow();
+                if window.is_null() || IsIconic(window) != 0 {
+                    return None;
+                }
+                let mut pid = 0;
+                GetWindowThreadProcessId(window, &mut pid);
+                if pid == std::process::id() {
+                    return None;
+                }
+                let mut rect: RECT = std::mem::zeroed();
+                if GetWindowRect(window, &mut rect) == 0 {
+                    return None;
+                }
+                Some(Bounds {
+                    x: rect.left,
+                    y: rect.top,
+                    width: rect.right - rect.left,
+                    height: rect.bottom - rect.top,
+                })
+            }
+        }
+
+        // Titles of the visible top-level windows
+        pub fn window_titles(&self) -> Vec<String> {
+            let mut titles: Vec<String> = Vec::new();
+            // SAFETY: the callback only runs during EnumWindows, while `titles` is alive
+            unsafe {
+                EnumWindows(
+                    Some(collect_title),
+                    &mut titles as *mut Vec<String> as LPARAM,
+                );
+            }
+            titles
+        }
+
+        // The visible top-level windows of other applications with their class and place
+        pub fn windows(&self) -> Vec<Window> {
+            let mut windows: Vec<Window> = Vec::new();
+            // SAFETY: the callback only runs during EnumWindows, while `windows` is alive
+            unsafe {
+                EnumWindows(
+                    Some(collect_window),
+                    &mut windows as *mut Vec<Window> as LPARAM,
+                );
+            }
+            windows
+        }
+    }
+
+    unsafe extern "system" fn collect_window(window: HWND, windows: LPARAM) -> BOOL {
+        if IsWindowVisible(window) == 0 || IsIconic(window) != 0 {
+            return 1;
+        }
+        let mut pid = 0;
+        GetWindowThreadProcessId(window, &mut pid);
+        let mut rect: RECT = std::mem::zeroed();
+        if pid == std::process::id() || GetWindowRect(window, &mut rect) == 0 {
+            return 1;
+        }
+        let mut title = [0u16; 512];
+        let title_len = GetWindowTextW(window, title.as_mut_ptr(), title.len() as i32);
+        let mut class = [0u16; 256];
+        let class_len = GetClassNameW(window, class.as_mut_ptr(), class.len() as i32);
+        let windows = &mut *(windows as *mut Vec<Window>);
+        windows.push(Window {
+            title: String::from_utf16_lossy(&title[..title_len.max(0) as usize]),
+            class: String::from_utf16_lossy(&class[..class_len.max(0) as usize]),
+            bounds: Bounds {
+                x: rect.left,
+                y: rect.top,
+                width: rect.right - rect.left,
+                height: rect.bottom - rect.top,
+            },
+        });
+        // Keep going
+        1
+    }
+
+    unsafe extern "system" fn collect_title(window: HWND, titles: LPARAM) -> BOOL {
+        if IsWindowVisible(window) == 0 {
+            return 1;
+        }
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(window, buffer.as_mut_ptr(), buffer.len() as i32);
+        if len > 0 {
+            let titles = &mut *(titles as *mut Vec<String>);
+            titles.push(String::from_utf16_lossy(&buffer[..len as usize]));
+        }
+        // Keep going
+        1
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use super::{Bounds, Window};
+
+    pub const SUPPORTED: bool = false;
+
+    pub struct Desktop;
+
+    impl Desktop {
+        pub fn open() -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+
+        pub fn active_window(&self) -> Option<Bounds> {
+            None
+        }
+
+        pub fn window_titles(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub fn windows(&self) -> Vec<Window> {
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Connection diagnostics for the diagnostics window: the session connection state as
+// reported by the main window, the latest connection events and the round-trip time to
+// the caption server. Latency is probed with a TCP handshake only while the window is open.
+
+use crate::clock::now_millis;
+use crate::window_manager::ManagedWindow;
+use crate::{failover, network, AppHandle, AppState};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use tauri::{Manager, Url};
+
+const MAX_EVENTS: usize = 20;
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticEvent {
+    // Milliseconds since the Unix epoch
+    pub at: u64,
+    pub kind: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsSnapshot {
+    // "connecting", "connected" or "disconnected"
+    pub status: String,
+    pub server_url: String,
+    pub latency_ms: Option<u64>,
+    // Remote session or virtual machine the app runs in, None on a local machine
+    pub environment: Option<String>,
+    // Newest first
+    pub events: Vec<DiagnosticEvent>,
+}
+
+pub struct Diagnostics {
+    status: String,
+    latency_ms: Option<u64>,
+    events: VecDeque<DiagnosticEvent>,
+    probing: bool,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            status: "disconnected".to_string(),
+            latency_ms: None,
+            events: VecDeque::new(),
+            probing: false,
+        }
+    }
+}
+
+pub fn snapshot(app: &AppHandle) -> Result<DiagnosticsSnapshot, String> {
+    let state = app.state::<AppState>();
+    let server_url = failover::active_url(app);
+    let diagnostics = state.diagnostics.lock().map_err(|e| e.to_string())?;
+    Ok(DiagnosticsSnapshot {
+        status: diagnostics.status.clone(),
+        server_url,
+        latency_ms: diagnostics.latency_ms,
+        environment: state.environment.describe(),
+        events: diagnostics.events.iter().rev().cloned().collect(),
+    })
+}
+
+fn notify_changed(app: &AppHandle) {
+    if !ManagedWindow::Diagnostics.is_open(app) {
+        return;
+    }
+    if let Ok(snapshot) = snapshot(app) {
+        let _ = ManagedWindow::Diagnostics.emit(app, "diagnostics-changed", snapshot);
+    }
+}
+
+// Log a connection event; status events also update the connection state
+pub fn record(app: &AppHandle, kind: &str, detail: Option<String>) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut diagnostics = state.diagnostics.lock().map_err(|e| e.to_string())?;
+        if matches!(kind, "connecting" | "connected" | "disconnected") {
+            diagnostics.status = kind.to_string();
+        }
+        diagnostics.events.push_back(DiagnosticEvent {
+            at: now_millis(),
+            kind: kind.to_string(),
+            detail,
+        });
+        if diagnostics.events.len() > MAX_EVENTS {
+            diagnostics.events.pop_front();
+        }
+    }
+    notify_changed(app);
+    Ok(())
+}
+
+// Ask the main window to drop and re-open the session connection
+pub fn force_reconnect(app: &AppHandle) -> Result<(), String> {
+    record(app, "reconnect", None)?;
+    ManagedWindow::Main.emit(app, "force-reconnect", ())
+}
+
+// Start measuring latency unless a probe is already running; it stops with the window
+pub fn start_probe(app: &AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        let mut diagnostics = match state.diagnostics.lock() {
+            Ok(d) => d,
+            Err(_) => return,
+        };
+        if diagnostics.probing {
+            return;
+        }
+        diagnostics.probing = true;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while ManagedWindow::Diagnostics.is_open(&app) {
+            let server_url = failover::active_url(&app);
+            let latency_ms = match measure_latency(&server_url) {
+                Ok(latency) => Some(latency.as_millis() as u64),
+                Err(e) => {
+                    log::warn!("Latency probe to {} failed: {}", server_url, e);
+                    None
+                }
+            };
+            if let Ok(mut diagnostics) = app.state::<AppState>().diagnostics.lock() {
+                diagnostics.latency_ms = latency_ms;
+            }
+            notify_changed(&app);
+            std::thread::sleep(PROBE_INTERVAL);
+        }
+
+        if let Ok(mut diagnostics) = app.state::<AppState>().diagnostics.lock() {
+            diagnostics.probing = false;
+            diagnostics.latency_ms = None;
+        }
+    });
+}
+
+// Time a TCP handshake with the server behind a ws:// or wss:// URL
+pub fn measure_latency(server_url: &str) -> Result<Duration, String> {
+    let url = Url::parse(server_url).map_err(|e| e.to_string())?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Server URL has no host".to_string())?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addr = network::resolve(host, port)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Could not resolve {}", host))?;
+
+    let started = Instant::now();
+    TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).map_err(|e| e.to_string())?;
+    Ok(started.elapsed())
+}
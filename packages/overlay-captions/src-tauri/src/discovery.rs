@@ -0,0 +1,169 @@
+// mDNS/zeroconf for the relay server (see relay.rs): while the relay runs it is advertised
+// as _jutukuva._tcp on the LAN, so viewer instances and the mobile remote can find the
+// caption machine without anyone typing in IP addresses. LAN sessions (see lan_session.rs)
+// are advertised the same way under their own service type.
+
+use crate::{AppHandle, AppState};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const SERVICE_TYPE: &str = "_jutukuva._tcp.local.";
+
+// How long discover_relays listens for answers
+pub const BROWSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct DiscoveryState {
+    // Started on first use, shared by advertising and browsing
+    daemon: Option<ServiceDaemon>,
+    // Full names of the services we advertise, by service type
+    advertised: HashMap<&'static str, String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayInfo {
+    pub name: String,
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub port: u16,
+}
+
+pub fn host_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "jutukuva".to_string())
+}
+
+fn daemon(state: &mut DiscoveryState) -> Result<ServiceDaemon, String> {
+    if let Some(daemon) = &state.daemon {
+        return Ok(daemon.clone());
+    }
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    state.daemon = Some(daemon.clone());
+    Ok(daemon)
+}
+
+// Advertise the relay on `port`, or stop advertising it with None
+pub fn advertise(app: &AppHandle, port: Option<u16>) -> Result<(), String> {
+    register(app, SERVICE_TYPE, port, &[])
+}
+
+// Advertise a service of `service_type` on `port` with extra TXT `properties`, or stop
+// advertising it with None
+pub fn register(
+    app: &AppHandle,
+    service_type: &'static str,
+    port: Option<u16>,
+    properties: &[(&str, &str)],
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut discovery = state.discovery.lock().map_err(|e| e.to_string())?;
+
+    if let Some(fullname) = discovery.advertised.remove(service_type) {
+        if let Some(daemon) = &discovery.daemon {
+            let _ = daemon.unregister(&fullname);
+        }
+    }
+    let port = match port {
+        Some(port) => port,
+        None => return Ok(()),
+    };
+
+    let host = host_name();
+    let mut txt = vec![("version", env!("CARGO_PKG_VERSION"))];
+    txt.extend_from_slice(properties);
+    let service = ServiceInfo::new(
+        service_type,
+        &format!("Jutukuva ({})", host),
+        &format!("{}.local.", host),
+        "",
+        port,
+        &txt[..],
+    )
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+    let fullname = service.get_fullname().to_string();
+
+    daemon(&mut discovery)?
+        .register(service)
+        .map_err(|e| e.to_string())?;
+    log::info!("Advertising {}", fullname);
+    discovery.advertised.insert(service_type, fullname);
+    Ok(())
+}
+
+// Stop advertising everything, e.g. on exit
+pub fn withdraw_all(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let mut discovery = match state.discovery.lock() {
+        Ok(discovery) => discovery,
+        Err(_) => return,
+    };
+    let advertised: Vec<String> = discovery.advertised.drain().map(|(_, name)| name).collect();
+    if let Some(daemon) = &discovery.daemon {
+        for fullname in advertised {
+            let _ = daemon.unregister(&fullname);
+        }
+    }
+}
+
+// Services of `service_type` answering on the LAN within `timeout`; blocks for that long
+pub fn browse(
+    app: &AppHandle,
+    service_type: &str,
+    timeout: Duration,
+) -> Result<Vec<ServiceInfo>, String> {
+    let daemon = {
+        let state = app.state::<AppState>();
+        let mut discovery = state.discovery.lock().map_err(|e| e.to_string())?;
+        daemon(&mut discovery)?
+    };
+    let events = daemon.browse(service_type).map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut services: Vec<ServiceInfo> = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match events.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                services.retain(|s| s.get_fullname() != info.get_fullname());
+                services.push(info);
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    let _ = daemon.stop_browse(service_type);
+    Ok(services)
+}
+
+// Instance name without the service type, e.g. "Jutukuva (studio-pc)"
+pub fn instance_name<'a>(info: &'a ServiceInfo, service_type: &str) -> &'a str {
+    info.get_fullname()
+        .trim_end_matches(service_type)
+        .trim_end_matches('.')
+}
+
+// Relays answering on the LAN within `timeout`; blocks for that long
+pub fn discover(app: &AppHandle, timeout: Duration) -> Result<Vec<RelayInfo>, String> {
+    Ok(browse(app, SERVICE_TYPE, timeout)?
+        .iter()
+        .map(|info| {
+            let mut addresses: Vec<String> =
+                info.get_addresses().iter().map(|a| a.to_string()).collect();
+            addresses.sort();
+            RelayInfo {
+                name: instance_name(info, SERVICE_TYPE).to_string(),
+                host: info.get_hostname().trim_end_matches('.').to_string(),
+                addresses,
+                port: info.get_port(),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,152 @@
+// Display delay: captions reach the overlay and the other public outputs
+// `display_delay.delay_ms` later, to line them up with a PA system or a broadcast delay line
+// behind which the audience hears the speaker. The operator overlay is not delayed.
+//
+// The delay is tuned live from the settings drawer or with the global shortcuts in
+// `display_delay`; captions already waiting follow the new delay.
+
+use crate::commands::{apply_settings_change, CaptionPayload};
+use crate::{AppHandle, AppState};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+// Longer than any PA or broadcast delay line
+pub const MAX_DELAY_MS: u64 = 30_000;
+// How often a waiting caption checks whether the delay was changed
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+struct DelayedCaption {
+    received: Instant,
+    payload: CaptionPayload,
+}
+
+#[derive(Default)]
+pub struct DisplayDelayState {
+    // Captions waiting out the delay, in order. Once there has been a delay every caption
+    // goes through here, so one sent with a shorter delay can't overtake those waiting.
+    queue: Option<Sender<DelayedCaption>>,
+    // Global shortcuts registered for adjusting the delay
+    shortcuts: Vec<String>,
+}
+
+fn delay(app: &AppHandle) -> Duration {
+    let delay_ms = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map(|s| s.display_delay.delay_ms)
+        .unwrap_or(0);
+    Duration::from_millis(delay_ms.min(MAX_DELAY_MS))
+}
+
+// Hold a caption back for the display delay; false when it can be shown right away
+pub fn hold(app: &AppHandle, payload: &CaptionPayload) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    let mut display_delay = state.display_delay.lock().map_err(|e| e.to_string())?;
+    if display_delay.queue.is_none() {
+        if delay(app).is_zero() {
+            return Ok(false);
+        }
+        let (sender, receiver) = mpsc::channel();
+        display_delay.queue = Some(sender);
+        let app = app.clone();
+        std::thread::spawn(move || deliver(&app, receiver));
+    }
+    if let Some(queue) = &display_delay.queue {
+        queue
+            .send(DelayedCaption {
+                received: Instant::now(),
+                payload: payload.clone(),
+            })
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(true)
+}
+
+fn deliver(app: &AppHandle, captions: Receiver<DelayedCaption>) {
+    for caption in captions {
+        // The delay is read again while waiting so adjustments apply at once
+        loop {
+            let remaining =
+                (caption.received + delay(app)).saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            std::thread::sleep(remaining.min(POLL_INTERVAL));
+        }
+        if let Err(e) = crate::commands::deliver_caption(app, &caption.payload) {
+            log::error!("Failed to publish delayed caption: {}", e);
+        }
+    }
+}
+
+pub fn set(app: &AppHandle, delay_ms: u64) -> Result<u64, String> {
+    let delay_ms = delay_ms.min(MAX_DELAY_MS);
+    apply_settings_change(app, |settings| settings.display_delay.delay_ms = delay_ms)?;
+    log::info!("Display delay set to {} ms", delay_ms);
+    Ok(delay_ms)
+}
+
+pub fn step(app: &AppHandle, delta_ms: i64) -> Result<u64, String> {
+    let current = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .display_delay
+        .delay_ms;
+    set(app, current.saturating_add_signed(delta_ms))
+}
+
+// (Re-)register the shortcuts for a longer and a shorter delay from settings
+pub fn register_shortcuts(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let shortcuts = match state.settings.lock() {
+        Ok(s) => s.display_delay.clone(),
+        Err(_) => return,
+    };
+    let mut display_delay = match state.display_delay.lock() {
+        Ok(d) => d,
+        Err(_) => return,
+    };
+
+    for shortcut in display_delay.shortcuts.drain(..) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut.as_str()) {
+            log::warn!(
+                "Failed to unregister display delay shortcut {}: {}",
+                shortcut,
+                e
+            );
+        }
+    }
+
+    let step_ms = shortcuts.step_ms.min(MAX_DELAY_MS) as i64;
+    for (shortcut, delta_ms) in [
+        (shortcuts.later_shortcut, step_ms),
+        (shortcuts.sooner_shortcut, -step_ms),
+    ] {
+        let shortcut = shortcut.trim().to_string();
+        if shortcut.is_empty() {
+            continue;
+        }
+        let result =
+            app.global_shortcut()
+                .on_shortcut(shortcut.as_str(), move |app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        if let Err(e) = step(app, delta_ms) {
+                            log::error!("Failed to change the display delay: {}", e);
+                        }
+                    }
+                });
+        match result {
+            Ok(()) => display_delay.shortcuts.push(shortcut),
+            Err(e) => log::error!(
+                "Failed to register display delay shortcut {}: {}",
+                shortcut,
+                e
+            ),
+        }
+    }
+}
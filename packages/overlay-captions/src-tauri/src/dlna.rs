@@ -0,0 +1,233 @@
+// DLNA/UPnP media renderers, for venues with smart TVs that can't be cast to (see cast.rs).
+// Renderers are found with an SSDP search; pushing hands a renderer the TV variant of the
+// caption page (see caption_page.rs) through its AVTransport service and starts it. Whether
+// a renderer shows a web page is up to its firmware; TVs that don't can still open the
+// page's address in their own browser.
+
+use crate::http::{request, split_url};
+use crate::{caption_page, AppHandle, AppState};
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const SSDP_ADDRESS: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+const RENDERER_TYPE: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+const AV_TRANSPORT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+
+// How long a search waits for renderers to answer
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DlnaRenderer {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    #[serde(skip)]
+    control_url: String,
+}
+
+#[derive(Default)]
+pub struct DlnaState {
+    // From the last search, so renderers can be picked by id
+    renderers: Vec<DlnaRenderer>,
+}
+
+// Text of the first <name> element
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", name))? + start;
+    Some(xml[start..end].trim())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Header value from an SSDP response, whose header names vary in case
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    response.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+// Description locations of the renderers answering an SSDP search, with their USNs
+fn search() -> Result<Vec<(String, String)>, String> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .map_err(|e| e.to_string())?;
+    let message = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}:{}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {}\r\n\r\n",
+        SSDP_ADDRESS, SSDP_PORT, RENDERER_TYPE
+    );
+    socket
+        .send_to(message.as_bytes(), (SSDP_ADDRESS, SSDP_PORT))
+        .map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + SEARCH_TIMEOUT;
+    let mut found: Vec<(String, String)> = Vec::new();
+    let mut buf = [0u8; 2048];
+    while Instant::now() < deadline {
+        let n = match socket.recv_from(&mut buf) {
+            Ok((n, _)) => n,
+            Err(_) => continue,
+        };
+        let response = String::from_utf8_lossy(&buf[..n]);
+        if let (Some(location), Some(usn)) =
+            (header(&response, "location"), header(&response, "usn"))
+        {
+            if !found.iter().any(|(l, _)| l == location) {
+                found.push((location.to_string(), usn.to_string()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Renderer from its device description, None if it has no AVTransport service
+fn describe(location: &str, usn: &str) -> Result<Option<DlnaRenderer>, String> {
+    let description = request(location, "GET", &[], "")?;
+    let service = match description
+        .split("<service>")
+        .find(|service| service.contains(AV_TRANSPORT))
+    {
+        Some(service) => service,
+        None => return Ok(None),
+    };
+    let control = element(service, "controlURL").ok_or("No AVTransport control URL")?;
+
+    let (host, _) = split_url(location).ok_or("Unsupported location")?;
+    let base = element(&description, "URLBase")
+        .map(|base| base.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("http://{}", host));
+    let control_url = if control.starts_with("http://") {
+        control.to_string()
+    } else if control.starts_with('/') {
+        format!("{}{}", base, control)
+    } else {
+        format!("{}/{}", base, control)
+    };
+
+    let id = usn.split("::").next().unwrap_or(usn).to_string();
+    Ok(Some(DlnaRenderer {
+        name: element(&description, "friendlyName")
+            .map(xml_unescape)
+            .unwrap_or_else(|| id.clone()),
+        id,
+        address: host.rsplit_once(':').map_or(host, |(ip, _)| ip).to_string(),
+        control_url,
+    }))
+}
+
+// Renderers on the LAN; blocks while they answer
+pub fn list_renderers(app: &AppHandle) -> Result<Vec<DlnaRenderer>, String> {
+    let mut renderers: Vec<DlnaRenderer> = Vec::new();
+    for (location, usn) in search()? {
+        match describe(&location, &usn) {
+            Ok(Some(renderer)) if !renderers.iter().any(|r| r.id == renderer.id) => {
+                renderers.push(renderer)
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("Skipping renderer at {}: {}", location, e),
+        }
+    }
+    renderers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let state = app.state::<AppState>();
+    state.dlna.lock().map_err(|e| e.to_string())?.renderers = renderers.clone();
+    Ok(renderers)
+}
+
+fn renderer(app: &AppHandle, id: &str) -> Result<DlnaRenderer, String> {
+    let state = app.state::<AppState>();
+    let dlna = state.dlna.lock().map_err(|e| e.to_string())?;
+    dlna.renderers
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown renderer: {}", id))
+}
+
+fn invoke(renderer: &DlnaRenderer, action: &str, arguments: &str) -> Result<(), String> {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body>\
+         <u:{action} xmlns:u=\"{service}\"><InstanceID>0</InstanceID>{arguments}</u:{action}>\
+         </s:Body></s:Envelope>",
+        action = action,
+        service = AV_TRANSPORT,
+        arguments = arguments
+    );
+    let soap_action = format!("\"{}#{}\"", AV_TRANSPORT, action);
+    request(
+        &renderer.control_url,
+        "POST",
+        &[
+            ("Content-Type", "text/xml; charset=\"utf-8\""),
+            ("SOAPACTION", &soap_action),
+        ],
+        &body,
+    )
+    .map(|_| ())
+    .map_err(|e| format!("{} on {} failed: {}", action, renderer.name, e))
+}
+
+// Show the TV caption page on a renderer from the last list_renderers
+pub fn push(app: &AppHandle, id: &str) -> Result<(), String> {
+    let renderer = renderer(app, id)?;
+    let port = caption_page::start(app)?;
+    let ip: IpAddr = renderer
+        .address
+        .parse()
+        .map_err(|_| format!("Invalid renderer address: {}", renderer.address))?;
+    let url = caption_page::url(ip, port, "/tv")?;
+
+    // Some renderers refuse a URI without DIDL-Lite metadata saying what it is
+    let metadata = format!(
+        "<DIDL-Lite xmlns=\"urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:upnp=\"urn:schemas-upnp-org:metadata-1-0/upnp/\">\
+         <item id=\"0\" parentID=\"-1\" restricted=\"1\"><dc:title>Jutukuva</dc:title>\
+         <upnp:class>object.item</upnp:class>\
+         <res protocolInfo=\"http-get:*:text/html:*\">{}</res></item></DIDL-Lite>",
+        xml_escape(&url)
+    );
+    invoke(
+        &renderer,
+        "SetAVTransportURI",
+        &format!(
+            "<CurrentURI>{}</CurrentURI><CurrentURIMetaData>{}</CurrentURIMetaData>",
+            xml_escape(&url),
+            xml_escape(&metadata)
+        ),
+    )?;
+    invoke(&renderer, "Play", "<Speed>1</Speed>")?;
+    log::info!("Pushed {} to {}", url, renderer.name);
+    Ok(())
+}
+
+pub fn stop(app: &AppHandle, id: &str) -> Result<(), String> {
+    invoke(&renderer(app, id)?, "Stop", "")
+}
+
+// Address to type into a TV browser, on the interface the LAN's multicast goes out on
+pub fn tv_page_url(app: &AppHandle) -> Result<String, String> {
+    let port = caption_page::start(app)?;
+    caption_page::url(IpAddr::V4(SSDP_ADDRESS), port, "/tv")
+}
@@ -0,0 +1,163 @@
+// Where an overlay can be picked up and moved. Each overlay page declares its drag handle
+// with `set_drag_region`, in CSS pixels of its window. A click-through window gets no mouse
+// input at all, so while one is click-through a watcher follows the cursor and lets input
+// through only over the handle, which keeps the overlay movable without it ever catching a
+// click meant for the slides underneath. Drags are started here too, and only from inside
+// the declared region.
+
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState, WebviewWindow};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::Manager;
+
+// Fast enough that the handle reacts before the pointer has crossed it
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const OVERLAYS: [ManagedWindow; 2] = [ManagedWindow::OVERLAY, ManagedWindow::OPERATOR_OVERLAY];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DragRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Default)]
+pub struct DragRegionState {
+    // Bumped to stop the running watcher
+    generation: u64,
+    // By window label
+    regions: HashMap<String, DragRect>,
+    // Labels of the click-through windows
+    click_through: HashSet<&'static str>,
+}
+
+// Whether the cursor is over `rect` of `window`
+fn contains(app: &AppHandle, window: &WebviewWindow, rect: &DragRect) -> Result<bool, String> {
+    let cursor = app.cursor_position().map_err(|e| e.to_string())?;
+    let origin = window.inner_position().map_err(|e| e.to_string())?;
+    let scale = window.scale_factor().map_err(|e| e.to_string())?;
+    let (x, y) = (
+        (cursor.x - f64::from(origin.x)) / scale,
+        (cursor.y - f64::from(origin.y)) / scale,
+    );
+    Ok(x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height)
+}
+
+// The overlay page declared its handle; None when it has none
+pub fn set_region(app: &AppHandle, label: &str, rect: Option<DragRect>) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut state = state.drag_region.lock().map_err(|e| e.to_string())?;
+        match rect {
+            Some(rect) => state.regions.insert(label.to_string(), rect),
+            None => state.regions.remove(label),
+        };
+    }
+    restart(app);
+    Ok(())
+}
+
+// `window` was made click-through or took input again
+pub fn set_click_through(app: &AppHandle, window: ManagedWindow, enabled: bool) {
+    if let Ok(mut state) = app.state::<AppState>().drag_region.lock() {
+        if enabled {
+            state.click_through.insert(window.label());
+        } else {
+            state.click_through.remove(window.label());
+        }
+    }
+    restart(app);
+}
+
+pub fn is_click_through(app: &AppHandle, label: &str) -> bool {
+    app.state::<AppState>()
+        .drag_region
+        .lock()
+        .map(|s| s.click_through.contains(label))
+        .unwrap_or(false)
+}
+
+// Move `window` along with the mouse, if the press was on its handle
+pub fn start_dragging(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let rect = {
+        let state = app.state::<AppState>();
+        let state = state.drag_region.lock().map_err(|e| e.to_string())?;
+        state.regions.get(window.label()).copied()
+    };
+    if let Some(rect) = rect {
+        if !contains(app, window, &rect)? {
+            return Err("Not in the drag region".to_string());
+        }
+    }
+    window.start_dragging().map_err(|e| e.to_string())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .drag_region
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running watcher and start one if a click-through window has a handle
+fn restart(app: &AppHandle) {
+    let watched = match app.state::<AppState>().drag_region.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            let watched = OVERLAYS
+                .iter()
+                .any(|w| s.click_through.contains(w.label()) && s.regions.contains_key(w.label()));
+            watched.then_some(s.generation)
+        }
+        Err(_) => return,
+    };
+
+    if let Some(generation) = watched {
+        let app = app.clone();
+        std::thread::spawn(move || watch(&app, generation));
+    }
+}
+
+fn watch(app: &AppHandle, generation: u64) {
+    // Whether each window currently takes input over its handle
+    let mut grabbable: HashMap<&'static str, bool> = HashMap::new();
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let watched: Vec<(ManagedWindow, DragRect)> = {
+            let state = app.state::<AppState>();
+            let state = match state.drag_region.lock() {
+                Ok(s) if s.generation == generation => s,
+                _ => return,
+            };
+            OVERLAYS
+                .iter()
+                .filter(|w| state.click_through.contains(w.label()))
+                .filter_map(|w| state.regions.get(w.label()).map(|rect| (*w, *rect)))
+                .collect()
+        };
+
+        for (managed, rect) in watched {
+            let window = match managed.get(app) {
+                Some(window) => window,
+                None => continue,
+            };
+            let inside = match contains(app, &window, &rect) {
+                Ok(inside) => inside,
+                Err(_) => continue,
+            };
+            if grabbable.get(managed.label()) == Some(&inside) || !is_current(app, generation) {
+                continue;
+            }
+            if let Err(e) = window.set_ignore_cursor_events(!inside) {
+                log::error!("Failed to update click-through: {}", e);
+                continue;
+            }
+            grabbable.insert(managed.label(), inside);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// Remote Desktop, VDI and virtual machine sessions. Over a remote session the window
+// manager on the far side is free to drop our always-on-top, and a reconnect from another
+// client brings a new resolution and DPI that can leave the overlay off screen. Detected
+// once at startup; when either applies, a keeper puts the overlays back on top and on
+// screen every few seconds and after each scale change, and diagnostics say why.
+
+use crate::window_manager::OverlayId;
+use crate::{diagnostics, resume, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+// Reconnects aren't announced, so the layering is checked this often
+const KEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+// Hypervisors by what they put in the firmware vendor and product strings
+const HYPERVISORS: &[(&str, &str)] = &[
+    ("VMware", "VMware"),
+    ("VirtualBox", "VirtualBox"),
+    ("innotek", "VirtualBox"),
+    ("QEMU", "QEMU/KVM"),
+    ("KVM", "QEMU/KVM"),
+    ("Xen", "Xen"),
+    ("Parallels", "Parallels"),
+    ("Virtual Machine", "Hyper-V"),
+];
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    // "Remote Desktop", "Citrix" or "xrdp"; None on the local console
+    pub remote_session: Option<String>,
+    // The hypervisor; None on real hardware
+    pub virtual_machine: Option<String>,
+}
+
+impl Environment {
+    pub fn detect() -> Self {
+        Self {
+            remote_session: remote_session(),
+            virtual_machine: virtual_machine(),
+        }
+    }
+
+    // Whether the adapted window strategy applies
+    pub fn is_adapted(&self) -> bool {
+        self.remote_session.is_some() || self.virtual_machine.is_some()
+    }
+
+    // For the diagnostics window, e.g. "Remote Desktop, VMware virtual machine"
+    pub fn describe(&self) -> Option<String> {
+        let parts: Vec<String> = self
+            .remote_session
+            .iter()
+            .cloned()
+            .chain(
+                self.virtual_machine
+                    .iter()
+                    .map(|vm| format!("{} virtual machine", vm)),
+            )
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+fn hypervisor(firmware: &str) -> Option<String> {
+    HYPERVISORS
+        .iter()
+        .find(|(marker, _)| firmware.contains(marker))
+        .map(|(_, name)| name.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn remote_session() -> Option<String> {
+    std::env::var_os("XRDP_SESSION").map(|_| "xrdp".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn virtual_machine() -> Option<String> {
+    let read = |path: &str| std::fs::read_to_string(path).unwrap_or_default();
+    let firmware = format!(
+        "{} {}",
+        read("/sys/class/dmi/id/sys_vendor").trim(),
+        read("/sys/class/dmi/id/product_name").trim()
+    );
+    // Xen guests often have no DMI strings at all
+    hypervisor(&firmware).or_else(|| match read("/sys/hypervisor/type").trim() {
+        "" => None,
+        "xen" => Some("Xen".to_string()),
+        other => Some(other.to_string()),
+    })
+}
+
+#[cfg(windows)]
+fn remote_session() -> Option<String> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_REMOTESESSION};
+
+    // Citrix sessions are named ICA-tcp#0 and the like
+    let session = std::env::var("SESSIONNAME").unwrap_or_default();
+    if session.starts_with("ICA") {
+        return Some("Citrix".to_string());
+    }
+    // SAFETY: GetSystemMetrics only reads
+    let remote = unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0;
+    (remote || session.starts_with("RDP-")).then(|| "Remote Desktop".to_string())
+}
+
+#[cfg(windows)]
+fn virtual_machine() -> Option<String> {
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let key = wide("HARDWARE\\DESCRIPTION\\System\\BIOS");
+    let read = |name: &str| {
+        let name = wide(name);
+        let mut buffer = [0u16; 256];
+        let mut len = std::mem::size_of_val(&buffer) as u32;
+        // SAFETY: the buffer and its length in bytes are passed together
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut _,
+                &mut len,
+            )
+        };
+        if status != 0 {
+            return String::new();
+        }
+        let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        String::from_utf16_lossy(&buffer[..end])
+    };
+    hypervisor(&format!(
+        "{} {}",
+        read("SystemManufacturer"),
+        read("SystemProductName")
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn remote_session() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn virtual_machine() -> Option<String> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "kern.hv_vmm_present"])
+        .output()
+        .ok()?;
+    (String::from_utf8_lossy(&output.stdout).trim() == "1").then(|| "macOS".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn remote_session() -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn virtual_machine() -> Option<String> {
+    None
+}
+
+pub fn is_adapted(app: &AppHandle) -> bool {
+    app.state::<AppState>().environment.is_adapted()
+}
+
+// Note the environment in diagnostics and keep the overlays in place for as long as the
+// app runs, if it needs it
+pub fn start(app: &AppHandle) {
+    let description = match app.state::<AppState>().environment.describe() {
+        Some(d) => d,
+        None => return,
+    };
+    log::info!(
+        "Running in {}, keeping the overlays on top and on screen",
+        description
+    );
+    let _ = diagnostics::record(app, "environment", Some(description));
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(KEEP_INTERVAL);
+        restore_overlays(&app);
+    });
+}
+
+// A window moved to a monitor or session with another DPI
+pub fn scale_changed(app: &AppHandle) {
+    if is_adapted(app) {
+        restore_overlays(app);
+    }
+}
+
+fn restore_overlays(app: &AppHandle) {
+    for id in [OverlayId::Public, OverlayId::Operator] {
+        if let Err(e) = resume::restore_overlay(app, id) {
+            log::error!("Failed to restore overlay: {}", e);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::settings::AppSettings;
+use crate::window_manager::ManagedWindow;
+use crate::AppHandle;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{Emitter, Manager};
+
+// Processed captions for the public overlay and everything downstream of it
+pub const CAPTIONS: &str = "captions";
+// Unreviewed captions with low-confidence words marked, for the operator
+pub const OPERATOR_CAPTIONS: &str = "operator-captions";
+// The operator's line of information beside the captions (see info_line.rs)
+pub const INFO_LINE: &str = "info-line";
+// Speaker name cards (see lower_third.rs)
+pub const LOWER_THIRD: &str = "lower-third";
+// Captions as shown, but with muted speakers left in, for the logs. Published alongside
+// CAPTIONS and never shown anywhere, so it has no style or frontend event.
+pub const RECORD: &str = "record";
+pub const CHANNELS: [&str; 4] = [CAPTIONS, OPERATOR_CAPTIONS, INFO_LINE, LOWER_THIRD];
+
+// Where routed events end up: a window, a log, later a relay server or OBS. Each output
+// subscribes to one or more channels and can be switched off in `settings.outputs`.
+pub trait Output: Send {
+    fn id(&self) -> &str;
+
+    fn channels(&self) -> &[&'static str];
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        channel: &str,
+        payload: &Value,
+    ) -> Result<(), String>;
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputInfo {
+    pub id: String,
+    pub channels: Vec<String>,
+    pub enabled: bool,
+}
+
+#[derive(Default)]
+pub struct EventRouter {
+    outputs: Vec<Box<dyn Output>>,
+}
+
+impl EventRouter {
+    pub fn add(&mut self, output: Box<dyn Output>) {
+        log::info!("Registered output: {}", output.id());
+        self.outputs.push(output);
+    }
+
+    pub fn publish(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        channel: &str,
+        payload: &Value,
+    ) {
+        for output in self
+            .outputs
+            .iter_mut()
+            .filter(|o| o.channels().contains(&channel))
+            .filter(|o| !settings.outputs.disabled.iter().any(|id| id == o.id()))
+        {
+            if let Err(e) = output.deliver(app, settings, channel, payload) {
+                log::error!("Output {} failed on {}: {}", output.id(), channel, e);
+            }
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.outputs.iter().any(|o| o.id() == id)
+    }
+
+    pub fn list(&self, settings: &AppSettings) -> Vec<OutputInfo> {
+        self.outputs
+            .iter()
+            .map(|o| OutputInfo {
+                id: o.id().to_string(),
+                channels: o.channels().iter().map(|c| c.to_string()).collect(),
+                enabled: !settings.outputs.disabled.iter().any(|id| id == o.id()),
+            })
+            .collect()
+    }
+}
+
+// Send a payload to every enabled output subscribed to `channel`
+pub fn publish<T: Serialize>(app: &AppHandle, channel: &str, payload: &T) -> Result<(), String> {
+    let payload = serde_json::to_value(payload).map_err(|e| e.to_string())?;
+    let state = app.state::<crate::AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let mut router = state.router.lock().map_err(|e| e.to_string())?;
+    router.publish(app, &settings, channel, &payload);
+    Ok(())
+}
+
+// Frontend event a channel is emitted as; renamed in `settings.outputs.event_names`
+pub fn event_name<'a>(settings: &'a AppSettings, channel: &str) -> &'a str {
+    if let Some(name) = settings.outputs.event_names.get(channel) {
+        return name;
+    }
+    match channel {
+        CAPTIONS => "caption-update",
+        OPERATOR_CAPTIONS => "operator-caption-update",
+        INFO_LINE => "info-line-update",
+        LOWER_THIRD => "lower-third-update",
+        _ => "",
+    }
+}
+
+// Broadcasts captions, the info line and lower thirds to every window; the public overlay renders them
+struct OverlayOutput;
+
+impl Output for OverlayOutput {
+    fn id(&self) -> &str {
+        "overlay"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS, INFO_LINE, LOWER_THIRD]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        app.emit(event_name(settings, channel), payload)
+            .map_err(|e| e.to_string())
+    }
+}
+
+struct OperatorOverlayOutput;
+
+impl Output for OperatorOverlayOutput {
+    fn id(&self) -> &str {
+        "operator-overlay"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[OPERATOR_CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        ManagedWindow::OPERATOR_OVERLAY.emit(app, event_name(settings, channel), payload)
+    }
+}
+
+struct LogOutput;
+
+impl Output for LogOutput {
+    fn id(&self) -> &str {
+        "log"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        _app: &AppHandle,
+        _settings: &AppSettings,
+        channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        let text = payload
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        log::info!("[{}] {}", channel, crate::text::truncate(text, 50));
+        Ok(())
+    }
+}
+
+pub fn register_builtin(router: &mut EventRouter) {
+    router.add(Box::new(OverlayOutput));
+    router.add(Box::new(OperatorOverlayOutput));
+    router.add(Box::new(LogOutput));
+}
@@ -0,0 +1,116 @@
+// Optional PIN for quitting the app and hiding the overlay, so that people passing an
+// unattended info screen can't dismiss the captions. Unlike kiosk mode (see kiosk.rs) the
+// settings stay open; only the ways out are protected. When the PIN is missing the main
+// window is brought up to ask for it and the action is retried from there with the PIN.
+//
+// `exit_protection.enabled` is a setting, but the PIN is kept in the secrets store (see
+// secrets.rs), so the settings sent to every window don't carry it. Changing the PIN, and
+// turning the protection off or resetting the settings while it is on, take the current PIN.
+
+use crate::window_manager::ManagedWindow;
+use crate::{diagnostics, presentation, secrets, settings_writer, AppHandle, AppState};
+use serde::Serialize;
+use tauri::Manager;
+
+// Error returned when a protected action is attempted without the PIN
+pub const PIN_REQUIRED: &str = "PinRequired";
+const PIN_SECRET: &str = "exitProtection.pin";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PinRequest {
+    // "quit" or "hideOverlay"
+    action: &'static str,
+}
+
+fn configured_pin(app: &AppHandle) -> Option<String> {
+    let state = app.try_state::<AppState>()?;
+    let enabled = state.settings.lock().ok()?.exit_protection.enabled;
+    if enabled {
+        secrets::get(PIN_SECRET)
+    } else {
+        None
+    }
+}
+
+pub fn has_pin() -> bool {
+    secrets::get(PIN_SECRET).is_some()
+}
+
+// Settings from before the PIN moved to the secrets store had it in `exit_protection.pin`
+pub fn migrate(app: &AppHandle) {
+    let pin = match app.state::<AppState>().settings.lock() {
+        Ok(mut settings) => std::mem::take(&mut settings.exit_protection.pin),
+        Err(_) => return,
+    };
+    if pin.trim().is_empty() {
+        return;
+    }
+    match secrets::set(PIN_SECRET, Some(&pin)) {
+        Ok(()) => {
+            log::info!("Exit PIN moved to the secrets store");
+            settings_writer::mark_dirty(app);
+        }
+        Err(e) => log::error!("Failed to move the exit PIN: {}", e),
+    }
+}
+
+// Whether `pin` is the current PIN, when one is set and in force
+fn matches(app: &AppHandle, action: &str, pin: Option<&str>) -> Result<(), String> {
+    let expected = match configured_pin(app) {
+        Some(expected) => expected,
+        None => return Ok(()),
+    };
+    match pin {
+        Some(pin) if pin.trim() == expected => Ok(()),
+        Some(_) => {
+            let _ = diagnostics::record(app, "wrong exit PIN", Some(action.to_string()));
+            Err("Wrong PIN".to_string())
+        }
+        None => Err(PIN_REQUIRED.to_string()),
+    }
+}
+
+// For changes to the protection itself; unlike `check` it doesn't bring up the prompt
+pub fn verify(app: &AppHandle, pin: Option<&str>) -> Result<(), String> {
+    matches(app, "settings", pin)
+}
+
+// Set a new PIN, or remove it with None; takes the current one when there is one
+pub fn set_pin(app: &AppHandle, current: Option<&str>, pin: Option<&str>) -> Result<(), String> {
+    if let Some(expected) = secrets::get(PIN_SECRET) {
+        if current.map(str::trim) != Some(expected.as_str()) {
+            let _ = diagnostics::record(app, "wrong exit PIN", Some("setPin".to_string()));
+            return Err("Wrong PIN".to_string());
+        }
+    }
+    secrets::set(PIN_SECRET, pin)
+}
+
+pub fn is_required(app: &AppHandle) -> bool {
+    configured_pin(app).is_some()
+}
+
+// Ask the main window for the PIN; a presentation keeps the prompt off screen
+pub fn request(app: &AppHandle, action: &'static str) {
+    if presentation::is_active(app) {
+        log::info!(
+            "Presentation mode active, not asking for the PIN to {}",
+            action
+        );
+        return;
+    }
+    let _ = ManagedWindow::Main.focus(app);
+    if let Err(e) = ManagedWindow::Main.emit(app, "pin-required", PinRequest { action }) {
+        log::error!("Failed to ask for the PIN: {}", e);
+    }
+}
+
+// Whether `action` may go ahead with the PIN given, if any
+pub fn check(app: &AppHandle, action: &'static str, pin: Option<&str>) -> Result<(), String> {
+    let result = matches(app, action, pin);
+    if pin.is_none() && result.is_err() {
+        request(app, action);
+    }
+    result
+}
@@ -0,0 +1,984 @@
+// Transcript export: captions with their times written out as subtitles, plain text, or a
+// formatted Word or PDF document with the session's title, date, speaker labels and the
+// header and footer from `settings.export`. From the app the caption history or an archived
+// session is exported to the documents folder. It also runs headless, without the GUI:
+//   jutukuva-subtiitrid convert <log>... --to srt|vtt|txt|docx|pdf|youtube|minutes-json|minutes-md
+//       [--title <t>] [--out <file>]
+//       [--start <time> | --marker <text> [--marker-at <position>]]
+// where each <log> is a caption history file (one {"at", "text"} object per line, as in
+// caption-history.jsonl). The result goes next to the log with the format's extension
+// unless --out names the file, which only works for a single log. Times count from the
+// first caption unless aligned to a separate recording (see `Alignment`): --start is when
+// the recording started, e.g. 2024-05-01T14:03:12+03:00, or --marker the caption seen at
+// --marker-at (e.g. 00:02:15.5, the beginning by default) into the recording. The youtube
+// format is the chapter list (see chapters.rs), and the minutes formats the tagged
+// bookmarks with the captions around them (see minutes.rs); logs carry no bookmarks.
+
+use crate::bookmarks::{self, Bookmark};
+use crate::chapters::{self, Chapter};
+use crate::history::HistoryEntry;
+use crate::minutes;
+use crate::pdf::{self, Font, Page, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::settings::{load_settings, AppSettings, ExportSettings};
+use crate::speaker_colors::{self, SpeakerColors};
+use crate::speaker_names;
+use crate::{clock, AppHandle, AppState};
+use directories::UserDirs;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const COMMAND: &str = "convert";
+const USAGE: &str = "Usage: jutukuva-subtiitrid convert <log>... --to srt|vtt|txt|docx|pdf|youtube|minutes-json|minutes-md [--title <title>] [--out <file>] [--start <time> | --marker <text> [--marker-at <position>]]";
+// A caption stays up until the next one, but no longer than this
+const MAX_CAPTION_MS: u64 = 7000;
+// Longer text before ": " is part of the caption rather than a speaker's name
+const MAX_SPEAKER_CHARS: usize = 40;
+// Font sizes of the formatted documents in points
+const TITLE_SIZE: f32 = 18.0;
+const CHAPTER_SIZE: f32 = 14.0;
+const TEXT_SIZE: f32 = 11.0;
+const HEADER_SIZE: f32 = 9.0;
+// PDF page layout in points and multiples of the font size
+const MARGIN: f32 = 56.0;
+const LINE_HEIGHT: f32 = 1.35;
+const PARAGRAPH_GAP: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Srt,
+    Vtt,
+    Txt,
+    Docx,
+    Pdf,
+    // Chapter list for a YouTube video description
+    Youtube,
+    // Tagged bookmarks with their context, for meeting minutes
+    MinutesJson,
+    MinutesMarkdown,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "srt" => Ok(Format::Srt),
+            "vtt" => Ok(Format::Vtt),
+            "txt" => Ok(Format::Txt),
+            "docx" => Ok(Format::Docx),
+            "pdf" => Ok(Format::Pdf),
+            "youtube" => Ok(Format::Youtube),
+            "minutes-json" => Ok(Format::MinutesJson),
+            "minutes-md" => Ok(Format::MinutesMarkdown),
+            _ => Err(format!("Unknown export format: {}", name)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Srt => "srt",
+            Format::Vtt => "vtt",
+            Format::Txt => "txt",
+            Format::Docx => "docx",
+            Format::Pdf => "pdf",
+            Format::Youtube => "chapters.txt",
+            Format::MinutesJson => "minutes.json",
+            Format::MinutesMarkdown => "minutes.md",
+        }
+    }
+}
+
+// Times in milliseconds from the start of the transcript
+pub struct TimedCaption {
+    pub start: u64,
+    pub end: u64,
+    pub text: String,
+}
+
+// A bookmark's time in milliseconds from the start of the transcript (see bookmarks.rs)
+pub struct TimedBookmark {
+    pub at: u64,
+    pub note: String,
+    pub tag: String,
+}
+
+pub struct Transcript {
+    pub title: String,
+    // Milliseconds since the Unix epoch
+    pub started_at: u64,
+    pub captions: Vec<TimedCaption>,
+    // Oldest first
+    pub bookmarks: Vec<TimedBookmark>,
+}
+
+impl Transcript {
+    pub fn new(
+        title: String,
+        entries: &[HistoryEntry],
+        bookmarks: &[Bookmark],
+        alignment: &Alignment,
+    ) -> Result<Self, String> {
+        let origin = alignment.origin(entries)?;
+        Ok(Transcript {
+            title,
+            started_at: entries.first().map_or_else(clock::now_millis, |e| e.at),
+            captions: timed(entries, alignment)?,
+            // Like captions, none from before time zero
+            bookmarks: bookmarks
+                .iter()
+                .filter_map(|bookmark| {
+                    let at = u64::try_from(bookmark.at as i64 - origin).ok()?;
+                    Some(TimedBookmark {
+                        at,
+                        note: bookmark.note.clone(),
+                        tag: bookmark.tag.clone(),
+                    })
+                })
+                .collect(),
+        })
+    }
+}
+
+// Where the exported times count from. Lining them up with a separate recording of the
+// event saves re-timing the subtitles in an editor.
+pub enum Alignment {
+    FirstCaption,
+    // When the recording started, in milliseconds since the Unix epoch
+    RecordingStart(u64),
+    // The first caption containing `text` is `at` milliseconds into the recording. A caption
+    // that is nothing but the marker, typed when the recording starts, is left out.
+    Marker { text: String, at: u64 },
+}
+
+impl Alignment {
+    // Milliseconds since the Unix epoch of time zero
+    fn origin(&self, entries: &[HistoryEntry]) -> Result<i64, String> {
+        match self {
+            Alignment::FirstCaption => Ok(entries.first().map_or(0, |e| e.at as i64)),
+            Alignment::RecordingStart(at) => Ok(*at as i64),
+            Alignment::Marker { text, at } => {
+                let marker = text.trim().to_lowercase();
+                if marker.is_empty() {
+                    return Err("The marker is empty".to_string());
+                }
+                match entries
+                    .iter()
+                    .find(|entry| entry.text.to_lowercase().contains(&marker))
+                {
+                    Some(entry) => Ok(entry.at as i64 - *at as i64),
+                    None => Err(format!(
+                        "No caption contains the marker \"{}\"",
+                        text.trim()
+                    )),
+                }
+            }
+        }
+    }
+
+    fn is_marker(&self, text: &str) -> bool {
+        match self {
+            Alignment::Marker { text: marker, .. } => {
+                text.trim().to_lowercase() == marker.trim().to_lowercase()
+            }
+            _ => false,
+        }
+    }
+}
+
+// History entries carry only the time a caption appeared; each lasts until the next one.
+// Captions over before time zero are dropped.
+pub fn timed(entries: &[HistoryEntry], alignment: &Alignment) -> Result<Vec<TimedCaption>, String> {
+    let origin = alignment.origin(entries)?;
+    let captions = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.text.trim().is_empty() && !alignment.is_marker(&entry.text))
+        .filter_map(|(i, entry)| {
+            let start = entry.at as i64 - origin;
+            let next = entries
+                .get(i + 1)
+                .map_or(i64::MAX, |next| next.at as i64 - origin);
+            let end = next.min(start + MAX_CAPTION_MS as i64).max(start + 1);
+            if end <= 0 {
+                return None;
+            }
+            Some(TimedCaption {
+                start: start.max(0) as u64,
+                end: end as u64,
+                text: entry.text.trim().to_string(),
+            })
+        })
+        .collect();
+    Ok(captions)
+}
+
+// `palette` colours the speaker names of Word and PDF documents, empty for none
+pub fn render(
+    format: Format,
+    transcript: &Transcript,
+    settings: &ExportSettings,
+    palette: &[String],
+) -> Result<Vec<u8>, String> {
+    let captions = &transcript.captions;
+    match format {
+        Format::Srt => Ok(srt(captions).into_bytes()),
+        Format::Vtt => Ok(vtt(captions, &transcript.bookmarks).into_bytes()),
+        Format::Txt => Ok(txt(transcript, settings).into_bytes()),
+        Format::Docx => docx(transcript, settings, palette),
+        Format::Pdf => Ok(pdf(transcript, settings, palette)),
+        Format::Youtube => {
+            Ok(chapters::youtube(&chapters::detect(captions, settings)).into_bytes())
+        }
+        Format::MinutesJson => minutes::json(transcript),
+        Format::MinutesMarkdown => Ok(minutes::markdown(transcript).into_bytes()),
+    }
+}
+
+// The speaker colours for documents, none while colour coding is off
+fn speaker_palette(settings: &AppSettings) -> Vec<String> {
+    if settings.speaker_colors.enabled {
+        settings.speaker_colors.palette.clone()
+    } else {
+        Vec::new()
+    }
+}
+
+// Chapters for the headings of the text formats, when they are on
+fn chapters(transcript: &Transcript, settings: &ExportSettings) -> Vec<Chapter> {
+    if settings.chapters {
+        chapters::detect(&transcript.captions, settings)
+    } else {
+        Vec::new()
+    }
+}
+
+fn chapter_heading(chapter: &Chapter) -> String {
+    format!("[{}] ", &vtt_time(chapter.start)[..8])
+}
+
+// "00:01:05.250"
+pub fn vtt_time(millis: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub fn vtt_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// "00:01:05,250"
+fn srt_time(millis: u64) -> String {
+    vtt_time(millis).replace('.', ",")
+}
+
+// "2024-05-01"
+pub fn date(millis: u64) -> String {
+    let t = clock::utc(millis);
+    format!("{:04}-{:02}-{:02}", t.year, t.month, t.day)
+}
+
+// Empty lines would end a cue early
+fn cue_lines(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn srt(captions: &[TimedCaption]) -> String {
+    let mut out = String::new();
+    for (i, caption) in captions.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            srt_time(caption.start),
+            srt_time(caption.end),
+            cue_lines(&caption.text)
+        ));
+    }
+    out
+}
+
+// "[00:12:03] Bookmark: decision on the budget"
+fn bookmark_line(bookmark: &TimedBookmark) -> String {
+    format!(
+        "[{}] {}",
+        &vtt_time(bookmark.at)[..8],
+        bookmark_text(bookmark)
+    )
+}
+
+fn bookmark_text(bookmark: &TimedBookmark) -> String {
+    format!("{} {}", bookmarks::label(&bookmark.tag), bookmark.note)
+        .trim_end()
+        .to_string()
+}
+
+// Bookmarks go in front of the first caption that started after them
+fn bookmarks_before<'a>(
+    bookmarks: &mut std::iter::Peekable<std::slice::Iter<'a, TimedBookmark>>,
+    start: u64,
+) -> Vec<&'a TimedBookmark> {
+    let mut before = Vec::new();
+    while let Some(bookmark) = bookmarks.next_if(|bookmark| bookmark.at <= start) {
+        before.push(bookmark);
+    }
+    before
+}
+
+// Bookmarks become NOTE blocks, which players skip
+fn vtt(captions: &[TimedCaption], bookmarks: &[TimedBookmark]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    let mut bookmarks = bookmarks.iter().peekable();
+    for caption in captions {
+        for bookmark in bookmarks_before(&mut bookmarks, caption.start) {
+            out.push_str(&format!("\nNOTE {}\n", note_text(&bookmark_line(bookmark))));
+        }
+        out.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            vtt_time(caption.start),
+            vtt_time(caption.end),
+            vtt_escape(&cue_lines(&caption.text))
+        ));
+    }
+    for bookmark in bookmarks {
+        out.push_str(&format!("\nNOTE {}\n", note_text(&bookmark_line(bookmark))));
+    }
+    out
+}
+
+// A NOTE ends at an empty line and may not contain "-->"
+fn note_text(text: &str) -> String {
+    text.replace("-->", "->").replace('\n', " ")
+}
+
+fn txt(transcript: &Transcript, settings: &ExportSettings) -> String {
+    let chapters = chapters(transcript, settings);
+    let mut chapters = chapters.iter().peekable();
+    let mut bookmarks = transcript.bookmarks.iter().peekable();
+    let mut out = String::new();
+    for (i, caption) in transcript.captions.iter().enumerate() {
+        for bookmark in bookmarks_before(&mut bookmarks, caption.start) {
+            out.push_str(&format!("{}\n\n", bookmark_line(bookmark)));
+        }
+        if let Some(chapter) = chapters.next_if(|chapter| chapter.first == i) {
+            out.push_str(&format!(
+                "{}{}\n\n",
+                chapter_heading(chapter),
+                chapter.title
+            ));
+            if chapter.marker {
+                continue;
+            }
+        }
+        out.push_str(&caption.text);
+        out.push_str("\n\n");
+    }
+    for bookmark in bookmarks {
+        out.push_str(&format!("{}\n\n", bookmark_line(bookmark)));
+    }
+    out
+}
+
+// Captions start with "Name: " when the session knows who is speaking
+pub fn split_speaker(text: &str) -> (Option<&str>, &str) {
+    match text.split_once(": ") {
+        Some((name, rest))
+            if !name.trim().is_empty()
+                && name.chars().count() <= MAX_SPEAKER_CHARS
+                && !name.contains(['\n', '.', '!', '?']) =>
+        {
+            (Some(name.trim()), rest)
+        }
+        _ => (None, text),
+    }
+}
+
+fn expand(template: &str, transcript: &Transcript) -> String {
+    template
+        .replace("{title}", &transcript.title)
+        .replace("{date}", &date(transcript.started_at))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Plain,
+    Bold,
+    Grey,
+    // A speaker's name in their colour, bold
+    Speaker([u8; 3]),
+}
+
+#[derive(Clone)]
+struct Run {
+    text: String,
+    style: Style,
+}
+
+fn run(text: &str, style: Style) -> Run {
+    Run {
+        text: text.to_string(),
+        style,
+    }
+}
+
+// A paragraph of the formatted documents
+struct Block {
+    size: f32,
+    runs: Vec<Run>,
+}
+
+// The title, the date, chapter headings, bookmarks and a paragraph per caption, the same
+// for Word and PDF. Speaker names get the colours of `palette`, as in the overlay.
+fn blocks(transcript: &Transcript, settings: &ExportSettings, palette: &[String]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    if !transcript.title.trim().is_empty() {
+        blocks.push(Block {
+            size: TITLE_SIZE,
+            runs: vec![run(&transcript.title, Style::Bold)],
+        });
+    }
+    blocks.push(Block {
+        size: TEXT_SIZE,
+        runs: vec![run(&date(transcript.started_at), Style::Grey)],
+    });
+
+    let chapters = chapters(transcript, settings);
+    let mut chapters = chapters.iter().peekable();
+    let mut bookmarks = transcript.bookmarks.iter().peekable();
+    let mut last_speaker = None;
+    let mut colors = SpeakerColors::default();
+    for (i, caption) in transcript.captions.iter().enumerate() {
+        for bookmark in bookmarks_before(&mut bookmarks, caption.start) {
+            blocks.push(bookmark_block(bookmark));
+            last_speaker = None;
+        }
+        if let Some(chapter) = chapters.next_if(|chapter| chapter.first == i) {
+            blocks.push(Block {
+                size: CHAPTER_SIZE,
+                runs: vec![
+                    run(&chapter_heading(chapter), Style::Grey),
+                    run(&chapter.title, Style::Bold),
+                ],
+            });
+            // Name the speaker again under the heading
+            last_speaker = None;
+            if chapter.marker {
+                continue;
+            }
+        }
+        let mut runs = Vec::new();
+        if settings.timestamps {
+            runs.push(run(
+                &format!("[{}] ", &vtt_time(caption.start)[..8]),
+                Style::Grey,
+            ));
+        }
+        let mut text = caption.text.as_str();
+        if settings.speaker_labels {
+            let (speaker, rest) = split_speaker(text);
+            if let Some(name) = speaker {
+                // Only when the speaker changes, like in minutes
+                // Handed out in order of speaking, like in the session
+                let style = match colors.color(name, palette).and_then(speaker_colors::rgb) {
+                    Some(color) => Style::Speaker(color),
+                    None => Style::Bold,
+                };
+                if last_speaker != speaker {
+                    runs.push(run(&format!("{}: ", name), style));
+                }
+                text = rest;
+            }
+            last_speaker = speaker;
+        }
+        runs.push(run(text, Style::Plain));
+        blocks.push(Block {
+            size: TEXT_SIZE,
+            runs,
+        });
+    }
+    blocks.extend(bookmarks.map(bookmark_block));
+    blocks
+}
+
+fn bookmark_block(bookmark: &TimedBookmark) -> Block {
+    Block {
+        size: TEXT_SIZE,
+        runs: vec![
+            run(&format!("[{}] ", &vtt_time(bookmark.at)[..8]), Style::Grey),
+            run(&bookmark_text(bookmark), Style::Bold),
+        ],
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    vtt_escape(text).replace('"', "&quot;")
+}
+
+fn docx_properties(style: Style, size: f32) -> String {
+    let style = match style {
+        Style::Plain => String::new(),
+        Style::Bold => "<w:b/>".to_string(),
+        Style::Grey => "<w:color w:val=\"808080\"/>".to_string(),
+        Style::Speaker([r, g, b]) => {
+            format!("<w:b/><w:color w:val=\"{:02X}{:02X}{:02X}\"/>", r, g, b)
+        }
+    };
+    // Sizes are in half points
+    format!(
+        "<w:rPr>{}<w:sz w:val=\"{}\"/></w:rPr>",
+        style,
+        (size * 2.0) as u32
+    )
+}
+
+// Text run of a WordprocessingML paragraph, with line breaks kept
+fn docx_run(text: &str, properties: &str) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| format!("<w:t xml:space=\"preserve\">{}</w:t>", xml_escape(line)))
+        .collect();
+    format!("<w:r>{}{}</w:r>", properties, lines.join("<w:br/>"))
+}
+
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const WORD_NAMESPACES: &str = "xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+
+const DOCX_CONTENT_TYPES: &str = r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/header1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml"/><Override PartName="/word/footer1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml"/></Types>"#;
+
+const DOCX_RELATIONSHIPS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCX_DOCUMENT_RELATIONSHIPS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footer" Target="footer1.xml"/></Relationships>"#;
+
+// A4 with 2 cm margins, in twentieths of a point
+const DOCX_SECTION: &str = r#"<w:sectPr><w:headerReference w:type="default" r:id="rId1"/><w:footerReference w:type="default" r:id="rId2"/><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1134" w:right="1134" w:bottom="1134" w:left="1134" w:header="567" w:footer="567" w:gutter="0"/></w:sectPr>"#;
+
+// Header or footer part ("hdr" or "ftr"), centred, with {page} as a page number field
+fn docx_header_footer(tag: &str, template: &str, transcript: &Transcript) -> String {
+    let properties = docx_properties(Style::Grey, HEADER_SIZE);
+    let page = format!(
+        "<w:fldSimple w:instr=\"PAGE\">{}</w:fldSimple>",
+        docx_run("1", &properties)
+    );
+    let parts: Vec<String> = expand(template, transcript)
+        .split("{page}")
+        .map(|part| docx_run(part, &properties))
+        .collect();
+    format!(
+        "{}<w:{} {}><w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr>{}</w:p></w:{}>",
+        XML_DECLARATION,
+        tag,
+        WORD_NAMESPACES,
+        parts.join(&page),
+        tag
+    )
+}
+
+fn docx(
+    transcript: &Transcript,
+    settings: &ExportSettings,
+    palette: &[String],
+) -> Result<Vec<u8>, String> {
+    let mut body = String::new();
+    for block in blocks(transcript, settings, palette) {
+        body.push_str("<w:p><w:pPr><w:spacing w:after=\"120\"/></w:pPr>");
+        for run in &block.runs {
+            body.push_str(&docx_run(
+                &run.text,
+                &docx_properties(run.style, block.size),
+            ));
+        }
+        body.push_str("</w:p>");
+    }
+    let document = format!(
+        "{}<w:document {}><w:body>{}{}</w:body></w:document>",
+        XML_DECLARATION, WORD_NAMESPACES, body, DOCX_SECTION
+    );
+    let parts = [
+        (
+            "[Content_Types].xml",
+            format!("{}{}", XML_DECLARATION, DOCX_CONTENT_TYPES),
+        ),
+        (
+            "_rels/.rels",
+            format!("{}{}", XML_DECLARATION, DOCX_RELATIONSHIPS),
+        ),
+        (
+            "word/_rels/document.xml.rels",
+            format!("{}{}", XML_DECLARATION, DOCX_DOCUMENT_RELATIONSHIPS),
+        ),
+        ("word/document.xml", document),
+        (
+            "word/header1.xml",
+            docx_header_footer("hdr", &settings.header, transcript),
+        ),
+        (
+            "word/footer1.xml",
+            docx_header_footer("ftr", &settings.footer, transcript),
+        ),
+    ];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in parts {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    let cursor = zip.finish().map_err(|e| e.to_string())?;
+    Ok(cursor.into_inner())
+}
+
+fn pdf_font(style: Style) -> Font {
+    match style {
+        Style::Bold | Style::Speaker(_) => Font::Bold,
+        Style::Plain | Style::Grey => Font::Regular,
+    }
+}
+
+// Lines of a block no wider than `width`, broken between words and at line breaks
+fn wrap(block: &Block, width: f32) -> Vec<Vec<Run>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<Run> = Vec::new();
+    let mut line_width = 0.0;
+    for block_run in &block.runs {
+        let font = pdf_font(block_run.style);
+        for (i, part) in block_run.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0.0;
+            }
+            for word in part.split_inclusive(' ') {
+                // Trailing spaces may hang over the margin
+                let visible = pdf::text_width(word.trim_end(), font, block.size);
+                if line_width > 0.0 && line_width + visible > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0.0;
+                }
+                match line.last_mut() {
+                    Some(last) if last.style == block_run.style => last.text.push_str(word),
+                    _ => line.push(run(word, block_run.style)),
+                }
+                line_width += pdf::text_width(word, font, block.size);
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn pdf(transcript: &Transcript, settings: &ExportSettings, palette: &[String]) -> Vec<u8> {
+    let width = PAGE_WIDTH - 2.0 * MARGIN;
+    let mut pages = vec![Page::default()];
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for block in blocks(transcript, settings, palette) {
+        let line_height = block.size * LINE_HEIGHT;
+        for line in wrap(&block, width) {
+            if y - line_height < MARGIN {
+                pages.push(Page::default());
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= line_height;
+            let runs: Vec<(&str, Font, [u8; 3])> = line
+                .iter()
+                .map(|run| {
+                    let color = match run.style {
+                        Style::Grey => pdf::GREY,
+                        Style::Speaker(color) => color,
+                        Style::Plain | Style::Bold => pdf::BLACK,
+                    };
+                    (run.text.as_str(), pdf_font(run.style), color)
+                })
+                .collect();
+            if let Some(page) = pages.last_mut() {
+                page.text(MARGIN, y, block.size, &runs);
+            }
+        }
+        y -= block.size * PARAGRAPH_GAP;
+    }
+
+    // Headers and footers centred in the margins
+    for (i, page) in pages.iter_mut().enumerate() {
+        let number = (i + 1).to_string();
+        for (template, y) in [
+            (&settings.header, PAGE_HEIGHT - MARGIN / 2.0),
+            (&settings.footer, MARGIN / 2.0 - HEADER_SIZE / 2.0),
+        ] {
+            let text = expand(template, transcript).replace("{page}", &number);
+            if text.trim().is_empty() {
+                continue;
+            }
+            let x = (PAGE_WIDTH - pdf::text_width(&text, Font::Regular, HEADER_SIZE)) / 2.0;
+            page.text(
+                x.max(MARGIN),
+                y,
+                HEADER_SIZE,
+                &[(&text, Font::Regular, pdf::GREY)],
+            );
+        }
+    }
+    pdf::document(&pages)
+}
+
+// Keeps the title readable in a file name on every platform
+fn file_name(title: &str, started_at: u64, format: Format) -> String {
+    let title: String = title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!(
+        "{} {}.{}",
+        title.trim(),
+        date(started_at),
+        format.extension()
+    )
+}
+
+// Export the caption history, or an archived session, to the documents folder without
+// replacing an earlier export
+pub fn save(
+    app: &AppHandle,
+    format: Format,
+    title: Option<String>,
+    session_id: Option<i64>,
+    alignment: &Alignment,
+) -> Result<PathBuf, String> {
+    let state = app.state::<AppState>();
+    let (settings, palette) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.export.clone(), speaker_palette(&settings))
+    };
+    let title = title.filter(|t| !t.trim().is_empty());
+    let transcript = match session_id {
+        Some(id) => {
+            let archived = state
+                .storage
+                .lock()
+                .map_err(|e| e.to_string())?
+                .transcript(id)?;
+            let (names, bookmarks) = {
+                let storage = state.storage.lock().map_err(|e| e.to_string())?;
+                (storage.speaker_names(id)?, storage.bookmarks(id)?)
+            };
+            let entries: Vec<HistoryEntry> = archived
+                .captions
+                .into_iter()
+                .map(|caption| HistoryEntry {
+                    at: caption.started_at,
+                    text: speaker_names::rename(&caption.text, &names),
+                })
+                .collect();
+            Transcript::new(
+                title.unwrap_or(archived.session.code),
+                &entries,
+                &bookmarks,
+                alignment,
+            )?
+        }
+        None => {
+            // Captions from before a speaker got their name get it here
+            let names = speaker_names::list(app)?;
+            let entries: Vec<HistoryEntry> = state
+                .caption_history
+                .lock()
+                .map_err(|e| e.to_string())?
+                .entries()
+                .into_iter()
+                .map(|entry| HistoryEntry {
+                    text: speaker_names::rename(&entry.text, &names),
+                    ..entry
+                })
+                .collect();
+            let code = state
+                .session_code
+                .lock()
+                .map_err(|e| e.to_string())?
+                .clone();
+            let title = title.or(code).unwrap_or_else(|| "Transcript".to_string());
+            let bookmarks = bookmarks::list(app)?;
+            Transcript::new(title, &entries, &bookmarks, alignment)?
+        }
+    };
+
+    let dir = match UserDirs::new().and_then(|dirs| dirs.document_dir().map(Path::to_path_buf)) {
+        Some(dir) => dir,
+        None => crate::settings::get_config_dir(),
+    };
+    let name = file_name(&transcript.title, transcript.started_at, format);
+    let mut path = dir.join(&name);
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = dir.join(name.replacen(
+            &format!(".{}", format.extension()),
+            &format!(" ({}).{}", copy, format.extension()),
+            1,
+        ));
+    }
+    fs::write(&path, render(format, &transcript, &settings, &palette)?)
+        .map_err(|e| e.to_string())?;
+    log::info!("Transcript exported to {}", path.display());
+    Ok(path)
+}
+
+pub fn read_log(path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<HistoryEntry>(line) {
+            Ok(entry) => entries.push(entry),
+            // A crash can leave a partly written last line
+            Err(e) => log::warn!("Skipping line {} of {}: {}", i + 1, path.display(), e),
+        }
+    }
+    Ok(entries)
+}
+
+// Without a title the log's file name is used
+pub fn convert(
+    log: &Path,
+    format: Format,
+    title: Option<&str>,
+    out: Option<&Path>,
+    settings: &ExportSettings,
+    palette: &[String],
+    alignment: &Alignment,
+) -> Result<PathBuf, String> {
+    let title = match title {
+        Some(title) => title.to_string(),
+        None => log
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let transcript = Transcript::new(title, &read_log(log)?, &[], alignment)?;
+    let out = match out {
+        Some(out) => out.to_path_buf(),
+        None => log.with_extension(format.extension()),
+    };
+    fs::write(&out, render(format, &transcript, settings, palette)?)
+        .map_err(|e| format!("{}: {}", out.display(), e))?;
+    Ok(out)
+}
+
+// The exit code when the command line asks for a conversion, None to start the app
+pub fn run_cli() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some(COMMAND) {
+        return None;
+    }
+    Some(match convert_command(&args[1..]) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            1
+        }
+    })
+}
+
+// "1:02:03.5", "02:03,250" or "3" as milliseconds, for positions in a recording
+fn parse_position(text: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid position in the recording: {}", text);
+    let mut seconds = 0.0;
+    for part in text.trim().replace(',', ".").split(':') {
+        let value = part.parse::<f64>().map_err(|_| invalid())?;
+        if value < 0.0 {
+            return Err(invalid());
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Ok((seconds * 1000.0).round() as u64)
+}
+
+fn convert_command(args: &[String]) -> Result<(), String> {
+    let mut logs = Vec::new();
+    let mut format = None;
+    let mut title = None;
+    let mut out = None;
+    let mut start = None;
+    let mut marker = None;
+    let mut marker_at = 0;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--to" | "--title" | "--out" | "--start" | "--marker" | "--marker-at" => {
+                match args.next() {
+                    Some(value) => value,
+                    None => return Err(format!("{} needs a value", arg)),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => {
+                logs.push(PathBuf::from(arg));
+                continue;
+            }
+        };
+        match arg.as_str() {
+            "--to" => format = Some(Format::parse(value)?),
+            "--title" => title = Some(value.as_str()),
+            "--out" => out = Some(PathBuf::from(value)),
+            "--start" => match clock::parse_iso_timestamp(value) {
+                Some(at) => start = Some(at),
+                None => return Err(format!("Invalid start time: {}", value)),
+            },
+            "--marker" => marker = Some(value.clone()),
+            _ => marker_at = parse_position(value)?,
+        }
+    }
+    let format = match format {
+        Some(format) => format,
+        None => return Err("No format given".to_string()),
+    };
+    if logs.is_empty() {
+        return Err("No caption log given".to_string());
+    }
+    if out.is_some() && logs.len() > 1 {
+        return Err("--out only works with a single log".to_string());
+    }
+    let alignment = match (start, marker) {
+        (Some(_), Some(_)) => return Err("Use either --start or --marker".to_string()),
+        (Some(at), None) => Alignment::RecordingStart(at),
+        (None, Some(text)) => Alignment::Marker {
+            text,
+            at: marker_at,
+        },
+        (None, None) => Alignment::FirstCaption,
+    };
+
+    // Headers, footers and speaker colours as configured in the app
+    let settings = tauri::async_runtime::block_on(load_settings());
+    let palette = speaker_palette(&settings);
+    for log in &logs {
+        let written = convert(
+            log,
+            format,
+            title,
+            out.as_deref(),
+            &settings.export,
+            &palette,
+            &alignment,
+        )?;
+        println!("{}", written.display());
+    }
+    Ok(())
+}
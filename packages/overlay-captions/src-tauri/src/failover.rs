@@ -0,0 +1,129 @@
+// Failover between caption servers. `connection.yjs_server_url` comes first and
+// `connection.fallback_server_urls` after it, in order of preference. The main window
+// connects to the active one and reports every closed connection (see diagnostics.rs);
+// after `failover_after` closes without getting connected the next server takes over, and
+// an `active-server` event tells the main window to rejoin the session there. The operator
+// can also pick a server with `switch_server`. Changing the servers starts over at the first.
+
+use crate::settings::ConnectionSettings;
+use crate::{AppHandle, AppState};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+#[derive(Default)]
+pub struct FailoverState {
+    // Index into `servers`
+    active: usize,
+    // Connection attempts failed in a row on the active server
+    failures: u32,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ActiveServer {
+    pub index: usize,
+    pub url: String,
+    // How many servers there are to fail over between
+    pub count: usize,
+}
+
+// The configured servers in order of preference, without blanks or repeats
+pub fn servers(connection: &ConnectionSettings) -> Vec<String> {
+    let mut servers: Vec<String> = Vec::new();
+    for url in std::iter::once(&connection.yjs_server_url).chain(&connection.fallback_server_urls) {
+        let url = url.trim();
+        if !url.is_empty() && !servers.iter().any(|s| s == url) {
+            servers.push(url.to_string());
+        }
+    }
+    servers
+}
+
+pub fn active(app: &AppHandle) -> Result<ActiveServer, String> {
+    let state = app.state::<AppState>();
+    let servers = servers(&state.settings.lock().map_err(|e| e.to_string())?.connection);
+    let failover = state.failover.lock().map_err(|e| e.to_string())?;
+    // The list may have shrunk since
+    let index = failover.active.min(servers.len().saturating_sub(1));
+    Ok(ActiveServer {
+        index,
+        url: servers.get(index).cloned().unwrap_or_default(),
+        count: servers.len(),
+    })
+}
+
+// URL of the server the session connects to
+pub fn active_url(app: &AppHandle) -> String {
+    active(app).map(|server| server.url).unwrap_or_default()
+}
+
+fn announce(app: &AppHandle) -> Result<ActiveServer, String> {
+    let server = active(app)?;
+    log::info!("Caption server {}: {}", server.index + 1, server.url);
+    if let Err(e) = app.emit("active-server", &server) {
+        log::error!("Failed to emit active server: {}", e);
+    }
+    Ok(server)
+}
+
+pub fn switch_server(app: &AppHandle, index: usize) -> Result<ActiveServer, String> {
+    {
+        let state = app.state::<AppState>();
+        let count = servers(&state.settings.lock().map_err(|e| e.to_string())?.connection).len();
+        if index >= count {
+            return Err(format!("There is no server {}", index + 1));
+        }
+        let mut failover = state.failover.lock().map_err(|e| e.to_string())?;
+        failover.active = index;
+        failover.failures = 0;
+    }
+    announce(app)
+}
+
+// A connection event from the main window: "connected" or "closed" count here
+pub fn connection_event(app: &AppHandle, kind: &str) {
+    let state = app.state::<AppState>();
+    let (count, failover_after) = match state.settings.lock() {
+        Ok(s) => (
+            servers(&s.connection).len(),
+            s.connection.failover_after.max(1),
+        ),
+        Err(_) => return,
+    };
+    let switched = match state.failover.lock() {
+        Ok(mut failover) => match kind {
+            "connected" => {
+                failover.failures = 0;
+                false
+            }
+            "closed" if count > 1 => {
+                failover.failures += 1;
+                if failover.failures >= failover_after {
+                    failover.active = (failover.active + 1) % count;
+                    failover.failures = 0;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        },
+        Err(_) => return,
+    };
+    if switched {
+        log::warn!(
+            "Caption server failed {} times, failing over",
+            failover_after
+        );
+        if let Err(e) = announce(app) {
+            log::error!("Failed to fail over: {}", e);
+        }
+    }
+}
+
+// The servers changed in settings; start over at the first
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut failover) = app.state::<AppState>().failover.lock() {
+        *failover = FailoverState::default();
+    }
+    let _ = announce(app);
+}
@@ -0,0 +1,107 @@
+// Focus-follow: the public overlay attaches itself to the bottom edge of whichever
+// application window has the focus, so the captions stay under the video call wherever it
+// is dragged. No platform announces other applications' focus and move events to us, so a
+// thread polls the active window's geometry and moves the overlay when it changes. Our own
+// windows never count, so clicking the overlay or the main window leaves it where it is.
+// Where other windows can't be seen (see desktop.rs) the mode is not available.
+
+use crate::desktop::{self, Bounds, Desktop};
+use crate::settings::FocusFollowSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use std::time::Duration;
+use tauri::Manager;
+
+// Quick enough to keep up with a window being dragged
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+pub struct FocusFollowState {
+    // Bumped to stop the running follower
+    generation: u64,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .focus_follow
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running follower and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.focus_follow.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.focus_follow.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+    if !desktop::SUPPORTED {
+        log::warn!("Focus-follow is not supported on this platform");
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+// Where the overlay goes for the active window: centred on it, at its bottom edge
+fn place(settings: &FocusFollowSettings, target: Bounds, width: i32, height: i32) -> (i32, i32) {
+    let x = target.x + (target.width - width) / 2;
+    let bottom = target.y + target.height;
+    let offset = settings.offset_px as i32;
+    let y = if settings.placement == "below" {
+        bottom + offset
+    } else {
+        bottom - height - offset
+    };
+    (x, y)
+}
+
+fn run(app: &AppHandle, settings: &FocusFollowSettings, generation: u64) {
+    let desktop = match Desktop::open() {
+        Ok(desktop) => desktop,
+        Err(e) => {
+            log::warn!("Focus-follow unavailable: {}", e);
+            return;
+        }
+    };
+    log::info!("Overlay follows the active window");
+    let mut last: Option<Bounds> = None;
+
+    while is_current(app, generation) {
+        std::thread::sleep(POLL_INTERVAL);
+        // Nothing, the desktop or one of ours: stay where we were
+        let target = match desktop.active_window() {
+            Some(target) if target.width > 0 && target.height > 0 => target,
+            _ => continue,
+        };
+        if last == Some(target) {
+            continue;
+        }
+        let window = match ManagedWindow::OVERLAY.get(app) {
+            Some(window) => window,
+            None => continue,
+        };
+        let size = match window.outer_size() {
+            Ok(size) => size,
+            Err(_) => continue,
+        };
+        let (x, y) = place(settings, target, size.width as i32, size.height as i32);
+        match window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y })) {
+            Ok(()) => last = Some(target),
+            Err(e) => log::error!("Failed to move the overlay to the active window: {}", e),
+        }
+    }
+}
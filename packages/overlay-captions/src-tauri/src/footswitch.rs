@@ -0,0 +1,197 @@
+// HID footswitches (X-keys pedals and generic USB foot controls) for operators whose hands
+// are on a steno keyboard. The device is opened by vendor/product ID and its input reports
+// are read as a single switch:
+//   "pause"      - each press pauses or resumes the public captions
+//   "pushToHide" - the overlay is hidden while the pedal is held down; with an exit PIN set
+//                  the main window asks for it instead
+
+use crate::settings::FootswitchSettings;
+use crate::{exit_protection, pause, AppHandle, AppState};
+use hidapi::{HidApi, HidDevice};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+// Read timeout, also how often the reader checks whether it was replaced
+const POLL_INTERVAL_MS: i32 = 200;
+// Wait between attempts to open a device that is not plugged in
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct FootswitchState {
+    // Bumped to stop the running reader, e.g. when the device changes
+    generation: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HidDeviceEntry {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub name: String,
+}
+
+// HID devices offered in the footswitch settings
+pub fn list_devices() -> Result<Vec<HidDeviceEntry>, String> {
+    let api = HidApi::new().map_err(|e| e.to_string())?;
+    let mut devices: Vec<HidDeviceEntry> = api
+        .device_list()
+        .map(|d| HidDeviceEntry {
+            vendor_id: d.vendor_id(),
+            product_id: d.product_id(),
+            name: format!(
+                "{} {}",
+                d.manufacturer_string().unwrap_or_default(),
+                d.product_string().unwrap_or_default()
+            )
+            .trim()
+            .to_string(),
+        })
+        .collect();
+    // A device shows up once per interface
+    devices.sort_by_key(|d| (d.vendor_id, d.product_id));
+    devices.dedup_by(|a, b| a.vendor_id == b.vendor_id && a.product_id == b.product_id);
+    Ok(devices)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .footswitch
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running reader and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.footswitch.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.footswitch.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &FootswitchSettings, generation: u64) {
+    let mut reported_missing = false;
+
+    while is_current(app, generation) {
+        let device =
+            HidApi::new().and_then(|api| api.open(settings.vendor_id, settings.product_id));
+        match device {
+            Ok(device) => {
+                log::info!(
+                    "Footswitch {:04x}:{:04x} connected",
+                    settings.vendor_id,
+                    settings.product_id
+                );
+                reported_missing = false;
+                if let Err(e) = read_switch(app, &device, settings, generation) {
+                    log::warn!("Footswitch disconnected: {}", e);
+                }
+            }
+            Err(e) => {
+                if !reported_missing {
+                    log::warn!(
+                        "Footswitch {:04x}:{:04x} not available: {}",
+                        settings.vendor_id,
+                        settings.product_id,
+                        e
+                    );
+                    reported_missing = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+fn is_pressed(report: &[u8], settings: &FootswitchSettings) -> bool {
+    match settings.button_byte {
+        Some(index) => report
+            .get(index)
+            .is_some_and(|b| b & settings.button_mask != 0),
+        None => report.iter().any(|b| b & settings.button_mask != 0),
+    }
+}
+
+fn read_switch(
+    app: &AppHandle,
+    device: &HidDevice,
+    settings: &FootswitchSettings,
+    generation: u64,
+) -> Result<(), String> {
+    let mut report = [0u8; 64];
+    let mut pressed = false;
+    // Whether the overlay is hidden by a held pedal and must come back on release
+    let mut overlay_hidden = false;
+
+    let result = loop {
+        if !is_current(app, generation) {
+            break Ok(());
+        }
+        let len = match device.read_timeout(&mut report, POLL_INTERVAL_MS) {
+            Ok(len) => len,
+            Err(e) => break Err(e.to_string()),
+        };
+        if len == 0 {
+            continue;
+        }
+
+        let now_pressed = is_pressed(&report[..len], settings);
+        if now_pressed == pressed {
+            continue;
+        }
+        pressed = now_pressed;
+
+        match settings.action.as_str() {
+            "pause" if pressed => {
+                if let Err(e) = pause::set_paused(app, !pause::is_paused(app)) {
+                    log::error!("Failed to toggle caption pause: {}", e);
+                }
+            }
+            // The captions stay up without the PIN, which the main window asks for
+            "pushToHide" if pressed && exit_protection::is_required(app) => {
+                exit_protection::request(app, "hideOverlay");
+            }
+            "pushToHide" if pressed => {
+                let visible = app
+                    .state::<AppState>()
+                    .overlay_visible
+                    .lock()
+                    .map(|v| *v)
+                    .unwrap_or(false);
+                if visible {
+                    match crate::hide_overlay_window(app) {
+                        Ok(()) => overlay_hidden = true,
+                        Err(e) => log::error!("Failed to hide overlay: {}", e),
+                    }
+                }
+            }
+            "pushToHide" if overlay_hidden => {
+                overlay_hidden = false;
+                crate::spawn_show_overlay_window(app.clone());
+            }
+            _ => {}
+        }
+    };
+
+    // Don't leave the overlay hidden when the pedal is unplugged or reconfigured mid-press
+    if overlay_hidden {
+        crate::spawn_show_overlay_window(app.clone());
+    }
+    result
+}
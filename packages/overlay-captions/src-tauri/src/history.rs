@@ -0,0 +1,211 @@
+// Recent caption history, fed by the event router like any other output. Captions are
+// snapshots of the transcript, so a snapshot that only extends the previous one replaces
+// it instead of adding an entry.
+//
+// With `caption_history.persist` enabled every entry is also appended to a JSON lines
+// file in the config directory. On startup the file is replayed, so a crash or restart
+// in the middle of an event does not lose what was already captioned.
+
+use crate::clock::now_millis;
+use crate::event_router::{Output, RECORD};
+use crate::settings::{self, AppSettings};
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::Manager;
+
+// Oldest entries are dropped beyond this
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    // Milliseconds since the Unix epoch
+    pub at: u64,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct CaptionHistory {
+    entries: VecDeque<HistoryEntry>,
+    // Open while the history is persisted
+    file: Option<File>,
+}
+
+// Sent to the transcript window as entries come in
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryUpdate {
+    entry: HistoryEntry,
+    // The entry extends the last one and takes its place
+    replaces_last: bool,
+}
+
+impl CaptionHistory {
+    // Returns whether the entry took the place of the last one
+    fn push(&mut self, entry: HistoryEntry) -> bool {
+        match self.entries.back_mut() {
+            Some(last) if entry.text.starts_with(&last.text) => {
+                *last = entry;
+                true
+            }
+            _ => {
+                self.entries.push_back(entry);
+                if self.entries.len() > MAX_ENTRIES {
+                    self.entries.pop_front();
+                }
+                false
+            }
+        }
+    }
+
+    fn append(&mut self, entry: HistoryEntry) -> Result<bool, String> {
+        if let Some(file) = self.file.as_mut() {
+            let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        }
+        Ok(self.push(entry))
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    // Change the text of every entry, e.g. for an operator correction
+    pub fn rewrite<F: Fn(&str) -> String>(&mut self, change: F) -> Result<(), String> {
+        for entry in self.entries.iter_mut() {
+            entry.text = change(&entry.text);
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.set_len(0).map_err(|e| e.to_string())?;
+            for entry in &self.entries {
+                let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+                writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.entries.clear();
+        if let Some(file) = self.file.as_mut() {
+            file.set_len(0).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn history_path() -> PathBuf {
+    settings::get_config_dir().join("caption-history.jsonl")
+}
+
+// Open or close the history file to match the settings
+pub fn restart(app: &AppHandle) {
+    if let Err(e) = reopen(app) {
+        log::error!("Failed to set up caption history file: {}", e);
+    }
+}
+
+fn reopen(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let persist = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .caption_history
+        .persist;
+    let mut history = state.caption_history.lock().map_err(|e| e.to_string())?;
+    history.file = None;
+
+    let path = history_path();
+    if !persist {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+
+    // Nothing in memory yet means we just started: pick up where the last run left off
+    if history.entries.is_empty() {
+        if let Ok(content) = fs::read_to_string(&path) {
+            for line in content.lines() {
+                match serde_json::from_str::<HistoryEntry>(line) {
+                    Ok(entry) => {
+                        history.push(entry);
+                    }
+                    // A crash can leave a partly written last line
+                    Err(e) => log::warn!("Skipping caption history line: {}", e),
+                }
+            }
+            log::info!("Restored {} caption history entries", history.entries.len());
+        }
+    }
+
+    // Rewrite the file with only what is kept so it does not grow without bound
+    let mut content = String::new();
+    for entry in &history.entries {
+        content.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+
+    history.file = Some(
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map_err(|e| e.to_string())?,
+    );
+    Ok(())
+}
+
+pub struct HistoryOutput;
+
+impl Output for HistoryOutput {
+    fn id(&self) -> &str {
+        "history"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[RECORD]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        _settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        let text = payload
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let entry = HistoryEntry {
+            at: now_millis(),
+            text: text.to_string(),
+        };
+        let replaces_last = {
+            let state = app.state::<AppState>();
+            let mut history = state.caption_history.lock().map_err(|e| e.to_string())?;
+            history.append(entry.clone())?
+        };
+        ManagedWindow::Transcript.emit(
+            app,
+            "caption-history-entry",
+            HistoryUpdate {
+                entry,
+                replaces_last,
+            },
+        )
+    }
+}
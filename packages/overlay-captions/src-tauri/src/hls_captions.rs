@@ -0,0 +1,273 @@
+// Subtitles for venues running their own HLS stream: captions are written as a rolling
+// WebVTT subtitle rendition, segments plus a playlist, to a directory the stream's web server
+// serves, or to an S3 bucket (see s3.rs). Adding the playlist to the master playlist
+//   #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID="subs",NAME="Captions",AUTOSELECT=YES,URI="captions.m3u8"
+// lets the web player offer them as toggleable subtitles.
+//
+// Cue times count from when the writer started. Viewers see the stream some time after the
+// room hears it, which the app can't measure, so every cue is moved `stream_delay_ms` later;
+// it is tuned once per venue until the subtitles line up in the player.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::export::{vtt_escape, vtt_time};
+use crate::settings::{AppSettings, HlsCaptionSettings};
+use crate::{clock, diagnostics, network, s3, text, AppHandle, AppState};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Manager;
+
+const PLAYLIST: &str = "captions.m3u8";
+const PLAYLIST_TYPE: &str = "application/vnd.apple.mpegurl";
+const SEGMENT_TYPE: &str = "text/vtt";
+// How often the writer checks for a finished segment
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// A caption without an update stays up this long at most
+const MAX_CUE_MS: u64 = 5000;
+
+struct Cue {
+    // Milliseconds since the Unix epoch, stream delay included
+    start: u64,
+    // None while it is the latest caption
+    end: Option<u64>,
+    text: String,
+}
+
+#[derive(Default)]
+pub struct HlsCaptionsState {
+    // Bumped to stop the running writer, e.g. when the directory changes
+    generation: u64,
+    cues: Vec<Cue>,
+}
+
+enum Destination {
+    Directory(PathBuf),
+    S3(s3::Bucket),
+}
+
+impl Destination {
+    fn open(settings: &HlsCaptionSettings) -> Result<Self, String> {
+        if settings.target == "s3" {
+            return Ok(Destination::S3(s3::Bucket::open(&settings.s3)?));
+        }
+        let directory = PathBuf::from(settings.directory.trim());
+        if directory.as_os_str().is_empty() {
+            return Err("No directory for the subtitle segments".to_string());
+        }
+        fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+        Ok(Destination::Directory(directory))
+    }
+
+    fn put(&self, name: &str, body: &str, content_type: &str) -> Result<(), String> {
+        match self {
+            Destination::Directory(directory) => {
+                // Written aside and renamed so the web server never serves half a file
+                let partial = directory.join(format!(".{}.partial", name));
+                fs::write(&partial, body).map_err(|e| e.to_string())?;
+                fs::rename(&partial, directory.join(name)).map_err(|e| e.to_string())
+            }
+            Destination::S3(bucket) => bucket.put(name, body, content_type),
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        match self {
+            Destination::Directory(directory) => {
+                fs::remove_file(directory.join(name)).map_err(|e| e.to_string())
+            }
+            Destination::S3(bucket) => bucket.delete(name),
+        }
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .hls_captions
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running writer and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.hls_captions.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.hls_captions.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.cues.clear();
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = run(&app, &settings, generation) {
+            log::error!("HLS subtitle writer stopped: {}", e);
+            let _ = diagnostics::record(&app, "hls subtitles failed", Some(e));
+        }
+    });
+}
+
+// Segment covering [from, to) of the timeline that starts at `origin`
+fn segment(cues: &[Cue], origin: u64, from: u64, to: u64, now: u64) -> String {
+    let mut vtt = String::from("WEBVTT\nX-TIMESTAMP-MAP=MPEGTS:0,LOCAL:00:00:00.000\n");
+    for cue in cues {
+        let end = cue.end.unwrap_or(now).min(cue.start + MAX_CUE_MS);
+        if cue.start >= to || end <= from || end <= cue.start {
+            continue;
+        }
+        // Empty lines would end the cue
+        let lines: Vec<&str> = cue.text.lines().filter(|l| !l.trim().is_empty()).collect();
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            vtt_time(cue.start - origin),
+            vtt_time(end - origin),
+            vtt_escape(&lines.join("\n"))
+        ));
+    }
+    vtt
+}
+
+fn playlist(segments: &VecDeque<(u64, String)>, origin: u64, segment_ms: u64) -> String {
+    let first = segments.front().map_or(0, |(index, _)| *index);
+    let mut m3u8 = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        segment_ms.div_ceil(1000),
+        first
+    );
+    for (index, name) in segments {
+        m3u8.push_str(&format!(
+            "#EXT-X-PROGRAM-DATE-TIME:{}Z\n#EXTINF:{:.3},\n{}\n",
+            clock::iso_timestamp(origin + index * segment_ms),
+            segment_ms as f64 / 1000.0,
+            name
+        ));
+    }
+    m3u8
+}
+
+fn run(app: &AppHandle, settings: &HlsCaptionSettings, generation: u64) -> Result<(), String> {
+    let destination = Destination::open(settings)?;
+    if let Destination::S3(bucket) = &destination {
+        network::check(app, &bucket.endpoint())?;
+    }
+    let segment_ms = settings.segment_seconds.max(1) * 1000;
+    let window = settings.window_segments.max(1);
+    let origin = clock::now_millis() + settings.stream_delay_ms;
+    log::info!(
+        "Writing HLS subtitles, {} ms behind the room",
+        settings.stream_delay_ms
+    );
+
+    // Index and file name of the segments in the playlist
+    let mut segments: VecDeque<(u64, String)> = VecDeque::new();
+    let mut next = 0u64;
+    let mut failing = false;
+
+    while is_current(app, generation) {
+        std::thread::sleep(POLL_INTERVAL);
+        let now = clock::now_millis() + settings.stream_delay_ms;
+
+        // Any caption from now on starts after `now`, so segments before it are final
+        while origin + (next + 1) * segment_ms <= now {
+            let from = origin + next * segment_ms;
+            let vtt = {
+                let state = app.state::<AppState>();
+                let mut hls = state.hls_captions.lock().map_err(|e| e.to_string())?;
+                // Cues of segments that left the playlist aren't needed anymore
+                let kept_from = origin + (next + 1).saturating_sub(window as u64) * segment_ms;
+                hls.cues
+                    .retain(|cue| cue.end.is_none_or(|end| end > kept_from));
+                segment(&hls.cues, origin, from, from + segment_ms, now)
+            };
+            let name = format!("captions{}_{}.vtt", origin / 1000, next);
+            next += 1;
+
+            let result = destination.put(&name, &vtt, SEGMENT_TYPE).and_then(|_| {
+                segments.push_back((next - 1, name));
+                while segments.len() > window {
+                    if let Some((_, old)) = segments.pop_front() {
+                        if let Err(e) = destination.delete(&old) {
+                            log::debug!("Failed to delete subtitle segment {}: {}", old, e);
+                        }
+                    }
+                }
+                destination.put(
+                    PLAYLIST,
+                    &playlist(&segments, origin, segment_ms),
+                    PLAYLIST_TYPE,
+                )
+            });
+            match result {
+                Ok(()) if failing => {
+                    log::info!("HLS subtitles written again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Reported once per outage, not for every segment
+                Err(e) if !failing => {
+                    log::warn!("Writing HLS subtitles failed: {}", e);
+                    let _ = diagnostics::record(app, "hls subtitles failed", Some(e));
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Turns captions into cues for the writer
+pub struct HlsCaptionsOutput;
+
+impl Output for HlsCaptionsOutput {
+    fn id(&self) -> &str {
+        "hls-subtitles"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.hls_captions.enabled {
+            return Ok(());
+        }
+        let caption = text::plain(payload.get("text").and_then(Value::as_str).unwrap_or(""));
+        let now = clock::now_millis() + settings.hls_captions.stream_delay_ms;
+
+        let state = app.state::<AppState>();
+        let mut hls = state.hls_captions.lock().map_err(|e| e.to_string())?;
+        // Each update replaces what was shown before
+        if let Some(last) = hls.cues.last_mut() {
+            if last.end.is_none() {
+                last.end = Some(now);
+            }
+        }
+        if !caption.trim().is_empty() {
+            hls.cues.push(Cue {
+                start: now,
+                end: None,
+                text: caption,
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+// The hot corner: moving the cursor into the configured corner of any screen brings up the
+// control strip there, a small window with pause, hide and font size buttons, and it goes
+// away again a moment after the cursor has left it. For operators who keep the overlay
+// click-through and everything else out of sight. There is no global hover event, so a
+// thread polls the cursor position.
+
+use crate::commands::{step_font_size, FONT_SIZE_STEP};
+use crate::settings::HotCornerSettings;
+use crate::window_manager::{self, ManagedWindow, CONTROL_STRIP_SIZE};
+use crate::{exit_protection, kiosk, pause, AppHandle, AppState};
+use std::time::{Duration, Instant};
+use tauri::{Manager, PhysicalPosition, PhysicalSize};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// How close to the corner, in physical pixels, the cursor has to be
+const TRIGGER_PX: f64 = 4.0;
+
+#[derive(Default)]
+pub struct HotCornerState {
+    // Bumped to stop the running watcher
+    generation: u64,
+}
+
+// A physical rectangle: origin and size
+type Rect = (PhysicalPosition<i32>, PhysicalSize<u32>);
+
+fn contains(rect: &Rect, point: PhysicalPosition<f64>) -> bool {
+    let (origin, size) = rect;
+    point.x >= f64::from(origin.x)
+        && point.x < f64::from(origin.x) + f64::from(size.width)
+        && point.y >= f64::from(origin.y)
+        && point.y < f64::from(origin.y) + f64::from(size.height)
+}
+
+// Where the strip goes on the monitor whose `corner` the cursor is in, in physical pixels
+fn strip_rect(monitor: &tauri::Monitor, corner: &str) -> Rect {
+    let (origin, extent, scale) = (monitor.position(), monitor.size(), monitor.scale_factor());
+    let size = PhysicalSize {
+        width: (CONTROL_STRIP_SIZE.0 * scale).round() as u32,
+        height: (CONTROL_STRIP_SIZE.1 * scale).round() as u32,
+    };
+    let right = origin.x + extent.width as i32 - size.width as i32;
+    let bottom = origin.y + extent.height as i32 - size.height as i32;
+    let position = match corner {
+        "topLeft" => PhysicalPosition::new(origin.x, origin.y),
+        "topRight" => PhysicalPosition::new(right, origin.y),
+        "bottomLeft" => PhysicalPosition::new(origin.x, bottom),
+        _ => PhysicalPosition::new(right, bottom),
+    };
+    (position, size)
+}
+
+// The monitor with the cursor in its `corner`
+fn corner_monitor(
+    app: &AppHandle,
+    corner: &str,
+    cursor: PhysicalPosition<f64>,
+) -> Option<tauri::Monitor> {
+    let monitors = app.available_monitors().ok()?;
+    monitors.into_iter().find(|monitor| {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        let (left, top) = (f64::from(origin.x), f64::from(origin.y));
+        let (right, bottom) = (
+            left + f64::from(extent.width),
+            top + f64::from(extent.height),
+        );
+        let near_left = cursor.x >= left && cursor.x < left + TRIGGER_PX;
+        let near_right = cursor.x < right && cursor.x >= right - TRIGGER_PX;
+        let near_top = cursor.y >= top && cursor.y < top + TRIGGER_PX;
+        let near_bottom = cursor.y < bottom && cursor.y >= bottom - TRIGGER_PX;
+        match corner {
+            "topLeft" => near_left && near_top,
+            "topRight" => near_right && near_top,
+            "bottomLeft" => near_left && near_bottom,
+            _ => near_right && near_bottom,
+        }
+    })
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .hot_corner
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running watcher and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.hot_corner.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.hot_corner.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+    let _ = ManagedWindow::ControlStrip.hide(app);
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &HotCornerSettings, generation: u64) {
+    let hide_delay = Duration::from_millis(settings.hide_delay_ms);
+    // Where the strip is showing, and since when the cursor has been away from it
+    let mut shown: Option<(Rect, Option<Instant>)> = None;
+
+    while is_current(app, generation) {
+        std::thread::sleep(POLL_INTERVAL);
+        let cursor = match app.cursor_position() {
+            Ok(cursor) => cursor,
+            Err(_) => continue,
+        };
+
+        match shown.as_mut() {
+            None => {
+                if let Some(monitor) = corner_monitor(app, &settings.corner, cursor) {
+                    let rect = strip_rect(&monitor, &settings.corner);
+                    match show_strip(app, &rect, monitor.scale_factor()) {
+                        Ok(()) => shown = Some((rect, None)),
+                        Err(e) => log::error!("Failed to show the control strip: {}", e),
+                    }
+                }
+            }
+            Some((rect, left_at)) => {
+                if contains(rect, cursor) {
+                    *left_at = None;
+                } else {
+                    let since = *left_at.get_or_insert_with(Instant::now);
+                    if since.elapsed() >= hide_delay {
+                        let _ = ManagedWindow::ControlStrip.hide(app);
+                        shown = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn show_strip(app: &AppHandle, rect: &Rect, scale: f64) -> Result<(), String> {
+    let (position, size) = rect;
+    let window = window_manager::create_control_strip(
+        app,
+        f64::from(position.x) / scale,
+        f64::from(position.y) / scale,
+    )?;
+    window
+        .set_size(tauri::Size::Physical(*size))
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(tauri::Position::Physical(*position))
+        .map_err(|e| e.to_string())?;
+    window.show().map_err(|e| e.to_string())?;
+    window.set_always_on_top(true).map_err(|e| e.to_string())
+}
+
+pub fn run_action(app: &AppHandle, action: &str) -> Result<(), String> {
+    match action {
+        "pause" => pause::set_paused(app, !pause::is_paused(app)),
+        // Ask for the PIN first, as the context menu does
+        "hide" if exit_protection::is_required(app) => {
+            exit_protection::request(app, "hideOverlay");
+            Ok(())
+        }
+        "hide" => crate::hide_overlay_window(app),
+        // The font size is a setting, which a locked kiosk keeps
+        "fontLarger" => kiosk::check(app).and_then(|_| step_font_size(app, FONT_SIZE_STEP)),
+        "fontSmaller" => kiosk::check(app).and_then(|_| step_font_size(app, -FONT_SIZE_STEP)),
+        _ => Err(format!("Unknown control strip action: {}", action)),
+    }
+}
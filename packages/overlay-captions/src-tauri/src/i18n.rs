@@ -0,0 +1,180 @@
+use crate::{AppHandle, AppState};
+use tauri::Manager;
+
+pub const DEFAULT_LANGUAGE: &str = "et";
+const SUPPORTED_LANGUAGES: [&str; 3] = ["et", "en", "fi"];
+
+// Language for backend-produced UI strings: the manual override from settings if set,
+// otherwise the language declared in the session metadata
+pub fn current_language(app: &AppHandle) -> String {
+    let state = match app.try_state::<AppState>() {
+        Some(s) => s,
+        None => return DEFAULT_LANGUAGE.to_string(),
+    };
+
+    let ui_language = state
+        .settings
+        .lock()
+        .map(|s| s.ui_language.clone())
+        .unwrap_or_default();
+    if ui_language != "auto" {
+        return normalize(&ui_language);
+    }
+
+    state
+        .session_language
+        .lock()
+        .ok()
+        .and_then(|l| l.clone())
+        .map(|l| normalize(&l))
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+// Language of the captions themselves: primary subtag of the session language, which
+// (unlike UI strings) is not limited to the languages we have translations for
+pub fn caption_language(state: &AppState) -> String {
+    state
+        .session_language
+        .lock()
+        .ok()
+        .and_then(|l| l.clone())
+        .and_then(|l| l.split(['-', '_']).next().map(|p| p.to_lowercase()))
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+// Reduce a language tag such as "en-GB" to a supported language code
+pub fn normalize(language: &str) -> String {
+    let primary = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if SUPPORTED_LANGUAGES.contains(&primary.as_str()) {
+        primary
+    } else {
+        DEFAULT_LANGUAGE.to_string()
+    }
+}
+
+pub fn tr(language: &str, key: &str) -> &'static str {
+    match (language, key) {
+        ("en", "tray.show_main") => "Show main window",
+        ("en", "tray.show_overlay") => "Show overlay",
+        ("en", "tray.presentation_mode") => "Presentation mode",
+        ("en", "tray.review") => "Review captions",
+        ("en", "tray.operator_overlay") => "Show operator overlay",
+        ("en", "tray.transcript") => "Transcript",
+        ("en", "tray.diagnostics") => "Connection diagnostics",
+        ("en", "tray.quit") => "Quit",
+        ("en", "tray.tooltip") => "Jutukuva Captions",
+        ("en", "context.hide") => "Hide overlay",
+        ("en", "context.edit_mode") => "Edit mode",
+        ("en", "context.compact") => "Compact",
+        ("en", "context.font_larger") => "Larger text",
+        ("en", "context.font_smaller") => "Smaller text",
+        ("en", "context.settings") => "Open settings",
+        ("en", "context.quit") => "Quit",
+        ("en", "notification.network_degraded_title") => "Caption connection degraded",
+        ("en", "notification.network_degraded_body") => {
+            "Captions may be delayed. Check the network connection."
+        }
+        ("en", "notification.battery_low_title") => "Battery low",
+        ("en", "notification.battery_low_body") => {
+            "Plug in the charger to keep the captions going."
+        }
+        ("en", "notification.calendar_session_title") => "Captioned event starting",
+        ("en", "tray.check_in") => "I'm here (check in)",
+        ("en", "notification.check_in_title") => "Check-in",
+        ("en", "notification.check_in_body") => {
+            "Confirm that you are at the caption station, from the main window or the tray menu."
+        }
+        ("en", "notification.check_in_missed_title") => "Check-in missed",
+        ("en", "notification.check_in_missed_body") => {
+            "The missed check-in was recorded and reported."
+        }
+        ("en", "notification.caption_app_title") => "Another caption app is on screen",
+        ("en", "notification.caption_app_body") => {
+            "Its window and the overlay may take turns on top. Settings can let it go first or keep the two apart."
+        }
+
+        ("fi", "tray.show_main") => "Näytä pääikkuna",
+        ("fi", "tray.show_overlay") => "Näytä tekstitys",
+        ("fi", "tray.presentation_mode") => "Esitystila",
+        ("fi", "tray.review") => "Tarkista tekstitys",
+        ("fi", "tray.operator_overlay") => "Näytä operaattorin tekstitys",
+        ("fi", "tray.transcript") => "Tekstitysloki",
+        ("fi", "tray.diagnostics") => "Yhteyden vianmääritys",
+        ("fi", "tray.quit") => "Lopeta",
+        ("fi", "tray.tooltip") => "Jutukuva Tekstitys",
+        ("fi", "context.hide") => "Piilota tekstitys",
+        ("fi", "context.edit_mode") => "Muokkaustila",
+        ("fi", "context.font_larger") => "Suurempi teksti",
+        ("fi", "context.font_smaller") => "Pienempi teksti",
+        ("fi", "context.compact") => "Kompakti tila",
+        ("fi", "context.settings") => "Avaa asetukset",
+        ("fi", "context.quit") => "Lopeta",
+        ("fi", "notification.network_degraded_title") => "Tekstitysyhteys heikentynyt",
+        ("fi", "notification.network_degraded_body") => {
+            "Tekstitys voi viivästyä. Tarkista verkkoyhteys."
+        }
+        ("fi", "notification.battery_low_title") => "Akku vähissä",
+        ("fi", "notification.battery_low_body") => "Kytke laturi, jotta tekstitys ei katkea.",
+        ("fi", "notification.calendar_session_title") => "Tekstitetty tapahtuma alkaa",
+        ("fi", "tray.check_in") => "Olen paikalla (kuittaa)",
+        ("fi", "notification.check_in_title") => "Läsnäolon kuittaus",
+        ("fi", "notification.check_in_body") => {
+            "Vahvista pääikkunasta tai ilmaisinalueen valikosta, että olet tekstitysasemalla."
+        }
+        ("fi", "notification.check_in_missed_title") => "Kuittaus jäi tekemättä",
+        ("fi", "notification.check_in_missed_body") => {
+            "Kuittaamatta jäänyt läsnäolo kirjattiin ja ilmoitettiin."
+        }
+        ("fi", "notification.caption_app_title") => "Toinen tekstityssovellus on näytöllä",
+        ("fi", "notification.caption_app_body") => {
+            "Sen ikkuna ja tekstitys voivat vuorotella päällimmäisinä. Asetuksista voit antaa sille etusijan tai pitää ne erillään."
+        }
+
+        (_, "tray.show_main") => "Näita peaaken",
+        (_, "tray.show_overlay") => "Näita ülekatet",
+        (_, "tray.presentation_mode") => "Esitlusrežiim",
+        (_, "tray.review") => "Subtiitrite ülevaatus",
+        (_, "tray.operator_overlay") => "Näita operaatori ülekatet",
+        (_, "tray.transcript") => "Transkriptsioon",
+        (_, "tray.diagnostics") => "Ühenduse diagnostika",
+        (_, "tray.quit") => "Välju",
+        (_, "tray.tooltip") => "Jutukuva Subtiitrid",
+        (_, "context.hide") => "Peida ülekate",
+        (_, "context.edit_mode") => "Muutmisrežiim",
+        (_, "context.font_larger") => "Suurem kiri",
+        (_, "context.font_smaller") => "Väiksem kiri",
+        (_, "context.compact") => "Kompaktne vaade",
+        (_, "context.settings") => "Ava seaded",
+        (_, "context.quit") => "Välju",
+        (_, "notification.network_degraded_title") => "Subtiitrite ühendus on halvenenud",
+        (_, "notification.network_degraded_body") => {
+            "Subtiitrid võivad hilineda. Kontrolli võrguühendust."
+        }
+        (_, "notification.battery_low_title") => "Aku on tühjenemas",
+        (_, "notification.battery_low_body") => "Ühenda laadija, et subtiitrid ei katkeks.",
+        (_, "notification.calendar_session_title") => "Subtiitritega sündmus algab",
+        (_, "tray.check_in") => "Olen kohal (kinnita)",
+        (_, "notification.check_in_title") => "Kohaloleku kinnitus",
+        (_, "notification.check_in_body") => {
+            "Kinnita peaaknas või süsteemisalve menüüs, et oled subtiitrijaama juures."
+        }
+        (_, "notification.check_in_missed_title") => "Kohalolek jäi kinnitamata",
+        (_, "notification.check_in_missed_body") => {
+            "Kinnitamata kohalolek salvestati ja sellest teatati."
+        }
+        (_, "notification.caption_app_title") => "Ekraanil on teine subtiitrirakendus",
+        (_, "notification.caption_app_body") => {
+            "Selle aken ja ülekate võivad kordamööda pealmiseks tõusta. Seadetes saab lasta sel ees olla või hoida need lahus."
+        }
+
+        _ => {
+            log::warn!("Missing translation for '{}'", key);
+            ""
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// A line of information next to the captions, such as "Next: Dr. Tamm — Quantum sensors",
+// set by the operator from the main window or over the remote control API. It goes out on
+// its own router channel, so it is neither delayed, held nor reviewed like captions, and
+// the overlay shows it where the layout puts it (see layout.rs).
+
+use crate::{event_router, AppHandle, AppState};
+use serde::Serialize;
+use tauri::Manager;
+
+// Long enough for a speaker and a talk title on one line
+const MAX_CHARS: usize = 120;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoLinePayload {
+    // Empty when the line is cleared
+    pub text: String,
+}
+
+pub fn current(app: &AppHandle) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let info_line = state.info_line.lock().map_err(|e| e.to_string())?;
+    Ok(info_line.clone())
+}
+
+// Show `text` in the info line, or clear it with an empty one
+pub fn set(app: &AppHandle, text: &str) -> Result<(), String> {
+    let text = crate::text::truncate(text.trim(), MAX_CHARS).to_string();
+    {
+        let state = app.state::<AppState>();
+        let mut info_line = state.info_line.lock().map_err(|e| e.to_string())?;
+        if *info_line == text {
+            return Ok(());
+        }
+        *info_line = text.clone();
+    }
+    log::info!("Info line: {}", text);
+    event_router::publish(app, event_router::INFO_LINE, &InfoLinePayload { text })
+}
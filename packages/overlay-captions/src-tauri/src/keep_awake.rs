@@ -0,0 +1,229 @@
+// Keeps the screen on and the machine awake while captions are up: the overlay is visible
+// or a session is connected. Each OS has its own way of asking:
+//   - Linux: an org.freedesktop.ScreenSaver inhibit on the session bus against blanking,
+//     and a logind "idle:sleep" inhibitor lock against suspend
+//   - Windows: SetThreadExecutionState, which holds only as long as the thread that set it
+//   - macOS: a `caffeinate` process watching ours
+// All of them are held by one thread, which lets go when captioning stops.
+
+use crate::{AppHandle, AppState};
+use std::time::Duration;
+use tauri::Manager;
+
+// How often the overlay and session are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct KeepAwakeState {
+    // Bumped to stop the running keeper
+    generation: u64,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .keep_awake
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn is_captioning(app: &AppHandle) -> bool {
+    let state = app.state::<AppState>();
+    let overlay_visible = state.overlay_visible.lock().map(|v| *v).unwrap_or(false);
+    let connected = state.connected.lock().map(|c| *c).unwrap_or(false);
+    overlay_visible || connected
+}
+
+// Stop the running keeper and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let enabled = match state.settings.lock() {
+        Ok(s) => s.keep_awake,
+        Err(_) => return,
+    };
+    let generation = match state.keep_awake.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, generation));
+}
+
+fn run(app: &AppHandle, generation: u64) {
+    let mut inhibitor: Option<platform::Inhibitor> = None;
+    // Don't retry a failed inhibit every poll; once per captioning stretch is enough
+    let mut failed = false;
+
+    while is_current(app, generation) {
+        let captioning = is_captioning(app);
+        if captioning && inhibitor.is_none() && !failed {
+            match platform::Inhibitor::acquire() {
+                Ok(acquired) => {
+                    log::info!("Keeping the system awake while captioning");
+                    inhibitor = Some(acquired);
+                }
+                Err(e) => {
+                    log::warn!("Failed to keep the system awake: {}", e);
+                    failed = true;
+                }
+            }
+        } else if !captioning {
+            if inhibitor.take().is_some() {
+                log::info!("No longer keeping the system awake");
+            }
+            failed = false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use zbus::blocking::Connection;
+    use zbus::zvariant::OwnedFd;
+
+    const APP_NAME: &str = "Jutukuva";
+    const REASON: &str = "Showing live captions";
+
+    pub struct Inhibitor {
+        // The screensaver inhibit ends with this connection
+        screensaver: Option<(Connection, u32)>,
+        // Released when closed
+        _sleep_lock: Option<OwnedFd>,
+    }
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            let screensaver = inhibit_screensaver();
+            let sleep_lock = inhibit_sleep();
+            match (screensaver, sleep_lock) {
+                (Err(screensaver), Err(sleep)) => Err(format!("{}; {}", screensaver, sleep)),
+                (screensaver, sleep_lock) => Ok(Self {
+                    screensaver: screensaver.ok(),
+                    _sleep_lock: sleep_lock.ok(),
+                }),
+            }
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            if let Some((connection, cookie)) = self.screensaver.take() {
+                let _ = connection.call_method(
+                    Some("org.freedesktop.ScreenSaver"),
+                    "/org/freedesktop/ScreenSaver",
+                    Some("org.freedesktop.ScreenSaver"),
+                    "UnInhibit",
+                    &(cookie,),
+                );
+            }
+        }
+    }
+
+    fn inhibit_screensaver() -> Result<(Connection, u32), String> {
+        let connection = Connection::session().map_err(|e| e.to_string())?;
+        let reply = connection
+            .call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "Inhibit",
+                &(APP_NAME, REASON),
+            )
+            .map_err(|e| format!("screensaver: {}", e))?;
+        let cookie: u32 = reply.body().deserialize().map_err(|e| e.to_string())?;
+        Ok((connection, cookie))
+    }
+
+    fn inhibit_sleep() -> Result<OwnedFd, String> {
+        let connection = Connection::system().map_err(|e| e.to_string())?;
+        let reply = connection
+            .call_method(
+                Some("org.freedesktop.login1"),
+                "/org/freedesktop/login1",
+                Some("org.freedesktop.login1.Manager"),
+                "Inhibit",
+                &("idle:sleep", APP_NAME, REASON, "block"),
+            )
+            .map_err(|e| format!("logind: {}", e))?;
+        reply.body().deserialize().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows_sys::Win32::System::Power::{
+        SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED,
+    };
+
+    // Must be dropped on the thread that acquired it
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            // SAFETY: only changes this thread's execution state
+            let previous = unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS | ES_DISPLAY_REQUIRED | ES_SYSTEM_REQUIRED)
+            };
+            if previous == 0 {
+                return Err("SetThreadExecutionState failed".to_string());
+            }
+            Ok(Self)
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            // SAFETY: as above
+            unsafe {
+                SetThreadExecutionState(ES_CONTINUOUS);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::{Child, Command};
+
+    pub struct Inhibitor {
+        caffeinate: Child,
+    }
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            // -d keeps the display on, -i the system; -w ends it should we crash
+            let caffeinate = Command::new("caffeinate")
+                .args(["-d", "-i", "-w", &std::process::id().to_string()])
+                .spawn()
+                .map_err(|e| format!("caffeinate: {}", e))?;
+            Ok(Self { caffeinate })
+        }
+    }
+
+    impl Drop for Inhibitor {
+        fn drop(&mut self) {
+            let _ = self.caffeinate.kill();
+            let _ = self.caffeinate.wait();
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+mod platform {
+    pub struct Inhibitor;
+
+    impl Inhibitor {
+        pub fn acquire() -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+    }
+}
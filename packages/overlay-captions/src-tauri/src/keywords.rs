@@ -0,0 +1,193 @@
+// Keywords for a "topics on stage right now" word cloud: the words said most in the last
+// `keywords.window_secs` of captions, counted as the public saw them. Short words, numbers
+// and common Estonian and English words are left out, as are `keywords.stop_words`. Every
+// `interval_secs` a `keywords-update` event carries the top `count`, and the caption page
+// serves the same at /keywords.json for a companion display on another machine.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::export::split_speaker;
+use crate::settings::{AppSettings, KeywordSettings};
+use crate::{clock, AppHandle, AppState};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+// Words too common to say anything about the topic
+const ESTONIAN_STOP_WORDS: &[&str] = &[
+    "aga", "ainult", "alati", "ehk", "ikka", "juba", "jälle", "kas", "kes", "kõik", "kuid",
+    "kuidas", "kuigi", "kuna", "kus", "mida", "miks", "mille", "millal", "mina", "minu", "mis",
+    "mitte", "meie", "nagu", "natuke", "need", "neid", "nemad", "nende", "ning", "niisiis", "nüüd",
+    "näiteks", "oled", "olen", "oleks", "oleme", "olete", "oli", "olid", "olnud", "ongi", "palju",
+    "peab", "peaks", "rohkem", "saab", "samuti", "seal", "seda", "selle", "sellega", "sellest",
+    "sest", "siin", "siis", "sinu", "tema", "teie", "tuleb", "tõesti", "veel", "võib", "või",
+    "väga", "vähem", "ütleme", "lihtsalt",
+];
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "about", "also", "because", "been", "being", "could", "does", "from", "going", "have", "here",
+    "into", "just", "know", "like", "more", "much", "okay", "only", "other", "over", "really",
+    "should", "some", "such", "than", "that", "their", "them", "then", "there", "these", "they",
+    "think", "this", "those", "very", "want", "well", "were", "what", "when", "which", "will",
+    "with", "would", "yeah", "your",
+];
+// Bounds the memory of a long window on a busy stage
+const MAX_MENTIONS: usize = 20_000;
+
+#[derive(Default)]
+pub struct KeywordState {
+    // Bumped to stop the running feed
+    generation: u64,
+    // Words as they were said, oldest first, with milliseconds since the Unix epoch
+    mentions: VecDeque<(u64, String)>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Keyword {
+    // Lowercase
+    pub word: String,
+    pub count: u32,
+}
+
+fn settings(app: &AppHandle) -> Result<KeywordSettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.keywords.clone())
+}
+
+fn is_keyword(word: &str, settings: &KeywordSettings) -> bool {
+    word.chars().count() >= settings.min_chars as usize
+        && !word.chars().all(|c| c.is_numeric())
+        && !ESTONIAN_STOP_WORDS.contains(&word)
+        && !ENGLISH_STOP_WORDS.contains(&word)
+        && !settings.stop_words.iter().any(|s| s.to_lowercase() == word)
+}
+
+// The keywords of a caption, speaker names left out
+fn words(text: &str, settings: &KeywordSettings) -> Vec<String> {
+    text.lines()
+        .map(|line| split_speaker(line).1)
+        .flat_map(|line| line.split(|c: char| !c.is_alphanumeric() && c != '-'))
+        .map(|word| word.trim_matches('-').to_lowercase())
+        .filter(|word| is_keyword(word, settings))
+        .collect()
+}
+
+// The most said words of the window, most first
+pub fn top(app: &AppHandle) -> Result<Vec<Keyword>, String> {
+    let settings = settings(app)?;
+    let since = clock::now_millis().saturating_sub(u64::from(settings.window_secs) * 1000);
+    let state = app.state::<AppState>();
+    let keywords = state.keywords.lock().map_err(|e| e.to_string())?;
+
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    for (_, word) in keywords.mentions.iter().filter(|(at, _)| *at >= since) {
+        *counts.entry(word.as_str()).or_default() += 1;
+    }
+    let mut top: Vec<Keyword> = counts
+        .into_iter()
+        .map(|(word, count)| Keyword {
+            word: word.to_string(),
+            count,
+        })
+        .collect();
+    // Ties alphabetically, so the cloud doesn't reshuffle between updates
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    top.truncate(settings.count as usize);
+    Ok(top)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .keywords
+        .lock()
+        .map(|k| k.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running feed and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let settings = match settings(app) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let generation = match app.state::<AppState>().keywords.lock() {
+        Ok(mut k) => {
+            k.generation += 1;
+            k.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    let interval = Duration::from_secs(u64::from(settings.interval_secs.max(1)));
+    std::thread::spawn(move || {
+        while is_current(&app, generation) {
+            match top(&app) {
+                Ok(keywords) => {
+                    if let Err(e) = app.emit("keywords-update", keywords) {
+                        log::error!("Failed to emit keywords: {}", e);
+                    }
+                }
+                Err(e) => log::error!("Failed to count keywords: {}", e),
+            }
+            std::thread::sleep(interval);
+        }
+    });
+}
+
+// A new session starts counting afresh
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut keywords) = app.state::<AppState>().keywords.lock() {
+        keywords.mentions.clear();
+    }
+    let _ = app.emit("keywords-update", Vec::<Keyword>::new());
+}
+
+// Counts the words of every caption shown while the feed is enabled
+pub struct KeywordOutput;
+
+impl Output for KeywordOutput {
+    fn id(&self) -> &str {
+        "keywords"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        let settings = &settings.keywords;
+        if !settings.enabled {
+            return Ok(());
+        }
+        let text = payload
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let now = clock::now_millis();
+        let since = now.saturating_sub(u64::from(settings.window_secs) * 1000);
+
+        let state = app.state::<AppState>();
+        let mut keywords = state.keywords.lock().map_err(|e| e.to_string())?;
+        keywords
+            .mentions
+            .extend(words(text, settings).into_iter().map(|word| (now, word)));
+        while keywords.mentions.front().is_some_and(|(at, _)| *at < since)
+            || keywords.mentions.len() > MAX_MENTIONS
+        {
+            keywords.mentions.pop_front();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+// Kiosk mode for unattended public installs: settings can't be changed and the app can't
+// be quit until someone enters the PIN. It is turned on with the --kiosk command line flag
+// or by an administrator placing a policy file next to the settings:
+//   kiosk.json: { "enabled": true, "pin": "1234" }
+// The PIN only ever comes from the policy file, so the settings UI can't reveal it. Without
+// a PIN the app stays locked for as long as it runs.
+
+use crate::settings::get_config_dir;
+use crate::{diagnostics, tray, AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{Emitter, Manager};
+
+// Error returned by commands that kiosk mode doesn't allow
+pub const POLICY_DENIED: &str = "PolicyDenied";
+
+const FLAG: &str = "--kiosk";
+
+#[derive(Default, Deserialize)]
+struct KioskPolicy {
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    pin: Option<String>,
+}
+
+#[derive(Default)]
+pub struct KioskState {
+    enabled: bool,
+    pin: Option<String>,
+    unlocked: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KioskStatus {
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+fn load_policy() -> KioskPolicy {
+    let path = get_config_dir().join("kiosk.json");
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            // A broken policy must not unlock the install
+            log::error!("Invalid kiosk policy {}: {}", path.display(), e);
+            KioskPolicy {
+                enabled: true,
+                pin: None,
+            }
+        }),
+        Err(_) => KioskPolicy::default(),
+    }
+}
+
+pub fn load() -> KioskState {
+    let policy = load_policy();
+    let enabled = policy.enabled || std::env::args().any(|arg| arg == FLAG);
+    let pin = policy.pin.filter(|pin| !pin.trim().is_empty());
+    if enabled {
+        log::info!(
+            "Kiosk mode enabled{}",
+            if pin.is_some() { "" } else { " without a PIN" }
+        );
+    }
+    KioskState {
+        enabled,
+        pin,
+        unlocked: false,
+    }
+}
+
+pub fn status(app: &AppHandle) -> KioskStatus {
+    app.try_state::<AppState>()
+        .and_then(|state| {
+            state.kiosk.lock().ok().map(|k| KioskStatus {
+                enabled: k.enabled,
+                locked: k.enabled && !k.unlocked,
+            })
+        })
+        .unwrap_or(KioskStatus {
+            enabled: false,
+            locked: false,
+        })
+}
+
+pub fn is_locked(app: &AppHandle) -> bool {
+    status(app).locked
+}
+
+// For commands that change settings or end the app
+pub fn check(app: &AppHandle) -> Result<(), String> {
+    if is_locked(app) {
+        return Err(POLICY_DENIED.to_string());
+    }
+    Ok(())
+}
+
+fn changed(app: &AppHandle) -> Result<(), String> {
+    // The quit item is only in the tray menu while unlocked
+    tray::refresh(app);
+    app.emit("kiosk-changed", status(app))
+        .map_err(|e| e.to_string())
+}
+
+pub fn unlock(app: &AppHandle, pin: &str) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut kiosk = state.kiosk.lock().map_err(|e| e.to_string())?;
+        if !kiosk.enabled || kiosk.unlocked {
+            return Ok(());
+        }
+        match kiosk.pin.as_deref() {
+            Some(expected) if expected == pin.trim() => kiosk.unlocked = true,
+            Some(_) => {
+                drop(kiosk);
+                let _ = diagnostics::record(app, "kiosk unlock failed", None);
+                return Err("Wrong PIN".to_string());
+            }
+            None => return Err("No kiosk PIN is configured".to_string()),
+        }
+    }
+    log::info!("Kiosk unlocked");
+    changed(app)
+}
+
+pub fn lock(app: &AppHandle) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut kiosk = state.kiosk.lock().map_err(|e| e.to_string())?;
+        if !kiosk.enabled || !kiosk.unlocked {
+            return Ok(());
+        }
+        kiosk.unlocked = false;
+    }
+    log::info!("Kiosk locked");
+    changed(app)
+}
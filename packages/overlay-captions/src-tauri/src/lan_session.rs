@@ -0,0 +1,386 @@
+// Offline sessions for venues without internet: this instance hosts a session on the LAN,
+// speaking the y-websocket protocol of the public session server, so the captioner's editor
+// and other caption instances join it at ws://<host>:<port> with the session code exactly as
+// they would join a session online. The session is advertised as _jutukuva-session._tcp with
+// its code, so joining instances find it without anyone typing addresses.
+//
+// The server never needs to understand the document: it keeps every update a client sends
+// and hands them all to anyone joining, which Yjs applies in any order. Awareness (who is
+// connected) is passed between clients as is. There is no password; the session is open to
+// anyone on the LAN who knows or discovers the code.
+
+use crate::discovery;
+use crate::AppHandle;
+use crate::AppState;
+use rand::Rng;
+use serde::Serialize;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+use tauri::Manager;
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::{Message, WebSocket};
+
+const SERVICE_TYPE: &str = "_jutukuva-session._tcp.local.";
+// How often the listener checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// How long an update can wait in a client's queue
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+// Session codes use the characters of the public server's codes
+const CODE_CHARACTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+// y-websocket message types
+const MESSAGE_SYNC: u64 = 0;
+const MESSAGE_AWARENESS: u64 = 1;
+// y-protocols sync message types
+const SYNC_STEP_1: u64 = 0;
+const SYNC_STEP_2: u64 = 1;
+const SYNC_UPDATE: u64 = 2;
+// Encoded empty state vector and empty update
+const EMPTY_STATE_VECTOR: &[u8] = &[0];
+const EMPTY_UPDATE: &[u8] = &[0, 0];
+
+#[derive(Default)]
+pub struct LanSessionState {
+    // Bumped to stop the running server, e.g. when the port changes
+    generation: u64,
+    // Code of the hosted session, kept while the app runs so clients can reconnect
+    code: Option<String>,
+    // Every document update received, in order
+    updates: Vec<Vec<u8>>,
+    clients: Vec<(u64, Sender<Vec<u8>>)>,
+    next_client: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanSession {
+    pub name: String,
+    pub code: String,
+    // Server URL to join the session with, e.g. "ws://192.168.1.20:1234"
+    pub url: String,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .lan_session
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn new_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_CHARACTERS[rng.gen_range(0..CODE_CHARACTERS.len())] as char)
+        .collect()
+}
+
+// The session this instance hosts, None when hosting is off
+pub fn hosted(app: &AppHandle) -> Result<Option<LanSession>, String> {
+    let state = app.state::<AppState>();
+    let (enabled, port) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (settings.lan_session.enabled, settings.lan_session.port)
+    };
+    let session = state.lan_session.lock().map_err(|e| e.to_string())?;
+    Ok(session
+        .code
+        .clone()
+        .filter(|_| enabled)
+        .map(|code| LanSession {
+            name: format!("Jutukuva ({})", discovery::host_name()),
+            code,
+            // The hosting instance joins its own session through the loopback address
+            url: format!("ws://127.0.0.1:{}", port),
+        }))
+}
+
+// Sessions hosted on the LAN answering within `timeout`; blocks for that long
+pub fn discover(app: &AppHandle, timeout: Duration) -> Result<Vec<LanSession>, String> {
+    Ok(discovery::browse(app, SERVICE_TYPE, timeout)?
+        .iter()
+        .filter_map(|info| {
+            let code = info.get_property_val_str("code")?;
+            let address = info.get_addresses().iter().min()?;
+            // IPv6 addresses need brackets next to a port
+            let url = if address.is_ipv6() {
+                format!("ws://[{}]:{}", address, info.get_port())
+            } else {
+                format!("ws://{}:{}", address, info.get_port())
+            };
+            Some(LanSession {
+                name: discovery::instance_name(info, SERVICE_TYPE).to_string(),
+                code: code.to_string(),
+                url,
+            })
+        })
+        .collect())
+}
+
+// Stop the running server and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, port, configured_code) = match state.settings.lock() {
+        Ok(s) => (
+            s.lan_session.enabled,
+            s.lan_session.port,
+            s.lan_session.code.trim().to_uppercase(),
+        ),
+        Err(_) => return,
+    };
+    let (generation, code) = match state.lan_session.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            // Connected clients notice the closed queue and disconnect
+            s.clients.clear();
+            if !configured_code.is_empty() && s.code.as_deref() != Some(&configured_code) {
+                s.code = Some(configured_code);
+                s.updates.clear();
+            }
+            let code = s.code.get_or_insert_with(new_code).clone();
+            (s.generation, code)
+        }
+        Err(_) => return,
+    };
+
+    let advertised = enabled.then_some(port);
+    if let Err(e) = discovery::register(app, SERVICE_TYPE, advertised, &[("code", &code)]) {
+        log::error!("Failed to advertise LAN session: {}", e);
+    }
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&app, port, &code, generation) {
+            log::error!("LAN session server on port {} failed: {}", port, e);
+        }
+    });
+}
+
+fn serve(app: &AppHandle, port: u16, code: &str, generation: u64) -> Result<(), String> {
+    // A server being replaced may hold the port until its next poll
+    let mut attempts = 0;
+    let listener = loop {
+        match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(listener) => break listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts < 5 => {
+                attempts += 1;
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    log::info!("Hosting LAN session {} on port {}", code, port);
+
+    while is_current(app, generation) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let app = app.clone();
+                let code = code.to_string();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_client(&app, stream, &code, generation) {
+                        log::warn!("LAN session client {} disconnected: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    log::info!("LAN session server on port {} stopped", port);
+    Ok(())
+}
+
+// lib0 variable-length unsigned integer
+fn write_var_uint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_var_uint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return Some(value);
+        }
+        shift += 7;
+        if shift > 63 {
+            return None;
+        }
+    }
+}
+
+fn sync_message(sync_type: u64, payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(payload.len() + 8);
+    write_var_uint(&mut message, MESSAGE_SYNC);
+    write_var_uint(&mut message, sync_type);
+    write_var_uint(&mut message, payload.len() as u64);
+    message.extend_from_slice(payload);
+    message
+}
+
+// Registers a client; returns its id and its queue of updates from other clients
+fn subscribe(app: &AppHandle) -> Result<(u64, Receiver<Vec<u8>>), String> {
+    let (sender, receiver) = mpsc::channel();
+    let state = app.state::<AppState>();
+    let mut session = state.lan_session.lock().map_err(|e| e.to_string())?;
+    let id = session.next_client;
+    session.next_client += 1;
+    session.clients.push((id, sender));
+    Ok((id, receiver))
+}
+
+fn broadcast(app: &AppHandle, from: u64, message: Vec<u8>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut session = state.lan_session.lock().map_err(|e| e.to_string())?;
+    session
+        .clients
+        .retain(|(id, client)| *id == from || client.send(message.clone()).is_ok());
+    Ok(())
+}
+
+// Reacts to one message of client `id`; returns the replies for that client
+fn handle_message(app: &AppHandle, id: u64, message: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let mut position = 0;
+    match read_var_uint(message, &mut position) {
+        Some(MESSAGE_SYNC) => {}
+        Some(MESSAGE_AWARENESS) => {
+            broadcast(app, id, message.to_vec())?;
+            return Ok(Vec::new());
+        }
+        // Auth and awareness queries have no meaning here
+        _ => return Ok(Vec::new()),
+    }
+    let sync_type = read_var_uint(message, &mut position).ok_or("Truncated sync message")?;
+    let length = read_var_uint(message, &mut position).ok_or("Truncated sync message")? as usize;
+    let payload = message
+        .get(position..position + length)
+        .ok_or("Truncated sync message")?;
+
+    match sync_type {
+        // The client asks for what it is missing: everything, as the server can't tell
+        SYNC_STEP_1 => {
+            let state = app.state::<AppState>();
+            let session = state.lan_session.lock().map_err(|e| e.to_string())?;
+            let mut replies: Vec<Vec<u8>> = session
+                .updates
+                .iter()
+                .map(|update| sync_message(SYNC_UPDATE, update))
+                .collect();
+            // Step 2 marks the client as synced
+            replies.push(sync_message(SYNC_STEP_2, EMPTY_UPDATE));
+            Ok(replies)
+        }
+        SYNC_STEP_2 | SYNC_UPDATE => {
+            if payload != EMPTY_UPDATE {
+                {
+                    let state = app.state::<AppState>();
+                    let mut session = state.lan_session.lock().map_err(|e| e.to_string())?;
+                    session.updates.push(payload.to_vec());
+                }
+                broadcast(app, id, sync_message(SYNC_UPDATE, payload))?;
+            }
+            Ok(Vec::new())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+// Handshake callback that keeps the path the client asked for
+struct RequestedPath(String);
+
+impl Callback for &mut RequestedPath {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        self.0 = request.uri().path().to_string();
+        Ok(response)
+    }
+}
+
+fn handle_client(
+    app: &AppHandle,
+    stream: TcpStream,
+    code: &str,
+    generation: u64,
+) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    // Clients join at /<code>, with y-websocket's query parameters after it
+    let mut requested = RequestedPath(String::new());
+    let mut socket: WebSocket<TcpStream> =
+        tungstenite::accept_hdr(stream, &mut requested).map_err(|e| e.to_string())?;
+    let requested = requested.0;
+    if !requested.eq_ignore_ascii_case(&format!("/{}", code)) {
+        let _ = socket.close(Some(CloseFrame {
+            code: CloseCode::Policy,
+            reason: "No such session".into(),
+        }));
+        let _ = socket.flush();
+        return Err(format!("No session at {}", requested));
+    }
+    // Reads time out so the loop can pass on updates in between
+    socket
+        .get_ref()
+        .set_read_timeout(Some(SEND_INTERVAL))
+        .map_err(|e| e.to_string())?;
+
+    let (id, updates) = subscribe(app)?;
+    // Ask for the client's document, which may have changed while it was offline
+    socket
+        .send(Message::Binary(sync_message(
+            SYNC_STEP_1,
+            EMPTY_STATE_VECTOR,
+        )))
+        .map_err(|e| e.to_string())?;
+
+    while is_current(app, generation) {
+        match socket.read() {
+            Ok(Message::Binary(message)) => {
+                for reply in handle_message(app, id, &message)? {
+                    socket
+                        .send(Message::Binary(reply))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+
+        loop {
+            match updates.try_recv() {
+                Ok(message) => socket
+                    .send(Message::Binary(message))
+                    .map_err(|e| e.to_string())?,
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    let _ = socket.close(None);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let _ = socket.close(None);
+    Ok(())
+}
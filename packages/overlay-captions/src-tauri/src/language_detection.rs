@@ -0,0 +1,107 @@
+// Identifies the language of each caption with whatlang, so that in a multilingual session
+// the processors and the overlay follow the language actually being spoken rather than
+// the one declared in the session metadata. A caption too short or too ambiguous to
+// identify keeps the language detected last.
+
+use crate::{AppHandle, AppState};
+use tauri::Manager;
+use whatlang::{Detector, Lang};
+
+// ISO 639-1 codes, as used everywhere else in the app, for the languages we expect
+const LANGUAGES: &[(&str, Lang)] = &[
+    ("et", Lang::Est),
+    ("en", Lang::Eng),
+    ("fi", Lang::Fin),
+    ("ru", Lang::Rus),
+    ("uk", Lang::Ukr),
+    ("lv", Lang::Lav),
+    ("lt", Lang::Lit),
+    ("de", Lang::Deu),
+    ("fr", Lang::Fra),
+    ("es", Lang::Spa),
+    ("it", Lang::Ita),
+    ("pt", Lang::Por),
+    ("nl", Lang::Nld),
+    ("sv", Lang::Swe),
+    ("da", Lang::Dan),
+    ("nb", Lang::Nob),
+    ("pl", Lang::Pol),
+    ("cs", Lang::Ces),
+    ("hu", Lang::Hun),
+    ("tr", Lang::Tur),
+    ("ar", Lang::Ara),
+    ("he", Lang::Heb),
+    ("ja", Lang::Jpn),
+    ("ko", Lang::Kor),
+    ("zh", Lang::Cmn),
+];
+
+// Fewer characters than this are not enough to tell languages apart
+const MIN_CHARS: usize = 20;
+
+#[derive(Default)]
+pub struct DetectedLanguage {
+    current: Option<String>,
+}
+
+fn to_code(lang: Lang) -> &'static str {
+    LANGUAGES
+        .iter()
+        .find(|(_, l)| *l == lang)
+        .map(|(code, _)| *code)
+        // ISO 639-3 for the rest
+        .unwrap_or_else(|| lang.code())
+}
+
+fn to_lang(code: &str) -> Option<Lang> {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, lang)| *lang)
+        .or_else(|| Lang::from_code(code))
+}
+
+// Language of the caption, None when detection is off or nothing was identified yet
+pub fn detect(app: &AppHandle, text: &str) -> Option<String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().ok()?.language_detection.clone();
+    if !settings.enabled {
+        return None;
+    }
+
+    let mut detected = state.detected_language.lock().ok()?;
+    if text.chars().count() >= MIN_CHARS {
+        let detector = if settings.candidates.is_empty() {
+            Detector::new()
+        } else {
+            Detector::with_allowlist(
+                settings
+                    .candidates
+                    .iter()
+                    .filter_map(|c| to_lang(c))
+                    .collect(),
+            )
+        };
+        if let Some(info) = detector.detect(text) {
+            if info.confidence() >= settings.min_confidence {
+                let code = to_code(info.lang());
+                if detected.current.as_deref() != Some(code) {
+                    log::info!(
+                        "Caption language detected: {} ({:.2})",
+                        code,
+                        info.confidence()
+                    );
+                }
+                detected.current = Some(code.to_string());
+            }
+        }
+    }
+    detected.current.clone()
+}
+
+// Forget the detected language, e.g. when a session ends
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut detected) = app.state::<AppState>().detected_language.lock() {
+        detected.current = None;
+    }
+}
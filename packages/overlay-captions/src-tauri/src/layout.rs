@@ -0,0 +1,116 @@
+// Where each element of the overlay window goes. The window is a 3×3 grid; each element is
+// anchored to one of its cells ("topLeft" … "bottomRight") and stacked by `z`, so a venue
+// rearranges the overlay in settings instead of in CSS. The app has no settings profiles,
+// so the layout is kept with the rest of the settings.
+//
+// The grid sits inside the safe margins, which keep everything out of the edges of a
+// projector image that the screen crops. Line breaking fits lines into the narrower width.
+
+use crate::settings::{AppSettings, LayoutRegion, SafeMargins};
+use serde::Serialize;
+
+pub const ELEMENTS: [&str; 5] = ["captions", "infoLine", "lowerThird", "watermark", "clock"];
+const ANCHORS: [&str; 9] = [
+    "topLeft",
+    "top",
+    "topRight",
+    "left",
+    "center",
+    "right",
+    "bottomLeft",
+    "bottom",
+    "bottomRight",
+];
+
+// More than this on one side would leave little room for captions
+const MAX_SAFE_MARGIN_PERCENT: f32 = 25.0;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayLayout {
+    // Bottom to top
+    pub regions: Vec<LayoutRegion>,
+    pub safe_margins: SafeMargins,
+}
+
+fn region(element: &str, anchor: &str, z: i32) -> LayoutRegion {
+    LayoutRegion {
+        element: element.to_string(),
+        anchor: anchor.to_string(),
+        z,
+    }
+}
+
+// Where elements go until moved; captions stay below everything else
+pub fn default_regions() -> Vec<LayoutRegion> {
+    vec![
+        region("captions", "bottom", 1),
+        region("infoLine", "top", 10),
+        region("lowerThird", "bottomLeft", 20),
+        region("watermark", "topRight", 5),
+        region("clock", "topLeft", 30),
+    ]
+}
+
+// Every element's region, the default one where settings have none, bottom to top
+pub fn effective(settings: &AppSettings) -> Vec<LayoutRegion> {
+    let mut regions: Vec<LayoutRegion> = default_regions()
+        .into_iter()
+        .map(|default| {
+            settings
+                .layout
+                .regions
+                .iter()
+                .find(|r| r.element == default.element)
+                .cloned()
+                .unwrap_or(default)
+        })
+        .collect();
+    regions.sort_by_key(|r| r.z);
+    regions
+}
+
+pub fn clamp_margins(margins: &SafeMargins) -> SafeMargins {
+    let clamp = |percent: f32| {
+        if percent.is_finite() {
+            percent.clamp(0.0, MAX_SAFE_MARGIN_PERCENT)
+        } else {
+            0.0
+        }
+    };
+    SafeMargins {
+        top: clamp(margins.top),
+        right: clamp(margins.right),
+        bottom: clamp(margins.bottom),
+        left: clamp(margins.left),
+    }
+}
+
+// Share of the window's width inside the safe margins
+pub fn usable_width(settings: &AppSettings) -> f32 {
+    let margins = clamp_margins(&settings.layout.safe_margins);
+    (100.0 - margins.left - margins.right) / 100.0
+}
+
+pub fn overlay_layout(settings: &AppSettings) -> OverlayLayout {
+    OverlayLayout {
+        regions: effective(settings),
+        safe_margins: clamp_margins(&settings.layout.safe_margins),
+    }
+}
+
+// Check regions from the frontend; elements left out keep their default region
+pub fn validate(regions: Vec<LayoutRegion>) -> Result<Vec<LayoutRegion>, String> {
+    for (i, region) in regions.iter().enumerate() {
+        if !ELEMENTS.contains(&region.element.as_str()) {
+            return Err(format!("Unknown overlay element: {}", region.element));
+        }
+        if !ANCHORS.contains(&region.anchor.as_str()) {
+            return Err(format!("Unknown anchor: {}", region.anchor));
+        }
+        if regions[..i].iter().any(|r| r.element == region.element) {
+            return Err(format!("{} is placed twice", region.element));
+        }
+    }
+    Ok(regions)
+}
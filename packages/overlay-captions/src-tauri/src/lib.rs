@@ -1,33 +1,300 @@
+mod arbitration;
+mod audio_monitor;
+mod audio_uplink;
+mod ble_broadcast;
+mod bookmarks;
+mod braille;
+mod browser_bridge;
+mod calendar;
+mod caption_page;
+mod caption_pipeline;
+mod caption_services;
+mod cast;
+mod chapters;
+mod check_in;
+mod clock;
 mod commands;
+mod compact;
+mod compositor;
+mod context_menu;
+mod contrast;
+mod corrections;
+mod desktop;
+mod diagnostics;
+mod discovery;
+mod display_delay;
+mod dlna;
+mod drag_region;
+mod environment;
+mod event_router;
+mod exit_protection;
+mod export;
+mod failover;
+mod focus_follow;
+mod footswitch;
+mod history;
+mod hls_captions;
+mod hot_corner;
+mod http;
+mod i18n;
+mod info_line;
+mod keep_awake;
+mod keywords;
+mod kiosk;
+mod lan_session;
+mod language_detection;
+mod layout;
+mod light_alert;
+mod lower_third;
+mod macros;
+mod meetings;
+mod midi;
+mod minutes;
+mod network;
+mod network_quality;
+mod pause;
+mod pdf;
+mod peek;
+mod plugins;
+mod power;
+mod presentation;
+mod processors;
+mod reading_speed;
+mod relay;
+mod remote_control;
+mod remote_support;
+mod replay;
+mod resume;
+mod review;
+mod s3;
+mod scripting;
+mod secrets;
+mod self_test;
 mod settings;
+mod serial_display;
+mod server_url;
+mod session_clock;
+mod settings_writer;
+mod signatures;
+mod speaker_colors;
+mod speaker_names;
+mod storage;
+mod summary;
+#[cfg(feature = "test-hooks")]
+mod test_hooks;
+mod text;
+mod theme;
+mod touch;
+mod training;
+mod tray;
+mod viewer;
+mod watermark;
 mod window_manager;
 
 use commands::*;
 use settings::{load_settings, AppSettings};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Emitter, Listener, Manager, WindowEvent,
+    Emitter, Listener, Manager, RunEvent, Theme, WindowEvent,
 };
+use window_manager::{ManagedWindow, OverlayId};
+
+// The app runs on Wry; unit tests run the backend on Tauri's mock runtime instead, without
+// windows or a webview. Modules take these rather than tauri's own types so they build for
+// either.
+#[cfg(not(test))]
+pub type AppRuntime = tauri::Wry;
+#[cfg(test)]
+pub type AppRuntime = tauri::test::MockRuntime;
+pub type AppHandle = tauri::AppHandle<AppRuntime>;
+pub type WebviewWindow = tauri::WebviewWindow<AppRuntime>;
 
 pub struct AppState {
     pub settings: Mutex<AppSettings>,
     pub overlay_visible: Mutex<bool>,
+    pub peek: Mutex<peek::PeekState>,
+    pub presentation_mode: Mutex<bool>,
+    pub session_language: Mutex<Option<String>>,
+    pub pipeline: Mutex<caption_pipeline::CaptionPipeline>,
+    pub router: Mutex<event_router::EventRouter>,
+    pub scripts: Arc<Mutex<scripting::ScriptHost>>,
+    pub connected: Mutex<bool>,
+    pub failover: Mutex<failover::FailoverState>,
+    pub reading_speed: Mutex<reading_speed::ReadingSpeedGovernor>,
+    pub review: Mutex<review::ReviewQueue>,
+    // Learned this session (see corrections.rs)
+    pub corrections: Mutex<corrections::CorrectionState>,
+    // Speakers in the order they first spoke this session
+    pub speaker_colors: Mutex<speaker_colors::SpeakerColors>,
+    // Label to name mapping of the active session
+    pub speaker_names: Mutex<Vec<speaker_names::SpeakerName>>,
+    // Accelerators currently registered for caption macros
+    pub macro_shortcuts: Mutex<Vec<String>>,
+    pub remote_control: Mutex<remote_control::RemoteControlState>,
+    pub midi: Mutex<midi::MidiState>,
+    pub footswitch: Mutex<footswitch::FootswitchState>,
+    pub caption_pause: Mutex<pause::PauseState>,
+    pub diagnostics: Mutex<diagnostics::Diagnostics>,
+    pub network_quality: Mutex<network_quality::NetworkQualityState>,
+    pub settings_writer: Mutex<settings_writer::SettingsWriter>,
+    pub caption_history: Mutex<history::CaptionHistory>,
+    pub storage: Mutex<storage::Storage>,
+    pub detected_language: Mutex<language_detection::DetectedLanguage>,
+    // Code of the session the main window has joined
+    pub session_code: Mutex<Option<String>>,
+    pub signing: Mutex<signatures::SigningState>,
+    pub kiosk: Mutex<kiosk::KioskState>,
+    pub relay: Mutex<relay::RelayState>,
+    pub viewer: Mutex<viewer::ViewerState>,
+    pub discovery: Mutex<discovery::DiscoveryState>,
+    pub caption_page: Mutex<caption_page::CaptionPageState>,
+    pub cast: Mutex<cast::CastState>,
+    pub dlna: Mutex<dlna::DlnaState>,
+    pub serial_display: Mutex<serial_display::SerialDisplayState>,
+    pub braille: Mutex<braille::BrailleState>,
+    pub caption_services: Mutex<caption_services::CaptionServicesState>,
+    pub hls_captions: Mutex<hls_captions::HlsCaptionsState>,
+    pub audio_monitor: Mutex<audio_monitor::AudioMonitorState>,
+    pub audio_uplink: Mutex<audio_uplink::AudioUplinkState>,
+    pub lan_session: Mutex<lan_session::LanSessionState>,
+    pub ble_broadcast: Mutex<ble_broadcast::BleBroadcastState>,
+    pub remote_support: Mutex<remote_support::RemoteSupportState>,
+    pub training: Mutex<training::TrainingState>,
+    pub display_delay: Mutex<display_delay::DisplayDelayState>,
+    pub session_clock: Mutex<session_clock::SessionClockState>,
+    // Text of the info line, empty while there is none
+    pub info_line: Mutex<String>,
+    pub lower_third: Mutex<lower_third::LowerThirdState>,
+    pub contrast: Mutex<contrast::ContrastState>,
+    pub theme: Mutex<theme::ThemeState>,
+    pub power: Mutex<power::PowerState>,
+    pub keep_awake: Mutex<keep_awake::KeepAwakeState>,
+    // Why overlays are opaque, None while they can be transparent
+    pub compositor_fallback: Mutex<Option<String>>,
+    // Remote session or virtual machine, detected at startup
+    pub environment: environment::Environment,
+    pub drag_region: Mutex<drag_region::DragRegionState>,
+    pub hot_corner: Mutex<hot_corner::HotCornerState>,
+    pub compact: Mutex<compact::CompactState>,
+    pub replay: Mutex<replay::ReplayState>,
+    pub bookmarks: Mutex<bookmarks::BookmarkState>,
+    pub keywords: Mutex<keywords::KeywordState>,
+    pub check_in: Mutex<check_in::CheckInState>,
+    pub arbitration: Mutex<arbitration::ArbitrationState>,
+    pub tray_clicks: Mutex<tray::TrayClicks>,
+    pub focus_follow: Mutex<focus_follow::FocusFollowState>,
+    pub meetings: Mutex<meetings::MeetingState>,
+    pub calendar: Mutex<calendar::CalendarState>,
+    pub browser_bridge: Mutex<browser_bridge::BrowserBridgeState>,
 }
 
-fn show_main_window(app: &tauri::AppHandle) {
+fn show_main_window(app: &AppHandle) {
     // Show main window (keep overlay visible if it exists)
-    if let Some(main_window) = app.get_webview_window("main") {
-        let _ = main_window.show();
-        let _ = main_window.set_focus();
+    let _ = ManagedWindow::Main.focus(app);
+}
+
+// Show main window and ask it to open the settings drawer
+fn show_main_with_settings_drawer(app: &AppHandle) -> Result<(), String> {
+    if presentation::is_active(app) {
+        log::info!("Presentation mode active, not showing settings");
+        return Ok(());
+    }
+
+    ManagedWindow::Main.focus(app)?;
+    // Emit event to open settings drawer
+    ManagedWindow::Main.emit(app, "open-settings", ())
+}
+
+// Hide the overlay window and update state
+fn hide_overlay_window(app: &AppHandle) -> Result<(), String> {
+    ManagedWindow::OVERLAY.hide(app)?;
+
+    let state = app.state::<AppState>();
+    let mut overlay_visible = state.overlay_visible.lock().map_err(|e| e.to_string())?;
+    *overlay_visible = false;
+    Ok(())
+}
+
+// Close all windows and exit
+fn quit_app(app: &AppHandle) {
+    let _ = ManagedWindow::OVERLAY.close(app);
+    let _ = ManagedWindow::Main.close(app);
+    app.exit(0);
+}
+
+// Run one of the configurable tray actions (see settings::TrayActions)
+fn run_tray_action(app: &AppHandle, action: &str) {
+    let result = match action {
+        "showMain" => {
+            show_main_window(app);
+            Ok(())
+        }
+        "toggleOverlay" => app.emit("toggle-overlay", ()).map_err(|e| e.to_string()),
+        // The settings, which a locked kiosk keeps
+        "openSettings" | "fontLarger" | "fontSmaller" if kiosk::is_locked(app) => kiosk::check(app),
+        "openSettings" => show_main_with_settings_drawer(app),
+        "fontLarger" => commands::step_font_size(app, commands::FONT_SIZE_STEP),
+        "fontSmaller" => commands::step_font_size(app, -commands::FONT_SIZE_STEP),
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        log::error!("Tray action '{}' failed: {}", action, e);
     }
 }
 
-// Spawn overlay window creation on a separate thread to avoid WebView2 deadlock
-fn spawn_show_overlay_window(app: tauri::AppHandle) {
-    std::thread::spawn(move || {
+// Create the operator overlay in a background task to avoid WebView2 deadlock
+fn spawn_show_operator_overlay_window(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let settings = match app.try_state::<AppState>() {
+            Some(state) => match state.settings.lock() {
+                Ok(s) => s.operator_overlay.overlay.clone(),
+                Err(_) => return,
+            },
+            None => return,
+        };
+
+        if let Err(e) = window_manager::create_overlay_window(&app, OverlayId::Operator, &settings)
+        {
+            log::error!("Failed to create operator overlay window: {}", e);
+        }
+    });
+}
+
+// Open the operator review window in a background task to avoid WebView2 deadlock
+fn spawn_open_review_window(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = ManagedWindow::Review.open(&app) {
+            log::error!("Failed to open review window: {}", e);
+        }
+    });
+}
+
+// Open the transcript window in a background task to avoid WebView2 deadlock
+fn spawn_open_transcript_window(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = ManagedWindow::Transcript.open(&app) {
+            log::error!("Failed to open transcript window: {}", e);
+        }
+    });
+}
+
+// Open or close the diagnostics window in a background task to avoid WebView2 deadlock
+fn spawn_toggle_diagnostics_window(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        match ManagedWindow::Diagnostics.toggle(&app) {
+            Ok(true) => diagnostics::start_probe(&app),
+            Ok(false) => {}
+            Err(e) => log::error!("Failed to toggle diagnostics window: {}", e),
+        }
+    });
+}
+
+// Spawn overlay window creation in a background task to avoid WebView2 deadlock
+fn spawn_show_overlay_window(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
         let state = match app.try_state::<AppState>() {
             Some(s) => s,
             None => return,
@@ -39,13 +306,15 @@ fn spawn_show_overlay_window(app: tauri::AppHandle) {
         };
 
         // Create or show overlay window (keep main window open)
-        if app.get_webview_window("overlay").is_none() {
-            if let Err(e) = window_manager::create_overlay_window(&app, &overlay_settings) {
+        if !ManagedWindow::OVERLAY.is_open(&app) {
+            if let Err(e) =
+                window_manager::create_overlay_window(&app, OverlayId::Public, &overlay_settings)
+            {
                 log::error!("Failed to create overlay window: {}", e);
                 return;
             }
         } else {
-            let _ = window_manager::show_overlay_window(&app);
+            let _ = ManagedWindow::OVERLAY.show(&app);
         }
 
         // Update state
@@ -57,18 +326,143 @@ fn spawn_show_overlay_window(app: tauri::AppHandle) {
     });
 }
 
+// Global shortcut that toggles the overlay
+fn overlay_shortcut() -> tauri_plugin_global_shortcut::Shortcut {
+    use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut};
+    Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyO)
+}
+
+fn build_caption_pipeline(
+    scripts: &Arc<Mutex<scripting::ScriptHost>>,
+    enabled_plugins: &[String],
+) -> caption_pipeline::CaptionPipeline {
+    let mut pipeline = caption_pipeline::CaptionPipeline::default();
+    // Ahead of the scripts, which can send the text anywhere
+    pipeline.add(Box::new(processors::speaker_mute::SpeakerMuteProcessor));
+    pipeline.add(Box::new(scripting::ScriptCaptionProcessor::new(scripts.clone())));
+    processors::register_builtin(&mut pipeline);
+    plugins::load_plugins(&mut pipeline, enabled_plugins);
+    pipeline
+}
+
+fn build_event_router() -> event_router::EventRouter {
+    let mut router = event_router::EventRouter::default();
+    event_router::register_builtin(&mut router);
+    router.add(Box::new(history::HistoryOutput));
+    router.add(Box::new(storage::ArchiveOutput));
+    router.add(Box::new(relay::RelayOutput));
+    router.add(Box::new(caption_page::CaptionPageOutput));
+    router.add(Box::new(serial_display::SerialDisplayOutput));
+    router.add(Box::new(braille::BrailleOutput));
+    for service in caption_services::Service::ALL {
+        router.add(Box::new(caption_services::CaptionServiceOutput(service)));
+    }
+    router.add(Box::new(hls_captions::HlsCaptionsOutput));
+    router.add(Box::new(audio_monitor::AudioMonitorOutput));
+    router.add(Box::new(ble_broadcast::BleBroadcastOutput));
+    router.add(Box::new(keywords::KeywordOutput));
+    router
+}
+
+// Everything the backend holds, before any worker has started; also what tests run on
+fn new_app_state(settings: AppSettings) -> AppState {
+    let scripts = Arc::new(Mutex::new(scripting::ScriptHost::new()));
+    let viewer = viewer::load(settings.relay.port);
+    let pipeline = build_caption_pipeline(&scripts, &settings.plugins.enabled);
+    AppState {
+        settings: Mutex::new(settings),
+        overlay_visible: Mutex::new(false),
+        peek: Mutex::new(peek::PeekState::default()),
+        presentation_mode: Mutex::new(false),
+        session_language: Mutex::new(None),
+        pipeline: Mutex::new(pipeline),
+        router: Mutex::new(build_event_router()),
+        scripts,
+        connected: Mutex::new(false),
+        failover: Mutex::new(failover::FailoverState::default()),
+        reading_speed: Mutex::new(reading_speed::ReadingSpeedGovernor::default()),
+        review: Mutex::new(review::ReviewQueue::default()),
+        corrections: Mutex::new(corrections::CorrectionState::default()),
+        speaker_colors: Mutex::new(speaker_colors::SpeakerColors::default()),
+        speaker_names: Mutex::new(Vec::new()),
+        macro_shortcuts: Mutex::new(Vec::new()),
+        remote_control: Mutex::new(remote_control::RemoteControlState::default()),
+        midi: Mutex::new(midi::MidiState::default()),
+        footswitch: Mutex::new(footswitch::FootswitchState::default()),
+        caption_pause: Mutex::new(pause::PauseState::default()),
+        diagnostics: Mutex::new(diagnostics::Diagnostics::default()),
+        network_quality: Mutex::new(network_quality::NetworkQualityState::default()),
+        settings_writer: Mutex::new(settings_writer::SettingsWriter::default()),
+        caption_history: Mutex::new(history::CaptionHistory::default()),
+        storage: Mutex::new(storage::Storage::default()),
+        detected_language: Mutex::new(language_detection::DetectedLanguage::default()),
+        session_code: Mutex::new(None),
+        signing: Mutex::new(signatures::SigningState::default()),
+        kiosk: Mutex::new(kiosk::load()),
+        relay: Mutex::new(relay::RelayState::default()),
+        viewer: Mutex::new(viewer),
+        discovery: Mutex::new(discovery::DiscoveryState::default()),
+        caption_page: Mutex::new(caption_page::CaptionPageState::default()),
+        cast: Mutex::new(cast::CastState::default()),
+        dlna: Mutex::new(dlna::DlnaState::default()),
+        serial_display: Mutex::new(serial_display::SerialDisplayState::default()),
+        braille: Mutex::new(braille::BrailleState::default()),
+        caption_services: Mutex::new(caption_services::CaptionServicesState::default()),
+        hls_captions: Mutex::new(hls_captions::HlsCaptionsState::default()),
+        audio_monitor: Mutex::new(audio_monitor::AudioMonitorState::default()),
+        audio_uplink: Mutex::new(audio_uplink::AudioUplinkState::default()),
+        lan_session: Mutex::new(lan_session::LanSessionState::default()),
+        ble_broadcast: Mutex::new(ble_broadcast::BleBroadcastState::default()),
+        remote_support: Mutex::new(remote_support::RemoteSupportState::default()),
+        training: Mutex::new(training::load()),
+        display_delay: Mutex::new(display_delay::DisplayDelayState::default()),
+        session_clock: Mutex::new(session_clock::SessionClockState::default()),
+        info_line: Mutex::new(String::new()),
+        lower_third: Mutex::new(lower_third::LowerThirdState::default()),
+        contrast: Mutex::new(contrast::ContrastState::default()),
+        theme: Mutex::new(theme::ThemeState::default()),
+        power: Mutex::new(power::PowerState::default()),
+        keep_awake: Mutex::new(keep_awake::KeepAwakeState::default()),
+        compositor_fallback: Mutex::new(None),
+        environment: environment::Environment::detect(),
+        drag_region: Mutex::new(drag_region::DragRegionState::default()),
+        hot_corner: Mutex::new(hot_corner::HotCornerState::default()),
+        compact: Mutex::new(compact::CompactState::default()),
+        replay: Mutex::new(replay::ReplayState::default()),
+        bookmarks: Mutex::new(bookmarks::BookmarkState::default()),
+        keywords: Mutex::new(keywords::KeywordState::default()),
+        check_in: Mutex::new(check_in::CheckInState::default()),
+        arbitration: Mutex::new(arbitration::ArbitrationState::default()),
+        tray_clicks: Mutex::new(tray::TrayClicks::default()),
+        focus_follow: Mutex::new(focus_follow::FocusFollowState::default()),
+        meetings: Mutex::new(meetings::MeetingState::default()),
+        calendar: Mutex::new(calendar::CalendarState::default()),
+        browser_bridge: Mutex::new(browser_bridge::BrowserBridgeState::default()),
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let app_state = AppState {
-        settings: Mutex::new(load_settings()),
-        overlay_visible: Mutex::new(false),
-    };
+    // `convert` and its arguments turn the app into a command line tool, no GUI
+    if let Some(code) = export::run_cli() {
+        std::process::exit(code);
+    }
+    // Started by a browser for the companion extension: relay to the running app, no GUI
+    if let Some(code) = browser_bridge::run_host() {
+        std::process::exit(code);
+    }
+    remote_support::init();
+    // Nothing can start before the settings are known, so wait for them here
+    let settings = tauri::async_runtime::block_on(load_settings());
+    let app_state = new_app_state(settings);
 
-    tauri::Builder::default()
+    tauri::Builder::<AppRuntime>::new()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state)
+        .on_menu_event(|app, event| context_menu::handle_menu_event(app, &event))
         .invoke_handler(tauri::generate_handler![
             get_settings,
             save_settings,
@@ -79,28 +473,181 @@ pub fn run() {
             toggle_overlay,
             set_overlay_position,
             set_overlay_size,
+            set_overlay_rotation,
             set_click_through,
+            set_drag_region,
+            start_window_drag,
+            overlay_touch,
+            get_captions_paused,
+            control_strip_action,
+            set_compact_mode,
+            get_compact_mode,
+            replay_recent,
+            slow_motion,
+            add_bookmark,
+            list_bookmarks,
+            get_keywords,
+            get_check_in_status,
+            acknowledge_check_in,
+            set_presentation_mode,
+            get_presentation_mode,
+            set_session_language,
+            get_ui_language,
+            show_overlay_context_menu,
             get_overlay_visible,
             set_last_session_code,
             get_last_session_code,
+            set_connection_status,
             broadcast_caption,
+            set_display_delay,
+            step_display_delay,
+            reset_session_timer,
+            toggle_session_timer,
+            get_layout,
+            set_layout,
+            set_safe_margins,
+            get_info_line,
+            set_info_line,
+            show_lower_third,
+            hide_lower_third,
+            set_watermark_image,
+            remove_watermark_image,
+            get_watermark_image,
+            set_channel_style,
+            get_caption_theme,
+            get_compositor_fallback,
+            list_caption_processors,
+            reload_plugins,
+            list_terms,
+            add_term,
+            remove_term,
             show_main_with_settings,
             close_app,
+            get_review_queue,
+            edit_pending_caption,
+            suppress_pending_caption,
+            release_pending_caption,
+            get_correction_candidates,
+            apply_correction,
+            dismiss_correction,
+            get_speaker_names,
+            set_speaker_name,
+            remove_speaker_name,
+            open_review_window,
+            show_operator_overlay,
+            close_operator_overlay,
+            list_midi_ports,
+            list_hid_devices,
+            set_captions_paused,
+            get_diagnostics,
+            report_connection_event,
+            force_reconnect,
+            get_active_server,
+            switch_server,
+            check_server_url,
+            filter_ice_servers,
+            list_outputs,
+            enable_output,
+            get_caption_history,
+            clear_caption_history,
+            export_transcript,
+            set_active_session,
+            list_archived_sessions,
+            summarize_session,
+            get_session_transcript,
+            list_check_ins,
+            delete_session,
+            search_archive,
+            get_usage_statistics,
+            get_kiosk_status,
+            unlock_kiosk,
+            lock_kiosk,
+            get_viewer_status,
+            discover_relays,
+            list_cast_targets,
+            start_casting,
+            stop_casting,
+            get_cast_status,
+            list_dlna_renderers,
+            push_to_renderer,
+            stop_renderer,
+            get_tv_page_url,
+            pair_light_bridge,
+            unpair_light_bridge,
+            is_light_bridge_paired,
+            list_lights,
+            test_light_alert,
+            list_serial_ports,
+            braille_page,
+            set_caption_service_url,
+            has_caption_service_url,
+            set_s3_credentials,
+            has_s3_credentials,
+            set_summary_api_key,
+            has_summary_api_key,
+            set_exit_pin,
+            has_exit_pin,
+            list_audio_devices,
+            get_audio_uplink_status,
+            get_hosted_lan_session,
+            discover_lan_sessions,
+            start_remote_support,
+            stop_remote_support,
+            get_remote_support_status,
+            run_self_test,
+            get_training_status,
+            simulate_disconnect,
+            simulate_latency,
+            simulate_overlay_crash,
+            #[cfg(feature = "test-hooks")]
+            test_hooks::test_freeze_clock,
+            #[cfg(feature = "test-hooks")]
+            test_hooks::test_advance_clock,
+            #[cfg(feature = "test-hooks")]
+            test_hooks::test_inject_caption,
+            #[cfg(feature = "test-hooks")]
+            test_hooks::test_dump_state,
         ])
         .on_window_event(|window, event| {
             match event {
-                WindowEvent::CloseRequested { .. } => {
+                WindowEvent::CloseRequested { api, .. } => {
                     let label = window.label();
                     log::info!("CloseRequested event for window: {}", label);
+                    let managed = ManagedWindow::from_label(label);
 
-                    if label == "main" {
+                    // Closing the main window quits, which a locked kiosk doesn't allow
+                    if managed == Some(ManagedWindow::Main) && kiosk::is_locked(window.app_handle())
+                    {
+                        api.prevent_close();
+                        return;
+                    }
+                    // Closing by hand (Alt+F4, the taskbar) takes the exit PIN, as the tray does
+                    let protected = match managed {
+                        Some(ManagedWindow::Main) => Some("quit"),
+                        Some(ManagedWindow::OVERLAY) => Some("hideOverlay"),
+                        _ => None,
+                    };
+                    if let Some(action) = protected {
+                        if exit_protection::is_required(window.app_handle()) {
+                            api.prevent_close();
+                            exit_protection::request(window.app_handle(), action);
+                            return;
+                        }
+                    }
+
+                    // Tool windows open again where they were closed
+                    if let Some(managed) = managed {
+                        if let Err(e) = managed.remember_geometry(window.app_handle()) {
+                            log::warn!("Failed to save where the {} window was: {}", label, e);
+                        }
+                    }
+
+                    if managed == Some(ManagedWindow::Main) {
                         // When main window closes, also close the overlay
                         let app = window.app_handle();
 
                         // Close overlay window if it exists
-                        if let Some(overlay) = app.get_webview_window("overlay") {
-                            let _ = overlay.close();
-                        }
+                        let _ = ManagedWindow::OVERLAY.close(app);
 
                         // Update state
                         if let Some(state) = app.try_state::<AppState>() {
@@ -108,7 +655,7 @@ pub fn run() {
                                 *visible = false;
                             }
                         }
-                    } else if label == "overlay" {
+                    } else if managed == Some(ManagedWindow::OVERLAY) {
                         // When overlay is closed directly, update state (main window stays open)
                         let app = window.app_handle();
                         if let Some(state) = app.try_state::<AppState>() {
@@ -118,11 +665,22 @@ pub fn run() {
                         }
                     }
                 }
+                WindowEvent::ThemeChanged(os_theme) => {
+                    theme::os_theme_changed(window.app_handle(), *os_theme == Theme::Dark);
+                }
+                WindowEvent::ScaleFactorChanged { .. } => {
+                    environment::scale_changed(window.app_handle());
+                }
                 WindowEvent::Destroyed => {
                     let label = window.label();
                     log::info!("Window destroyed: {}", label);
 
-                    if label == "main" {
+                    // A new overlay starts out full size
+                    if ManagedWindow::from_label(label) == Some(ManagedWindow::OVERLAY) {
+                        compact::reset(window.app_handle());
+                    }
+
+                    if ManagedWindow::from_label(label) == Some(ManagedWindow::Main) {
                         // Ensure app exits when main window is destroyed
                         let app = window.app_handle();
                         app.exit(0);
@@ -132,12 +690,11 @@ pub fn run() {
             }
         })
         .setup(|app| {
-            // Create system tray menu
-            let show_main_item = MenuItem::with_id(app, "show_main", "Näita peaaken", true, None::<&str>)?;
-            let show_overlay_item = MenuItem::with_id(app, "show_overlay", "Näita ülekatet", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "Välju", true, None::<&str>)?;
-
-            let menu = Menu::with_items(app, &[&show_main_item, &show_overlay_item, &quit_item])?;
+            // Rebuild the tray menu so its check mark follows presentation mode changes
+            let app_handle = app.handle().clone();
+            app.listen("presentation-mode-changed", move |_event| {
+                tray::refresh(&app_handle);
+            });
 
             // Load tray icon
             let icon = Image::from_path("icons/32x32.png")
@@ -145,10 +702,11 @@ pub fn run() {
                 .unwrap_or_else(|_| Image::from_bytes(include_bytes!("../icons/32x32.png")).unwrap());
 
             // Create system tray
-            let _tray = TrayIconBuilder::new()
+            let lang = i18n::current_language(app.handle());
+            let _tray = TrayIconBuilder::with_id(tray::TRAY_ID)
                 .icon(icon)
-                .menu(&menu)
-                .tooltip("Jutukuva Subtiitrid")
+                .menu(&tray::build_menu(app.handle())?)
+                .tooltip(i18n::tr(&lang, "tray.tooltip"))
                 .on_menu_event(|app, event| {
                     match event.id.as_ref() {
                         "show_main" => {
@@ -158,46 +716,187 @@ pub fn run() {
                             // Spawn on separate thread to avoid WebView2 deadlock
                             spawn_show_overlay_window(app.clone());
                         }
-                        "quit" => {
-                            // Close all windows and exit
-                            if let Some(overlay) = app.get_webview_window("overlay") {
-                                let _ = overlay.close();
+                        "review" => {
+                            spawn_open_review_window(app.clone());
+                        }
+                        "operator_overlay" => {
+                            spawn_show_operator_overlay_window(app.clone());
+                        }
+                        "transcript" => {
+                            spawn_open_transcript_window(app.clone());
+                        }
+                        "diagnostics" => {
+                            spawn_toggle_diagnostics_window(app.clone());
+                        }
+                        "check_in" => {
+                            if let Err(e) = check_in::acknowledge(app) {
+                                log::error!("Failed to check in: {}", e);
+                            }
+                        }
+                        "presentation_mode" => {
+                            let enabled = !presentation::is_active(app);
+                            if let Err(e) = presentation::set_enabled(app, enabled) {
+                                log::error!("Failed to toggle presentation mode: {}", e);
                             }
-                            if let Some(main_window) = app.get_webview_window("main") {
-                                let _ = main_window.close();
+                        }
+                        // A locked kiosk can't be quit
+                        "quit" if !kiosk::is_locked(app) => {
+                            if exit_protection::is_required(app) {
+                                exit_protection::request(app, "quit");
+                            } else {
+                                quit_app(app);
                             }
-                            app.exit(0);
                         }
                         _ => {}
                     }
                 })
                 .on_tray_icon_event(|tray, event| {
-                    // Click on tray icon shows main window
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        let app = tray.app_handle();
-                        show_main_window(&app);
-                    }
+                    let app = tray.app_handle();
+                    let tray_actions = match app.state::<AppState>().settings.lock() {
+                        Ok(s) => s.tray_actions.clone(),
+                        Err(_) => return,
+                    };
+
+                    // Clicks on the tray icon run the action configured for that button
+                    let action = match event {
+                        TrayIconEvent::Click {
+                            button: MouseButton::Left,
+                            button_state: MouseButtonState::Up,
+                            ..
+                        } => {
+                            tray::left_click(app, &tray_actions);
+                            return;
+                        }
+                        TrayIconEvent::Click {
+                            button: MouseButton::Middle,
+                            button_state: MouseButtonState::Up,
+                            ..
+                        } => tray_actions.middle_click,
+                        TrayIconEvent::DoubleClick {
+                            button: MouseButton::Left,
+                            ..
+                        } => {
+                            tray::double_click(app, &tray_actions);
+                            return;
+                        }
+                        _ => return,
+                    };
+                    run_tray_action(app, &action);
                 })
                 .build(app)?;
 
             // Register global shortcut for overlay toggle (Ctrl+Shift+O)
-            use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
-
-            let shortcut =
-                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyO);
+            use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
             let handle = app.handle().clone();
             app.global_shortcut()
-                .on_shortcut(shortcut, move |_app, _shortcut, _event| {
-                    let _ = handle.emit("toggle-overlay", ());
+                .on_shortcut(overlay_shortcut(), move |_app, _shortcut, event| {
+                    let peek_enabled = handle
+                        .state::<AppState>()
+                        .settings
+                        .lock()
+                        .map(|s| s.peek.enabled)
+                        .unwrap_or(false);
+
+                    // With peek mode the shortcut shows captions briefly instead of toggling
+                    match (peek_enabled, event.state) {
+                        (true, ShortcutState::Pressed) => peek::on_shortcut_pressed(&handle),
+                        (true, ShortcutState::Released) => peek::on_shortcut_released(&handle),
+                        (false, ShortcutState::Pressed) => {
+                            let _ = handle.emit("toggle-overlay", ());
+                        }
+                        (false, ShortcutState::Released) => {}
+                    }
                 })
                 .ok();
 
+            // Caption theme, starting from the OS dark mode as the main window sees it
+            if let Some(window) = ManagedWindow::Main.get(app.handle()) {
+                if let Ok(os_theme) = window.theme() {
+                    theme::os_theme_changed(app.handle(), os_theme == Theme::Dark);
+                }
+            }
+            theme::restart(app.handle());
+            // No screen blanking in the middle of a talk
+            keep_awake::restart(app.handle());
+            // Dead connections and lost overlays after the laptop was asleep
+            resume::start(app.handle());
+            // Overlays that lose their place over Remote Desktop and in virtual machines
+            environment::start(app.handle());
+            // The exit PIN out of settings files from before it had its place in secrets.json
+            exit_protection::migrate(app.handle());
+            // Names looked up past venue networks that hijack DNS, before anything connects
+            network::configure(app.handle());
+
+            if viewer::is_active(app.handle()) {
+                // Nothing but the overlay: controllers, history and the archive are the
+                // primary's business
+                ManagedWindow::Main.hide(app.handle())?;
+                spawn_show_overlay_window(app.handle().clone());
+                viewer::start(app.handle());
+            } else {
+                // Canned phrase shortcuts from settings
+                macros::register_shortcuts(app.handle());
+                // Lining captions up with a PA or broadcast delay line
+                display_delay::register_shortcuts(app.handle());
+                // Shrinking the overlay out of the way and back
+                compact::register_shortcut(app.handle());
+                // Showing the last few captions again, or slowly, for whoever missed them
+                replay::register_shortcuts(app.handle());
+                // Marking decisions and action items as they happen
+                bookmarks::register_shortcut(app.handle());
+                // Time and speaker timer for moderators
+                session_clock::restart(app.handle());
+                // Topics on stage for a word cloud
+                keywords::restart(app.handle());
+                // Proof that the caption station was attended
+                check_in::restart(app.handle());
+                // Captions that stay readable over bright slides
+                contrast::restart(app.handle());
+
+                // Stream Deck and other controllers
+                remote_control::restart(app.handle());
+                midi::restart(app.handle());
+                footswitch::restart(app.handle());
+
+                // Early warning before captions stall on a bad connection
+                network_quality::restart(app.handle());
+                // ... or the caption laptop runs out of battery
+                power::restart(app.handle());
+                // Controls in a screen corner for operators who keep the rest click-through
+                hot_corner::restart(app.handle());
+                // Captions that stay under the video call window
+                focus_follow::restart(app.handle());
+                // Captions on by themselves when a Zoom or Teams meeting starts
+                meetings::restart(app.handle());
+                // Making way for other caption apps instead of fighting over the top
+                arbitration::restart(app.handle());
+                // ... or a calendar event with a session code is about to start
+                calendar::restart(app.handle());
+
+                // Bring back the caption history of an interrupted run
+                history::restart(app.handle());
+                storage::open(app.handle());
+
+                // Captions for viewer instances
+                relay::restart(app.handle());
+
+                // Hardware caption displays
+                serial_display::restart(app.handle());
+                braille::restart(app.handle());
+
+                // Captions for remote meeting participants
+                caption_services::restart_all(app.handle());
+                hls_captions::restart(app.handle());
+                audio_monitor::restart(app.handle());
+                audio_uplink::restart(app.handle());
+                lan_session::restart(app.handle());
+                ble_broadcast::restart(app.handle());
+
+                // Captions from web meetings, forwarded by the browser extension
+                browser_bridge::restart(app.handle());
+            }
+
             // Register deep link handler
             let app_handle = app.handle().clone();
             app.listen("deep-link://new-url", move |event| {
@@ -209,13 +908,33 @@ pub fn run() {
                         // Emit to frontend for handling
                         let _ = app_handle.emit("deep-link", url.clone());
                         // Also show main window when deep link is received
-                        show_main_window(&app_handle);
+                        if !presentation::is_active(&app_handle) {
+                            show_main_window(&app_handle);
+                        }
                     }
                 }
             });
 
+            // Pre-event scripts: report and exit
+            if self_test::requested() {
+                self_test::run_from_cli(app.handle().clone());
+            }
+
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                if let Err(e) = storage::set_active_session(app, None) {
+                    log::error!("Failed to close archived session: {}", e);
+                }
+                // Viewers shouldn't keep finding a relay or session that is gone
+                discovery::withdraw_all(app);
+                // Don't lose changes still waiting for the settings writer
+                if let Err(e) = tauri::async_runtime::block_on(settings_writer::flush(app)) {
+                    log::error!("Failed to save settings on exit: {}", e);
+                }
+            }
+        });
 }
@@ -0,0 +1,150 @@
+// Flashing smart lights as an alert the operator notices without looking at the screen, for
+// loud venues: when an emergency message is shown (a macro marked as emergency, see
+// macros.rs) and when the session connection drops. Works with a Philips Hue bridge on the
+// LAN. Pairing needs the bridge's link button pressed; the user name the bridge hands out
+// is kept in the secrets store (see secrets.rs).
+
+use crate::http::request;
+use crate::settings::LightAlertSettings;
+use crate::{diagnostics, secrets, AppHandle, AppState};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::Manager;
+
+const USERNAME_SECRET: &str = "hue.username";
+// Shown in the bridge's list of paired apps
+const DEVICE_TYPE: &str = "jutukuva#overlay-captions";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Trigger {
+    Emergency,
+    Disconnect,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HueLight {
+    pub id: String,
+    pub name: String,
+}
+
+fn api_url(bridge: &str, path: &str) -> Result<String, String> {
+    let bridge = bridge.trim();
+    if bridge.is_empty() {
+        return Err("No bridge address configured".to_string());
+    }
+    Ok(format!("http://{}/api{}", bridge, path))
+}
+
+// The bridge answers 200 with [{"error": {...}}] when something is wrong
+fn call(url: &str, method: &str, body: Option<Value>) -> Result<Value, String> {
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let response = request(url, method, &[("Content-Type", "application/json")], &body)?;
+    let response: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    let error = response
+        .as_array()
+        .and_then(|items| items.iter().find_map(|item| item.get("error")));
+    match error {
+        Some(error) => Err(error["description"]
+            .as_str()
+            .unwrap_or("bridge error")
+            .to_string()),
+        None => Ok(response),
+    }
+}
+
+fn username() -> Result<String, String> {
+    secrets::get(USERNAME_SECRET).ok_or_else(|| "Bridge not paired".to_string())
+}
+
+fn settings(app: &AppHandle) -> Result<LightAlertSettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.light_alert.clone())
+}
+
+// Pair with the bridge, whose link button must have been pressed just before
+pub fn pair(bridge: &str) -> Result<(), String> {
+    let response = call(
+        &api_url(bridge, "")?,
+        "POST",
+        Some(json!({ "devicetype": DEVICE_TYPE })),
+    )?;
+    let username = response[0]["success"]["username"]
+        .as_str()
+        .ok_or("Unexpected answer from the bridge")?;
+    secrets::set(USERNAME_SECRET, Some(username))?;
+    log::info!("Paired with light bridge at {}", bridge);
+    Ok(())
+}
+
+pub fn unpair() -> Result<(), String> {
+    secrets::set(USERNAME_SECRET, None)
+}
+
+pub fn is_paired() -> bool {
+    secrets::get(USERNAME_SECRET).is_some()
+}
+
+pub fn list_lights(app: &AppHandle) -> Result<Vec<HueLight>, String> {
+    let settings = settings(app)?;
+    let path = format!("/{}/lights", username()?);
+    let response = call(&api_url(&settings.bridge, &path)?, "GET", None)?;
+    let mut lights: Vec<HueLight> = response
+        .as_object()
+        .map(|lights| {
+            lights
+                .iter()
+                .map(|(id, light)| HueLight {
+                    id: id.clone(),
+                    name: light["name"].as_str().unwrap_or(id).to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    lights.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(lights)
+}
+
+// Blocks until the bridge has taken every light's alert
+pub fn flash(app: &AppHandle) -> Result<(), String> {
+    let settings = settings(app)?;
+    if settings.lights.is_empty() {
+        return Err("No lights selected".to_string());
+    }
+    let username = username()?;
+    for light in &settings.lights {
+        let path = format!("/{}/lights/{}/state", username, light);
+        // "lselect" flashes for about 15 seconds, then the light goes back to how it was
+        call(
+            &api_url(&settings.bridge, &path)?,
+            "PUT",
+            Some(json!({ "alert": "lselect" })),
+        )?;
+    }
+    Ok(())
+}
+
+// Flash the lights if alerts are on for `trigger`; doesn't wait for the bridge
+pub fn alert(app: &AppHandle, trigger: Trigger) {
+    let settings = match settings(app) {
+        Ok(settings) => settings,
+        Err(_) => return,
+    };
+    let wanted = match trigger {
+        Trigger::Emergency => settings.on_emergency,
+        Trigger::Disconnect => settings.on_disconnect,
+    };
+    if !settings.enabled || !wanted {
+        return;
+    }
+
+    log::info!("Light alert: {:?}", trigger);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = flash(&app) {
+            log::warn!("Light alert failed: {}", e);
+            let _ = diagnostics::record(&app, "light alert failed", Some(e));
+        }
+    });
+}
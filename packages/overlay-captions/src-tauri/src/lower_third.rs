@@ -0,0 +1,99 @@
+// Lower-third name cards ("Dr. Mari Tamm / Tallinn University of Technology") shown in the
+// overlay for a few seconds when a speaker takes the stage, so small productions need no
+// separate graphics system for them. Like the info line they go out on their own router
+// channel; a card replaces the one before it and hides itself after its duration.
+
+use crate::{event_router, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+const MAX_CHARS: usize = 80;
+
+#[derive(Default)]
+pub struct LowerThirdState {
+    // Bumped by each card, so an earlier card's timer doesn't hide a later one
+    generation: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowerThirdPayload {
+    pub visible: bool,
+    pub name: String,
+    pub title: String,
+}
+
+fn bump_generation(app: &AppHandle) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+    let mut lower_third = state.lower_third.lock().map_err(|e| e.to_string())?;
+    lower_third.generation += 1;
+    Ok(lower_third.generation)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .lower_third
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn publish_hidden(app: &AppHandle) -> Result<(), String> {
+    let payload = LowerThirdPayload {
+        visible: false,
+        name: String::new(),
+        title: String::new(),
+    };
+    event_router::publish(app, event_router::LOWER_THIRD, &payload)
+}
+
+// Show a card for `duration_secs`, by default `settings.lower_third.duration_secs`; 0 keeps
+// it up until hidden
+pub fn show(
+    app: &AppHandle,
+    name: &str,
+    title: &str,
+    duration_secs: Option<u32>,
+) -> Result<(), String> {
+    let name = crate::text::truncate(name.trim(), MAX_CHARS).to_string();
+    let title = crate::text::truncate(title.trim(), MAX_CHARS).to_string();
+    if name.is_empty() {
+        return Err("A lower third needs a name".to_string());
+    }
+    let duration_secs = match duration_secs {
+        Some(secs) => secs,
+        None => {
+            let state = app.state::<AppState>();
+            let settings = state.settings.lock().map_err(|e| e.to_string())?;
+            settings.lower_third.duration_secs
+        }
+    };
+
+    let generation = bump_generation(app)?;
+    log::info!("Lower third: {} / {}", name, title);
+    let payload = LowerThirdPayload {
+        visible: true,
+        name,
+        title,
+    };
+    event_router::publish(app, event_router::LOWER_THIRD, &payload)?;
+
+    if duration_secs > 0 {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(u64::from(duration_secs)));
+            if is_current(&app, generation) {
+                if let Err(e) = publish_hidden(&app) {
+                    log::error!("Failed to hide lower third: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+pub fn hide(app: &AppHandle) -> Result<(), String> {
+    bump_generation(app)?;
+    publish_hidden(app)
+}
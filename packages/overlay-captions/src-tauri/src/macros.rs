@@ -0,0 +1,76 @@
+use crate::light_alert::{self, Trigger};
+use crate::settings::CaptionMacro;
+use crate::signatures::Verdict;
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use serde::Serialize;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MacroFiredPayload {
+    text: String,
+}
+
+// Inject a canned phrase as a caption. Phrases marked for the session are also handed to
+// the main window, which appends them to the shared document.
+pub fn fire(app: &AppHandle, caption_macro: &CaptionMacro) {
+    log::info!("Macro fired: {}", caption_macro.text);
+
+    if let Err(e) =
+        crate::commands::submit_caption(app, caption_macro.text.clone(), Verdict::Trusted)
+    {
+        log::error!("Failed to inject macro caption: {}", e);
+    }
+    if caption_macro.emergency {
+        light_alert::alert(app, Trigger::Emergency);
+    }
+
+    if caption_macro.push_to_session {
+        let payload = MacroFiredPayload {
+            text: caption_macro.text.clone(),
+        };
+        if let Err(e) = ManagedWindow::Main.emit(app, "macro-fired", payload) {
+            log::error!("Failed to push macro to session: {}", e);
+        }
+    }
+}
+
+// (Re-)register the global shortcuts of all macros from settings
+pub fn register_shortcuts(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let macros = match state.settings.lock() {
+        Ok(s) => s.macros.clone(),
+        Err(_) => return,
+    };
+    let mut registered = match state.macro_shortcuts.lock() {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+
+    for shortcut in registered.drain(..) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut.as_str()) {
+            log::warn!("Failed to unregister macro shortcut {}: {}", shortcut, e);
+        }
+    }
+
+    for caption_macro in macros {
+        let shortcut = caption_macro.shortcut.trim().to_string();
+        if shortcut.is_empty() || caption_macro.text.trim().is_empty() {
+            continue;
+        }
+
+        let result =
+            app.global_shortcut()
+                .on_shortcut(shortcut.as_str(), move |app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        fire(app, &caption_macro);
+                    }
+                });
+        match result {
+            Ok(()) => registered.push(shortcut),
+            Err(e) => log::error!("Failed to register macro shortcut {}: {}", shortcut, e),
+        }
+    }
+}
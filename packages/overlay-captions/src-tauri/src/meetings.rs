@@ -0,0 +1,148 @@
+// Meeting detection. People forget to turn captions on until minutes into a call, so when
+// a window titled like a meeting (`meeting_detection.window_titles`, e.g. "Zoom Meeting")
+// appears, the overlay is shown and, with `auto_join`, the main window is asked to join the
+// session. When the meeting window goes away the overlay is hidden again, if it was the
+// meeting that showed it. Window titles are polled (see desktop.rs).
+
+use crate::desktop::{self, Desktop};
+use crate::settings::MeetingDetectionSettings;
+use crate::window_manager::ManagedWindow;
+use crate::{diagnostics, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+// A meeting can wait this long for its captions
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct MeetingState {
+    // Bumped to stop the running watcher
+    generation: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeetingPayload {
+    pub title: String,
+    // The session the main window should join, if any
+    pub join_code: Option<String>,
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .meetings
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn is_meeting(settings: &MeetingDetectionSettings, title: &str) -> bool {
+    let title = title.to_lowercase();
+    settings
+        .window_titles
+        .iter()
+        .map(|pattern| pattern.trim())
+        .any(|pattern| !pattern.is_empty() && title.contains(&pattern.to_lowercase()))
+}
+
+// Stop the running watcher and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.meeting_detection.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.meetings.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+    if !desktop::SUPPORTED {
+        log::warn!("Meeting detection is not supported on this platform");
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &MeetingDetectionSettings, generation: u64) {
+    let desktop = match Desktop::open() {
+        Ok(desktop) => desktop,
+        Err(e) => {
+            log::warn!("Meeting detection unavailable: {}", e);
+            return;
+        }
+    };
+    // Whether a meeting window is open, and whether it showed the overlay
+    let mut meeting: Option<bool> = None;
+
+    while is_current(app, generation) {
+        let found = desktop
+            .window_titles()
+            .into_iter()
+            .find(|title| is_meeting(settings, title));
+        match (meeting, found) {
+            (None, Some(title)) => meeting = Some(started(app, settings, title)),
+            (Some(shown), None) => {
+                ended(app, settings, shown);
+                meeting = None;
+            }
+            _ => {}
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Returns whether the overlay was shown for the meeting
+fn started(app: &AppHandle, settings: &MeetingDetectionSettings, title: String) -> bool {
+    log::info!("Meeting window appeared: {}", title);
+    let _ = diagnostics::record(app, "meeting started", Some(title.clone()));
+
+    let state = app.state::<AppState>();
+    let visible = state.overlay_visible.lock().map(|v| *v).unwrap_or(false);
+    if !visible {
+        crate::spawn_show_overlay_window(app.clone());
+    }
+
+    let connected = state.connected.lock().map(|c| *c).unwrap_or(false);
+    let join_code = if settings.auto_join && !connected {
+        match settings.session_code.trim() {
+            "" => state
+                .settings
+                .lock()
+                .ok()
+                .and_then(|s| s.last_session_code.clone()),
+            code => Some(code.to_string()),
+        }
+    } else {
+        None
+    };
+
+    let payload = MeetingPayload { title, join_code };
+    if let Err(e) = ManagedWindow::Main.emit(app, "meeting-started", payload) {
+        log::error!("Failed to emit meeting start: {}", e);
+    }
+    !visible
+}
+
+fn ended(app: &AppHandle, settings: &MeetingDetectionSettings, shown: bool) {
+    log::info!("Meeting window closed");
+    let _ = diagnostics::record(app, "meeting ended", None);
+
+    if settings.hide_when_ended && shown {
+        if let Err(e) = crate::hide_overlay_window(app) {
+            log::error!("Failed to hide the overlay after the meeting: {}", e);
+        }
+    }
+    if let Err(e) = ManagedWindow::Main.emit(app, "meeting-ended", ()) {
+        log::error!("Failed to emit meeting end: {}", e);
+    }
+}
@@ -0,0 +1,149 @@
+// MIDI control surface input. Each mapping in `settings.midi.mappings` ties a note or CC
+// number (optionally on one channel) to an action:
+//   "toggleOverlay", "togglePresentation", "macro" - fired by note-on or CC value >= 64
+//   "opacity", "fontSize"                       - follow a CC fader or knob
+
+use crate::commands;
+use crate::settings::MidiMapping;
+use crate::{presentation, AppHandle, AppState};
+use midir::{MidiInput, MidiInputConnection};
+use tauri::{Emitter, Manager};
+
+const CLIENT_NAME: &str = "Jutukuva Subtiitrid";
+
+const NOTE_ON: u8 = 0x90;
+const CONTROL_CHANGE: u8 = 0xB0;
+
+#[derive(Default)]
+pub struct MidiState {
+    connection: Option<MidiInputConnection<()>>,
+}
+
+pub fn list_ports() -> Result<Vec<String>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|e| e.to_string())?;
+    Ok(input
+        .ports()
+        .iter()
+        .filter_map(|p| input.port_name(p).ok())
+        .collect())
+}
+
+// Close the current connection and reconnect if MIDI is enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let midi = match state.settings.lock() {
+        Ok(s) => s.midi.clone(),
+        Err(_) => return,
+    };
+    let mut midi_state = match state.midi.lock() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    if let Some(connection) = midi_state.connection.take() {
+        connection.close();
+    }
+    if !midi.enabled {
+        return;
+    }
+
+    match connect(app, midi.port_name.as_deref()) {
+        Ok(connection) => midi_state.connection = Some(connection),
+        Err(e) => log::error!("Failed to open MIDI input: {}", e),
+    }
+}
+
+fn connect(app: &AppHandle, port_name: Option<&str>) -> Result<MidiInputConnection<()>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|e| e.to_string())?;
+    let ports = input.ports();
+
+    // First port whose name contains the configured one, or simply the first port
+    let port = ports
+        .iter()
+        .find(|p| match port_name {
+            Some(wanted) => input
+                .port_name(p)
+                .map(|name| name.contains(wanted))
+                .unwrap_or(false),
+            None => true,
+        })
+        .ok_or_else(|| "No matching MIDI input port".to_string())?;
+    let name = input.port_name(port).unwrap_or_default();
+
+    let app = app.clone();
+    let connection = input
+        .connect(
+            port,
+            "jutukuva-in",
+            move |_timestamp, message, _| handle_message(&app, message),
+            (),
+        )
+        .map_err(|e| e.to_string())?;
+
+    log::info!("Listening to MIDI input {}", name);
+    Ok(connection)
+}
+
+fn handle_message(app: &AppHandle, message: &[u8]) {
+    if message.len() < 3 {
+        return;
+    }
+    let kind = message[0] & 0xF0;
+    let channel = (message[0] & 0x0F) + 1;
+    let (number, value) = (message[1], message[2]);
+
+    let message_kind = match kind {
+        // Note-on with velocity 0 is a note-off
+        NOTE_ON if value > 0 => "note",
+        CONTROL_CHANGE => "cc",
+        _ => return,
+    };
+
+    let mappings = match app.state::<AppState>().settings.lock() {
+        Ok(s) => s.midi.mappings.clone(),
+        Err(_) => return,
+    };
+
+    for mapping in mappings.iter().filter(|m| {
+        m.message == message_kind && m.number == number && m.channel.is_none_or(|c| c == channel)
+    }) {
+        run_action(app, mapping, message_kind, value);
+    }
+}
+
+fn run_action(app: &AppHandle, mapping: &MidiMapping, message_kind: &str, value: u8) {
+    // Buttons send CC 127 on press and 0 on release
+    let pressed = message_kind == "note" || value >= 64;
+
+    let result = match mapping.action.as_str() {
+        "toggleOverlay" if pressed => app.emit("toggle-overlay", ()).map_err(|e| e.to_string()),
+        "togglePresentation" if pressed => {
+            presentation::set_enabled(app, !presentation::is_active(app))
+        }
+        "macro" if pressed => {
+            let caption_macro = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .ok()
+                .and_then(|s| s.macros.get(mapping.macro_index).cloned());
+            if let Some(m) = caption_macro {
+                crate::macros::fire(app, &m);
+            }
+            Ok(())
+        }
+        "opacity" if message_kind == "cc" => commands::apply_settings_change(app, |s| {
+            s.overlay.opacity = value as f32 / 127.0;
+        })
+        .map(|_| ()),
+        "fontSize" if message_kind == "cc" => {
+            let range = commands::FONT_SIZE_MAX - commands::FONT_SIZE_MIN;
+            commands::set_font_size(app, commands::FONT_SIZE_MIN + range * value as u32 / 127)
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        log::error!("MIDI action {} failed: {}", mapping.action, e);
+    }
+}
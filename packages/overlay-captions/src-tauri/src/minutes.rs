@@ -0,0 +1,113 @@
+// Meeting minutes from the tagged bookmarks of a session (see bookmarks.rs): its decisions,
+// action items and questions, each with the captions said around it, as JSON for other
+// tools or as Markdown to paste into the minutes. Plain bookmarks are left out.
+
+use crate::bookmarks::{self, TAGS};
+use crate::export::{date, vtt_time, TimedBookmark, Transcript};
+use serde::Serialize;
+
+// Captions before and after the one on screen when the bookmark was added
+const CONTEXT_BEFORE: usize = 2;
+const CONTEXT_AFTER: usize = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Minutes<'a> {
+    title: &'a str,
+    // "2024-05-01"
+    date: String,
+    items: Vec<Item<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Item<'a> {
+    tag: &'a str,
+    // "00:12:03" from the start of the transcript
+    time: String,
+    note: &'a str,
+    context: Vec<&'a str>,
+}
+
+fn item<'a>(transcript: &'a Transcript, bookmark: &'a TimedBookmark) -> Item<'a> {
+    let captions = &transcript.captions;
+    // A bookmark before the first caption gets the first few
+    let current = captions
+        .iter()
+        .rposition(|caption| caption.start <= bookmark.at)
+        .unwrap_or(0);
+    let from = current.saturating_sub(CONTEXT_BEFORE);
+    let to = (current + CONTEXT_AFTER + 1).min(captions.len());
+    Item {
+        tag: &bookmark.tag,
+        time: vtt_time(bookmark.at)[..8].to_string(),
+        note: &bookmark.note,
+        context: captions
+            .get(from..to)
+            .unwrap_or_default()
+            .iter()
+            .map(|caption| caption.text.as_str())
+            .collect(),
+    }
+}
+
+// Tagged bookmarks in the order of TAGS, then by time
+fn items(transcript: &Transcript) -> Vec<Item<'_>> {
+    TAGS.iter()
+        .flat_map(|tag| {
+            transcript
+                .bookmarks
+                .iter()
+                .filter(move |bookmark| bookmark.tag == *tag)
+        })
+        .map(|bookmark| item(transcript, bookmark))
+        .collect()
+}
+
+pub fn json(transcript: &Transcript) -> Result<Vec<u8>, String> {
+    let minutes = Minutes {
+        title: &transcript.title,
+        date: date(transcript.started_at),
+        items: items(transcript),
+    };
+    serde_json::to_vec_pretty(&minutes).map_err(|e| e.to_string())
+}
+
+fn heading(tag: &str) -> &'static str {
+    match tag {
+        "decision" => "Decisions",
+        "action" => "Action items",
+        _ => "Questions",
+    }
+}
+
+// A section per tag with a list item per bookmark and its context quoted under it
+pub fn markdown(transcript: &Transcript) -> String {
+    let mut out = format!(
+        "# {}\n\n{}\n",
+        transcript.title.trim(),
+        date(transcript.started_at)
+    );
+    let items = items(transcript);
+    for tag in TAGS {
+        let tagged: Vec<&Item> = items.iter().filter(|item| item.tag == tag).collect();
+        if tagged.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n\n", heading(tag)));
+        for item in tagged {
+            let note = if item.note.is_empty() {
+                bookmarks::label(tag).trim_end_matches(':')
+            } else {
+                item.note
+            };
+            out.push_str(&format!("- **[{}]** {}\n", item.time, note));
+            for line in item.context.iter().flat_map(|text| text.lines()) {
+                if !line.trim().is_empty() {
+                    out.push_str(&format!("  > {}\n", line.trim()));
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,413 @@
+// Data residency: with `offline_only` set, nothing the app hears leaves the premises except
+// through the caption servers (see failover.rs). Every integration that connects out (the
+// audio uplink, caption services, S3, the calendar, summaries, remote support) asks `check`
+// first, which allows the caption servers' hosts and the local network only:
+// loopback and private addresses, "localhost" and ".local" names. Devices on the LAN such as
+// DLNA renderers or the light bridge are not outbound and work either way. `agent` and
+// `connect` hold to the same policy, for a caller that forgot to ask, and the WebRTC peer
+// link gets only the STUN and TURN servers it allows (`allowed_ice_servers`).
+//
+// Names are looked up here too. Some venue guest networks hijack DNS, so with
+// `dns_over_https` enabled the backend's own connections (`agent` for HTTP, `connect` for
+// WebSockets, `resolve` for the rest) ask the resolver at `dns_over_https.url` instead, with
+// its JSON API (application/dns-json). The resolver's URL should name it by address, e.g.
+// https://1.1.1.1/dns-query, so that reaching it takes no DNS. Local names still go to the
+// system resolver, which is the only one that knows them.
+
+use crate::settings::DnsOverHttpsSettings;
+use crate::{clock, failover, AppHandle, AppState};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::WebSocket;
+
+const DOH_TIMEOUT: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Answers are kept for their TTL, but at least this long
+const MIN_TTL_SECS: u64 = 30;
+// DNS record types
+const TYPE_A: u64 = 1;
+const TYPE_AAAA: u64 = 28;
+
+struct Doh {
+    url: String,
+    agent: ureq::Agent,
+    // Addresses by name, with when they expire in milliseconds since the Unix epoch
+    cache: HashMap<String, (Vec<IpAddr>, u64)>,
+}
+
+// Set from settings by `configure`; None resolves with the system
+static DOH: Mutex<Option<Doh>> = Mutex::new(None);
+// `offline_only` and the caption servers, set by `configure` for `agent` and `connect`
+static POLICY: Mutex<(bool, Vec<String>)> = Mutex::new((false, Vec::new()));
+
+// Host of a URL in lowercase, without brackets, port or user info
+fn host(url: &str) -> Option<String> {
+    let rest = url
+        .trim()
+        .split_once("://")
+        .map_or(url.trim(), |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+fn is_local(host: &str) -> bool {
+    if host == "localhost" || host.ends_with(".localhost") || host.ends_with(".local") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => {
+            let first = ip.segments()[0];
+            // Unique local fc00::/7 and link-local fe80::/10
+            ip.is_loopback() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+        }
+        Err(_) => false,
+    }
+}
+
+// Whether the policy lets the app connect to `url`
+fn allows(offline_only: bool, servers: &[String], url: &str) -> bool {
+    if !offline_only {
+        return true;
+    }
+    match host(url) {
+        Some(name) => {
+            is_local(&name)
+                || servers
+                    .iter()
+                    .any(|server| host(server).as_deref() == Some(name.as_str()))
+        }
+        None => false,
+    }
+}
+
+fn policy_error(url: &str) -> String {
+    format!(
+        "Blocked by the offline only policy: {}",
+        host(url).unwrap_or_else(|| url.to_string())
+    )
+}
+
+// Err when `offline_only` keeps the app from connecting to `url`
+pub fn check(app: &AppHandle, url: &str) -> Result<(), String> {
+    let (offline_only, servers) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.offline_only,
+            failover::servers(&settings.connection),
+        )
+    };
+    if allows(offline_only, &servers, url) {
+        Ok(())
+    } else {
+        Err(policy_error(url))
+    }
+}
+
+// `check` as of the last `configure`, for where there is no app handle
+fn enforce(url: &str) -> Result<(), String> {
+    let policy = POLICY.lock().map_err(|e| e.to_string())?;
+    if allows(policy.0, &policy.1, url) {
+        Ok(())
+    } else {
+        Err(policy_error(url))
+    }
+}
+
+// Host of a STUN or TURN URL such as stun:stun.example.com:19302
+fn ice_host(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once(':')?;
+    match scheme.to_lowercase().as_str() {
+        "stun" | "stuns" | "turn" | "turns" => host(rest),
+        _ => None,
+    }
+}
+
+// The ICE servers the peer link may use; with `offline_only` those on the local network
+// or a caption server's host
+pub fn allowed_ice_servers(app: &AppHandle, urls: Vec<String>) -> Result<Vec<String>, String> {
+    Ok(urls
+        .into_iter()
+        .filter(|url| match ice_host(url) {
+            Some(host) => check(app, &host).is_ok(),
+            None => false,
+        })
+        .collect())
+}
+
+// Take `dns_over_https` and the offline only policy from settings, at startup and whenever
+// they change
+pub fn configure(app: &AppHandle) {
+    let settings: DnsOverHttpsSettings = match app.state::<AppState>().settings.lock() {
+        Ok(s) => {
+            if let Ok(mut policy) = POLICY.lock() {
+                *policy = (s.offline_only, failover::servers(&s.connection));
+            }
+            s.dns_over_https.clone()
+        }
+        Err(_) => return,
+    };
+    let doh = match native_tls::TlsConnector::new() {
+        Ok(tls) if settings.enabled && !settings.url.trim().is_empty() => Some(Doh {
+            url: settings.url.trim().to_string(),
+            agent: ureq::AgentBuilder::new()
+                .tls_connector(Arc::new(tls))
+                .timeout(DOH_TIMEOUT)
+                .build(),
+            cache: HashMap::new(),
+        }),
+        Ok(_) => None,
+        Err(e) => {
+            log::error!("DNS over HTTPS unavailable: {}", e);
+            None
+        }
+    };
+    if let Some(doh) = &doh {
+        log::info!("Resolving names with {}", doh.url);
+    }
+    if let Ok(mut current) = DOH.lock() {
+        *current = doh;
+    }
+}
+
+// Addresses of one record type from the resolver, with the shortest TTL among them
+fn query(
+    agent: &ureq::Agent,
+    url: &str,
+    name: &str,
+    kind: u64,
+) -> Result<(Vec<IpAddr>, u64), String> {
+    let response = match agent
+        .get(url)
+        .query("name", name)
+        .query("type", &kind.to_string())
+        .set("Accept", "application/dns-json")
+        .call()
+    {
+        Ok(response) => response.into_string().map_err(|e| e.to_string())?,
+        Err(ureq::Error::Status(code, _)) => return Err(format!("HTTP {}", code)),
+        Err(e) => return Err(e.to_string()),
+    };
+    let response: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    let answers = response
+        .get("Answer")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let mut ttl = u64::MAX;
+    let mut addresses = Vec::new();
+    // CNAMEs come first and are followed by the resolver itself
+    for answer in answers
+        .iter()
+        .filter(|a| a.get("type").and_then(Value::as_u64) == Some(kind))
+    {
+        if let Some(ip) = answer
+            .get("data")
+            .and_then(Value::as_str)
+            .and_then(|data| data.parse::<IpAddr>().ok())
+        {
+            addresses.push(ip);
+            ttl = ttl.min(answer.get("TTL").and_then(Value::as_u64).unwrap_or(0));
+        }
+    }
+    Ok((addresses, ttl.max(MIN_TTL_SECS)))
+}
+
+// A name's addresses from the resolver, IPv4 first
+fn lookup(name: &str) -> Result<Option<Vec<IpAddr>>, String> {
+    let now = clock::now_millis();
+    let (url, agent) = {
+        let doh = DOH.lock().map_err(|e| e.to_string())?;
+        match doh.as_ref() {
+            Some(doh) => match doh.cache.get(name) {
+                Some((addresses, expires)) if *expires > now => return Ok(Some(addresses.clone())),
+                _ => (doh.url.clone(), doh.agent.clone()),
+            },
+            None => return Ok(None),
+        }
+    };
+
+    let (mut addresses, ttl) = query(&agent, &url, name, TYPE_A)?;
+    let (v6, v6_ttl) = query(&agent, &url, name, TYPE_AAAA)?;
+    addresses.extend(v6);
+    if addresses.is_empty() {
+        return Err(format!("{} has no address", name));
+    }
+    if let Some(doh) = DOH.lock().map_err(|e| e.to_string())?.as_mut() {
+        let expires = now + ttl.min(v6_ttl) * 1000;
+        doh.cache
+            .insert(name.to_string(), (addresses.clone(), expires));
+    }
+    Ok(Some(addresses))
+}
+
+// Addresses of `host`, with DNS over HTTPS when configured
+pub fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let doh = if is_local(&host.to_lowercase()) {
+        None
+    } else {
+        lookup(host)?
+    };
+    match doh {
+        Some(addresses) => Ok(addresses
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect()),
+        None => (host, port)
+            .to_socket_addrs()
+            .map(Iterator::collect)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+// Lets ureq look names up through `resolve`
+struct Resolver;
+
+impl ureq::Resolver for Resolver {
+    fn resolve(&self, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        let (host, port) = netloc
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse().ok()?)))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, netloc.to_string()))?;
+        // Before the name is looked up, which would already leave the premises
+        enforce(host).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+        resolve(host, port).map_err(io::Error::other)
+    }
+}
+
+// HTTP client for connections out of the app
+pub fn agent(timeout: Duration) -> Result<ureq::Agent, String> {
+    let tls = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+    Ok(ureq::AgentBuilder::new()
+        .tls_connector(Arc::new(tls))
+        .timeout(timeout)
+        .resolver(Resolver)
+        .build())
+}
+
+// Open a WebSocket connection (ws:// or wss://)
+pub fn connect<R: IntoClientRequest>(
+    request: R,
+) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+    let request = request.into_client_request().map_err(|e| e.to_string())?;
+    let uri = request.uri();
+    let host = uri
+        .host()
+        .ok_or_else(|| format!("No host in {}", uri))?
+        .to_string();
+    enforce(&host)?;
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("wss") {
+            443
+        } else {
+            80
+        });
+
+    let mut last_error = format!("No address for {}", host);
+    for address in resolve(&host, port)? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                return tungstenite::client_tls(request, stream)
+                    .map(|(socket, _)| socket)
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_is_lowercase_without_port_user_info_or_brackets() {
+        assert_eq!(
+            host("wss://User:pw@Tekstiks.EE:443/kk?x#y").as_deref(),
+            Some("tekstiks.ee")
+        );
+        assert_eq!(host("ws://[FD00::1]:1234/kk").as_deref(), Some("fd00::1"));
+        assert_eq!(host(" example.com/path ").as_deref(), Some("example.com"));
+        assert_eq!(host("https://"), None);
+        assert_eq!(host("ws://:1234"), None);
+    }
+
+    #[test]
+    fn local_hosts_are_loopback_private_and_local_names() {
+        for local in [
+            "localhost",
+            "printer.localhost",
+            "bridge.local",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.10",
+            "169.254.0.5",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+        ] {
+            assert!(is_local(local), "{}", local);
+        }
+        for remote in [
+            "tekstiks.ee",
+            "8.8.8.8",
+            "172.32.0.1",
+            "2001:db8::1",
+            "local",
+        ] {
+            assert!(!is_local(remote), "{}", remote);
+        }
+    }
+
+    #[test]
+    fn offline_only_allows_caption_servers_and_the_local_network() {
+        let servers = ["wss://tekstiks.ee/kk".to_string()];
+        assert!(allows(false, &servers, "https://example.com"));
+        assert!(allows(true, &servers, "https://Tekstiks.ee:8443/api"));
+        assert!(allows(true, &servers, "http://192.168.1.20/upload"));
+        assert!(allows(true, &servers, "ws://[fd00::2]:1234"));
+        assert!(!allows(true, &servers, "https://example.com"));
+        assert!(!allows(true, &servers, "https://tekstiks.ee.example.com"));
+        assert!(!allows(true, &servers, "https://"));
+    }
+
+    #[test]
+    fn ice_host_takes_the_host_of_stun_and_turn_urls() {
+        assert_eq!(
+            ice_host("stun:stun.l.google.com:19302").as_deref(),
+            Some("stun.l.google.com")
+        );
+        assert_eq!(
+            ice_host("TURN:[fd00::1]:3478?transport=udp").as_deref(),
+            Some("fd00::1")
+        );
+        assert_eq!(
+            ice_host("turns:Relay.Example.com").as_deref(),
+            Some("relay.example.com")
+        );
+        assert_eq!(ice_host("https://stun.example.com"), None);
+        assert_eq!(ice_host("stun.example.com"), None);
+    }
+}
@@ -0,0 +1,159 @@
+// Periodic probing of the caption server while a session is connected. Each probe times
+// a TCP handshake (see diagnostics::measure_latency); over the last few probes we derive
+// latency, jitter and loss, emit them as `network-quality` events and raise a desktop
+// notification when quality drops below the configured thresholds.
+
+use crate::i18n::{self, tr};
+use crate::settings::NetworkQualitySettings;
+use crate::{diagnostics, failover, presentation, AppHandle, AppState};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Probes the statistics are computed over
+const WINDOW: usize = 10;
+// Fewer probes than this are not enough to call the connection degraded
+const MIN_SAMPLES: usize = 3;
+
+#[derive(Default)]
+pub struct NetworkQualityState {
+    // Bumped to stop the running prober, e.g. when the interval changes
+    generation: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkQuality {
+    pub latency_ms: Option<u64>,
+    pub jitter_ms: Option<u64>,
+    pub loss_percent: u32,
+    pub degraded: bool,
+}
+
+// `samples` holds round-trip times in milliseconds, None for a failed probe
+fn evaluate(samples: &VecDeque<Option<u64>>, settings: &NetworkQualitySettings) -> NetworkQuality {
+    let rtts: Vec<u64> = samples.iter().flatten().copied().collect();
+    let latency_ms = (!rtts.is_empty()).then(|| rtts.iter().sum::<u64>() / rtts.len() as u64);
+    // Mean difference between consecutive round trips
+    let jitter_ms = (rtts.len() > 1).then(|| {
+        rtts.windows(2).map(|w| w[0].abs_diff(w[1])).sum::<u64>() / (rtts.len() - 1) as u64
+    });
+    let loss_percent = if samples.is_empty() {
+        0
+    } else {
+        ((samples.len() - rtts.len()) * 100 / samples.len()) as u32
+    };
+
+    let degraded = samples.len() >= MIN_SAMPLES
+        && (loss_percent > settings.max_loss_percent
+            || latency_ms.is_some_and(|l| l > settings.max_latency_ms)
+            || jitter_ms.is_some_and(|j| j > settings.max_jitter_ms));
+
+    NetworkQuality {
+        latency_ms,
+        jitter_ms,
+        loss_percent,
+        degraded,
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .network_quality
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running prober and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.network_quality.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.network_quality.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &NetworkQualitySettings, generation: u64) {
+    let interval = Duration::from_secs(settings.interval_secs.max(1) as u64);
+    let mut samples: VecDeque<Option<u64>> = VecDeque::with_capacity(WINDOW);
+    let mut degraded = false;
+
+    while is_current(app, generation) {
+        let state = app.state::<AppState>();
+        let connected = state.connected.lock().map(|c| *c).unwrap_or(false);
+        let server_url = failover::active_url(app);
+
+        if !connected {
+            // Start over with the next session
+            samples.clear();
+            degraded = false;
+            std::thread::sleep(interval);
+            continue;
+        }
+
+        let sample = diagnostics::measure_latency(&server_url)
+            .ok()
+            .map(|rtt| rtt.as_millis() as u64);
+        samples.push_back(sample);
+        if samples.len() > WINDOW {
+            samples.pop_front();
+        }
+
+        let quality = evaluate(&samples, settings);
+        if quality.degraded != degraded {
+            degraded = quality.degraded;
+            quality_changed(app, &quality);
+        }
+        let _ = app.emit("network-quality", &quality);
+
+        std::thread::sleep(interval);
+    }
+}
+
+fn quality_changed(app: &AppHandle, quality: &NetworkQuality) {
+    let detail = format!(
+        "latency {} ms, jitter {} ms, loss {}%",
+        quality.latency_ms.unwrap_or_default(),
+        quality.jitter_ms.unwrap_or_default(),
+        quality.loss_percent
+    );
+    let kind = if quality.degraded {
+        "network degraded"
+    } else {
+        "network recovered"
+    };
+    log::warn!("Network quality: {} ({})", kind, detail);
+    let _ = diagnostics::record(app, kind, Some(detail));
+
+    // Nothing may pop up over the projector output in presentation mode
+    if !quality.degraded || presentation::is_active(app) {
+        return;
+    }
+    let lang = i18n::current_language(app);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(tr(&lang, "notification.network_degraded_title"))
+        .body(tr(&lang, "notification.network_degraded_body"))
+        .show()
+    {
+        log::error!("Failed to show network quality notification: {}", e);
+    }
+}
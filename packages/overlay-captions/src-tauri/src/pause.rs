@@ -0,0 +1,50 @@
+use crate::processors::confidence::ScoredCaption;
+use crate::{AppHandle, AppState};
+use tauri::{Emitter, Manager};
+
+// While captions are paused the public overlay keeps showing what it had; the operator
+// overlay and review queue still follow the live text. On resume the latest caption
+// that arrived in the meantime is published.
+#[derive(Default)]
+pub struct PauseState {
+    active: bool,
+    held: Option<ScoredCaption>,
+}
+
+pub fn is_paused(app: &AppHandle) -> bool {
+    app.try_state::<AppState>()
+        .and_then(|state| state.caption_pause.lock().ok().map(|p| p.active))
+        .unwrap_or(false)
+}
+
+pub fn set_paused(app: &AppHandle, paused: bool) -> Result<(), String> {
+    let held = {
+        let state = app.state::<AppState>();
+        let mut pause = state.caption_pause.lock().map_err(|e| e.to_string())?;
+        if pause.active == paused {
+            return Ok(());
+        }
+        pause.active = paused;
+        pause.held.take()
+    };
+
+    log::info!("Captions {}", if paused { "paused" } else { "resumed" });
+    app.emit("captions-paused-changed", paused)
+        .map_err(|e| e.to_string())?;
+
+    match held {
+        Some(caption) => crate::commands::publish_caption(app, caption),
+        None => Ok(()),
+    }
+}
+
+// Keep the caption back while paused; otherwise hand it back for publishing
+pub fn hold(app: &AppHandle, caption: ScoredCaption) -> Result<Option<ScoredCaption>, String> {
+    let state = app.state::<AppState>();
+    let mut pause = state.caption_pause.lock().map_err(|e| e.to_string())?;
+    if !pause.active {
+        return Ok(Some(caption));
+    }
+    pause.held = Some(caption);
+    Ok(None)
+}
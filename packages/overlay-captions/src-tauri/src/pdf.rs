@@ -0,0 +1,177 @@
+// Just enough PDF for transcripts: A4 pages of text in the standard Helvetica fonts, which
+// every PDF reader has built in, so nothing is embedded. Text is WinAnsi encoded, which
+// covers Estonian and the other Western European languages; other characters print as '?'.
+
+// A4 in points
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+// Text colours as red, green and blue
+pub const BLACK: [u8; 3] = [0, 0, 0];
+pub const GREY: [u8; 3] = [115, 115, 115];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "/F1",
+            Font::Bold => "/F2",
+        }
+    }
+}
+
+// Helvetica advance widths of ' ' to '~' in thousandths of the font size
+const WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+// Width of the other characters, mostly accented letters
+const OTHER_WIDTH: u16 = 556;
+// Helvetica-Bold is this much wider on average; erring wide keeps lines inside the margins
+const BOLD_FACTOR: f32 = 1.1;
+
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        'Š' => 0x8a,
+        'Œ' => 0x8c,
+        'Ž' => 0x8e,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '™' => 0x99,
+        'š' => 0x9a,
+        'œ' => 0x9c,
+        'ž' => 0x9e,
+        'Ÿ' => 0x9f,
+        _ => b'?',
+    }
+}
+
+pub fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match win_ansi(c) {
+            b @ b' '..=b'~' => u32::from(WIDTHS[usize::from(b - b' ')]),
+            _ => u32::from(OTHER_WIDTH),
+        })
+        .sum();
+    let width = units as f32 * size / 1000.0;
+    match font {
+        Font::Regular => width,
+        Font::Bold => width * BOLD_FACTOR,
+    }
+}
+
+// A literal string operand, kept to ASCII with octal escapes
+fn string(text: &str) -> String {
+    let mut out = String::from("(");
+    for byte in text.chars().map(win_ansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            b' '..=b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+// Content stream of one page
+#[derive(Default)]
+pub struct Page {
+    content: String,
+}
+
+impl Page {
+    // One line of text starting at (x, y) from the bottom left corner, in grey when `grey`
+    pub fn text(&mut self, x: f32, y: f32, size: f32, runs: &[(&str, Font, [u8; 3])]) {
+        self.content.push_str(&format!("BT {:.2} {:.2} Td\n", x, y));
+        for (text, font, [r, g, b]) in runs {
+            let channel = |c: &u8| f32::from(*c) / 255.0;
+            self.content.push_str(&format!(
+                "{} {} Tf {:.2} {:.2} {:.2} rg {} Tj\n",
+                font.resource(),
+                size,
+                channel(r),
+                channel(g),
+                channel(b),
+                string(text)
+            ));
+        }
+        self.content.push_str("ET\n");
+    }
+}
+
+pub fn document(pages: &[Page]) -> Vec<u8> {
+    // Objects 1 to 4 are fixed, then each page and its content stream
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            6 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+    let xref = out.len();
+    out.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    out.into_bytes()
+}
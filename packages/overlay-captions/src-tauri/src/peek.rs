@@ -0,0 +1,137 @@
+use crate::{AppHandle, AppState};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+// Releasing the shortcut after holding it this long hides the overlay right away
+const HOLD_THRESHOLD: Duration = Duration::from_millis(400);
+// A second press within this window after a brief press pins the overlay
+const DOUBLE_PRESS_WINDOW: Duration = Duration::from_millis(350);
+
+#[derive(Default)]
+pub struct PeekState {
+    active: bool,
+    pressed_at: Option<Instant>,
+    released_at: Option<Instant>,
+    // Bumped whenever a pending auto-hide timer should be discarded
+    generation: u64,
+}
+
+// Toggle shortcut pressed while peek mode is enabled
+pub fn on_shortcut_pressed(app: &AppHandle) {
+    let state = match app.try_state::<AppState>() {
+        Some(s) => s,
+        None => return,
+    };
+
+    let overlay_visible = match state.overlay_visible.lock() {
+        Ok(v) => *v,
+        Err(_) => return,
+    };
+
+    let mut peek = match state.peek.lock() {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    let now = Instant::now();
+
+    if peek.active {
+        let double_press = peek
+            .released_at
+            .is_some_and(|released| now.duration_since(released) <= DOUBLE_PRESS_WINDOW);
+        if double_press {
+            // Pin: keep the overlay visible and drop the pending auto-hide
+            log::info!("Peek pinned by double press");
+            peek.active = false;
+            peek.generation += 1;
+            return;
+        }
+
+        // Another press while peeking restarts the peek
+        peek.pressed_at = Some(now);
+        peek.released_at = None;
+        peek.generation += 1;
+        return;
+    }
+
+    if overlay_visible {
+        // Overlay is shown permanently, the shortcut hides it as usual
+        let _ = app.emit("toggle-overlay", ());
+        return;
+    }
+
+    peek.active = true;
+    peek.pressed_at = Some(now);
+    peek.released_at = None;
+    peek.generation += 1;
+    drop(peek);
+
+    crate::spawn_show_overlay_window(app.clone());
+}
+
+// Toggle shortcut released while peek mode is enabled
+pub fn on_shortcut_released(app: &AppHandle) {
+    let state = match app.try_state::<AppState>() {
+        Some(s) => s,
+        None => return,
+    };
+
+    let duration_secs = match state.settings.lock() {
+        Ok(s) => s.peek.duration_secs,
+        Err(_) => return,
+    };
+
+    let mut peek = match state.peek.lock() {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    if !peek.active {
+        return;
+    }
+
+    let now = Instant::now();
+    let held = peek
+        .pressed_at
+        .map(|pressed| now.duration_since(pressed))
+        .unwrap_or_default();
+    peek.released_at = Some(now);
+    peek.generation += 1;
+
+    if held >= HOLD_THRESHOLD {
+        // Held down: the overlay was visible only while the keys were held
+        peek.active = false;
+        drop(peek);
+        end_peek(app);
+        return;
+    }
+
+    // Brief press: keep the overlay visible for the configured time
+    let generation = peek.generation;
+    drop(peek);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(duration_secs as u64));
+
+        let state = match app.try_state::<AppState>() {
+            Some(s) => s,
+            None => return,
+        };
+        {
+            let mut peek = match state.peek.lock() {
+                Ok(p) => p,
+                Err(_) => return,
+            };
+            if !peek.active || peek.generation != generation {
+                return;
+            }
+            peek.active = false;
+        }
+        end_peek(&app);
+    });
+}
+
+fn end_peek(app: &AppHandle) {
+    if let Err(e) = crate::hide_overlay_window(app) {
+        log::error!("Failed to hide overlay after peek: {}", e);
+    }
+}
@@ -0,0 +1,168 @@
+// Native caption processor plugins loaded from `<config dir>/plugins`.
+//
+// A plugin is a shared library (.so/.dylib/.dll) exporting this C ABI:
+//
+//   const char *jutukuva_processor_name(void);
+//   char *jutukuva_process_caption(const char *text);  // NULL keeps the caption unchanged
+//   void jutukuva_free_string(char *text);             // frees strings from process_caption
+//
+// Plugins are only opened, and so only run any of their code, when their file name is listed
+// in `settings.plugins.enabled`; the list is reloaded when it changes.
+
+use crate::caption_pipeline::{CaptionContext, CaptionPipeline, CaptionProcessor};
+use crate::{settings, AppHandle, AppState};
+use libloading::Library;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type ProcessFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+pub fn get_plugins_dir() -> PathBuf {
+    let dir = settings::get_config_dir().join("plugins");
+    fs::create_dir_all(&dir).ok();
+    dir
+}
+
+struct NativePlugin {
+    file_name: String,
+    name: String,
+    process_fn: ProcessFn,
+    free_fn: FreeFn,
+    // Keeps the function pointers above valid
+    _library: Library,
+}
+
+impl NativePlugin {
+    fn load(path: &Path) -> Result<Self, String> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        // SAFETY: plugins are trusted native code the user placed in the plugins dir and enabled
+        unsafe {
+            let library = Library::new(path).map_err(|e| e.to_string())?;
+            let name_fn = *library
+                .get::<NameFn>(b"jutukuva_processor_name\0")
+                .map_err(|e| e.to_string())?;
+            let process_fn = *library
+                .get::<ProcessFn>(b"jutukuva_process_caption\0")
+                .map_err(|e| e.to_string())?;
+            let free_fn = *library
+                .get::<FreeFn>(b"jutukuva_free_string\0")
+                .map_err(|e| e.to_string())?;
+
+            let name_ptr = name_fn();
+            let name = if name_ptr.is_null() {
+                file_name.clone()
+            } else {
+                CStr::from_ptr(name_ptr).to_string_lossy().to_string()
+            };
+
+            Ok(Self {
+                file_name,
+                name,
+                process_fn,
+                free_fn,
+                _library: library,
+            })
+        }
+    }
+}
+
+impl CaptionProcessor for NativePlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_builtin(&self) -> bool {
+        false
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        ctx.settings.plugins.enabled.contains(&self.file_name)
+    }
+
+    fn process(&mut self, caption: String, _ctx: &CaptionContext) -> String {
+        let input = match CString::new(caption.as_str()) {
+            Ok(c) => c,
+            Err(_) => return caption,
+        };
+
+        // SAFETY: see NativePlugin::load; the returned string is released by the plugin itself
+        unsafe {
+            let output = (self.process_fn)(input.as_ptr());
+            if output.is_null() {
+                return caption;
+            }
+            let processed = CStr::from_ptr(output).to_string_lossy().to_string();
+            (self.free_fn)(output);
+            processed
+        }
+    }
+}
+
+fn is_library(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("so") | Some("dylib") | Some("dll")
+    )
+}
+
+// (Re)load the enabled plugins from the plugins dir into the pipeline
+pub fn load_plugins(pipeline: &mut CaptionPipeline, enabled: &[String]) {
+    pipeline.remove_plugins();
+    if enabled.is_empty() {
+        return;
+    }
+
+    let entries = match fs::read_dir(get_plugins_dir()) {
+        Ok(e) => e,
+        Err(e) => {
+            log::warn!("Failed to read plugins dir: {}", e);
+            return;
+        }
+    };
+
+    for path in entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| is_library(p))
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| enabled.iter().any(|e| n.to_string_lossy() == e.as_str()))
+        })
+    {
+        match NativePlugin::load(&path) {
+            Ok(plugin) => {
+                log::info!(
+                    "Loaded caption plugin '{}' from {}",
+                    plugin.name,
+                    path.display()
+                );
+                pipeline.add(Box::new(plugin));
+            }
+            Err(e) => log::error!("Failed to load plugin {}: {}", path.display(), e),
+        }
+    }
+}
+
+// After `settings.plugins.enabled` has changed
+pub fn reload(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let enabled = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .plugins
+        .enabled
+        .clone();
+    let mut pipeline = state.pipeline.lock().map_err(|e| e.to_string())?;
+    load_plugins(&mut pipeline, &enabled);
+    Ok(())
+}
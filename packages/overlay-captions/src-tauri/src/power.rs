@@ -0,0 +1,214 @@
+// Battery awareness for caption laptops. The power state is polled, since the platforms
+// have no common change notification, and sent as `power-state` events; on battery below
+// `warn_below_percent` during a session the operator gets a notification once, until the
+// charger goes back in. With `save_power_on_battery` the overlay drops its animations and
+// batches caption updates, and the contrast sampler pauses, while on battery.
+
+use crate::i18n::{self, tr};
+use crate::settings::PowerSettings;
+use crate::{diagnostics, presentation, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Battery levels change slowly; this is plenty to warn in time
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct PowerState {
+    // Bumped to stop the running poller
+    generation: u64,
+    // On battery with power saving enabled
+    saving: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    // Charge left; None when the platform doesn't say
+    pub percent: Option<u32>,
+    pub low: bool,
+    pub saving: bool,
+}
+
+// On battery, and the charge left; None without a battery
+#[cfg(target_os = "linux")]
+fn read_battery() -> Option<(bool, Option<u32>)> {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|s| s.trim().to_string())
+    };
+    let mut found = false;
+    let mut discharging = false;
+    let mut levels = Vec::new();
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        if read(path.join("type")).as_deref() != Some("Battery") {
+            continue;
+        }
+        found = true;
+        discharging |= read(path.join("status")).as_deref() == Some("Discharging");
+        if let Some(level) = read(path.join("capacity")).and_then(|c| c.parse::<u32>().ok()) {
+            levels.push(level);
+        }
+    }
+    // The emptiest battery of several decides
+    found.then(|| (discharging, levels.into_iter().min()))
+}
+
+#[cfg(windows)]
+fn read_battery() -> Option<(bool, Option<u32>)> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    // From WinBase.h
+    const AC_LINE_OFFLINE: u8 = 0;
+    const BATTERY_FLAG_NO_BATTERY: u8 = 128;
+    const BATTERY_PERCENTAGE_UNKNOWN: u8 = 255;
+
+    // SAFETY: GetSystemPowerStatus only writes the struct it is given
+    let status = unsafe {
+        let mut status: SYSTEM_POWER_STATUS = std::mem::zeroed();
+        if GetSystemPowerStatus(&mut status) == 0 {
+            return None;
+        }
+        status
+    };
+    if status.BatteryFlag & BATTERY_FLAG_NO_BATTERY != 0 {
+        return None;
+    }
+    let percent = (status.BatteryLifePercent != BATTERY_PERCENTAGE_UNKNOWN)
+        .then_some(u32::from(status.BatteryLifePercent));
+    Some((status.ACLineStatus == AC_LINE_OFFLINE, percent))
+}
+
+// From `pmset -g batt`: "Now drawing from 'Battery Power'" and a line with " 85%; discharging"
+#[cfg(target_os = "macos")]
+fn read_battery() -> Option<(bool, Option<u32>)> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let battery_line = text.lines().find(|line| line.contains("InternalBattery"))?;
+    let percent = battery_line
+        .split_whitespace()
+        .find_map(|word| word.strip_suffix("%;"))
+        .and_then(|p| p.parse::<u32>().ok());
+    Some((text.contains("'Battery Power'"), percent))
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn read_battery() -> Option<(bool, Option<u32>)> {
+    None
+}
+
+pub fn is_saving(app: &AppHandle) -> bool {
+    app.state::<AppState>()
+        .power
+        .lock()
+        .map(|s| s.saving)
+        .unwrap_or(false)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .power
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn set_saving(app: &AppHandle, saving: bool) {
+    if let Ok(mut state) = app.state::<AppState>().power.lock() {
+        state.saving = saving;
+    }
+}
+
+// Stop the running poller and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.power.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.power.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.saving = false;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+    if read_battery().is_none() {
+        log::info!("No battery found, not watching the power state");
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &PowerSettings, generation: u64) {
+    let mut last: Option<PowerStatus> = None;
+    let mut warned = false;
+
+    while is_current(app, generation) {
+        if let Some((on_battery, percent)) = read_battery() {
+            let low = on_battery && percent.is_some_and(|p| p < settings.warn_below_percent);
+            let status = PowerStatus {
+                on_battery,
+                percent,
+                low,
+                saving: on_battery && settings.save_power_on_battery,
+            };
+
+            set_saving(app, status.saving);
+            if !low {
+                warned = false;
+            } else if !warned {
+                let connected = app
+                    .state::<AppState>()
+                    .connected
+                    .lock()
+                    .map(|c| *c)
+                    .unwrap_or(false);
+                // Only worth interrupting the operator for while captions are going out
+                if connected {
+                    warned = true;
+                    battery_low(app, percent.unwrap_or_default());
+                }
+            }
+            if last.as_ref() != Some(&status) {
+                let _ = app.emit("power-state", &status);
+                last = Some(status);
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn battery_low(app: &AppHandle, percent: u32) {
+    log::warn!("Battery low during a session: {}%", percent);
+    let _ = diagnostics::record(app, "battery low", Some(format!("{}%", percent)));
+
+    // Nothing may pop up over the projector output in presentation mode
+    if presentation::is_active(app) {
+        return;
+    }
+    let lang = i18n::current_language(app);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(tr(&lang, "notification.battery_low_title"))
+        .body(tr(&lang, "notification.battery_low_body"))
+        .show()
+    {
+        log::error!("Failed to show battery notification: {}", e);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::window_manager::ManagedWindow;
+use crate::{AppHandle, AppState};
+use tauri::{Emitter, Manager};
+
+// Presentation mode keeps everything but the overlay off screen: the main window is hidden
+// and nothing the backend triggers on its own (deep links, settings requests, notifications)
+// may bring a window up over the projector output until the mode is turned off again.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut presentation_mode = state.presentation_mode.lock().map_err(|e| e.to_string())?;
+        *presentation_mode = enabled;
+    }
+
+    log::info!(
+        "Presentation mode {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+
+    if enabled {
+        ManagedWindow::Main.hide(app)?;
+    }
+
+    app.emit("presentation-mode-changed", enabled)
+        .map_err(|e| e.to_string())
+}
+
+// Whether windows, notifications and other app chrome must stay hidden
+pub fn is_active(app: &AppHandle) -> bool {
+    app.try_state::<AppState>()
+        .and_then(|state| state.presentation_mode.lock().ok().map(|p| *p))
+        .unwrap_or(false)
+}
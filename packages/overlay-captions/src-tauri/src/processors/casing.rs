@@ -0,0 +1,111 @@
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+
+// Rule-based cleanup of raw ASR text: normalizes spacing around punctuation and
+// capitalizes sentence starts. Enabled per caption language in `settings.casing`.
+pub struct CasingProcessor;
+
+impl CaptionProcessor for CasingProcessor {
+    fn name(&self) -> &str {
+        "casing"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        ctx.settings
+            .casing
+            .languages
+            .iter()
+            .any(|l| l == ctx.language)
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        let text = fix_spacing(&caption);
+        let text = capitalize_sentences(&text);
+        if ctx.language == "en" {
+            capitalize_english_i(&text)
+        } else {
+            text
+        }
+    }
+}
+
+fn is_closing_punctuation(c: char) -> bool {
+    matches!(c, '.' | ',' | '!' | '?' | ':' | ';' | '…')
+}
+
+// Collapse runs of spaces and drop spaces in front of punctuation ("tere , maailm" -> "tere, maailm").
+// A space that sat before the punctuation moves after it when a word follows ("a .b" -> "a. b").
+fn fix_spacing(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pending_space = false;
+    let mut moved_space = false;
+
+    for c in text.chars() {
+        if c == ' ' || c == '\t' {
+            pending_space = true;
+            continue;
+        }
+
+        let punctuation = is_closing_punctuation(c);
+        if (pending_space && !punctuation && c != '\n' && !result.is_empty())
+            || (moved_space && c.is_alphabetic())
+        {
+            result.push(' ');
+        }
+        moved_space = pending_space && punctuation;
+        pending_space = false;
+        result.push(c);
+    }
+
+    result
+}
+
+// Uppercase the first letter of the caption, of every line and after sentence-ending punctuation
+fn capitalize_sentences(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut sentence_start = true;
+    let mut after_terminator = false;
+
+    for c in text.chars() {
+        if sentence_start && c.is_alphabetic() {
+            result.extend(c.to_uppercase());
+            sentence_start = false;
+            after_terminator = false;
+            continue;
+        }
+
+        if c == '\n' {
+            sentence_start = true;
+        } else if matches!(c, '.' | '!' | '?' | '…') {
+            after_terminator = true;
+        } else if c.is_whitespace() {
+            if after_terminator {
+                sentence_start = true;
+            }
+        } else if !matches!(c, '"' | '\'' | '(' | '«' | '„' | '“') {
+            // Anything else (digits, "3.5", ...) ends the search for a sentence start
+            sentence_start = false;
+            after_terminator = false;
+        }
+
+        result.push(c);
+    }
+
+    result
+}
+
+// English pronoun "i" and its contractions ("i'm", "i'll") are always capitalized
+fn capitalize_english_i(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let standalone = c == 'i'
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+            && chars
+                .get(i + 1)
+                .is_none_or(|next| !next.is_alphanumeric() || *next == '\'');
+        result.push(if standalone { 'I' } else { c });
+    }
+
+    result
+}
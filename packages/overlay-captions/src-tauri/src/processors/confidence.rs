@@ -0,0 +1,180 @@
+use super::terminology::wrap_terms;
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+
+// The main window marks words that came with an ASR confidence score as
+// `<conf v="0.42">word</conf>`. The markup is stripped before the pipeline runs; words
+// below the threshold are marked again near the end as `<low>word</low>`, so the
+// other processors only ever see plain text.
+const SOURCE_OPEN: &str = "<conf v=\"";
+const SOURCE_CLOSE: &str = "</conf>";
+
+pub const LOW_OPEN: &str = "<low>";
+pub const LOW_CLOSE: &str = "</low>";
+
+// Placeholder for low-confidence words on the public overlay
+const REDACTED: &str = "…";
+
+pub struct ScoredCaption {
+    pub text: String,
+    // Words scored below the threshold
+    pub low_confidence: Vec<String>,
+    // Lowest word score, if the source provided any
+    pub min_confidence: Option<f32>,
+    // Failed signature verification but is shown anyway (see signatures.rs)
+    pub unverified: bool,
+}
+
+// Strip the source confidence markup, collecting the words below `threshold`
+pub fn extract(text: &str, threshold: f32) -> ScoredCaption {
+    let mut result = String::with_capacity(text.len());
+    let mut low_confidence = Vec::new();
+    let mut min_confidence: Option<f32> = None;
+    let mut rest = text;
+
+    while let Some(start) = rest.find(SOURCE_OPEN) {
+        result.push_str(&rest[..start]);
+        let after_open = &rest[start + SOURCE_OPEN.len()..];
+
+        // `0.42">word</conf>`; anything malformed (e.g. cut off by trimming) is dropped
+        let Some((score, after_score)) = after_open.split_once("\">") else {
+            rest = "";
+            break;
+        };
+        let (word, after_word) = after_score
+            .split_once(SOURCE_CLOSE)
+            .unwrap_or((after_score, ""));
+
+        if let Ok(score) = score.parse::<f32>() {
+            min_confidence = Some(min_confidence.map_or(score, |m| m.min(score)));
+            let word = word.trim();
+            if score < threshold && !word.is_empty() && !low_confidence.iter().any(|w| w == word) {
+                low_confidence.push(word.to_string());
+            }
+        }
+        result.push_str(word);
+        rest = after_word;
+    }
+    result.push_str(rest);
+
+    ScoredCaption {
+        text: result.replace(SOURCE_CLOSE, ""),
+        low_confidence,
+        min_confidence,
+        unverified: false,
+    }
+}
+
+// Public overlay variant: low-confidence words are replaced with an ellipsis
+pub fn redact(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    // Where the last ellipsis put in ends, so one already in the text isn't taken for it
+    let mut redacted_end: Option<usize> = None;
+    let mut rest = text;
+    while let Some(start) = rest.find(LOW_OPEN) {
+        result.push_str(&rest[..start]);
+        let after_open = &rest[start + LOW_OPEN.len()..];
+        rest = after_open
+            .split_once(LOW_CLOSE)
+            .map_or("", |(_, after)| after);
+        // Neighbouring redactions collapse into one
+        match redacted_end {
+            Some(end) if result[end..].trim().is_empty() => result.truncate(end),
+            _ => {
+                result.push_str(REDACTED);
+                redacted_end = Some(result.len());
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// Wrap the low-confidence words of an unprocessed caption
+pub fn mark_low(text: &str, low_confidence: &[String]) -> String {
+    if low_confidence.is_empty() {
+        return text.to_string();
+    }
+    wrap_terms(text, low_confidence, LOW_OPEN, LOW_CLOSE)
+}
+
+pub struct ConfidenceProcessor;
+
+impl CaptionProcessor for ConfidenceProcessor {
+    fn name(&self) -> &str {
+        "confidence"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        !ctx.low_confidence.is_empty()
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        mark_low(&caption, ctx.low_confidence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_strips_scores_and_collects_low_words() {
+        let caption = extract(
+            "<conf v=\"0.9\">Tere</conf> <conf v=\"0.3\">homikust</conf> \
+             <conf v=\"0.2\">homikust</conf> kõigile",
+            0.5,
+        );
+        assert_eq!(caption.text, "Tere homikust homikust kõigile");
+        assert_eq!(caption.low_confidence, ["homikust"]);
+        assert_eq!(caption.min_confidence, Some(0.2));
+    }
+
+    #[test]
+    fn extract_without_scores() {
+        let caption = extract("Tere kõigile", 0.5);
+        assert_eq!(caption.text, "Tere kõigile");
+        assert!(caption.low_confidence.is_empty());
+        assert_eq!(caption.min_confidence, None);
+    }
+
+    #[test]
+    fn extract_drops_cut_off_markup() {
+        assert_eq!(extract("Tere <conf v=\"0.4", 0.5).text, "Tere ");
+        assert_eq!(extract("<conf v=\"x\">Tere</conf>", 0.5).text, "Tere");
+    }
+
+    #[test]
+    fn mark_low_wraps_whole_words() {
+        assert_eq!(
+            mark_low("Tere homikust, homikustki", &["homikust".to_string()]),
+            "Tere <low>homikust</low>, homikustki"
+        );
+        assert_eq!(mark_low("Tere", &[]), "Tere");
+    }
+
+    #[test]
+    fn redact_replaces_low_words_with_an_ellipsis() {
+        assert_eq!(redact("Tere <low>homikust</low> kõigile"), "Tere … kõigile");
+        assert_eq!(redact("Tere kõigile"), "Tere kõigile");
+    }
+
+    #[test]
+    fn neighbouring_redactions_collapse() {
+        assert_eq!(
+            redact("a <low>b</low> <low>c</low>  <low>d</low> e"),
+            "a … e"
+        );
+        assert_eq!(redact("a <low>b</low> x <low>c</low>"), "a … x …");
+    }
+
+    #[test]
+    fn an_ellipsis_in_the_text_is_not_a_redaction() {
+        assert_eq!(redact("Ootame… <low>homikust</low>"), "Ootame… …");
+        assert_eq!(redact("… <low>b</low> c"), "… … c");
+    }
+
+    #[test]
+    fn unclosed_low_markup_redacts_the_rest() {
+        assert_eq!(redact("Tere <low>homikust kõigile"), "Tere …");
+    }
+}
@@ -0,0 +1,312 @@
+use super::confidence::{LOW_CLOSE, LOW_OPEN};
+use super::markup::{BOLD_CLOSE, BOLD_OPEN, ITALIC_CLOSE, ITALIC_OPEN};
+use super::terminology::{HIGHLIGHT_CLOSE, HIGHLIGHT_OPEN};
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+use crate::layout;
+use crate::text;
+
+// Breaks long caption lines the way broadcast subtitles do: no line longer than
+// `max_chars_per_line`, lines of similar length, and no break right after a word that
+// belongs with the next one (articles, prepositions, conjunctions).
+// Runs last so markup is already in place; it doesn't count towards length, and a break
+// inside a tag pair (see `PAIRED_TAGS`) costs more and closes and reopens the tags.
+// Safe margins (see layout.rs) take their share of the line off `max_chars_per_line`.
+pub struct LineBreakProcessor;
+
+// Markup the pipeline puts around words, opening and closing tag
+const PAIRED_TAGS: [(&str, &str); 4] = [
+    (HIGHLIGHT_OPEN, HIGHLIGHT_CLOSE),
+    (LOW_OPEN, LOW_CLOSE),
+    (BOLD_OPEN, BOLD_CLOSE),
+    (ITALIC_OPEN, ITALIC_CLOSE),
+];
+// Word confidence from the source, `<conf v="0.42">word</conf>`, if still in the text
+const CONF_OPEN: &str = "<conf v=\"";
+const CONF_CLOSE: &str = "</conf>";
+
+impl CaptionProcessor for LineBreakProcessor {
+    fn name(&self) -> &str {
+        "line_breaks"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        ctx.settings.line_breaks.enabled
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        let max_chars = (ctx.settings.line_breaks.max_chars_per_line as f32
+            * layout::usable_width(ctx.settings)) as usize;
+        let max_chars = max_chars.max(10);
+        caption
+            .split('\n')
+            .map(|line| break_line(line, max_chars, ctx.language))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Words that should not end a line, per caption language
+fn no_break_after(language: &str) -> &'static [&'static str] {
+    match language {
+        "et" => &[
+            "ja", "ning", "ega", "või", "aga", "kuid", "et", "kui", "sest", "nagu", "ehk", "ei",
+            "on", "see", "selle", "need", "üle", "enne", "pärast", "läbi", "mööda", "ilma",
+        ],
+        "en" => &[
+            "a", "an", "the", "of", "to", "in", "on", "at", "for", "with", "by", "from", "into",
+            "and", "or", "but", "nor", "that", "my", "your", "his", "her", "its", "our", "their",
+        ],
+        "fi" => &["ja", "tai", "mutta", "että", "kun", "jos", "ei"],
+        _ => &[],
+    }
+}
+
+// The tag of `PAIRED_TAGS` or `<conf>` that `rest` starts with, and whether it opens
+fn tag_at(rest: &str) -> Option<(&str, bool)> {
+    for (open, close) in PAIRED_TAGS {
+        if rest.starts_with(open) {
+            return Some((open, true));
+        }
+        if rest.starts_with(close) {
+            return Some((close, false));
+        }
+    }
+    if rest.starts_with(CONF_CLOSE) {
+        return Some((CONF_CLOSE, false));
+    }
+    match rest.find('>') {
+        Some(end) if rest.starts_with(CONF_OPEN) => Some((&rest[..=end], true)),
+        _ => None,
+    }
+}
+
+fn closing_tag(open: &str) -> &'static str {
+    PAIRED_TAGS
+        .iter()
+        .find(|(o, _)| *o == open)
+        .map_or(CONF_CLOSE, |(_, close)| close)
+}
+
+// Tags still open after each word, outermost first
+fn open_tags(words: &[&str]) -> Vec<Vec<String>> {
+    let mut open: Vec<String> = Vec::new();
+    words
+        .iter()
+        .map(|word| {
+            let mut rest = *word;
+            while let Some(start) = rest.find('<') {
+                rest = &rest[start..];
+                match tag_at(rest) {
+                    Some((tag, true)) => {
+                        open.push(tag.to_string());
+                        rest = &rest[tag.len()..];
+                    }
+                    Some((tag, false)) => {
+                        if let Some(i) = open.iter().rposition(|o| closing_tag(o) == tag) {
+                            open.remove(i);
+                        }
+                        rest = &rest[tag.len()..];
+                    }
+                    None => rest = &rest[1..],
+                }
+            }
+            open.clone()
+        })
+        .collect()
+}
+
+// Visible text of a word: without markup and with escaped characters counted once
+fn strip_markup(word: &str) -> String {
+    let mut visible = String::with_capacity(word.len());
+    let mut rest = word;
+    while let Some(start) = rest.find('<') {
+        visible.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match tag_at(rest) {
+            Some((tag, _)) => rest = &rest[tag.len()..],
+            None => {
+                visible.push('<');
+                rest = &rest[1..];
+            }
+        }
+    }
+    visible.push_str(rest);
+    visible
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn visible_len(word: &str) -> usize {
+    text::grapheme_count(&strip_markup(word))
+}
+
+// Penalty for ending a line after `words[i]`
+fn break_penalty(words: &[&str], i: usize, inside_markup: bool, language: &str, max: usize) -> u64 {
+    let max = max as u64;
+    if inside_markup {
+        // Splitting a highlighted term or a bold phrase would break its markup across lines
+        return max * max * 4;
+    }
+    let word = strip_markup(words[i]).to_lowercase();
+    if no_break_after(language).contains(&word.as_str()) {
+        max * max
+    } else {
+        0
+    }
+}
+
+fn break_line(line: &str, max_chars: usize, language: &str) -> String {
+    let words: Vec<&str> = line.split(' ').filter(|w| !w.is_empty()).collect();
+    let lengths: Vec<usize> = words.iter().map(|w| visible_len(w)).collect();
+    let total = lengths.iter().sum::<usize>() + lengths.len().saturating_sub(1);
+    if total <= max_chars {
+        return line.to_string();
+    }
+
+    let open = open_tags(&words);
+
+    let n = words.len();
+    let line_len =
+        |from: usize, to: usize| lengths[from..to].iter().sum::<usize>() + (to - from - 1);
+
+    // Fewest lines that fit; balancing must not add lines
+    let mut line_count = 1;
+    let mut current = 0;
+    for (i, len) in lengths.iter().enumerate() {
+        if i > 0 && current + 1 + len > max_chars {
+            line_count += 1;
+            current = *len;
+        } else {
+            current += if i > 0 { 1 + len } else { *len };
+        }
+    }
+
+    // cost[k][i]: best cost of laying out the first i words on k lines. Squared slack keeps
+    // lines even; overlong lines are only allowed for a single word that cannot fit anyway.
+    const INFEASIBLE: u64 = u64::MAX;
+    let mut cost = vec![vec![INFEASIBLE; n + 1]; line_count + 1];
+    let mut from = vec![vec![0usize; n + 1]; line_count + 1];
+    cost[0][0] = 0;
+    for k in 1..=line_count {
+        for i in 1..=n {
+            for j in (k - 1)..i {
+                if cost[k - 1][j] == INFEASIBLE {
+                    continue;
+                }
+                let len = line_len(j, i);
+                if len > max_chars && i - j > 1 {
+                    continue;
+                }
+                let slack = max_chars.saturating_sub(len) as u64;
+                let mut c = cost[k - 1][j] + slack * slack;
+                if i < n {
+                    c += break_penalty(&words, i - 1, !open[i - 1].is_empty(), language, max_chars);
+                }
+                if c < cost[k][i] {
+                    cost[k][i] = c;
+                    from[k][i] = j;
+                }
+            }
+        }
+    }
+
+    if cost[line_count][n] == INFEASIBLE {
+        return line.to_string();
+    }
+
+    let mut ranges = Vec::with_capacity(line_count);
+    let mut i = n;
+    for k in (1..=line_count).rev() {
+        let j = from[k][i];
+        ranges.push((j, i));
+        i = j;
+    }
+    ranges.reverse();
+
+    // Markup that had to be split is closed and reopened so each line stays well-formed
+    ranges
+        .iter()
+        .map(|&(j, i)| {
+            let mut line = String::new();
+            if j > 0 {
+                line.extend(open[j - 1].iter().map(String::as_str));
+            }
+            line.push_str(&words[j..i].join(" "));
+            line.extend(open[i - 1].iter().rev().map(|tag| closing_tag(tag)));
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each line opens and closes the same tags
+    fn well_formed(line: &str) -> bool {
+        open_tags(&[line]).last().is_some_and(Vec::is_empty)
+    }
+
+    #[test]
+    fn short_lines_are_left_alone() {
+        assert_eq!(
+            break_line("Tere <b>hommikust</b>", 20, "et"),
+            "Tere <b>hommikust</b>"
+        );
+    }
+
+    #[test]
+    fn lines_stay_within_the_limit_and_even() {
+        assert_eq!(
+            break_line("one two three four five six seven", 20, "en"),
+            "one two three four\nfive six seven"
+        );
+    }
+
+    #[test]
+    fn no_break_after_a_word_that_belongs_with_the_next() {
+        let broken = break_line("he walked to the market today", 20, "en");
+        assert_eq!(broken, "he walked\nto the market today");
+    }
+
+    #[test]
+    fn markup_is_not_counted() {
+        assert_eq!(visible_len("<em><b>sõna</b></em>"), 4);
+        assert_eq!(visible_len("<conf v=\"0.42\">sõna</conf>"), 4);
+        assert_eq!(visible_len("a&lt;b"), 3);
+    }
+
+    #[test]
+    fn breaks_go_outside_tag_pairs_when_they_can() {
+        for tags in [
+            ("<em>", "</em>"),
+            ("<low>", "</low>"),
+            ("<b>", "</b>"),
+            ("<i>", "</i>"),
+        ] {
+            let line = format!("alpha {}beta gamma{} delta", tags.0, tags.1);
+            let broken = break_line(&line, 16, "en");
+            assert!(
+                broken.contains(&format!("{}beta gamma{}", tags.0, tags.1)),
+                "{}",
+                broken
+            );
+            assert!(broken.lines().all(well_formed), "{}", broken);
+        }
+    }
+
+    #[test]
+    fn split_markup_is_closed_and_reopened() {
+        let broken = break_line(
+            "<i>one two <b>three four</b> five six</i> <conf v=\"0.3\">seven eight</conf>",
+            10,
+            "en",
+        );
+        assert!(broken.lines().count() > 2, "{}", broken);
+        assert!(broken.lines().all(well_formed), "{}", broken);
+        assert!(broken.lines().next().unwrap().starts_with("<i>"));
+        assert!(broken.lines().nth(1).unwrap().starts_with("<i>"));
+    }
+}
@@ -0,0 +1,215 @@
+// Markup the caption source embedded in the text (HTML tags, markdown emphasis) is handled
+// per `settings.markup.policy` before anything else sees the caption:
+//   "strip"   - tags and emphasis markers are dropped, their text kept
+//   "escape"  - the markup is kept and shown literally
+//   "convert" - bold and italic become <b>/<i> styling spans, other markup is dropped
+// Whatever the policy, `<`, `>` and `&` in the result are entity-escaped. Later stages can
+// still put raw text in (speaker names, corrections, scripts), so whatever renders a caption
+// keeps only the backend's own tags and treats the rest as text.
+
+pub const BOLD_OPEN: &str = "<b>";
+pub const BOLD_CLOSE: &str = "</b>";
+pub const ITALIC_OPEN: &str = "<i>";
+pub const ITALIC_CLOSE: &str = "</i>";
+
+// Longer "tags" are more likely a stray "<" in the text
+const MAX_TAG_LEN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Plain,
+    Bold,
+    Italic,
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        _ => out.push(c),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    text.chars().for_each(|c| push_escaped(&mut out, c));
+    out
+}
+
+// "<b>", "</strong>", "<br/>", "<span class=x>" -> (lowercase name, closing, length in bytes)
+fn parse_tag(text: &str) -> Option<(String, bool, usize)> {
+    let end = text
+        .char_indices()
+        .take(MAX_TAG_LEN)
+        .find(|(_, c)| *c == '>')?
+        .0;
+    let inner = &text[1..end];
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    let name: String = inner
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    if name.is_empty()
+        || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || inner.contains('<')
+    {
+        return None;
+    }
+    Some((name.to_ascii_lowercase(), closing, end + 1))
+}
+
+struct Writer {
+    out: String,
+    convert: bool,
+    style: Style,
+}
+
+impl Writer {
+    // Styles do not nest: opening one closes the other
+    fn set_style(&mut self, style: Style) {
+        if style == self.style {
+            return;
+        }
+        if self.convert {
+            match self.style {
+                Style::Bold => self.out.push_str(BOLD_CLOSE),
+                Style::Italic => self.out.push_str(ITALIC_CLOSE),
+                Style::Plain => {}
+            }
+            match style {
+                Style::Bold => self.out.push_str(BOLD_OPEN),
+                Style::Italic => self.out.push_str(ITALIC_OPEN),
+                Style::Plain => {}
+            }
+        }
+        self.style = style;
+    }
+
+    fn toggle(&mut self, style: Style) {
+        let next = if self.style == style {
+            Style::Plain
+        } else {
+            style
+        };
+        self.set_style(next);
+    }
+}
+
+pub fn sanitize(text: &str, policy: &str) -> String {
+    if policy == "escape" {
+        return escape(text);
+    }
+
+    let mut writer = Writer {
+        out: String::with_capacity(text.len()),
+        convert: policy == "convert",
+        style: Style::Plain,
+    };
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some((name, closing, len)) = parse_tag(rest) {
+                let style = match name.as_str() {
+                    "b" | "strong" => Some(Style::Bold),
+                    "i" | "em" => Some(Style::Italic),
+                    _ => None,
+                };
+                match (style, closing) {
+                    (Some(style), false) => writer.set_style(style),
+                    (Some(style), true) if writer.style == style => writer.set_style(Style::Plain),
+                    (None, _) if name == "br" => writer.out.push('\n'),
+                    _ => {}
+                }
+                rest = &rest[len..];
+                continue;
+            }
+        }
+
+        // Markdown: **bold** or __bold__, and *italic* around a word
+        if rest.starts_with("**") || rest.starts_with("__") {
+            writer.toggle(Style::Bold);
+            rest = &rest[2..];
+            continue;
+        }
+        if c == '*' {
+            let opens = writer.style != Style::Italic
+                && rest[1..].starts_with(|n: char| !n.is_whitespace());
+            let closes =
+                writer.style == Style::Italic && writer.out.ends_with(|p: char| !p.is_whitespace());
+            if opens || closes {
+                writer.toggle(Style::Italic);
+                rest = &rest[1..];
+                continue;
+            }
+        }
+
+        push_escaped(&mut writer.out, c);
+        rest = &rest[c.len_utf8()..];
+    }
+    writer.set_style(Style::Plain);
+    writer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_keeps_the_text() {
+        assert_eq!(
+            sanitize(
+                "<strong>Tere</strong> **kõik** <span class=x>*sõbrad*</span>",
+                "strip"
+            ),
+            "Tere kõik sõbrad"
+        );
+    }
+
+    #[test]
+    fn convert_keeps_bold_and_italic_only() {
+        assert_eq!(
+            sanitize("<strong>Tere</strong> <u>kõik</u> *sõbrad*", "convert"),
+            "<b>Tere</b> kõik <i>sõbrad</i>"
+        );
+        assert_eq!(sanitize("__a__ <em>b", "convert"), "<b>a</b> <i>b</i>");
+    }
+
+    #[test]
+    fn styles_do_not_nest() {
+        assert_eq!(
+            sanitize("<b>a <i>b</i> c</b>", "convert"),
+            "<b>a </b><i>b</i> c"
+        );
+    }
+
+    #[test]
+    fn escape_shows_markup_literally() {
+        assert_eq!(
+            sanitize("<b>a</b> & b", "escape"),
+            "&lt;b&gt;a&lt;/b&gt; &amp; b"
+        );
+    }
+
+    #[test]
+    fn stray_brackets_and_ampersands_are_escaped() {
+        assert_eq!(
+            sanitize("1 < 2 & 3 > 2", "strip"),
+            "1 &lt; 2 &amp; 3 &gt; 2"
+        );
+        assert_eq!(sanitize("a<3 b", "convert"), "a&lt;3 b");
+    }
+
+    #[test]
+    fn line_break_tags_become_newlines() {
+        assert_eq!(sanitize("rida<br/>teine<BR>", "strip"), "rida\nteine\n");
+    }
+
+    #[test]
+    fn lone_asterisks_are_text() {
+        assert_eq!(sanitize("2 * 3", "convert"), "2 * 3");
+    }
+}
@@ -0,0 +1,19 @@
+// Built-in caption processors, registered in the order they run after the scripts.
+// speaker_mute is the exception: lib.rs puts it first of all.
+mod casing;
+pub mod confidence;
+mod line_breaks;
+pub mod markup;
+mod numbers;
+pub mod speaker_mute;
+mod terminology;
+
+use crate::caption_pipeline::CaptionPipeline;
+
+pub fn register_builtin(pipeline: &mut CaptionPipeline) {
+    pipeline.add(Box::new(casing::CasingProcessor));
+    pipeline.add(Box::new(numbers::NumberFormatProcessor));
+    pipeline.add(Box::new(terminology::TerminologyProcessor));
+    pipeline.add(Box::new(confidence::ConfidenceProcessor));
+    pipeline.add(Box::new(line_breaks::LineBreakProcessor));
+}
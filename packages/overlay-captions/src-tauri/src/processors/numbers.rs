@@ -0,0 +1,469 @@
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+use crate::settings::NumberFormatSettings;
+
+// Rewrites numbers to match on-screen style guides (Estonian and English):
+// - "digits" mode turns spelled-out numbers into digits ("kakskümmend viis" -> "25"),
+//   "words" mode spells out small numbers ("3" -> "three"); numbers below
+//   `spell_out_below` are always written as words
+// - unit words after a number become symbols ("5 protsenti" -> "5%")
+// - Estonian times and dates use dots ("10:30" -> "10.30", "16/10/2026" -> "16.10.2026")
+pub struct NumberFormatProcessor;
+
+impl CaptionProcessor for NumberFormatProcessor {
+    fn name(&self) -> &str {
+        "numbers"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        let numbers = &ctx.settings.numbers;
+        (numbers.mode != "off" || numbers.units || numbers.times_and_dates)
+            && matches!(ctx.language, "et" | "en")
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        caption
+            .split('\n')
+            .map(|line| format_line(line, ctx.language, &ctx.settings.numbers))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+enum NumberWord {
+    // A plain value that is added up ("viis", "kakskümmend", "kakssada")
+    Value(u64),
+    // Multiplies what came before it ("hundred", "sada" after a digit word)
+    Hundred,
+    // Closes a group ("tuhat", "thousand", "miljonit")
+    Scale(u64),
+    // "and" in "one hundred and five"
+    And,
+}
+
+const ET_UNITS: [&str; 10] = [
+    "null", "üks", "kaks", "kolm", "neli", "viis", "kuus", "seitse", "kaheksa", "üheksa",
+];
+const EN_UNITS: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const EN_TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+fn estonian_number_word(word: &str) -> Option<NumberWord> {
+    if let Some(n) = ET_UNITS.iter().position(|w| *w == word) {
+        return Some(NumberWord::Value(n as u64));
+    }
+    match word {
+        "kümme" => return Some(NumberWord::Value(10)),
+        "sada" => return Some(NumberWord::Hundred),
+        "tuhat" | "tuhande" => return Some(NumberWord::Scale(1_000)),
+        "miljon" | "miljonit" => return Some(NumberWord::Scale(1_000_000)),
+        _ => {}
+    }
+
+    // Compounds: "kaksteist" (12), "kakskümmend" (20), "kakssada" (200)
+    for (suffix, offset, multiplier) in [("teist", 10, 1), ("kümmend", 0, 10), ("sada", 0, 100)] {
+        if let Some(prefix) = word.strip_suffix(suffix) {
+            if let Some(n) = ET_UNITS
+                .iter()
+                .position(|w| *w == prefix)
+                .filter(|n| *n > 0)
+            {
+                return Some(NumberWord::Value(n as u64 * multiplier + offset));
+            }
+        }
+    }
+    None
+}
+
+fn english_number_word(word: &str) -> Option<NumberWord> {
+    if let Some(n) = EN_UNITS.iter().position(|w| *w == word) {
+        return Some(NumberWord::Value(n as u64));
+    }
+    if let Some(n) = EN_TENS.iter().position(|w| !w.is_empty() && *w == word) {
+        return Some(NumberWord::Value(n as u64 * 10));
+    }
+    match word {
+        "hundred" => Some(NumberWord::Hundred),
+        "thousand" => Some(NumberWord::Scale(1_000)),
+        "million" => Some(NumberWord::Scale(1_000_000)),
+        "and" => Some(NumberWord::And),
+        _ => None,
+    }
+}
+
+fn number_word(language: &str, word: &str) -> Option<NumberWord> {
+    let word = word.to_lowercase();
+    if language == "et" {
+        estonian_number_word(&word)
+    } else {
+        english_number_word(&word)
+    }
+}
+
+// Accumulates number words into a value, rejecting sequences like "five six"
+#[derive(Clone, Default)]
+struct NumberParser {
+    total: u64,
+    current: u64,
+    // Smallest scale used so far; "kaks tuhat kolm tuhat" is two numbers, not 5000
+    last_scale: u64,
+}
+
+impl NumberParser {
+    fn push(&mut self, word: &NumberWord) -> bool {
+        match *word {
+            NumberWord::Value(n) => {
+                let fits = self.current == 0
+                    || (self.current.is_multiple_of(100) && n < 100)
+                    || (self.current.is_multiple_of(10) && self.current % 100 >= 20 && n < 10);
+                if !fits {
+                    return false;
+                }
+                self.current += n;
+            }
+            NumberWord::Hundred => {
+                if self.current >= 100 {
+                    return false;
+                }
+                self.current = self.current.max(1) * 100;
+            }
+            NumberWord::Scale(scale) => {
+                if self.last_scale != 0 && scale >= self.last_scale {
+                    return false;
+                }
+                self.total += self.current.max(1) * scale;
+                self.current = 0;
+                self.last_scale = scale;
+            }
+            NumberWord::And => {
+                if self.current < 100 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+}
+
+// Separate leading/trailing punctuation from the word itself
+fn split_token(token: &str) -> (&str, &str, &str) {
+    let is_edge = |c: char| !c.is_alphanumeric();
+    let start = token.len() - token.trim_start_matches(is_edge).len();
+    let end = token.trim_end_matches(is_edge).len().max(start);
+    (&token[..start], &token[start..end], &token[end..])
+}
+
+fn group_thousands(value: u64, language: &str) -> String {
+    let digits = value.to_string();
+    if value < 10_000 {
+        return digits;
+    }
+    let separator = if language == "et" { ' ' } else { ',' };
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            result.push(separator);
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn number_to_words(value: u64, language: &str) -> Option<String> {
+    if language == "et" {
+        match value {
+            0..=9 => Some(ET_UNITS[value as usize].to_string()),
+            10 => Some("kümme".to_string()),
+            11..=19 => Some(format!("{}teist", ET_UNITS[value as usize - 10])),
+            _ => None,
+        }
+    } else {
+        EN_UNITS.get(value as usize).map(|w| w.to_string())
+    }
+}
+
+fn unit_symbol(language: &str, word: &str) -> Option<(&'static str, bool)> {
+    // (symbol, attached without a space)
+    let word = word.to_lowercase();
+    let symbol = match (language, word.as_str()) {
+        ("et", "protsenti" | "protsent") | ("en", "percent") => ("%", true),
+        ("et", "kraadi" | "kraad") | ("en", "degrees" | "degree") => ("°", true),
+        ("et", "kilomeetrit" | "kilomeeter") | ("en", "kilometers" | "kilometres") => ("km", false),
+        ("et", "meetrit" | "meeter") | ("en", "meters" | "metres") => ("m", false),
+        ("et", "sentimeetrit") | ("en", "centimeters" | "centimetres") => ("cm", false),
+        ("et", "millimeetrit") | ("en", "millimeters" | "millimetres") => ("mm", false),
+        ("et", "kilogrammi" | "kilogramm") | ("en", "kilograms" | "kilogram") => ("kg", false),
+        ("et", "grammi") | ("en", "grams") => ("g", false),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn is_integer(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+}
+
+// "10:30" -> "10.30" and "16/10/2026" -> "16.10.2026" (Estonian only)
+fn format_time_or_date(word: &str, language: &str) -> Option<String> {
+    if language != "et" {
+        return None;
+    }
+    for separator in [':', '/'] {
+        let parts: Vec<&str> = word.split(separator).collect();
+        let valid = match parts.len() {
+            2 => separator == ':' && parts[0].len() <= 2 && parts[1].len() == 2,
+            3 => separator == '/' && parts[0].len() <= 2 && parts[1].len() <= 2,
+            _ => false,
+        };
+        if valid && parts.iter().all(|p| is_integer(p)) {
+            return Some(parts.join("."));
+        }
+    }
+    None
+}
+
+fn format_line(line: &str, language: &str, settings: &NumberFormatSettings) -> String {
+    let tokens: Vec<&str> = line.split(' ').collect();
+    let mut output: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let (prefix, word, suffix) = split_token(tokens[i]);
+
+        // Longest run of number words, which may not continue past punctuation
+        if settings.mode == "digits" {
+            let mut parser = NumberParser::default();
+            let mut end = i;
+            let mut last_suffix = "";
+            let mut last_value_end = i;
+            // The run up to its last scale word, where a number ends if another scale follows
+            let mut after_scale: Option<(NumberParser, usize, &str)> = None;
+            while end < tokens.len() {
+                let (p, w, s) = split_token(tokens[end]);
+                if !(p.is_empty() || end == i) {
+                    break;
+                }
+                // English compounds are written with a hyphen: "twenty-five"
+                let parts: Vec<&str> = if language == "en" {
+                    w.split('-').collect()
+                } else {
+                    vec![w]
+                };
+                let words: Option<Vec<NumberWord>> = parts
+                    .iter()
+                    .map(|part| number_word(language, part))
+                    .collect();
+                // Try the token on a copy so a half-accepted "twenty-five" leaves no trace
+                let mut attempt = parser.clone();
+                let accepted = match &words {
+                    Some(words) => words.iter().all(|word| attempt.push(word)),
+                    None => false,
+                };
+                let has_scale = words
+                    .iter()
+                    .flatten()
+                    .any(|word| matches!(word, NumberWord::Scale(_)));
+                if !accepted {
+                    // "kaks tuhat kolm tuhat": the words since the last scale start the next
+                    match after_scale {
+                        Some((before, scale_end, scale_suffix))
+                            if has_scale && parser.current > 0 =>
+                        {
+                            parser = before;
+                            last_value_end = scale_end;
+                            last_suffix = scale_suffix;
+                        }
+                        _ => {}
+                    }
+                    break;
+                }
+                parser = attempt;
+                end += 1;
+                if !matches!(number_word(language, w), Some(NumberWord::And)) {
+                    last_value_end = end;
+                    last_suffix = s;
+                }
+                if has_scale {
+                    after_scale = Some((parser.clone(), end, s));
+                }
+                if !s.is_empty() {
+                    break;
+                }
+            }
+
+            if last_value_end > i && parser.value() >= settings.spell_out_below as u64 {
+                output.push(format!(
+                    "{}{}{}",
+                    prefix,
+                    group_thousands(parser.value(), language),
+                    last_suffix
+                ));
+                i = last_value_end;
+                continue;
+            }
+        }
+
+        if settings.mode == "words" && is_integer(word) {
+            let value: u64 = word.parse().unwrap_or(u64::MAX);
+            if value < settings.spell_out_below as u64 {
+                if let Some(words) = number_to_words(value, language) {
+                    output.push(format!("{}{}{}", prefix, words, suffix));
+                    i += 1;
+                    continue;
+                }
+            }
+        }
+
+        if settings.times_and_dates {
+            if let Some(formatted) = format_time_or_date(word, language) {
+                output.push(format!("{}{}{}", prefix, formatted, suffix));
+                i += 1;
+                continue;
+            }
+        }
+
+        output.push(tokens[i].to_string());
+        i += 1;
+    }
+
+    if settings.units {
+        replace_units(&mut output, language);
+    }
+
+    output.join(" ")
+}
+
+// Replace a unit word that directly follows a number with its symbol
+fn replace_units(tokens: &mut Vec<String>, language: &str) {
+    let mut i = 1;
+    while i < tokens.len() {
+        let (_, previous, previous_suffix) = split_token(&tokens[i - 1]);
+        let (prefix, word, suffix) = split_token(&tokens[i]);
+        let follows_number = previous_suffix.is_empty()
+            && prefix.is_empty()
+            && previous.chars().next().is_some_and(|c| c.is_ascii_digit())
+            && previous
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == ',' || c == '.');
+
+        if follows_number {
+            if let Some((symbol, attached)) = unit_symbol(language, word) {
+                if attached {
+                    let merged = format!("{}{}{}", tokens[i - 1], symbol, suffix);
+                    tokens[i - 1] = merged;
+                    tokens.remove(i);
+                    continue;
+                }
+                tokens[i] = format!("{}{}", symbol, suffix);
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: &str, spell_out_below: u32) -> NumberFormatSettings {
+        NumberFormatSettings {
+            mode: mode.to_string(),
+            spell_out_below,
+            units: false,
+            times_and_dates: false,
+        }
+    }
+
+    fn digits(line: &str, language: &str) -> String {
+        format_line(line, language, &settings("digits", 0))
+    }
+
+    #[test]
+    fn spelled_out_numbers_become_digits() {
+        assert_eq!(digits("kakskümmend viis inimest", "et"), "25 inimest");
+        assert_eq!(digits("kaksteist last", "et"), "12 last");
+        assert_eq!(digits("kolmsada neli", "et"), "304");
+        assert_eq!(digits("twenty-five people", "en"), "25 people");
+        assert_eq!(digits("one hundred and five", "en"), "105");
+        assert_eq!(digits("Two thousand three hundred.", "en"), "2300.");
+    }
+
+    #[test]
+    fn large_numbers_are_grouped_by_language() {
+        assert_eq!(digits("kaks miljonit", "et"), "2 000 000");
+        assert_eq!(digits("two million", "en"), "2,000,000");
+        assert_eq!(digits("five thousand", "en"), "5000");
+    }
+
+    #[test]
+    fn separate_numbers_stay_separate() {
+        assert_eq!(digits("five six", "en"), "5 6");
+        assert_eq!(digits("twenty, five", "en"), "20, 5");
+        assert_eq!(digits("kaks tuhat kolm tuhat", "et"), "2000 3000");
+        assert_eq!(digits("and then", "en"), "and then");
+    }
+
+    #[test]
+    fn small_numbers_stay_words_in_digits_mode() {
+        let settings = settings("digits", 10);
+        assert_eq!(format_line("kolm last", "et", &settings), "kolm last");
+        assert_eq!(format_line("kümme last", "et", &settings), "10 last");
+    }
+
+    #[test]
+    fn words_mode_spells_out_small_numbers() {
+        let settings = settings("words", 20);
+        assert_eq!(format_line("3 apples", "en", &settings), "three apples");
+        assert_eq!(format_line("(12)", "et", &settings), "(kaksteist)");
+        assert_eq!(format_line("25 last", "et", &settings), "25 last");
+    }
+
+    #[test]
+    fn unit_words_after_numbers_become_symbols() {
+        let settings = NumberFormatSettings {
+            units: true,
+            ..settings("off", 0)
+        };
+        assert_eq!(format_line("5 protsenti", "et", &settings), "5%");
+        assert_eq!(format_line("10 kilomeetrit,", "et", &settings), "10 km,");
+        assert_eq!(format_line("20 degrees.", "en", &settings), "20°.");
+        assert_eq!(format_line("some percent", "en", &settings), "some percent");
+    }
+
+    #[test]
+    fn estonian_times_and_dates_use_dots() {
+        let settings = NumberFormatSettings {
+            times_and_dates: true,
+            ..settings("off", 0)
+        };
+        assert_eq!(format_line("kell 10:30", "et", &settings), "kell 10.30");
+        assert_eq!(format_line("16/10/2026.", "et", &settings), "16.10.2026.");
+        assert_eq!(format_line("at 10:30", "en", &settings), "at 10:30");
+        assert_eq!(format_line("10:300", "et", &settings), "10:300");
+    }
+}
@@ -0,0 +1,53 @@
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+use crate::export::split_speaker;
+use crate::settings::SpeakerMuteSettings;
+
+// Speakers kept off the public display, such as the interpreter's own feedback channel in
+// relay interpretation. A muted speaker's paragraph is dropped along with the unnamed ones
+// after it, up to the next speaker. This stage runs first, ahead of the Rhai scripts and
+// plugins (see `build_caption_pipeline` in lib.rs), so no later stage sees the text; the
+// operator overlay still shows it and the caption history and archive keep the full
+// caption (see `CaptionPayload::record`). Set in `settings.speaker_mute`.
+pub struct SpeakerMuteProcessor;
+
+pub fn is_muted(settings: &SpeakerMuteSettings, name: &str) -> bool {
+    settings
+        .muted
+        .iter()
+        .any(|muted| muted.trim().to_lowercase() == name.to_lowercase())
+}
+
+// The lines of `text` left for the public, None when no speaker of it is muted
+pub fn filter(settings: &SpeakerMuteSettings, text: &str) -> Option<String> {
+    let mut muted = false;
+    let mut removed = false;
+    let mut kept: Vec<&str> = Vec::new();
+    for line in text.split('\n') {
+        if let (Some(name), _) = split_speaker(line) {
+            muted = is_muted(settings, name);
+        }
+        if muted {
+            removed = true;
+        } else {
+            kept.push(line);
+        }
+    }
+    removed.then(|| kept.join("\n").trim().to_string())
+}
+
+impl CaptionProcessor for SpeakerMuteProcessor {
+    fn name(&self) -> &str {
+        "speaker_mute"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        !ctx.settings.speaker_mute.muted.is_empty()
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        match filter(&ctx.settings.speaker_mute, &caption) {
+            Some(text) => text,
+            None => caption,
+        }
+    }
+}
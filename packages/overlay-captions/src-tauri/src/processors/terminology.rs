@@ -0,0 +1,82 @@
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+
+// Markup wrapped around matched terms; the overlay renders it as emphasis
+pub const HIGHLIGHT_OPEN: &str = "<em>";
+pub const HIGHLIGHT_CLOSE: &str = "</em>";
+
+// Highlights names and product terms from the terminology list so operators can spot
+// where the ASR got them right (and, by their absence, where it mangled them)
+pub struct TerminologyProcessor;
+
+impl CaptionProcessor for TerminologyProcessor {
+    fn name(&self) -> &str {
+        "terminology"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        ctx.settings.terminology.enabled && !ctx.settings.terminology.terms.is_empty()
+    }
+
+    fn process(&mut self, caption: String, ctx: &CaptionContext) -> String {
+        highlight_terms(&caption, &ctx.settings.terminology.terms)
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '\''
+}
+
+fn chars_match(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+pub fn highlight_terms(text: &str, terms: &[String]) -> String {
+    wrap_terms(text, terms, HIGHLIGHT_OPEN, HIGHLIGHT_CLOSE)
+}
+
+// Wrap whole-word, case-insensitive matches of the terms, preferring the longest match
+pub fn wrap_terms(text: &str, terms: &[String], open: &str, close: &str) -> String {
+    let mut terms: Vec<Vec<char>> = terms
+        .iter()
+        .map(|t| t.trim().chars().collect::<Vec<char>>())
+        .filter(|t| !t.is_empty())
+        .collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let at_word_start = i == 0 || !is_word_char(chars[i - 1]);
+        let matched = if at_word_start {
+            terms.iter().find(|term| {
+                let end = i + term.len();
+                end <= chars.len()
+                    && (end == chars.len() || !is_word_char(chars[end]))
+                    && term
+                        .iter()
+                        .zip(&chars[i..end])
+                        .all(|(a, b)| chars_match(*a, *b))
+            })
+        } else {
+            None
+        };
+
+        match matched {
+            Some(term) => {
+                let end = i + term.len();
+                result.push_str(open);
+                result.extend(&chars[i..end]);
+                result.push_str(close);
+                i = end;
+            }
+            None => {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    result
+}
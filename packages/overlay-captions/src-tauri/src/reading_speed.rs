@@ -0,0 +1,125 @@
+use crate::commands::CaptionPayload;
+use crate::{AppHandle, AppState};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// Every caption stays up at least this long, however little text it added
+const MIN_DISPLAY: Duration = Duration::from_millis(300);
+
+// Holds captions back so viewers get at least 1/max_cps seconds per newly shown character.
+// Captions are full snapshots, so while the current one is held only the latest pending
+// snapshot is kept; it is released once the current one has been on screen long enough.
+#[derive(Default)]
+pub struct ReadingSpeedGovernor {
+    shown: String,
+    hold_until: Option<Instant>,
+    pending: Option<CaptionPayload>,
+    // Bumped whenever a scheduled release should be discarded
+    generation: u64,
+}
+
+// Characters the viewer has not read yet: everything after the part shared with the
+// caption on screen
+fn new_chars(shown: &str, next: &str) -> usize {
+    let common = shown
+        .chars()
+        .zip(next.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    next.chars().count().saturating_sub(common)
+}
+
+fn display_time(chars: usize, max_cps: f64) -> Duration {
+    if max_cps <= 0.0 {
+        return MIN_DISPLAY;
+    }
+    Duration::from_secs_f64(chars as f64 / max_cps).max(MIN_DISPLAY)
+}
+
+// Show a processed caption now, or queue it if the current one needs more reading time
+pub fn submit(app: &AppHandle, caption: CaptionPayload) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let (enabled, max_cps) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.reading_speed.enabled,
+            settings.reading_speed.max_chars_per_second,
+        )
+    };
+
+    let mut governor = state.reading_speed.lock().map_err(|e| e.to_string())?;
+    let now = Instant::now();
+
+    if !enabled {
+        governor.pending = None;
+        governor.hold_until = None;
+        governor.generation += 1;
+        governor.shown = caption.text.clone();
+        drop(governor);
+        return crate::commands::emit_caption(app, caption);
+    }
+
+    let waiting = governor.hold_until.is_some_and(|until| until > now);
+    // Clearing the screen is never held back
+    if waiting && !caption.text.is_empty() {
+        let schedule = governor.pending.is_none();
+        governor.pending = Some(caption);
+        if schedule {
+            let delay = governor
+                .hold_until
+                .map(|until| until - now)
+                .unwrap_or_default();
+            let generation = governor.generation;
+            drop(governor);
+            schedule_release(app.clone(), delay, generation);
+        }
+        return Ok(());
+    }
+
+    show(&mut governor, &caption.text, max_cps, now);
+    drop(governor);
+    crate::commands::emit_caption(app, caption)
+}
+
+fn show(governor: &mut ReadingSpeedGovernor, text: &str, max_cps: f64, now: Instant) {
+    let chars = new_chars(&governor.shown, text);
+    governor.hold_until = Some(now + display_time(chars, max_cps));
+    governor.pending = None;
+    governor.generation += 1;
+    governor.shown = text.to_string();
+}
+
+fn schedule_release(app: AppHandle, delay: Duration, generation: u64) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+
+        let state = match app.try_state::<AppState>() {
+            Some(s) => s,
+            None => return,
+        };
+        let max_cps = match state.settings.lock() {
+            Ok(s) => s.reading_speed.max_chars_per_second,
+            Err(_) => return,
+        };
+
+        let caption = {
+            let mut governor = match state.reading_speed.lock() {
+                Ok(g) => g,
+                Err(_) => return,
+            };
+            if governor.generation != generation {
+                return;
+            }
+            let caption = match governor.pending.take() {
+                Some(c) => c,
+                None => return,
+            };
+            show(&mut governor, &caption.text, max_cps, Instant::now());
+            caption
+        };
+
+        if let Err(e) = crate::commands::emit_caption(&app, caption) {
+            log::error!("Failed to release held caption: {}", e);
+        }
+    });
+}
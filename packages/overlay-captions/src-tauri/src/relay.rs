@@ -0,0 +1,273 @@
+// Relay server that viewer instances (see viewer.rs) connect to over the LAN. Viewers on
+// other monitors or machines show the same captions as this instance without joining the
+// session themselves, styled the way the operator set up the font and overlay here.
+// Clients connect to ws://<host>:<port> and receive
+//
+//   {"event": "settings", "settings": {...}}  on connect and whenever the look changes
+//   {"event": "caption", "caption": {...}}    every processed caption (see CaptionPayload)
+//
+// The relay only sends; nothing a client says changes anything here.
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::{AppSettings, FontSettings};
+use crate::{discovery, viewer, AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tungstenite::{Message, WebSocket};
+
+// How often the listener checks for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// How long a caption can wait in a client's queue
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+// Pings let viewers tell a quiet session from a primary that is gone
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct RelayState {
+    // Bumped to stop the running server, e.g. when the port changes
+    generation: u64,
+    clients: Vec<Sender<String>>,
+    // Sent to viewers as they connect so they don't start out blank
+    last_caption: Option<String>,
+}
+
+// The part of the settings viewers take over; their window placement stays their own
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedSettings {
+    pub font: FontSettings,
+    pub opacity: f32,
+    pub display_mode: String,
+    pub background_color: String,
+}
+
+impl SharedSettings {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            font: settings.font.clone(),
+            opacity: settings.overlay.opacity,
+            display_mode: settings.overlay.display_mode.clone(),
+            background_color: settings.overlay.background_color.clone(),
+        }
+    }
+
+    pub fn apply(self, settings: &mut AppSettings) {
+        settings.font = self.font;
+        settings.overlay.opacity = self.opacity;
+        settings.overlay.display_mode = self.display_mode;
+        settings.overlay.background_color = self.background_color;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum RelayMessage {
+    Settings { settings: SharedSettings },
+    Caption { caption: Value },
+}
+
+fn encode(message: &RelayMessage) -> Result<String, String> {
+    serde_json::to_string(message).map_err(|e| e.to_string())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .relay
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running server and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, port) = match state.settings.lock() {
+        Ok(s) => (s.relay.enabled, s.relay.port),
+        Err(_) => return,
+    };
+    let generation = match state.relay.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            // Connected viewers notice the closed queue and disconnect
+            s.clients.clear();
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    // A viewer on the primary's machine shares its settings, relay port included
+    let enabled = enabled && !viewer::is_active(app);
+    if let Err(e) = discovery::advertise(app, enabled.then_some(port)) {
+        log::error!("Failed to advertise relay: {}", e);
+    }
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&app, port, generation) {
+            log::error!("Relay server on port {} failed: {}", port, e);
+        }
+    });
+}
+
+fn serve(app: &AppHandle, port: u16, generation: u64) -> Result<(), String> {
+    // A server being replaced may hold the port until its next poll
+    let mut attempts = 0;
+    let listener = loop {
+        match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(listener) => break listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts < 5 => {
+                attempts += 1;
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    log::info!("Relay server listening on port {}", port);
+
+    while is_current(app, generation) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                log::info!("Viewer connected: {}", peer);
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_client(&app, stream, generation) {
+                        log::warn!("Viewer {} disconnected: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    log::info!("Relay server on port {} stopped", port);
+    Ok(())
+}
+
+fn current_settings(app: &AppHandle) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    encode(&RelayMessage::Settings {
+        settings: SharedSettings::from_settings(&settings),
+    })
+}
+
+fn subscribe(app: &AppHandle) -> Result<(Receiver<String>, Option<String>), String> {
+    let (sender, receiver) = mpsc::channel();
+    let state = app.state::<AppState>();
+    let mut relay = state.relay.lock().map_err(|e| e.to_string())?;
+    relay.clients.push(sender);
+    Ok((receiver, relay.last_caption.clone()))
+}
+
+fn handle_client(app: &AppHandle, stream: TcpStream, generation: u64) -> Result<(), String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    let mut socket: WebSocket<TcpStream> =
+        tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    // Reads time out so the loop can pass on captions in between
+    socket
+        .get_ref()
+        .set_read_timeout(Some(SEND_INTERVAL))
+        .map_err(|e| e.to_string())?;
+
+    let mut last_settings = current_settings(app)?;
+    socket
+        .send(Message::Text(last_settings.clone()))
+        .map_err(|e| e.to_string())?;
+    let (captions, last_caption) = subscribe(app)?;
+    if let Some(caption) = last_caption {
+        socket
+            .send(Message::Text(caption))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut last_ping = Instant::now();
+    while is_current(app, generation) {
+        match socket.read() {
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+
+        if last_ping.elapsed() >= KEEPALIVE_INTERVAL {
+            socket
+                .send(Message::Ping(Vec::new()))
+                .map_err(|e| e.to_string())?;
+            last_ping = Instant::now();
+        }
+
+        let settings = current_settings(app)?;
+        if settings != last_settings {
+            socket
+                .send(Message::Text(settings.clone()))
+                .map_err(|e| e.to_string())?;
+            last_settings = settings;
+        }
+
+        loop {
+            match captions.try_recv() {
+                Ok(caption) => socket
+                    .send(Message::Text(caption))
+                    .map_err(|e| e.to_string())?,
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    let _ = socket.close(None);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let _ = socket.close(None);
+    Ok(())
+}
+
+// Passes processed captions on to the connected viewers
+pub struct RelayOutput;
+
+impl Output for RelayOutput {
+    fn id(&self) -> &str {
+        "relay"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.relay.enabled {
+            return Ok(());
+        }
+        let message = encode(&RelayMessage::Caption {
+            caption: payload.clone(),
+        })?;
+        let state = app.state::<AppState>();
+        let mut relay = state.relay.lock().map_err(|e| e.to_string())?;
+        relay
+            .clients
+            .retain(|client| client.send(message.clone()).is_ok());
+        relay.last_caption = Some(message);
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+// Local WebSocket endpoint for hardware controllers such as the Elgato Stream Deck.
+// A Stream Deck plugin (or any other client) connects to ws://127.0.0.1:<port>, sends the
+// token the app keeps in `remote-control.token` in the config directory as its first
+// message, and then
+//
+//   {"action": "toggleOverlay"}          also "showOverlay", "hideOverlay"
+//   {"action": "togglePresentation"}
+//   {"action": "fireMacro", "index": 0}  index into `settings.macros`
+//   {"action": "setInfoLine", "text": "Next: Dr. Tamm"}  "" clears it (see info_line.rs)
+//
+// and receives {"event": "state", "overlayVisible": .., "presentationMode": ..} once the
+// token checks out and whenever either changes, so button icons can follow the app state.
+// The app has no settings profiles, so there is no profile switching action.
+//
+// Web pages open in the operator's browser can reach 127.0.0.1 too. Browsers send an Origin
+// header with every WebSocket handshake, and controllers don't, so handshakes with one are
+// turned away; the token keeps out the rest, and a client that hasn't sent it within
+// `AUTH_TIMEOUT` is disconnected. It stays the same across restarts, so a
+// controller set up once keeps working.
+
+use crate::settings::get_config_dir;
+use crate::{exit_protection, info_line, kiosk, macros, presentation, AppHandle, AppState};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request as HandshakeRequest, Response,
+};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::{Message, WebSocket};
+
+const TOKEN_FILE: &str = "remote-control.token";
+const TOKEN_LENGTH: usize = 32;
+// How often connections check for state changes and the listener for shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// For the handshake and the token together
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct RemoteControlState {
+    // Bumped to stop the running server, e.g. when the port changes
+    generation: u64,
+    token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+enum Request {
+    ToggleOverlay,
+    ShowOverlay,
+    HideOverlay,
+    TogglePresentation,
+    FireMacro { index: usize },
+    SetInfoLine { text: String },
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateEvent {
+    event: &'static str,
+    overlay_visible: bool,
+    presentation_mode: bool,
+}
+
+fn current_state(app: &AppHandle) -> StateEvent {
+    let state = app.state::<AppState>();
+    StateEvent {
+        event: "state",
+        overlay_visible: state.overlay_visible.lock().map(|v| *v).unwrap_or(false),
+        presentation_mode: presentation::is_active(app),
+    }
+}
+
+fn token_path() -> PathBuf {
+    get_config_dir().join(TOKEN_FILE)
+}
+
+// The token from `remote-control.token`, written with a new one the first time
+fn load_token() -> Result<String, String> {
+    if let Ok(token) = std::fs::read_to_string(token_path()) {
+        if token.trim().len() == TOKEN_LENGTH {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let token: String = {
+        let mut rng = rand::thread_rng();
+        (0..TOKEN_LENGTH)
+            .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0'))
+            .collect()
+    };
+    std::fs::write(token_path(), &token).map_err(|e| e.to_string())?;
+    Ok(token)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .remote_control
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running server and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, port) = match state.settings.lock() {
+        Ok(s) => (s.remote_control.enabled, s.remote_control.port),
+        Err(_) => return,
+    };
+    let generation = match state.remote_control.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !enabled {
+        return;
+    }
+    let token = match load_token() {
+        Ok(token) => token,
+        Err(e) => {
+            log::error!("Remote control unavailable, token not written: {}", e);
+            return;
+        }
+    };
+    if let Ok(mut s) = state.remote_control.lock() {
+        s.token = token;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = serve(&app, port, generation) {
+            log::error!("Remote control server on port {} failed: {}", port, e);
+        }
+    });
+}
+
+fn serve(app: &AppHandle, port: u16, generation: u64) -> Result<(), String> {
+    // A server being replaced may hold the port until its next poll
+    let mut attempts = 0;
+    let listener = loop {
+        match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(listener) => break listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts < 5 => {
+                attempts += 1;
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    log::info!("Remote control listening on 127.0.0.1:{}", port);
+
+    while is_current(app, generation) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                log::info!("Remote control client connected: {}", peer);
+                let app = app.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = handle_client(&app, stream, generation) {
+                        log::warn!("Remote control client {} disconnected: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    log::info!("Remote control on port {} stopped", port);
+    Ok(())
+}
+
+fn send_state(socket: &mut WebSocket<TcpStream>, state: &StateEvent) -> Result<(), String> {
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    socket.send(Message::Text(json)).map_err(|e| e.to_string())
+}
+
+// Handshake callback that turns browsers away
+struct RejectBrowsers;
+
+impl Callback for RejectBrowsers {
+    fn on_request(
+        self,
+        request: &HandshakeRequest,
+        response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        if request.headers().contains_key("origin") {
+            let mut refusal = ErrorResponse::new(Some("Not for web pages".to_string()));
+            *refusal.status_mut() = StatusCode::FORBIDDEN;
+            return Err(refusal);
+        }
+        Ok(response)
+    }
+}
+
+fn handle_client(app: &AppHandle, stream: TcpStream, generation: u64) -> Result<(), String> {
+    let deadline = Instant::now() + AUTH_TIMEOUT;
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    // A client that never finishes the handshake would otherwise hold its thread forever
+    stream
+        .set_read_timeout(Some(AUTH_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut socket = tungstenite::accept_hdr(stream, RejectBrowsers).map_err(|e| e.to_string())?;
+    // Reads time out so the loop can push state changes in between
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+
+    let mut authorized = false;
+    let mut last_state = current_state(app);
+
+    while is_current(app, generation) {
+        if !authorized && Instant::now() >= deadline {
+            let _ = socket.close(Some(CloseFrame {
+                code: CloseCode::Policy,
+                reason: "No token".into(),
+            }));
+            let _ = socket.flush();
+            return Err("no token in time".to_string());
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) if !authorized => {
+                let token = app
+                    .state::<AppState>()
+                    .remote_control
+                    .lock()
+                    .map(|s| s.token.clone())
+                    .map_err(|e| e.to_string())?;
+                if text.trim() != token {
+                    let _ = socket.close(Some(CloseFrame {
+                        code: CloseCode::Policy,
+                        reason: "Wrong token".into(),
+                    }));
+                    let _ = socket.flush();
+                    return Err("wrong token".to_string());
+                }
+                authorized = true;
+                last_state = current_state(app);
+                send_state(&mut socket, &last_state)?;
+                continue;
+            }
+            Ok(Message::Text(text)) => match serde_json::from_str::<Request>(&text) {
+                Ok(request) => handle_request(app, request),
+                Err(e) => log::warn!("Invalid remote control request {}: {}", text, e),
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+
+        let state = current_state(app);
+        if authorized && state != last_state {
+            send_state(&mut socket, &state)?;
+            last_state = state;
+        }
+    }
+
+    let _ = socket.close(None);
+    Ok(())
+}
+
+fn handle_request(app: &AppHandle, request: Request) {
+    // A locked kiosk runs as set up, whatever is plugged in
+    if kiosk::is_locked(app) {
+        log::warn!("Remote control request ignored, kiosk mode is locked");
+        return;
+    }
+    match request {
+        // Same path as the global shortcut, so the main window stays in sync
+        Request::ToggleOverlay => {
+            let _ = app.emit("toggle-overlay", ());
+        }
+        Request::ShowOverlay => crate::spawn_show_overlay_window(app.clone()),
+        // Ask for the PIN first, as the context menu does
+        Request::HideOverlay if exit_protection::is_required(app) => {
+            exit_protection::request(app, "hideOverlay");
+        }
+        Request::HideOverlay => {
+            if let Err(e) = crate::hide_overlay_window(app) {
+                log::error!("Failed to hide overlay: {}", e);
+            }
+        }
+        Request::TogglePresentation => {
+            let enabled = !presentation::is_active(app);
+            if let Err(e) = presentation::set_enabled(app, enabled) {
+                log::error!("Failed to toggle presentation mode: {}", e);
+            }
+        }
+        Request::FireMacro { index } => {
+            let caption_macro = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .ok()
+                .and_then(|s| s.macros.get(index).cloned());
+            match caption_macro {
+                Some(m) => macros::fire(app, &m),
+                None => log::warn!("No caption macro at index {}", index),
+            }
+        }
+        Request::SetInfoLine { text } => {
+            if let Err(e) = info_line::set(app, &text) {
+                log::error!("Failed to set info line: {}", e);
+            }
+        }
+    }
+}
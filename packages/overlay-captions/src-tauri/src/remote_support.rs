@@ -0,0 +1,292 @@
+// Opt-in remote support: for a limited time the app streams its log and connection metrics
+// to the support staff's WebSocket endpoint (`settings.remote_support.url`), so they can
+// debug a venue machine without remote desktop. The operator at the venue starts it from the
+// diagnostics window with the token support gave them; the token is sent as a bearer token
+// when connecting and never stored. Streaming stops by itself after `window_minutes`.
+//
+// Every message is a JSON text frame:
+//   { "type": "hello", "version": "0.7.0", "host": "...", "expiresAt": ms }
+//   { "type": "log", "at": ms, "level": "INFO", "target": "...", "message": "..." }
+//   { "type": "metrics", "at": ms, "diagnostics": { ...DiagnosticsSnapshot } }
+// Only the app's own log records are sent, not those of the libraries it uses.
+
+use crate::clock::now_millis;
+use crate::{diagnostics, discovery, network, AppHandle, AppState};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use serde_json::Value;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::HeaderValue;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+// Log records kept while the endpoint is unreachable; newer ones are dropped
+const QUEUE_SIZE: usize = 1000;
+const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_millis(1);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Where the logger sends records while streaming
+static SINK: Mutex<Option<SyncSender<Value>>> = Mutex::new(None);
+
+struct RemoteLogger;
+
+static LOGGER: RemoteLogger = RemoteLogger;
+
+impl Log for RemoteLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(sink) = SINK.lock() {
+            if let Some(sink) = sink.as_ref() {
+                let _ = sink.try_send(serde_json::json!({
+                    "type": "log",
+                    "at": now_millis(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                }));
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// Installs the logger; records are not even formatted until streaming starts
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Off);
+    }
+}
+
+#[derive(Default)]
+pub struct RemoteSupportState {
+    // Bumped to stop the running stream
+    generation: u64,
+    // Milliseconds since the Unix epoch when streaming ends, None when it is off
+    expires_at: Option<u64>,
+    connected: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSupportStatus {
+    pub active: bool,
+    pub connected: bool,
+    pub expires_at: Option<u64>,
+}
+
+pub fn status(app: &AppHandle) -> Result<RemoteSupportStatus, String> {
+    let state = app.state::<AppState>();
+    let support = state.remote_support.lock().map_err(|e| e.to_string())?;
+    Ok(RemoteSupportStatus {
+        active: support.expires_at.is_some(),
+        connected: support.connected,
+        expires_at: support.expires_at,
+    })
+}
+
+fn notify_changed(app: &AppHandle) {
+    if let Ok(status) = status(app) {
+        let _ = app.emit("remote-support-status", status);
+    }
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .remote_support
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn set_connected(app: &AppHandle, generation: u64, connected: bool) {
+    match app.state::<AppState>().remote_support.lock() {
+        Ok(mut s) if s.generation == generation => s.connected = connected,
+        _ => return,
+    }
+    notify_changed(app);
+}
+
+// Start streaming for the configured window, replacing a stream already running
+pub fn start(app: &AppHandle, token: &str) -> Result<RemoteSupportStatus, String> {
+    let state = app.state::<AppState>();
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remote_support
+        .clone();
+    let url = settings.url.trim().to_string();
+    let token = token.trim().to_string();
+    if url.is_empty() {
+        return Err("No remote support endpoint configured".to_string());
+    }
+    if token.is_empty() {
+        return Err("A support token is needed".to_string());
+    }
+    network::check(app, &url)?;
+
+    let expires_at = now_millis() + u64::from(settings.window_minutes.max(1)) * 60_000;
+    let generation = {
+        let mut support = state.remote_support.lock().map_err(|e| e.to_string())?;
+        support.generation += 1;
+        support.expires_at = Some(expires_at);
+        support.connected = false;
+        support.generation
+    };
+
+    let (sink, records) = mpsc::sync_channel(QUEUE_SIZE);
+    *SINK.lock().map_err(|e| e.to_string())? = Some(sink);
+    log::set_max_level(LevelFilter::Debug);
+    log::info!(
+        "Remote support started for {} minutes",
+        settings.window_minutes
+    );
+
+    let app_handle = app.clone();
+    std::thread::spawn(move || run(&app_handle, &url, &token, expires_at, generation, records));
+    notify_changed(app);
+    status(app)
+}
+
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    {
+        let state = app.state::<AppState>();
+        let mut support = state.remote_support.lock().map_err(|e| e.to_string())?;
+        support.generation += 1;
+        support.expires_at = None;
+        support.connected = false;
+    }
+    log::set_max_level(LevelFilter::Off);
+    *SINK.lock().map_err(|e| e.to_string())? = None;
+    notify_changed(app);
+    Ok(())
+}
+
+fn run(
+    app: &AppHandle,
+    url: &str,
+    token: &str,
+    expires_at: u64,
+    generation: u64,
+    records: Receiver<Value>,
+) {
+    while is_current(app, generation) && now_millis() < expires_at {
+        match connect(url, token) {
+            Ok(mut socket) => {
+                set_connected(app, generation, true);
+                if let Err(e) = stream(app, &mut socket, &records, expires_at, generation) {
+                    log::warn!("Remote support connection lost: {}", e);
+                }
+                let _ = socket.close(None);
+                set_connected(app, generation, false);
+            }
+            Err(e) => {
+                log::warn!("Remote support endpoint not reachable: {}", e);
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+    // The window ended rather than someone stopping it
+    if is_current(app, generation) {
+        if let Err(e) = stop(app) {
+            log::error!("Failed to stop remote support: {}", e);
+        }
+    }
+}
+
+fn connect(url: &str, token: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    let authorization = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| "The support token contains invalid characters".to_string())?;
+    request.headers_mut().insert("Authorization", authorization);
+
+    let socket = network::connect(request)?;
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream,
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref(),
+        _ => return Err("Unsupported connection type".to_string()),
+    };
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    stream
+        .set_write_timeout(Some(WRITE_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+fn send(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, message: &Value) -> Result<(), String> {
+    socket
+        .send(Message::Text(message.to_string()))
+        .map_err(|e| e.to_string())
+}
+
+// Reads whatever the endpoint sent, which also answers its pings
+fn drain_incoming(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<(), String> {
+    loop {
+        match socket.read() {
+            Ok(Message::Close(_)) => return Err("The endpoint closed the connection".to_string()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(())
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn stream(
+    app: &AppHandle,
+    socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    records: &Receiver<Value>,
+    expires_at: u64,
+    generation: u64,
+) -> Result<(), String> {
+    let hello = serde_json::json!({
+        "type": "hello",
+        "version": app.package_info().version.to_string(),
+        "host": discovery::host_name(),
+        "expiresAt": expires_at,
+    });
+    send(socket, &hello)?;
+
+    let mut metrics_sent: Option<Instant> = None;
+    while is_current(app, generation) && now_millis() < expires_at {
+        match records.recv_timeout(POLL_INTERVAL) {
+            Ok(record) => send(socket, &record)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        if metrics_sent.is_none_or(|sent| sent.elapsed() >= METRICS_INTERVAL) {
+            let metrics = serde_json::json!({
+                "type": "metrics",
+                "at": now_millis(),
+                "diagnostics": diagnostics::snapshot(app)?,
+            });
+            send(socket, &metrics)?;
+            metrics_sent = Some(Instant::now());
+        }
+        drain_incoming(socket)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,247 @@
+// Replay for "what did they just say?": the captions of the last `replay.seconds` from the
+// caption history are shown on the overlay again, marked as a replay, for `show_secs`, and
+// then the live caption comes back. Slow motion instead shows the last paragraph again a
+// word at a time, `slow_words_per_minute`, for language learners and the like. Both are
+// asked for with a command or the global shortcuts in `replay`, and a `caption-live` event
+// tells the overlay when it is back to live captions. Only the overlay windows get
+// replays; the public outputs don't, and speakers muted from the display stay muted.
+
+use crate::export::split_speaker;
+use crate::processors::speaker_mute;
+use crate::settings::ReplaySettings;
+use crate::speaker_colors::{self, SpeakerColor};
+use crate::{clock, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+// Longer than anyone would want to read again
+const MAX_SECONDS: u32 = 120;
+// The whole paragraph stays this long after its last word in slow motion
+const SLOW_MOTION_HOLD: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct ReplayState {
+    // Bumped so an earlier replay doesn't end a later one
+    generation: u64,
+    // Global shortcuts registered for replaying and slow motion
+    shortcuts: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ReplayPayload {
+    // False when the replay is over and the live caption is back
+    pub active: bool,
+    pub text: String,
+    pub speakers: Vec<SpeakerColor>,
+    // Slow motion, shown a word at a time
+    pub slow: bool,
+}
+
+fn settings(app: &AppHandle) -> Result<ReplaySettings, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.replay.clone())
+}
+
+fn emit(app: &AppHandle, payload: &ReplayPayload) -> Result<(), String> {
+    app.emit("caption-replay", payload)
+        .map_err(|e| e.to_string())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .replay
+        .lock()
+        .map(|r| r.generation == generation)
+        .unwrap_or(false)
+}
+
+// Start a replay, ending any earlier one
+fn begin(app: &AppHandle) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+    let mut replay = state.replay.lock().map_err(|e| e.to_string())?;
+    replay.generation += 1;
+    Ok(replay.generation)
+}
+
+// Back to live captions, unless a later replay has started
+fn end(app: &AppHandle, generation: u64) {
+    if !is_current(app, generation) {
+        return;
+    }
+    let end = ReplayPayload {
+        active: false,
+        text: String::new(),
+        speakers: Vec::new(),
+        slow: false,
+    };
+    if let Err(e) = emit(app, &end) {
+        log::error!("Failed to end caption replay: {}", e);
+    }
+    if let Err(e) = app.emit("caption-live", ()) {
+        log::error!("Failed to emit live indicator: {}", e);
+    }
+}
+
+// Recent history entries as the public saw them, oldest first
+fn recent(app: &AppHandle, since: u64) -> Result<Vec<String>, String> {
+    let state = app.state::<AppState>();
+    let mute = state
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .speaker_mute
+        .clone();
+    let entries = state
+        .caption_history
+        .lock()
+        .map_err(|e| e.to_string())?
+        .entries();
+
+    // When nothing was said that recently, the last caption is what was missed
+    let recent: Vec<&str> = match entries.iter().position(|e| e.at >= since) {
+        Some(first) => entries[first..].iter().map(|e| e.text.as_str()).collect(),
+        None => entries
+            .last()
+            .map(|e| e.text.as_str())
+            .into_iter()
+            .collect(),
+    };
+    // The history keeps what muted speakers said
+    Ok(recent
+        .into_iter()
+        .map(|text| speaker_mute::filter(&mute, text).unwrap_or_else(|| text.to_string()))
+        .filter(|text| !text.trim().is_empty())
+        .collect())
+}
+
+fn speakers(app: &AppHandle, text: &str) -> Vec<SpeakerColor> {
+    let mut speakers = speaker_colors::for_caption(app, text);
+    if let Ok(settings) = app.state::<AppState>().settings.lock() {
+        speakers.retain(|s| !speaker_mute::is_muted(&settings.speaker_mute, &s.name));
+    }
+    speakers
+}
+
+// Show the captions of the last `seconds` again; returns the replayed text
+pub fn replay_recent(app: &AppHandle, seconds: u32) -> Result<String, String> {
+    let seconds = seconds.clamp(1, MAX_SECONDS);
+    let show_secs = settings(app)?.show_secs;
+    let since = clock::now_millis().saturating_sub(u64::from(seconds) * 1000);
+    let text = recent(app, since)?.join("\n");
+    if text.is_empty() {
+        return Err("Nothing to replay".to_string());
+    }
+
+    let generation = begin(app)?;
+    log::info!("Replaying the last {} s of captions", seconds);
+    emit(
+        app,
+        &ReplayPayload {
+            active: true,
+            text: text.clone(),
+            speakers: speakers(app, &text),
+            slow: false,
+        },
+    )?;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(u64::from(show_secs.max(1))));
+        end(&app, generation);
+    });
+    Ok(text)
+}
+
+// Show the last paragraph again a word at a time; returns the paragraph
+pub fn slow_motion(app: &AppHandle) -> Result<String, String> {
+    let words_per_minute = settings(app)?.slow_words_per_minute.max(1);
+    // Nothing is newer than u64::MAX, so only the last caption comes back
+    let paragraph = recent(app, u64::MAX)?
+        .iter()
+        .rev()
+        .find_map(|text| text.lines().rev().find(|line| !line.trim().is_empty()))
+        .map(|line| line.trim().to_string())
+        .ok_or_else(|| "Nothing to replay".to_string())?;
+
+    // The speaker's name shows from the start
+    let (prefix, rest) = match split_speaker(&paragraph) {
+        (Some(name), rest) => (format!("{}: ", name), rest.to_string()),
+        (None, rest) => (String::new(), rest.to_string()),
+    };
+    let speakers = speakers(app, &paragraph);
+    let generation = begin(app)?;
+    log::info!("Slow motion at {} words per minute", words_per_minute);
+
+    let app = app.clone();
+    let interval = Duration::from_millis(60_000 / u64::from(words_per_minute));
+    std::thread::spawn(move || {
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        for shown in 1..=words.len() {
+            if !is_current(&app, generation) {
+                return;
+            }
+            let payload = ReplayPayload {
+                active: true,
+                text: format!("{}{}", prefix, words[..shown].join(" ")),
+                speakers: speakers.clone(),
+                slow: true,
+            };
+            if let Err(e) = emit(&app, &payload) {
+                log::error!("Failed to show slow motion: {}", e);
+                return;
+            }
+            std::thread::sleep(interval);
+        }
+        std::thread::sleep(SLOW_MOTION_HOLD);
+        end(&app, generation);
+    });
+    Ok(paragraph)
+}
+
+// (Re-)register the replay and slow motion shortcuts from settings
+pub fn register_shortcuts(app: &AppHandle) {
+    let shortcuts = match settings(app) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let state = app.state::<AppState>();
+    let mut replay = match state.replay.lock() {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+
+    for shortcut in replay.shortcuts.drain(..) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut.as_str()) {
+            log::warn!("Failed to unregister replay shortcut {}: {}", shortcut, e);
+        }
+    }
+
+    for (shortcut, slow) in [(shortcuts.shortcut, false), (shortcuts.slow_shortcut, true)] {
+        let shortcut = shortcut.trim().to_string();
+        if shortcut.is_empty() {
+            continue;
+        }
+        let result =
+            app.global_shortcut()
+                .on_shortcut(shortcut.as_str(), move |app, _shortcut, event| {
+                    if event.state != ShortcutState::Pressed {
+                        return;
+                    }
+                    let result = if slow {
+                        slow_motion(app)
+                    } else {
+                        settings(app).and_then(|s| replay_recent(app, s.seconds))
+                    };
+                    if let Err(e) = result {
+                        log::warn!("Failed to replay captions: {}", e);
+                    }
+                });
+        match result {
+            Ok(()) => replay.shortcuts.push(shortcut),
+            Err(e) => log::error!("Failed to register replay shortcut {}: {}", shortcut, e),
+        }
+    }
+}
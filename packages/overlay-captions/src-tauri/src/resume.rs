@@ -0,0 +1,85 @@
+// Waking from sleep. There is no portable suspend notification, so a thread notices the
+// wall clock jumping past its own sleeps, which don't count time spent suspended. After a
+// resume the session connection is most likely dead and monitors may have changed, so the
+// main window is asked to reconnect, the overlays are put back on top and on screen, and
+// a `resumed` event tells the frontend how long the machine slept.
+
+use crate::arbitration;
+use crate::commands::apply_settings_change;
+use crate::diagnostics;
+use crate::settings::Position;
+use crate::window_manager::{self, ManagedWindow, OverlayId};
+use crate::AppHandle;
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+use tauri::Emitter;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// A gap this much longer than the interval was a suspend, not a busy machine
+const MIN_SLEEP_MS: u64 = 30_000;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumedPayload {
+    pub slept_secs: u64,
+}
+
+// Watch for resumes for as long as the app runs
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // The real clock, which the test hooks' frozen one would make look like a resume
+        let mut last = SystemTime::now();
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let now = SystemTime::now();
+            let gap = now.duration_since(last).unwrap_or_default();
+            last = now;
+            if gap >= CHECK_INTERVAL + Duration::from_millis(MIN_SLEEP_MS) {
+                resumed(&app, gap.as_secs());
+            }
+        }
+    });
+}
+
+fn resumed(app: &AppHandle, slept_secs: u64) {
+    log::info!("Resumed after about {} s asleep", slept_secs);
+    let _ = diagnostics::record(app, "resumed", Some(format!("{} s asleep", slept_secs)));
+
+    if let Err(e) = diagnostics::force_reconnect(app) {
+        log::error!("Failed to reconnect after resume: {}", e);
+    }
+    for id in [OverlayId::Public, OverlayId::Operator] {
+        if let Err(e) = restore_overlay(app, id) {
+            log::error!("Failed to restore overlay after resume: {}", e);
+        }
+    }
+    if let Err(e) = app.emit("resumed", &ResumedPayload { slept_secs }) {
+        log::error!("Failed to emit resumed: {}", e);
+    }
+}
+
+pub fn restore_overlay(app: &AppHandle, id: OverlayId) -> Result<(), String> {
+    let managed = ManagedWindow::Overlay(id);
+    let window = match managed.get(app) {
+        Some(window) => window,
+        None => return Ok(()),
+    };
+    // Window managers tend to forget this across a suspend, unless another caption app
+    // goes first (see arbitration.rs)
+    window
+        .set_always_on_top(!arbitration::is_yielding(app))
+        .map_err(|e| e.to_string())?;
+
+    if let Some((x, y)) = window_manager::keep_on_screen(app, managed)? {
+        log::info!("Moved the {} window back on screen", managed.label());
+        apply_settings_change(app, |settings| {
+            let overlay = match id {
+                OverlayId::Public => &mut settings.overlay,
+                OverlayId::Operator => &mut settings.operator_overlay.overlay,
+            };
+            overlay.position = Position { x, y };
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,292 @@
+// Operator review buffer. With review enabled, captions wait `delay_secs` in a queue
+// before they go through the pipeline to the public overlay; meanwhile the operator can
+// edit, suppress or release them early from the review window.
+//
+// Captions are snapshots of the transcript, so an edit is also remembered as a correction
+// and applied to later snapshots that still contain the original text. A suppressed
+// snapshot is taken off the start of later ones that extend it, until one that doesn't is
+// released.
+
+use crate::clock::{self, now_millis};
+use crate::processors::confidence::ScoredCaption;
+use crate::{AppHandle, AppState};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+// Oldest corrections are forgotten beyond this
+const MAX_CORRECTIONS: usize = 50;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingCaption {
+    pub id: u64,
+    pub text: String,
+    // Text as received, before any operator edit
+    pub original: String,
+    // Milliseconds since the Unix epoch
+    pub release_at: u64,
+    pub edited: bool,
+    pub unverified: bool,
+    #[serde(skip)]
+    low_confidence: Vec<String>,
+    #[serde(skip)]
+    min_confidence: Option<f32>,
+}
+
+#[derive(Default)]
+pub struct ReviewQueue {
+    items: VecDeque<PendingCaption>,
+    next_id: u64,
+    // (original, replacement) pairs
+    corrections: Vec<(String, String)>,
+    // The last suppressed snapshot as received
+    suppressed: Option<String>,
+}
+
+impl ReviewQueue {
+    // Text to publish for a snapshot: without the suppressed text it extends, corrected
+    fn rewrite(&self, original: &str) -> String {
+        let text = match &self.suppressed {
+            Some(suppressed) => original
+                .strip_prefix(suppressed.as_str())
+                .map_or(original, str::trim_start),
+            None => original,
+        };
+        self.corrections
+            .iter()
+            .fold(text.to_string(), |text, (from, to)| text.replace(from, to))
+    }
+
+    // Snapshots queued after a corrected or suppressed one contain the same text
+    fn refresh(&mut self) {
+        for i in 0..self.items.len() {
+            if !self.items[i].edited {
+                let text = self.rewrite(&self.items[i].original);
+                self.items[i].text = text;
+            }
+        }
+    }
+
+    fn remember(&mut self, from: String, to: String) {
+        if from.is_empty() || from == to {
+            return;
+        }
+        self.corrections.retain(|(f, _)| *f != from);
+        self.corrections.push((from, to));
+        if self.corrections.len() > MAX_CORRECTIONS {
+            self.corrections.remove(0);
+        }
+        self.refresh();
+    }
+
+    fn suppress(&mut self, original: String) {
+        self.suppressed = Some(original);
+        self.refresh();
+    }
+
+    // The transcript has moved on from the suppressed text once a released snapshot
+    // doesn't extend it
+    fn released(&mut self, original: &str) {
+        if let Some(suppressed) = &self.suppressed {
+            if !original.starts_with(suppressed.as_str()) {
+                self.suppressed = None;
+            }
+        }
+    }
+}
+
+fn notify_changed(app: &AppHandle) {
+    if let Ok(items) = list(app) {
+        let _ = app.emit("review-queue-changed", items);
+    }
+}
+
+pub fn list(app: &AppHandle) -> Result<Vec<PendingCaption>, String> {
+    let state = app.state::<AppState>();
+    let queue = state.review.lock().map_err(|e| e.to_string())?;
+    Ok(queue.items.iter().cloned().collect())
+}
+
+// Hold a caption for review; it is published when its delay runs out
+pub fn enqueue(app: &AppHandle, caption: ScoredCaption, delay_secs: u32) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let id = {
+        let mut queue = state.review.lock().map_err(|e| e.to_string())?;
+        let text = queue.rewrite(&caption.text);
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.items.push_back(PendingCaption {
+            id,
+            text,
+            original: caption.text,
+            release_at: now_millis() + delay_secs as u64 * 1000,
+            edited: false,
+            unverified: caption.unverified,
+            low_confidence: caption.low_confidence,
+            min_confidence: caption.min_confidence,
+        });
+        id
+    };
+    notify_changed(app);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(delay_secs as u64));
+        // A frozen clock releases captions only as it is moved on (see release_due)
+        if clock::is_frozen() {
+            return;
+        }
+        if let Err(e) = release(&app, id) {
+            log::error!("Failed to release reviewed caption: {}", e);
+        }
+    });
+    Ok(())
+}
+
+// Publish every pending caption whose delay has run out
+#[cfg(feature = "test-hooks")]
+pub fn release_due(app: &AppHandle) -> Result<(), String> {
+    let now = now_millis();
+    let due = list(app)?
+        .into_iter()
+        .filter(|item| item.release_at <= now)
+        .map(|item| item.id)
+        .max();
+    match due {
+        // Releasing the newest one drops the older ones it supersedes
+        Some(id) => release(app, id),
+        None => Ok(()),
+    }
+}
+
+// Publish a pending caption now. Older pending snapshots are superseded by it and dropped.
+pub fn release(app: &AppHandle, id: u64) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let item = {
+        let mut queue = state.review.lock().map_err(|e| e.to_string())?;
+        let position = match queue.items.iter().position(|i| i.id == id) {
+            Some(p) => p,
+            // Already released early or suppressed
+            None => return Ok(()),
+        };
+        let item = queue.items.drain(..=position).next_back();
+        if let Some(item) = &item {
+            queue.released(&item.original);
+        }
+        item
+    };
+    notify_changed(app);
+
+    match item {
+        Some(item) => crate::commands::publish_caption(
+            app,
+            ScoredCaption {
+                text: item.text,
+                low_confidence: item.low_confidence,
+                min_confidence: item.min_confidence,
+                unverified: item.unverified,
+            },
+        ),
+        None => Ok(()),
+    }
+}
+
+pub fn edit(app: &AppHandle, id: u64, text: String) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    {
+        let mut queue = state.review.lock().map_err(|e| e.to_string())?;
+        let original = match queue.items.iter_mut().find(|i| i.id == id) {
+            Some(item) => {
+                item.text = text.clone();
+                item.edited = true;
+                item.original.clone()
+            }
+            None => return Err("Caption was already released".to_string()),
+        };
+        queue.remember(original, text);
+    }
+    notify_changed(app);
+    Ok(())
+}
+
+pub fn suppress(app: &AppHandle, id: u64) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    {
+        let mut queue = state.review.lock().map_err(|e| e.to_string())?;
+        let position = match queue.items.iter().position(|i| i.id == id) {
+            Some(p) => p,
+            None => return Err("Caption was already released".to_string()),
+        };
+        if let Some(item) = queue.items.remove(position) {
+            queue.suppress(item.original);
+        }
+    }
+    notify_changed(app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(originals: &[&str]) -> ReviewQueue {
+        let mut queue = ReviewQueue::default();
+        for original in originals {
+            queue.items.push_back(PendingCaption {
+                id: queue.next_id,
+                text: original.to_string(),
+                original: original.to_string(),
+                release_at: 0,
+                edited: false,
+                unverified: false,
+                low_confidence: Vec::new(),
+                min_confidence: None,
+            });
+            queue.next_id += 1;
+        }
+        queue
+    }
+
+    fn texts(queue: &ReviewQueue) -> Vec<&str> {
+        queue.items.iter().map(|i| i.text.as_str()).collect()
+    }
+
+    #[test]
+    fn suppression_only_takes_text_off_snapshots_that_extend_it() {
+        let mut queue = queue(&[
+            "Oops wrong",
+            "Oops wrong\nTere",
+            "Something else, Oops wrong",
+        ]);
+        let suppressed = queue.items.pop_front().unwrap().original;
+        queue.suppress(suppressed);
+        assert_eq!(texts(&queue), ["Tere", "Something else, Oops wrong"]);
+        assert_eq!(
+            queue.rewrite("Oops wrong\nTere hommikust"),
+            "Tere hommikust"
+        );
+    }
+
+    #[test]
+    fn suppression_is_dropped_once_a_released_snapshot_moves_on() {
+        let mut queue = queue(&[]);
+        queue.suppress("Oops wrong".to_string());
+        queue.released("Oops wrong\nTere");
+        assert_eq!(
+            queue.rewrite("Oops wrong\nTere hommikust"),
+            "Tere hommikust"
+        );
+        queue.released("New paragraph");
+        assert_eq!(queue.rewrite("Oops wrong again"), "Oops wrong again");
+    }
+
+    #[test]
+    fn edits_apply_to_later_snapshots() {
+        let mut queue = queue(&["Tere homikust", "Tere homikust kõigile"]);
+        queue.items[0].edited = true;
+        queue.remember("Tere homikust".to_string(), "Tere hommikust".to_string());
+        assert_eq!(texts(&queue)[1], "Tere hommikust kõigile");
+    }
+}
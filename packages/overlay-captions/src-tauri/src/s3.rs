@@ -0,0 +1,157 @@
+// Uploads to an S3 bucket (AWS or a compatible service such as MinIO) with Signature
+// Version 4 signed requests. The access key is kept in the secrets store (see secrets.rs).
+
+use crate::settings::S3Settings;
+use crate::{clock, network, secrets};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+const ACCESS_KEY_SECRET: &str = "s3.accessKeyId";
+const SECRET_KEY_SECRET: &str = "s3.secretAccessKey";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+pub struct Bucket {
+    settings: S3Settings,
+    access_key: String,
+    secret_key: String,
+    agent: ureq::Agent,
+}
+
+pub fn has_credentials() -> bool {
+    secrets::get(ACCESS_KEY_SECRET).is_some() && secrets::get(SECRET_KEY_SECRET).is_some()
+}
+
+// Store the access key, or forget it with None
+pub fn set_credentials(keys: Option<(&str, &str)>) -> Result<(), String> {
+    secrets::set(ACCESS_KEY_SECRET, keys.map(|k| k.0))?;
+    secrets::set(SECRET_KEY_SECRET, keys.map(|k| k.1))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Percent-encoding of a key for the request path; "/" separates its segments
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+impl Bucket {
+    pub fn open(settings: &S3Settings) -> Result<Self, String> {
+        if settings.bucket.trim().is_empty() || settings.region.trim().is_empty() {
+            return Err("S3 bucket and region are required".to_string());
+        }
+        let access_key = secrets::get(ACCESS_KEY_SECRET).ok_or("No S3 access key")?;
+        let secret_key = secrets::get(SECRET_KEY_SECRET).ok_or("No S3 access key")?;
+        Ok(Self {
+            settings: settings.clone(),
+            access_key,
+            secret_key,
+            agent: network::agent(REQUEST_TIMEOUT)?,
+        })
+    }
+
+    // Where the requests go, e.g. "https://bucket.s3.eu-north-1.amazonaws.com"
+    pub fn endpoint(&self) -> String {
+        let (scheme, host, _) = self.location("");
+        format!("{}://{}", scheme, host)
+    }
+
+    // Scheme and host, and the request path of `name`
+    fn location(&self, name: &str) -> (String, String, String) {
+        let key = format!("{}{}", self.settings.prefix.trim_start_matches('/'), name);
+        let endpoint = self.settings.endpoint.trim().trim_end_matches('/');
+        if endpoint.is_empty() {
+            // AWS, virtual-hosted style
+            let host = format!(
+                "{}.s3.{}.amazonaws.com",
+                self.settings.bucket, self.settings.region
+            );
+            ("https".to_string(), host, encode_path(&format!("/{}", key)))
+        } else {
+            // Compatible services, path style
+            let (scheme, host) = endpoint.split_once("://").unwrap_or(("https", endpoint));
+            (
+                scheme.to_string(),
+                host.to_string(),
+                encode_path(&format!("/{}/{}", self.settings.bucket, key)),
+            )
+        }
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        name: &str,
+        body: &[u8],
+        content_type: &str,
+    ) -> Result<(), String> {
+        let (scheme, host, path) = self.location(name);
+        let t = clock::utc(clock::now_millis());
+        let date = format!("{:04}{:02}{:02}", t.year, t.month, t.day);
+        let timestamp = format!("{}T{:02}{:02}{:02}Z", date, t.hour, t.minute, t.second);
+        let payload_hash = hex(&Sha256::digest(body));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, timestamp, SIGNED_HEADERS, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.settings.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        let key = hmac(&key, &self.settings.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        let signature = hex(&hmac(&key, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, SIGNED_HEADERS, signature
+        );
+
+        let request = self
+            .agent
+            .request(method, &format!("{}://{}{}", scheme, host, path))
+            .set("Host", &host)
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("x-amz-date", &timestamp)
+            .set("Authorization", &authorization);
+        let request = if content_type.is_empty() {
+            request
+        } else {
+            request.set("Content-Type", content_type)
+        };
+        match request.send_bytes(body) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, _)) => Err(format!("S3 answered HTTP {}", code)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn put(&self, name: &str, body: &str, content_type: &str) -> Result<(), String> {
+        self.request("PUT", name, body.as_bytes(), content_type)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        self.request("DELETE", name, &[], "")
+    }
+}
@@ -0,0 +1,167 @@
+// User script hooks. A Rhai script at `<config dir>/script.rhai` may define any of:
+//
+//   fn on_caption(text) { ... }   // return a string to replace the caption
+//   fn on_connect() { ... }
+//   fn on_disconnect() { ... }
+//
+// Besides the Rhai standard library, scripts can call `log(msg)`,
+// `write_file(path, text)` and `run_command(program, [args])`.
+// The script is reloaded whenever the file changes on disk.
+
+use crate::caption_pipeline::{CaptionContext, CaptionProcessor};
+use crate::settings;
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// Keeps a runaway script from stalling the caption path
+const MAX_OPERATIONS: u64 = 500_000;
+
+pub fn get_script_path() -> PathBuf {
+    settings::get_config_dir().join("script.rhai")
+}
+
+pub struct ScriptHost {
+    engine: Engine,
+    ast: Option<AST>,
+    loaded_at: Option<SystemTime>,
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptHost {
+    pub fn new() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        engine.register_fn("log", |msg: &str| {
+            log::info!("[script] {}", msg);
+        });
+        engine.register_fn("write_file", |path: &str, text: &str| -> bool {
+            match fs::write(path, text) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("[script] write_file({}) failed: {}", path, e);
+                    false
+                }
+            }
+        });
+        engine.register_fn("run_command", |program: &str, args: Array| -> bool {
+            let args: Vec<String> = args.into_iter().map(|a| a.to_string()).collect();
+            match Command::new(program).args(&args).spawn() {
+                Ok(_) => true,
+                Err(e) => {
+                    log::error!("[script] run_command({}) failed: {}", program, e);
+                    false
+                }
+            }
+        });
+
+        Self {
+            engine,
+            ast: None,
+            loaded_at: None,
+        }
+    }
+
+    // Compile the script again if it changed since it was last loaded
+    fn reload_if_changed(&mut self) {
+        let path = get_script_path();
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(_) => {
+                // No script (or it was removed)
+                self.ast = None;
+                self.loaded_at = None;
+                return;
+            }
+        };
+
+        if self.loaded_at == Some(modified) {
+            return;
+        }
+        self.loaded_at = Some(modified);
+
+        match self.engine.compile_file(path.clone()) {
+            Ok(ast) => {
+                log::info!("Loaded script {}", path.display());
+                self.ast = Some(ast);
+            }
+            Err(e) => {
+                log::error!("Failed to compile script {}: {}", path.display(), e);
+                self.ast = None;
+            }
+        }
+    }
+
+    // Call a hook if the script defines it
+    fn call_hook(&mut self, name: &str, args: Vec<Dynamic>) -> Option<Dynamic> {
+        self.reload_if_changed();
+
+        let ast = self.ast.as_ref()?;
+        let defined = ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == args.len());
+        if !defined {
+            return None;
+        }
+
+        let mut scope = Scope::new();
+        match self.engine.call_fn::<Dynamic>(&mut scope, ast, name, args) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                log::error!("Script hook {} failed: {}", name, e);
+                None
+            }
+        }
+    }
+
+    pub fn on_caption(&mut self, text: &str) -> Option<String> {
+        self.call_hook("on_caption", vec![text.into()])
+            .and_then(|result| result.into_string().ok())
+    }
+
+    pub fn on_connect(&mut self) {
+        self.call_hook("on_connect", vec![]);
+    }
+
+    pub fn on_disconnect(&mut self) {
+        self.call_hook("on_disconnect", vec![]);
+    }
+}
+
+// Runs the script's on_caption hook as a stage of the caption pipeline
+pub struct ScriptCaptionProcessor {
+    host: Arc<Mutex<ScriptHost>>,
+}
+
+impl ScriptCaptionProcessor {
+    pub fn new(host: Arc<Mutex<ScriptHost>>) -> Self {
+        Self { host }
+    }
+}
+
+impl CaptionProcessor for ScriptCaptionProcessor {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn enabled(&self, ctx: &CaptionContext) -> bool {
+        ctx.settings.scripting.enabled
+    }
+
+    fn process(&mut self, caption: String, _ctx: &CaptionContext) -> String {
+        let mut host = match self.host.lock() {
+            Ok(h) => h,
+            Err(_) => return caption,
+        };
+        host.on_caption(&caption).unwrap_or(caption)
+    }
+}
@@ -0,0 +1,56 @@
+// Credentials for integrations (e.g. the smart light bridge, see light_alert.rs), kept in
+// secrets.json next to the settings rather than in them: the settings go to the frontend
+// as a whole and get copied between machines, secrets are only read here. On Unix the
+// file is readable by the current user only.
+
+use crate::settings::get_config_dir;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Serializes read-modify-write of the file
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn path() -> PathBuf {
+    get_config_dir().join("secrets.json")
+}
+
+fn read() -> BTreeMap<String, String> {
+    fs::read_to_string(path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write(secrets: &BTreeMap<String, String>) -> Result<(), String> {
+    let path = path();
+    let content = serde_json::to_string_pretty(secrets).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn get(name: &str) -> Option<String> {
+    let _guard = LOCK.lock().ok()?;
+    read().remove(name)
+}
+
+// Store a secret, or remove it with None
+pub fn set(name: &str, value: Option<&str>) -> Result<(), String> {
+    let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+    let mut secrets = read();
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(value) => {
+            secrets.insert(name.to_string(), value.to_string());
+        }
+        None => {
+            secrets.remove(name);
+        }
+    }
+    write(&secrets)
+}
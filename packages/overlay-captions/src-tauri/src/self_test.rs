@@ -0,0 +1,237 @@
+// Self-test for venue checklists: the things that most often go wrong on a venue machine,
+// each reported as passed, failed or skipped when the feature isn't configured. The
+// diagnostics window shows the report; pre-event scripts run it from the command line with
+//   --self-test
+// which prints the report as JSON and exits with 0 when nothing failed, 1 otherwise.
+
+use crate::settings::{get_config_dir, get_settings_path, AppSettings, OverlaySettings};
+use crate::window_manager::ManagedWindow;
+use crate::{audio_monitor, diagnostics, failover, viewer, AppHandle, AppState};
+use cpal::traits::DeviceTrait;
+use serde::Serialize;
+use std::fs;
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+const FLAG: &str = "--self-test";
+const PROBE_WINDOW: &str = "self-test";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestCheck {
+    pub id: &'static str,
+    // "pass", "fail" or "skip"
+    pub status: &'static str,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+pub fn requested() -> bool {
+    std::env::args().any(|arg| arg == FLAG)
+}
+
+fn pass(id: &'static str, detail: Option<String>) -> SelfTestCheck {
+    SelfTestCheck {
+        id,
+        status: "pass",
+        detail,
+    }
+}
+
+fn fail(id: &'static str, detail: String) -> SelfTestCheck {
+    SelfTestCheck {
+        id,
+        status: "fail",
+        detail: Some(detail),
+    }
+}
+
+fn skip(id: &'static str) -> SelfTestCheck {
+    SelfTestCheck {
+        id,
+        status: "skip",
+        detail: None,
+    }
+}
+
+// Blocks for up to a few seconds on the server probe, so keep it off the main thread
+pub fn run(app: &AppHandle) -> Result<SelfTestReport, String> {
+    let settings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let checks = vec![
+        settings_writable(),
+        overlay_window(app, &settings.overlay),
+        server_reachable(app),
+        shortcuts(app, &settings),
+        audio_devices(&settings),
+    ];
+    Ok(SelfTestReport {
+        passed: checks.iter().all(|check| check.status != "fail"),
+        checks,
+    })
+}
+
+// Run from the command line: print the report once the app is up and exit with its result
+pub fn run_from_cli(app: AppHandle) {
+    let _ = ManagedWindow::Main.hide(&app);
+    std::thread::spawn(move || {
+        let code = match run(&app) {
+            Ok(report) => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).unwrap_or_default()
+                );
+                if report.passed {
+                    0
+                } else {
+                    1
+                }
+            }
+            Err(e) => {
+                eprintln!("Self-test failed to run: {}", e);
+                1
+            }
+        };
+        app.exit(code);
+    });
+}
+
+fn settings_writable() -> SelfTestCheck {
+    const ID: &str = "settingsWritable";
+    let probe = get_config_dir().join(".self-test");
+    let result = fs::write(&probe, b"ok").and_then(|_| fs::remove_file(&probe));
+    if let Err(e) = result {
+        return fail(ID, format!("{}: {}", probe.display(), e));
+    }
+    match fs::metadata(get_settings_path()) {
+        Ok(metadata) if metadata.permissions().readonly() => {
+            fail(ID, "The settings file is read-only".to_string())
+        }
+        _ => pass(ID, Some(get_settings_path().display().to_string())),
+    }
+}
+
+// The overlay's position must be on a connected monitor and a window must open there
+fn overlay_window(app: &AppHandle, overlay: &OverlaySettings) -> SelfTestCheck {
+    const ID: &str = "overlayWindow";
+    let monitors = match app.available_monitors() {
+        Ok(monitors) => monitors,
+        Err(e) => return fail(ID, e.to_string()),
+    };
+    let (x, y) = (overlay.position.x as f64, overlay.position.y as f64);
+    let monitor = monitors.iter().find(|m| {
+        let scale = m.scale_factor();
+        let position = m.position().to_logical::<f64>(scale);
+        let size = m.size().to_logical::<f64>(scale);
+        x >= position.x
+            && x < position.x + size.width
+            && y >= position.y
+            && y < position.y + size.height
+    });
+    let monitor_name = match monitor {
+        Some(monitor) => monitor.name().cloned().unwrap_or_default(),
+        None => {
+            return fail(
+                ID,
+                format!("The overlay position ({}, {}) is not on any monitor", x, y),
+            )
+        }
+    };
+    if ManagedWindow::OVERLAY.is_open(app) {
+        return pass(ID, Some(monitor_name));
+    }
+
+    let url = match "about:blank".parse() {
+        Ok(url) => WebviewUrl::External(url),
+        Err(_) => return fail(ID, "Invalid probe window address".to_string()),
+    };
+    let window = WebviewWindowBuilder::new(app, PROBE_WINDOW, url)
+        .position(x, y)
+        .inner_size(overlay.size.width as f64, overlay.size.height as f64)
+        .decorations(false)
+        .skip_taskbar(true)
+        .visible(false)
+        .build();
+    match window {
+        Ok(window) => {
+            let _ = window.destroy();
+            pass(ID, Some(monitor_name))
+        }
+        Err(e) => fail(ID, e.to_string()),
+    }
+}
+
+// The server the session connects to, a backup after failing over
+fn server_reachable(app: &AppHandle) -> SelfTestCheck {
+    const ID: &str = "serverReachable";
+    match diagnostics::measure_latency(&failover::active_url(app)) {
+        Ok(latency) => pass(ID, Some(format!("{} ms", latency.as_millis()))),
+        Err(e) => fail(ID, e),
+    }
+}
+
+// The overlay toggle and every macro shortcut were registered at startup
+fn shortcuts(app: &AppHandle, settings: &AppSettings) -> SelfTestCheck {
+    const ID: &str = "shortcuts";
+    let mut missing = Vec::new();
+    if !app
+        .global_shortcut()
+        .is_registered(crate::overlay_shortcut())
+    {
+        missing.push(crate::overlay_shortcut().to_string());
+    }
+    // Viewers don't register macros
+    if !viewer::is_active(app) {
+        for caption_macro in &settings.macros {
+            let shortcut = caption_macro.shortcut.trim();
+            if shortcut.is_empty() || caption_macro.text.trim().is_empty() {
+                continue;
+            }
+            if !app.global_shortcut().is_registered(shortcut) {
+                missing.push(shortcut.to_string());
+            }
+        }
+    }
+    if missing.is_empty() {
+        pass(ID, None)
+    } else {
+        fail(ID, format!("Not registered: {}", missing.join(", ")))
+    }
+}
+
+// Devices of the audio monitor and the audio uplink, where those are on
+fn audio_devices(settings: &AppSettings) -> SelfTestCheck {
+    const ID: &str = "audioDevice";
+    let mut configured = Vec::new();
+    if settings.audio_monitor.enabled {
+        configured.push((
+            settings.audio_monitor.device.as_str(),
+            settings.audio_monitor.loopback,
+        ));
+    }
+    if settings.audio_uplink.enabled {
+        configured.push((settings.audio_uplink.device.as_str(), false));
+    }
+    if configured.is_empty() {
+        return skip(ID);
+    }
+
+    let mut found = Vec::new();
+    for (name, loopback) in configured {
+        match audio_monitor::find_device(name, loopback) {
+            Ok(device) => found.push(device.name().unwrap_or_default()),
+            Err(e) => return fail(ID, e),
+        }
+    }
+    pass(ID, Some(found.join(", ")))
+}
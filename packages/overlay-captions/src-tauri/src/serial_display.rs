@@ -0,0 +1,251 @@
+// Serial/USB character displays (podium LCDs and VFDs, e-ink boards with a serial text
+// interface) showing the latest caption. The display is sent its clear sequence followed by
+// one screen of text, each line padded to the display width. A caption longer than the
+// screen is shown per `settings.serial_display.paging`:
+//   "scroll" - the last lines, as on the overlay
+//   "pages"  - a screen at a time, moving on every `page_seconds` and stopping at the end
+
+use crate::event_router::{Output, CAPTIONS};
+use crate::settings::{AppSettings, SerialDisplaySettings};
+use crate::{text, AppHandle, AppState};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// How often the writer looks for a new caption and checks whether it was replaced
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Wait between attempts to open a port that is not there
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct SerialDisplayState {
+    // Bumped to stop the running writer, e.g. when the port changes
+    generation: u64,
+    // Plain text of the latest caption
+    caption: String,
+}
+
+// Serial ports offered in the display settings
+pub fn list_ports() -> Result<Vec<String>, String> {
+    let mut ports: Vec<String> = serialport::available_ports()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect();
+    ports.sort();
+    Ok(ports)
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .serial_display
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+fn caption(app: &AppHandle) -> String {
+    app.state::<AppState>()
+        .serial_display
+        .lock()
+        .map(|s| s.caption.clone())
+        .unwrap_or_default()
+}
+
+// Stop the running writer and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.serial_display.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.serial_display.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled || settings.port.trim().is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || run(&app, &settings, generation));
+}
+
+fn run(app: &AppHandle, settings: &SerialDisplaySettings, generation: u64) {
+    let clear = match parse_hex(&settings.clear_sequence) {
+        Ok(clear) => clear,
+        Err(e) => {
+            log::error!("Invalid clear sequence for serial display: {}", e);
+            return;
+        }
+    };
+    let mut reported_missing = false;
+
+    while is_current(app, generation) {
+        match serialport::new(&settings.port, settings.baud_rate)
+            .timeout(WRITE_TIMEOUT)
+            .open()
+        {
+            Ok(port) => {
+                log::info!("Serial display on {} connected", settings.port);
+                reported_missing = false;
+                if let Err(e) = write_captions(app, port, settings, &clear, generation) {
+                    log::warn!("Serial display on {} disconnected: {}", settings.port, e);
+                }
+            }
+            Err(e) => {
+                if !reported_missing {
+                    log::warn!("Serial display on {} not available: {}", settings.port, e);
+                    reported_missing = true;
+                }
+                std::thread::sleep(RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+// "1B 5B 32 4A" or "1b5b324a" -> bytes
+fn parse_hex(sequence: &str) -> Result<Vec<u8>, String> {
+    let digits: String = sequence.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in {:?}", sequence));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+// Character displays mostly have an ASCII character set
+fn to_ascii(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'õ' | 'ö' | 'ó' | 'ò' | 'ô' => 'o',
+            'Õ' | 'Ö' | 'Ó' | 'Ò' | 'Ô' => 'O',
+            'ä' | 'á' | 'à' | 'â' | 'å' => 'a',
+            'Ä' | 'Á' | 'À' | 'Â' | 'Å' => 'A',
+            'ü' | 'ú' | 'ù' | 'û' => 'u',
+            'Ü' | 'Ú' | 'Ù' | 'Û' => 'U',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'É' | 'È' | 'Ê' | 'Ë' => 'E',
+            'š' => 's',
+            'Š' => 'S',
+            'ž' => 'z',
+            'Ž' => 'Z',
+            '’' | '‘' => '\'',
+            '“' | '”' | '„' => '"',
+            '–' | '—' => '-',
+            '…' => '.',
+            c if c.is_ascii() => c,
+            _ => '?',
+        })
+        .collect()
+}
+
+// One screen of the caption; `page` counts screens from the start in "pages" mode
+fn screen(lines: &[String], settings: &SerialDisplaySettings, page: usize) -> Vec<String> {
+    let rows = settings.lines.max(1);
+    let start = if settings.paging == "pages" {
+        (page * rows).min(lines.len().saturating_sub(1) / rows * rows)
+    } else {
+        lines.len().saturating_sub(rows)
+    };
+    lines.iter().skip(start).take(rows).cloned().collect()
+}
+
+fn page_count(lines: &[String], settings: &SerialDisplaySettings) -> usize {
+    lines.len().div_ceil(settings.lines.max(1)).max(1)
+}
+
+fn write_screen(
+    port: &mut dyn serialport::SerialPort,
+    screen: &[String],
+    settings: &SerialDisplaySettings,
+    clear: &[u8],
+) -> Result<(), String> {
+    let mut out = clear.to_vec();
+    for row in 0..settings.lines.max(1) {
+        let line = screen.get(row).map(String::as_str).unwrap_or("");
+        let padding = settings.width.saturating_sub(text::grapheme_count(line));
+        out.extend_from_slice(line.as_bytes());
+        out.extend(std::iter::repeat_n(b' ', padding));
+    }
+    port.write_all(&out).map_err(|e| e.to_string())?;
+    port.flush().map_err(|e| e.to_string())
+}
+
+fn write_captions(
+    app: &AppHandle,
+    mut port: Box<dyn serialport::SerialPort>,
+    settings: &SerialDisplaySettings,
+    clear: &[u8],
+    generation: u64,
+) -> Result<(), String> {
+    let page_interval = Duration::from_secs(settings.page_seconds.max(1));
+    let mut shown: Option<Vec<String>> = None;
+    let mut last_text = String::new();
+    let mut page = 0;
+    let mut page_shown = Instant::now();
+
+    while is_current(app, generation) {
+        let mut current = caption(app);
+        if settings.ascii_only {
+            current = to_ascii(&current);
+        }
+        // A new caption starts over, one that only grew keeps its place
+        if !current.starts_with(&last_text) {
+            page = 0;
+            page_shown = Instant::now();
+        }
+        last_text = current;
+
+        let lines = text::wrap(&last_text, settings.width);
+        if page + 1 < page_count(&lines, settings) && page_shown.elapsed() >= page_interval {
+            page += 1;
+            page_shown = Instant::now();
+        }
+
+        let screen = screen(&lines, settings, page);
+        if shown.as_ref() != Some(&screen) {
+            write_screen(port.as_mut(), &screen, settings, clear)?;
+            shown = Some(screen);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+// Keeps the latest caption for the display writer
+pub struct SerialDisplayOutput;
+
+impl Output for SerialDisplayOutput {
+    fn id(&self) -> &str {
+        "serial-display"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[CAPTIONS]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        if !settings.serial_display.enabled {
+            return Ok(());
+        }
+        let caption = payload.get("text").and_then(Value::as_str).unwrap_or("");
+        let state = app.state::<AppState>();
+        let mut display = state.serial_display.lock().map_err(|e| e.to_string())?;
+        display.caption = text::plain(caption);
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+// Clock and session timer for the overlay, so moderators can keep speakers to their time:
+// once a second a `clock-tick` event carries the current time and how long the session or
+// talk has been going. The time is sent as milliseconds since the epoch and formatted by
+// the overlay, which knows the local time zone.
+//
+// The timer starts when the first session connects and keeps running across reconnects.
+// It can be paused and reset for each talk.
+
+use crate::settings::ClockSettings;
+use crate::{clock, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+#[derive(Default)]
+pub struct SessionClockState {
+    // Bumped to stop the running ticker
+    generation: u64,
+    // When the timer started, moved forward by the time spent paused; None before the
+    // first session
+    started_at: Option<u64>,
+    paused_at: Option<u64>,
+}
+
+impl SessionClockState {
+    fn elapsed_secs(&self, now: u64) -> Option<u64> {
+        self.started_at
+            .map(|started| self.paused_at.unwrap_or(now).saturating_sub(started) / 1000)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockTick {
+    // None when the time is not shown
+    pub now: Option<u64>,
+    // "00:23:10"; None when the timer is not shown or hasn't started
+    pub elapsed: Option<String>,
+    pub elapsed_secs: Option<u64>,
+    pub paused: bool,
+}
+
+fn hms(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn tick(app: &AppHandle, settings: &ClockSettings) -> Result<(), String> {
+    let now = clock::now_millis();
+    let (elapsed_secs, paused) = {
+        let state = app.state::<AppState>();
+        let clock = state.session_clock.lock().map_err(|e| e.to_string())?;
+        (clock.elapsed_secs(now), clock.paused_at.is_some())
+    };
+    let elapsed_secs = elapsed_secs.filter(|_| settings.show_timer);
+    let tick = ClockTick {
+        now: settings.show_time.then_some(now),
+        elapsed: elapsed_secs.map(hms),
+        elapsed_secs,
+        paused,
+    };
+    app.emit("clock-tick", &tick).map_err(|e| e.to_string())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .session_clock
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Stop the running ticker and start a new one if enabled in settings
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = match state.settings.lock() {
+        Ok(s) => s.clock.clone(),
+        Err(_) => return,
+    };
+    let generation = match state.session_clock.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if !settings.enabled {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while is_current(&app, generation) {
+            if let Err(e) = tick(&app, &settings) {
+                log::error!("Failed to emit clock tick: {}", e);
+            }
+            // Tick on the second so the clock and the timer turn over together
+            let millis = clock::now_millis();
+            std::thread::sleep(Duration::from_millis(1000 - millis % 1000));
+        }
+    });
+}
+
+// A session connected; starts the timer unless it is already running
+pub fn session_started(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let mut clock = match state.session_clock.lock() {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+    if clock.started_at.is_none() {
+        clock.started_at = Some(clock::now_millis());
+    }
+}
+
+// Start the timer from zero, e.g. for the next talk; a paused timer stays paused
+pub fn reset_timer(app: &AppHandle) -> Result<(), String> {
+    let now = clock::now_millis();
+    let state = app.state::<AppState>();
+    let mut clock = state.session_clock.lock().map_err(|e| e.to_string())?;
+    clock.started_at = Some(now);
+    if clock.paused_at.is_some() {
+        clock.paused_at = Some(now);
+    }
+    Ok(())
+}
+
+// Pause or resume the timer; returns whether it is paused now
+pub fn toggle_timer(app: &AppHandle) -> Result<bool, String> {
+    let now = clock::now_millis();
+    let state = app.state::<AppState>();
+    let mut clock = state.session_clock.lock().map_err(|e| e.to_string())?;
+    match (clock.started_at, clock.paused_at) {
+        (None, _) => {
+            clock.started_at = Some(now);
+            Ok(false)
+        }
+        (Some(started), Some(paused)) => {
+            clock.started_at = Some(started + now.saturating_sub(paused));
+            clock.paused_at = None;
+            Ok(false)
+        }
+        (Some(_), None) => {
+            clock.paused_at = Some(now);
+            Ok(true)
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
@@ -15,6 +18,15 @@ pub struct Size {
     pub height: u32,
 }
 
+// Where a tool window such as Review was last closed, in logical pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OverlaySettings {
@@ -27,6 +39,10 @@ pub struct OverlaySettings {
     pub always_on_top: bool,
     pub display_mode: String,
     pub background_color: String,
+    // Degrees clockwise the captions are turned for portrait-mounted displays: 0, 90 or 270.
+    // `size` stays that of the unturned caption box (see window_manager.rs).
+    #[serde(default)]
+    pub rotation: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,88 +55,1637 @@ pub struct FontSettings {
     pub align: String,
     #[serde(default = "default_line_height")]
     pub line_height: f64,
+    // "auto" (from the caption text), "ltr" or "rtl"
+    #[serde(default = "default_direction")]
+    pub direction: String,
+    // Let every line take its own direction, for sessions that mix scripts
+    #[serde(default = "default_auto_bidi")]
+    pub auto_bidi: bool,
 }
 
 fn default_line_height() -> f64 {
     1.3
 }
 
+fn default_direction() -> String {
+    "auto".to_string()
+}
+
+fn default_auto_bidi() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectionSettings {
+    // wss:// or ws://, IPv6 addresses in brackets; checked when saved (see server_url.rs)
     pub yjs_server_url: String,
     pub auto_connect: bool,
+    // "websocket" joins sessions on the server; "webrtc" also offers direct peer connections
+    #[serde(default = "default_transport")]
+    pub transport: String,
+    // Backup servers after `yjs_server_url`, in order of preference (see failover.rs)
+    #[serde(default)]
+    pub fallback_server_urls: Vec<String>,
+    // Failed connection attempts before the next server takes over
+    #[serde(default = "default_failover_after")]
+    pub failover_after: u32,
+}
+
+fn default_transport() -> String {
+    "websocket".to_string()
 }
 
+fn default_failover_after() -> u32 {
+    3
+}
+
+// Action names: "showMain", "toggleOverlay", "openSettings", "fontLarger", "fontSmaller", "none".
+// There is no scroll-wheel trigger: tray-icon reports clicks and the pointer entering,
+// moving over and leaving the icon, but not the wheel, on any platform. With a double-click
+// action set, a left click waits to see whether it was a double click (see tray.rs).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct AppSettings {
+pub struct TrayActions {
+    pub left_click: String,
+    pub middle_click: String,
+    pub double_click: String,
+}
+
+impl Default for TrayActions {
+    fn default() -> Self {
+        Self {
+            left_click: "showMain".to_string(),
+            middle_click: "toggleOverlay".to_string(),
+            double_click: "openSettings".to_string(),
+        }
+    }
+}
+
+// Peek mode: a brief press of the toggle shortcut shows the overlay for `duration_secs`,
+// holding it shows the overlay until release and a double press keeps it visible
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeekSettings {
+    pub enabled: bool,
+    pub duration_secs: u32,
+}
+
+impl Default for PeekSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_secs: 5,
+        }
+    }
+}
+
+// Caption processor plugins are native code, so each one has to be enabled by file name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSettings {
+    pub enabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptSettings {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminologySettings {
+    pub enabled: bool,
+    pub terms: Vec<String>,
+}
+
+impl Default for TerminologySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            terms: Vec::new(),
+        }
+    }
+}
+
+// Caption languages (primary subtags, e.g. "et", "en") for which casing cleanup runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CasingSettings {
+    pub languages: Vec<String>,
+}
+
+// Number formatting stage. `mode` is "off", "digits" (spelled-out numbers become digits)
+// or "words" (small digits are spelled out); numbers below `spell_out_below` stay words
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberFormatSettings {
+    pub mode: String,
+    pub spell_out_below: u32,
+    pub units: bool,
+    pub times_and_dates: bool,
+}
+
+impl Default for NumberFormatSettings {
+    fn default() -> Self {
+        Self {
+            mode: "off".to_string(),
+            spell_out_below: 10,
+            units: false,
+            times_and_dates: false,
+        }
+    }
+}
+
+// Broadcast-style line segmentation of long captions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineBreakSettings {
+    pub enabled: bool,
+    pub max_chars_per_line: usize,
+}
+
+impl Default for LineBreakSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_chars_per_line: 42,
+        }
+    }
+}
+
+// Upper bound on how fast new caption text appears; broadcast guidelines suggest 15-20 cps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingSpeedSettings {
+    pub enabled: bool,
+    pub max_chars_per_second: f64,
+}
+
+impl Default for ReadingSpeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_chars_per_second: 17.0,
+        }
+    }
+}
+
+// Words scored below `threshold` are marked as low confidence. `low_confidence_display` is
+// "style" (shown with a distinct style) or "ellipsis" (replaced with "…" on the overlay)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfidenceSettings {
+    pub threshold: f32,
+    pub low_confidence_display: String,
+}
+
+impl Default for ConfidenceSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            low_confidence_display: "style".to_string(),
+        }
+    }
+}
+
+// Operator review buffer: captions are held `delay_secs` before they reach the overlay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewSettings {
+    pub enabled: bool,
+    pub delay_secs: u32,
+}
+
+impl Default for ReviewSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_secs: 5,
+        }
+    }
+}
+
+// Second overlay for the operator: styled on its own and fed unreviewed captions with
+// confidence, while the public overlay only gets what passed review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorOverlaySettings {
+    pub enabled: bool,
     pub overlay: OverlaySettings,
     pub font: FontSettings,
-    pub connection: ConnectionSettings,
-    pub last_session_code: Option<String>,
-    pub theme: String,
 }
 
-impl Default for AppSettings {
+impl Default for OperatorOverlaySettings {
     fn default() -> Self {
         Self {
+            enabled: false,
             overlay: OverlaySettings {
-                enabled: false,
-                position: Position { x: 500, y: 600 },
-                size: Size {
-                    width: 600,
-                    height: 160,
-                },
-                position_preset: "bottom".to_string(),
-                opacity: 0.95,
-                click_through: false,
-                always_on_top: true,
-                display_mode: "lastOnly".to_string(),
-                background_color: "#000000".to_string(),
+                position: Position { x: 500, y: 380 },
+                background_color: "#1f2937".to_string(),
+                ..default_overlay_settings()
             },
             font: FontSettings {
-                family: "Inter, system-ui, sans-serif".to_string(),
-                size: 32,
-                weight: 500,
-                color: "#ffffff".to_string(),
-                align: "justify".to_string(),
-                line_height: 1.3,
-            },
-            connection: ConnectionSettings {
-                yjs_server_url: "wss://tekstiks.ee/kk".to_string(),
-                auto_connect: true,
+                size: 24,
+                ..default_font_settings()
             },
-            last_session_code: None,
-            theme: "system".to_string(),
         }
     }
 }
 
-pub fn get_settings_path() -> PathBuf {
-    if let Some(proj_dirs) = ProjectDirs::from("ee", "jutukuva", "overlay-captions") {
-        let config_dir = proj_dirs.config_dir();
-        fs::create_dir_all(config_dir).ok();
-        config_dir.join("settings.json")
-    } else {
-        PathBuf::from("settings.json")
+// A canned phrase ("[applause]") bound to a global shortcut such as "Ctrl+Alt+1"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionMacro {
+    pub shortcut: String,
+    pub text: String,
+    // Also append the phrase to the shared session document
+    #[serde(default)]
+    pub push_to_session: bool,
+    // An emergency message, e.g. an evacuation notice; raises a light alert
+    #[serde(default)]
+    pub emergency: bool,
+}
+
+// Local WebSocket endpoint for Stream Deck and similar controllers (see remote_control.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteControlSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for RemoteControlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27543,
+        }
     }
 }
 
-pub fn load_settings() -> AppSettings {
-    let path = get_settings_path();
-    if path.exists() {
-        if let Ok(content) = fs::read_to_string(&path) {
-            if let Ok(settings) = serde_json::from_str(&content) {
-                return settings;
-            }
+// A MIDI note or CC bound to an action (see midi.rs for the action names)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiMapping {
+    // "note" or "cc"
+    pub message: String,
+    // 1-16, None matches any channel
+    #[serde(default)]
+    pub channel: Option<u8>,
+    pub number: u8,
+    pub action: String,
+    // Index into `macros` for the "macro" action
+    #[serde(default)]
+    pub macro_index: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiSettings {
+    pub enabled: bool,
+    // Substring of the input port name, None uses the first available port
+    #[serde(default)]
+    pub port_name: Option<String>,
+    #[serde(default)]
+    pub mappings: Vec<MidiMapping>,
+}
+
+// HID footswitch (see footswitch.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootswitchSettings {
+    pub enabled: bool,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    // Input report byte carrying the switch, None treats any set bit in the report as pressed
+    #[serde(default)]
+    pub button_byte: Option<usize>,
+    #[serde(default = "default_button_mask")]
+    pub button_mask: u8,
+    // "pause" or "pushToHide"
+    pub action: String,
+}
+
+fn default_button_mask() -> u8 {
+    0xFF
+}
+
+impl Default for FootswitchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vendor_id: 0,
+            product_id: 0,
+            button_byte: None,
+            button_mask: default_button_mask(),
+            action: "pause".to_string(),
         }
     }
-    AppSettings::default()
 }
 
-pub fn save_settings(settings: &AppSettings) -> Result<(), String> {
-    let path = get_settings_path();
-    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
-    fs::write(path, content).map_err(|e| e.to_string())
+// Serial/USB character display for the latest caption (see serial_display.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialDisplaySettings {
+    pub enabled: bool,
+    // "COM3", "/dev/ttyUSB0"
+    pub port: String,
+    pub baud_rate: u32,
+    // Characters per line and lines on the display
+    pub width: usize,
+    pub lines: usize,
+    // "scroll" or "pages"
+    pub paging: String,
+    pub page_seconds: u64,
+    // Hex bytes sent before each screen, e.g. "0C" (form feed) to clear and home
+    pub clear_sequence: String,
+    // Replace letters the display's character set likely lacks
+    pub ascii_only: bool,
+}
+
+impl Default for SerialDisplaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: String::new(),
+            baud_rate: 9600,
+            width: 20,
+            lines: 4,
+            paging: "scroll".to_string(),
+            page_seconds: 3,
+            clear_sequence: "0C".to_string(),
+            ascii_only: true,
+        }
+    }
+}
+
+// Refreshable braille display through BRLTTY (see braille.rs)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrailleSettings {
+    pub enabled: bool,
+    // "host:port" of a BRLTTY on another machine, empty for the local one
+    pub host: String,
+}
+
+// A meeting or streaming platform taking captions (see caption_services.rs); its caption
+// URL is in the secrets store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionServiceSettings {
+    pub enabled: bool,
+    // Caption language code sent along, e.g. "et-EE"; empty leaves it to the platform
+    pub language: String,
+}
+
+// S3 bucket or compatible storage (see s3.rs); its access key is in the secrets store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Settings {
+    // Empty for AWS, otherwise e.g. "https://minio.example.org:9000"
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    // Prepended to object names, e.g. "live/"
+    pub prefix: String,
+}
+
+// WebVTT subtitles for an HLS stream (see hls_captions.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HlsCaptionSettings {
+    pub enabled: bool,
+    // "directory" or "s3"
+    pub target: String,
+    pub directory: String,
+    pub s3: S3Settings,
+    pub segment_seconds: u64,
+    // Segments listed in the playlist; older ones are deleted
+    pub window_segments: usize,
+    // How far the stream runs behind the room
+    pub stream_delay_ms: u64,
+}
+
+impl Default for HlsCaptionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: "directory".to_string(),
+            directory: String::new(),
+            s3: S3Settings::default(),
+            segment_seconds: 6,
+            window_segments: 10,
+            stream_delay_ms: 0,
+        }
+    }
+}
+
+// Level meter and silence warnings for the captioned audio (see audio_monitor.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioMonitorSettings {
+    pub enabled: bool,
+    // Device name, empty for the default one
+    pub device: String,
+    // Capture what an output device plays (Windows only)
+    pub loopback: bool,
+    // Audio quieter than this counts as silence
+    pub silence_threshold_db: f32,
+    pub warn_after_secs: u64,
+}
+
+impl Default for AudioMonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device: String::new(),
+            loopback: false,
+            silence_threshold_db: -50.0,
+            warn_after_secs: 10,
+        }
+    }
+}
+
+// Venue audio sent to the speech recognition server (see audio_uplink.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioUplinkSettings {
+    pub enabled: bool,
+    // ws:// or wss:// address of the recognition server's audio endpoint
+    pub url: String,
+    // Input device name, empty for the default one
+    pub device: String,
+    // Opus bitrate in bits per second
+    pub bitrate: u32,
+}
+
+impl Default for AudioUplinkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            device: String::new(),
+            bitrate: 24000,
+        }
+    }
+}
+
+// Peer connections of the "webrtc" transport (see peer.ts in the frontend)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebrtcSettings {
+    // STUN/TURN server URLs used to get through NAT
+    pub ice_servers: Vec<String>,
+    // Send the microphone to the peer, e.g. room audio for a remote captioner
+    pub send_audio: bool,
+}
+
+impl Default for WebrtcSettings {
+    fn default() -> Self {
+        Self {
+            ice_servers: vec!["stun:stun.l.google.com:19302".to_string()],
+            send_audio: false,
+        }
+    }
+}
+
+// Session hosted on the LAN for venues without internet (see lan_session.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanSessionSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Fixed session code, empty for a new one each time the app starts
+    pub code: String,
+}
+
+impl Default for LanSessionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27546,
+            code: String::new(),
+        }
+    }
+}
+
+// Experimental Bluetooth LE output for caption glasses (see ble_broadcast.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BleBroadcastSettings {
+    pub enabled: bool,
+    // Local name in the advertisement
+    pub name: String,
+    // Longest caption tail in one notification; glasses negotiate an MTU of 185 or more
+    pub max_bytes: usize,
+}
+
+impl Default for BleBroadcastSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "Jutukuva".to_string(),
+            max_bytes: 180,
+        }
+    }
+}
+
+// Log and metrics streaming to support staff (see remote_support.rs); the token is entered
+// each time and never saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSupportSettings {
+    // wss:// endpoint of the support staff
+    pub url: String,
+    // How long streaming lasts once started
+    pub window_minutes: u32,
+}
+
+impl Default for RemoteSupportSettings {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            window_minutes: 30,
+        }
+    }
+}
+
+// Formatted transcript export (see export.rs). In the header and footer {title} and {date}
+// stand for the transcript's title and date and {page} for the page number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSettings {
+    pub header: String,
+    pub footer: String,
+    // Time of each caption before its text
+    pub timestamps: bool,
+    // The speaker's name in bold whenever the speaker changes
+    pub speaker_labels: bool,
+    // Chapter headings (see chapters.rs); the YouTube chapter list always has them
+    #[serde(default)]
+    pub chapters: bool,
+    // Captions starting with this start a chapter titled by the rest of the caption
+    #[serde(default = "default_chapter_marker")]
+    pub chapter_marker: String,
+    // A silence this long starts a chapter, 0 for chapters only at markers
+    #[serde(default = "default_chapter_silence_secs")]
+    pub chapter_silence_secs: u32,
+}
+
+fn default_chapter_marker() -> String {
+    "##".to_string()
+}
+
+fn default_chapter_silence_secs() -> u32 {
+    120
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            header: "{title}".to_string(),
+            footer: "{date} – {page}".to_string(),
+            timestamps: true,
+            speaker_labels: true,
+            chapters: false,
+            chapter_marker: default_chapter_marker(),
+            chapter_silence_secs: default_chapter_silence_secs(),
+        }
+    }
+}
+
+// Captions shown this much later to line up with a PA system or broadcast delay line (see
+// display_delay.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayDelaySettings {
+    pub delay_ms: u64,
+    // Global shortcuts for a longer and a shorter delay, by step_ms each
+    pub later_shortcut: String,
+    pub sooner_shortcut: String,
+    pub step_ms: u64,
+}
+
+impl Default for DisplayDelaySettings {
+    fn default() -> Self {
+        Self {
+            delay_ms: 0,
+            later_shortcut: "Ctrl+Alt+Shift+Up".to_string(),
+            sooner_shortcut: "Ctrl+Alt+Shift+Down".to_string(),
+            step_ms: 100,
+        }
+    }
+}
+
+// Clock and session timer in the overlay (see session_clock.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSettings {
+    pub enabled: bool,
+    pub show_time: bool,
+    pub show_timer: bool,
+}
+
+impl Default for ClockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            show_time: true,
+            show_timer: true,
+        }
+    }
+}
+
+// Where one element goes in the overlay window (see layout.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutRegion {
+    // "captions", "infoLine", "lowerThird", "watermark" or "clock"
+    pub element: String,
+    // Cell of the 3×3 grid: "topLeft", "top", "topRight", "left", "center", … "bottomRight"
+    pub anchor: String,
+    // Higher is in front
+    pub z: i32,
+}
+
+// Title-safe area: percentages of the window kept clear on each side, for projector
+// images that venue screens crop
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeMargins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutSettings {
+    // Elements moved from their default region
+    pub regions: Vec<LayoutRegion>,
+    #[serde(default)]
+    pub safe_margins: SafeMargins,
+}
+
+// Speaker name cards in the overlay (see lower_third.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowerThirdSettings {
+    // How long a card stays up unless given its own duration, 0 until hidden
+    pub duration_secs: u32,
+    // Colour of the bar beside the name
+    pub accent_color: String,
+}
+
+impl Default for LowerThirdSettings {
+    fn default() -> Self {
+        Self {
+            duration_secs: 8,
+            accent_color: "#3b82f6".to_string(),
+        }
+    }
+}
+
+// Logo in the overlay (see watermark.rs); placed by the layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkSettings {
+    pub enabled: bool,
+    // File name of the imported image in the config directory, empty for none
+    pub image: String,
+    // Height of the logo in pixels
+    pub size_px: u32,
+    pub opacity: f32,
+}
+
+impl Default for WatermarkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image: String::new(),
+            size_px: 64,
+            opacity: 0.8,
+        }
+    }
+}
+
+// Style of one router channel over the overlay's font (see event_router.rs), e.g. a smaller
+// italic info line; unset fields keep the overlay's
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelStyle {
+    pub family: Option<String>,
+    pub size: Option<u32>,
+    pub weight: Option<u32>,
+    pub color: Option<String>,
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub italic: bool,
+}
+
+// Sharing the screen with other apps' caption windows (see arbitration.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArbitrationSettings {
+    // "off", "warn", "yield" or "share"
+    pub policy: String,
+    // Parts of other caption windows' titles or window classes, any case
+    pub windows: Vec<String>,
+    // Between the overlay and the other window when sharing the screen
+    pub gap_px: u32,
+}
+
+impl Default for ArbitrationSettings {
+    fn default() -> Self {
+        Self {
+            policy: "off".to_string(),
+            windows: vec![
+                // Windows 11 live captions
+                "LiveCaptionsDesktopWindow".to_string(),
+                "Live captions".to_string(),
+                // Zoom's and Teams' caption and transcript windows
+                "Closed Caption".to_string(),
+                "Live Transcript".to_string(),
+            ],
+            gap_px: 8,
+        }
+    }
+}
+
+// Periodic proof that the operator is at the caption station (see check_in.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckInSettings {
+    pub enabled: bool,
+    // Between prompts while a session is joined
+    pub interval_mins: u32,
+    // For answering a prompt before it counts as missed
+    pub grace_secs: u32,
+    // Gets a JSON POST for every missed check-in, empty for none
+    pub webhook_url: String,
+}
+
+impl Default for CheckInSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_mins: 15,
+            grace_secs: 120,
+            webhook_url: String::new(),
+        }
+    }
+}
+
+// Looking names up with a resolver of our own on networks that hijack DNS (see network.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsOverHttpsSettings {
+    pub enabled: bool,
+    // A resolver with the JSON API, named by address, e.g. "https://1.1.1.1/dns-query"
+    pub url: String,
+}
+
+impl Default for DnsOverHttpsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://1.1.1.1/dns-query".to_string(),
+        }
+    }
+}
+
+// Summaries of finished sessions from a language model (see summary.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummarySettings {
+    // Sends whole transcripts to `endpoint`, so off unless a deployment opts in
+    pub enabled: bool,
+    // Chat completions URL, e.g. "http://localhost:8080/v1/chat/completions"
+    pub endpoint: String,
+    pub model: String,
+    // Instructions sent before the transcript
+    pub prompt: String,
+}
+
+impl Default for SummarySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            model: String::new(),
+            prompt: "Summarize this meeting transcript in its own language: the main topics, \
+                     the decisions made and the action items with who takes them on. Use \
+                     Markdown."
+                .to_string(),
+        }
+    }
+}
+
+// Rolling keyword counts for a word cloud (see keywords.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeywordSettings {
+    pub enabled: bool,
+    // How far back words are counted
+    pub window_secs: u32,
+    // How often `keywords-update` is emitted
+    pub interval_secs: u32,
+    // How many of the most said words are sent
+    pub count: u32,
+    // Shorter words are left out
+    pub min_chars: u32,
+    // Left out on top of the built-in common words, e.g. the event's own name
+    pub stop_words: Vec<String>,
+}
+
+impl Default for KeywordSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 300,
+            interval_secs: 10,
+            count: 30,
+            min_chars: 4,
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+// Markers dropped during a session (see bookmarks.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkSettings {
+    // Global shortcut adding a bookmark without a note
+    pub shortcut: String,
+}
+
+impl Default for BookmarkSettings {
+    fn default() -> Self {
+        Self {
+            shortcut: "Ctrl+Alt+Shift+B".to_string(),
+        }
+    }
+}
+
+// Showing the last captions again on the overlay (see replay.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaySettings {
+    // How far back the shortcut replays
+    pub seconds: u32,
+    // How long the replay stays before the live caption comes back
+    pub show_secs: u32,
+    pub shortcut: String,
+    // Slow motion shows the last paragraph again a word at a time
+    #[serde(default = "default_slow_words_per_minute")]
+    pub slow_words_per_minute: u32,
+    #[serde(default = "default_slow_shortcut")]
+    pub slow_shortcut: String,
+}
+
+fn default_slow_words_per_minute() -> u32 {
+    60
+}
+
+fn default_slow_shortcut() -> String {
+    "Ctrl+Alt+Shift+S".to_string()
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            seconds: 20,
+            show_secs: 8,
+            shortcut: "Ctrl+Alt+Shift+R".to_string(),
+            slow_words_per_minute: default_slow_words_per_minute(),
+            slow_shortcut: default_slow_shortcut(),
+        }
+    }
+}
+
+// Speakers whose captions stay off the public display (see processors/speaker_mute.rs)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerMuteSettings {
+    // Names as the captions give them, e.g. "Interpreter"
+    pub muted: Vec<String>,
+}
+
+// A colour per speaker in the overlay and exports (see speaker_colors.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerColorSettings {
+    pub enabled: bool,
+    // "#RRGGBB", handed out in order of speaking
+    pub palette: Vec<String>,
+}
+
+impl Default for SpeakerColorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            palette: [
+                "#FFD166", "#4CC9F0", "#06D6A0", "#F78C6B", "#C77DFF", "#A7C957",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+        }
+    }
+}
+
+// Captions forwarded by the companion browser extension (see browser_bridge.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserBridgeSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Extensions allowed to start the native messaging host
+    pub chrome_extension_ids: Vec<String>,
+    pub firefox_extension_ids: Vec<String>,
+}
+
+impl Default for BrowserBridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27547,
+            chrome_extension_ids: Vec::new(),
+            firefox_extension_ids: Vec::new(),
+        }
+    }
+}
+
+// Sessions announced from calendar events (see calendar.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarSettings {
+    pub enabled: bool,
+    // An ICS feed: https or webcal URL, or a local file
+    pub ics_url: String,
+    // How early before the start the overlay comes up
+    pub lead_minutes: u32,
+    pub refresh_minutes: u32,
+}
+
+impl Default for CalendarSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ics_url: String::new(),
+            lead_minutes: 2,
+            refresh_minutes: 15,
+        }
+    }
+}
+
+// Showing captions when a meeting window appears (see meetings.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeetingDetectionSettings {
+    pub enabled: bool,
+    // Parts of meeting window titles, any case
+    pub window_titles: Vec<String>,
+    pub auto_join: bool,
+    // The session to join; empty for the last one joined
+    pub session_code: String,
+    pub hide_when_ended: bool,
+}
+
+impl Default for MeetingDetectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_titles: vec![
+                "Zoom Meeting".to_string(),
+                "Zoom Webinar".to_string(),
+                "Meeting in ".to_string(),
+                "Meeting with ".to_string(),
+            ],
+            auto_join: false,
+            session_code: String::new(),
+            hide_when_ended: true,
+        }
+    }
+}
+
+// The overlay attached to the focused application window (see focus_follow.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusFollowSettings {
+    pub enabled: bool,
+    // "inside" over the window's bottom edge or "below" it
+    pub placement: String,
+    pub offset_px: u32,
+}
+
+impl Default for FocusFollowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            placement: "inside".to_string(),
+            offset_px: 16,
+        }
+    }
+}
+
+// The overlay shrunk to a pill with the last few words (see compact.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactSettings {
+    pub words: u32,
+    // Size of the pill in logical pixels
+    pub width: u32,
+    pub height: u32,
+    // Global shortcut for going compact and back
+    pub shortcut: String,
+}
+
+impl Default for CompactSettings {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            width: 320,
+            height: 48,
+            shortcut: "Ctrl+Alt+Shift+C".to_string(),
+        }
+    }
+}
+
+// A screen corner that brings up the control strip (see hot_corner.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotCornerSettings {
+    pub enabled: bool,
+    // "topLeft", "topRight", "bottomLeft" or "bottomRight"
+    pub corner: String,
+    // How long the strip stays after the cursor has left it
+    pub hide_delay_ms: u64,
+}
+
+impl Default for HotCornerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            corner: "bottomRight".to_string(),
+            hide_delay_ms: 800,
+        }
+    }
+}
+
+// Battery warnings and power saving on laptops (see power.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerSettings {
+    pub enabled: bool,
+    pub warn_below_percent: u32,
+    pub save_power_on_battery: bool,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            warn_below_percent: 20,
+            save_power_on_battery: false,
+        }
+    }
+}
+
+// Light caption theme and when "auto" uses it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeSettings {
+    pub light_text_color: String,
+    pub light_background_color: String,
+    // Local times, "HH:MM"
+    pub day_starts: String,
+    pub night_starts: String,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            light_text_color: "#111827".to_string(),
+            light_background_color: "#f9fafb".to_string(),
+            day_starts: "07:00".to_string(),
+            night_starts: "19:00".to_string(),
+        }
+    }
+}
+
+// Caption contrast kept up against the screen behind the overlay (see contrast.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastSettings {
+    pub enabled: bool,
+    pub interval_secs: u32,
+    // WCAG contrast ratio between the caption text and what is seen behind it
+    pub min_ratio: f32,
+}
+
+impl Default for ContrastSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 2,
+            min_ratio: 4.5,
+        }
+    }
+}
+
+// Caption server probing and degradation warnings (see network_quality.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkQualitySettings {
+    pub enabled: bool,
+    pub interval_secs: u32,
+    pub max_latency_ms: u64,
+    pub max_jitter_ms: u64,
+    pub max_loss_percent: u32,
+}
+
+impl Default for NetworkQualitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 5,
+            max_latency_ms: 300,
+            max_jitter_ms: 100,
+            max_loss_percent: 20,
+        }
+    }
+}
+
+// Outputs fed by the event router (see event_router.rs)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSettings {
+    // Ids of outputs that are switched off
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    // Channel to frontend event name overrides, e.g. "captions" -> "caption-update"
+    #[serde(default)]
+    pub event_names: BTreeMap<String, String>,
+}
+
+// Per-caption language identification (see language_detection.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageDetectionSettings {
+    pub enabled: bool,
+    // ISO 639-1 codes of the languages spoken in the session; empty allows any language
+    #[serde(default)]
+    pub candidates: Vec<String>,
+    // Detections below this confidence (0.0 - 1.0) keep the previous language
+    pub min_confidence: f64,
+}
+
+impl Default for LanguageDetectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            candidates: Vec::new(),
+            min_confidence: 0.5,
+        }
+    }
+}
+
+// What happens to HTML or markdown the caption source embeds: "strip", "escape" or
+// "convert" (see processors/markup.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkupSettings {
+    pub policy: String,
+}
+
+impl Default for MarkupSettings {
+    fn default() -> Self {
+        Self {
+            policy: "strip".to_string(),
+        }
+    }
+}
+
+// Relay server for viewer instances (see relay.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for RelaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27544,
+        }
+    }
+}
+
+// Web page with the current caption, for Chromecasts and smart TVs (see caption_page.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionPageSettings {
+    pub port: u16,
+}
+
+impl Default for CaptionPageSettings {
+    fn default() -> Self {
+        Self { port: 27545 }
+    }
+}
+
+// Smart lights flashed as an operator alert (see light_alert.rs); the bridge user name is
+// in the secrets store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LightAlertSettings {
+    pub enabled: bool,
+    // Address of the Hue bridge on the LAN
+    pub bridge: String,
+    // Light ids on the bridge
+    pub lights: Vec<String>,
+    pub on_emergency: bool,
+    pub on_disconnect: bool,
+}
+
+impl Default for LightAlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bridge: String::new(),
+            lights: Vec::new(),
+            on_emergency: true,
+            on_disconnect: true,
+        }
+    }
+}
+
+// PIN for quitting and hiding the overlay (see exit_protection.rs); the PIN itself is in
+// the secrets store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitProtectionSettings {
+    pub enabled: bool,
+    // Only read, from settings files older than the secrets store, and moved there
+    #[serde(default, skip_serializing)]
+    pub pin: String,
+}
+
+// Signature checks on captions from the session (see signatures.rs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionSigningSettings {
+    // "off", "flag" or "block"
+    pub mode: String,
+    // Session code to base64 Ed25519 public key of its captioner
+    #[serde(default)]
+    pub public_keys: BTreeMap<String, String>,
+}
+
+impl Default for CaptionSigningSettings {
+    fn default() -> Self {
+        Self {
+            mode: "off".to_string(),
+            public_keys: BTreeMap::new(),
+        }
+    }
+}
+
+// Recent caption history (see history.rs)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionHistorySettings {
+    // Keep the history on disk so it survives a crash or restart
+    pub persist: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSettings {
+    pub overlay: OverlaySettings,
+    pub font: FontSettings,
+    pub connection: ConnectionSettings,
+    pub last_session_code: Option<String>,
+    // Caption colours: "dark" (the overlay's own), "light", "system" (following the OS dark
+    // mode) or "auto" (by time of day), see theme.rs
+    pub theme: String,
+    #[serde(default)]
+    pub tray_actions: TrayActions,
+    #[serde(default)]
+    pub peek: PeekSettings,
+    // "auto" follows the session language, otherwise a fixed language code for tray and menus
+    #[serde(default = "default_ui_language")]
+    pub ui_language: String,
+    #[serde(default)]
+    pub plugins: PluginSettings,
+    #[serde(default)]
+    pub scripting: ScriptSettings,
+    #[serde(default)]
+    pub terminology: TerminologySettings,
+    #[serde(default)]
+    pub casing: CasingSettings,
+    #[serde(default)]
+    pub numbers: NumberFormatSettings,
+    #[serde(default)]
+    pub line_breaks: LineBreakSettings,
+    #[serde(default)]
+    pub reading_speed: ReadingSpeedSettings,
+    #[serde(default)]
+    pub confidence: ConfidenceSettings,
+    #[serde(default)]
+    pub review: ReviewSettings,
+    #[serde(default)]
+    pub operator_overlay: OperatorOverlaySettings,
+    #[serde(default)]
+    pub macros: Vec<CaptionMacro>,
+    #[serde(default)]
+    pub remote_control: RemoteControlSettings,
+    #[serde(default)]
+    pub midi: MidiSettings,
+    #[serde(default)]
+    pub footswitch: FootswitchSettings,
+    #[serde(default)]
+    pub network_quality: NetworkQualitySettings,
+    #[serde(default)]
+    pub outputs: OutputSettings,
+    #[serde(default)]
+    pub caption_history: CaptionHistorySettings,
+    #[serde(default)]
+    pub language_detection: LanguageDetectionSettings,
+    #[serde(default)]
+    pub markup: MarkupSettings,
+    #[serde(default)]
+    pub caption_signing: CaptionSigningSettings,
+    #[serde(default)]
+    pub exit_protection: ExitProtectionSettings,
+    #[serde(default)]
+    pub relay: RelaySettings,
+    #[serde(default)]
+    pub caption_page: CaptionPageSettings,
+    #[serde(default)]
+    pub light_alert: LightAlertSettings,
+    #[serde(default)]
+    pub serial_display: SerialDisplaySettings,
+    #[serde(default)]
+    pub braille: BrailleSettings,
+    #[serde(default)]
+    pub zoom: CaptionServiceSettings,
+    #[serde(default)]
+    pub teams: CaptionServiceSettings,
+    #[serde(default)]
+    pub youtube: CaptionServiceSettings,
+    #[serde(default)]
+    pub hls_captions: HlsCaptionSettings,
+    #[serde(default)]
+    pub audio_monitor: AudioMonitorSettings,
+    #[serde(default)]
+    pub audio_uplink: AudioUplinkSettings,
+    #[serde(default)]
+    pub webrtc: WebrtcSettings,
+    #[serde(default)]
+    pub lan_session: LanSessionSettings,
+    #[serde(default)]
+    pub ble_broadcast: BleBroadcastSettings,
+    #[serde(default)]
+    pub remote_support: RemoteSupportSettings,
+    #[serde(default)]
+    pub export: ExportSettings,
+    #[serde(default)]
+    pub display_delay: DisplayDelaySettings,
+    #[serde(default)]
+    pub clock: ClockSettings,
+    #[serde(default)]
+    pub layout: LayoutSettings,
+    #[serde(default)]
+    pub lower_third: LowerThirdSettings,
+    #[serde(default)]
+    pub watermark: WatermarkSettings,
+    // Channel name to its style
+    #[serde(default)]
+    pub channel_styles: BTreeMap<String, ChannelStyle>,
+    #[serde(default)]
+    pub contrast: ContrastSettings,
+    #[serde(default)]
+    pub themes: ThemeSettings,
+    #[serde(default)]
+    pub power: PowerSettings,
+    // Keep the screen on and the system awake while captioning (see keep_awake.rs)
+    #[serde(default = "default_keep_awake")]
+    pub keep_awake: bool,
+    #[serde(default)]
+    pub hot_corner: HotCornerSettings,
+    #[serde(default)]
+    pub compact: CompactSettings,
+    #[serde(default)]
+    pub focus_follow: FocusFollowSettings,
+    #[serde(default)]
+    pub meeting_detection: MeetingDetectionSettings,
+    #[serde(default)]
+    pub calendar: CalendarSettings,
+    #[serde(default)]
+    pub browser_bridge: BrowserBridgeSettings,
+    #[serde(default)]
+    pub speaker_colors: SpeakerColorSettings,
+    #[serde(default)]
+    pub speaker_mute: SpeakerMuteSettings,
+    #[serde(default)]
+    pub replay: ReplaySettings,
+    #[serde(default)]
+    pub bookmarks: BookmarkSettings,
+    #[serde(default)]
+    pub keywords: KeywordSettings,
+    #[serde(default)]
+    pub summary: SummarySettings,
+    // Blocks every outbound integration but the caption server (see network.rs)
+    #[serde(default)]
+    pub offline_only: bool,
+    #[serde(default)]
+    pub dns_over_https: DnsOverHttpsSettings,
+    #[serde(default)]
+    pub check_in: CheckInSettings,
+    #[serde(default)]
+    pub arbitration: ArbitrationSettings,
+    // Window label to where it was last closed (see window_manager.rs)
+    #[serde(default)]
+    pub window_geometry: BTreeMap<String, WindowGeometry>,
+}
+
+fn default_keep_awake() -> bool {
+    true
+}
+
+fn default_ui_language() -> String {
+    "auto".to_string()
+}
+
+fn default_overlay_settings() -> OverlaySettings {
+    OverlaySettings {
+        enabled: false,
+        position: Position { x: 500, y: 600 },
+        size: Size {
+            width: 600,
+            height: 160,
+        },
+        position_preset: "bottom".to_string(),
+        opacity: 0.95,
+        click_through: false,
+        always_on_top: true,
+        display_mode: "lastOnly".to_string(),
+        background_color: "#000000".to_string(),
+        rotation: 0,
+    }
+}
+
+fn default_font_settings() -> FontSettings {
+    FontSettings {
+        family: "Inter, system-ui, sans-serif".to_string(),
+        size: 32,
+        weight: 500,
+        color: "#ffffff".to_string(),
+        align: "justify".to_string(),
+        line_height: 1.3,
+        direction: default_direction(),
+        auto_bidi: true,
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            overlay: default_overlay_settings(),
+            font: default_font_settings(),
+            connection: ConnectionSettings {
+                yjs_server_url: "wss://tekstiks.ee/kk".to_string(),
+                auto_connect: true,
+                transport: default_transport(),
+                fallback_server_urls: Vec::new(),
+                failover_after: default_failover_after(),
+            },
+            last_session_code: None,
+            theme: "dark".to_string(),
+            tray_actions: TrayActions::default(),
+            peek: PeekSettings::default(),
+            ui_language: default_ui_language(),
+            plugins: PluginSettings::default(),
+            scripting: ScriptSettings::default(),
+            terminology: TerminologySettings::default(),
+            casing: CasingSettings::default(),
+            numbers: NumberFormatSettings::default(),
+            line_breaks: LineBreakSettings::default(),
+            reading_speed: ReadingSpeedSettings::default(),
+            confidence: ConfidenceSettings::default(),
+            review: ReviewSettings::default(),
+            operator_overlay: OperatorOverlaySettings::default(),
+            macros: Vec::new(),
+            remote_control: RemoteControlSettings::default(),
+            midi: MidiSettings::default(),
+            footswitch: FootswitchSettings::default(),
+            network_quality: NetworkQualitySettings::default(),
+            outputs: OutputSettings::default(),
+            caption_history: CaptionHistorySettings::default(),
+            language_detection: LanguageDetectionSettings::default(),
+            markup: MarkupSettings::default(),
+            caption_signing: CaptionSigningSettings::default(),
+            exit_protection: ExitProtectionSettings::default(),
+            relay: RelaySettings::default(),
+            caption_page: CaptionPageSettings::default(),
+            light_alert: LightAlertSettings::default(),
+            serial_display: SerialDisplaySettings::default(),
+            braille: BrailleSettings::default(),
+            zoom: CaptionServiceSettings::default(),
+            teams: CaptionServiceSettings::default(),
+            youtube: CaptionServiceSettings::default(),
+            hls_captions: HlsCaptionSettings::default(),
+            audio_monitor: AudioMonitorSettings::default(),
+            audio_uplink: AudioUplinkSettings::default(),
+            webrtc: WebrtcSettings::default(),
+            lan_session: LanSessionSettings::default(),
+            ble_broadcast: BleBroadcastSettings::default(),
+            remote_support: RemoteSupportSettings::default(),
+            export: ExportSettings::default(),
+            display_delay: DisplayDelaySettings::default(),
+            clock: ClockSettings::default(),
+            layout: LayoutSettings::default(),
+            lower_third: LowerThirdSettings::default(),
+            watermark: WatermarkSettings::default(),
+            channel_styles: BTreeMap::new(),
+            contrast: ContrastSettings::default(),
+            themes: ThemeSettings::default(),
+            power: PowerSettings::default(),
+            keep_awake: true,
+            hot_corner: HotCornerSettings::default(),
+            compact: CompactSettings::default(),
+            focus_follow: FocusFollowSettings::default(),
+            meeting_detection: MeetingDetectionSettings::default(),
+            calendar: CalendarSettings::default(),
+            browser_bridge: BrowserBridgeSettings::default(),
+            speaker_colors: SpeakerColorSettings::default(),
+            speaker_mute: SpeakerMuteSettings::default(),
+            replay: ReplaySettings::default(),
+            bookmarks: BookmarkSettings::default(),
+            keywords: KeywordSettings::default(),
+            summary: SummarySettings::default(),
+            offline_only: false,
+            dns_over_https: DnsOverHttpsSettings::default(),
+            check_in: CheckInSettings::default(),
+            arbitration: ArbitrationSettings::default(),
+            window_geometry: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn get_config_dir() -> PathBuf {
+    if let Some(proj_dirs) = ProjectDirs::from("ee", "jutukuva", "overlay-captions") {
+        let config_dir = proj_dirs.config_dir();
+        fs::create_dir_all(config_dir).ok();
+        config_dir.to_path_buf()
+    } else {
+        PathBuf::from(".")
+    }
+}
+
+pub fn get_settings_path() -> PathBuf {
+    get_config_dir().join("settings.json")
+}
+
+// Bumped for every save so that a slow write can never overwrite a newer one
+static SAVE_VERSION: AtomicU64 = AtomicU64::new(0);
+// Version of the settings last written to disk; also serializes the writes
+static SAVED_VERSION: tokio::sync::Mutex<u64> = tokio::sync::Mutex::const_new(0);
+
+pub async fn load_settings() -> AppSettings {
+    let path = get_settings_path();
+    if let Ok(content) = tokio::fs::read_to_string(&path).await {
+        if let Ok(settings) = serde_json::from_str(&content) {
+            return settings;
+        }
+    }
+    AppSettings::default()
+}
+
+// Snapshots the settings right away so the caller can release the settings lock before
+// awaiting the write
+pub fn save_settings(
+    settings: &AppSettings,
+) -> impl Future<Output = Result<(), String>> + Send + 'static {
+    let version = SAVE_VERSION.fetch_add(1, Ordering::SeqCst) + 1;
+    let content = serde_json::to_string_pretty(settings).map_err(|e| e.to_string());
+    async move {
+        let content = content?;
+        let mut saved = SAVED_VERSION.lock().await;
+        if *saved > version {
+            return Ok(());
+        }
+        tokio::fs::write(get_settings_path(), content)
+            .await
+            .map_err(|e| e.to_string())?;
+        *saved = version;
+        Ok(())
+    }
 }
@@ -0,0 +1,68 @@
+// Coalesces settings writes. Changes only mark the settings dirty; a writer task flushes
+// them to disk after a short delay, so a burst of small changes (dragging the overlay,
+// stepping the font size) ends up as a single write. Whatever is pending is flushed on exit.
+
+use crate::{settings, viewer, AppHandle, AppState};
+use std::time::Duration;
+use tauri::Manager;
+
+const FLUSH_DELAY: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct SettingsWriter {
+    dirty: bool,
+    // A writer task is waiting to flush
+    scheduled: bool,
+}
+
+// Note that the in-memory settings changed and schedule a flush unless one is pending
+pub fn mark_dirty(app: &AppHandle) {
+    // The settings file belongs to the primary
+    if viewer::is_active(app) {
+        return;
+    }
+    {
+        let state = app.state::<AppState>();
+        let mut writer = match state.settings_writer.lock() {
+            Ok(w) => w,
+            Err(_) => return,
+        };
+        writer.dirty = true;
+        if writer.scheduled {
+            return;
+        }
+        writer.scheduled = true;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FLUSH_DELAY).await;
+        if let Err(e) = flush(&app).await {
+            log::error!("Failed to save settings: {}", e);
+        }
+    });
+}
+
+// Write the settings now if anything changed since the last write
+pub async fn flush(app: &AppHandle) -> Result<(), String> {
+    let save = {
+        let state = app.state::<AppState>();
+        let mut writer = state.settings_writer.lock().map_err(|e| e.to_string())?;
+        writer.scheduled = false;
+        if !writer.dirty {
+            return Ok(());
+        }
+        writer.dirty = false;
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings::save_settings(&settings)
+    };
+    // Cleared before the write so changes made during it are written next time; a failed
+    // write leaves them dirty for the next flush, at the latest on exit
+    let result = save.await;
+    if result.is_err() {
+        if let Ok(mut writer) = app.state::<AppState>().settings_writer.lock() {
+            writer.dirty = true;
+        }
+    }
+    result
+}
@@ -0,0 +1,214 @@
+// Signed captions. For official events the captioner signs every paragraph they write
+// with an Ed25519 key; the signature travels with the paragraph in the session document
+// as its `signature` attribute. With `caption_signing.mode` set, the main window passes the
+// paragraphs behind each caption along with it and we check them against the public key
+// configured for the session. A hijacked session code then can't put text on screen:
+//   "flag"  - unverified captions are shown, marked as such on the overlays
+//   "block" - unverified captions only reach the operator overlay
+//
+// The signed message is "<session code>\n<paragraph text>" (UTF-8), the text without the
+// speaker name, trimmed and with every run of whitespace made a single space, so a signed
+// paragraph can't be replayed into another session. Keys and signatures are base64.
+//
+// Captions from outside the session, such as the browser extension's (see
+// browser_bridge.rs), have no signatures to check; `unsigned` gives them the verdict for
+// unverified captions without touching what is logged about the session's own.
+
+use crate::{diagnostics, AppHandle, AppState};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use tauri::Manager;
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedParagraph {
+    pub text: String,
+    pub signature: Option<String>,
+}
+
+#[derive(Default)]
+pub struct SigningState {
+    // Whether the last caption failed verification; only changes are logged
+    rejecting: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Verdict {
+    // Signing is off or every paragraph carries a valid signature
+    Trusted,
+    Flagged,
+    Blocked,
+}
+
+fn decode_key(key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(key.trim())
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "Public key must be 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+fn verify_paragraph(key: &VerifyingKey, session_code: &str, paragraph: &SignedParagraph) -> bool {
+    let signature = match paragraph
+        .signature
+        .as_deref()
+        .and_then(|s| STANDARD.decode(s.trim()).ok())
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+    let text = paragraph
+        .text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let message = format!("{}\n{}", session_code, text);
+    key.verify(message.as_bytes(), &signature).is_ok()
+}
+
+// What happens to an unverified caption under `caption_signing.mode`
+fn rejected_verdict(mode: &str) -> Verdict {
+    match mode {
+        "flag" => Verdict::Flagged,
+        "block" => Verdict::Blocked,
+        _ => Verdict::Trusted,
+    }
+}
+
+// A caption that didn't come from the session, so has no signatures
+pub fn unsigned(app: &AppHandle) -> Verdict {
+    match app.state::<AppState>().settings.lock() {
+        Ok(s) => rejected_verdict(&s.caption_signing.mode),
+        Err(_) => Verdict::Blocked,
+    }
+}
+
+// Check the paragraphs a caption from the session was built from
+pub fn verify(app: &AppHandle, paragraphs: &[SignedParagraph]) -> Verdict {
+    let state = app.state::<AppState>();
+    let (mode, keys) = match state.settings.lock() {
+        Ok(s) => (
+            s.caption_signing.mode.clone(),
+            s.caption_signing.public_keys.clone(),
+        ),
+        Err(_) => return Verdict::Blocked,
+    };
+    let rejected = match rejected_verdict(&mode) {
+        Verdict::Trusted => return Verdict::Trusted,
+        rejected => rejected,
+    };
+
+    let session_code = state.session_code.lock().ok().and_then(|c| c.clone());
+    let result = match session_code.as_deref() {
+        None => Err("no active session".to_string()),
+        Some(_) if paragraphs.is_empty() => Err("caption has no signed paragraphs".to_string()),
+        Some(code) => match keys.get(code) {
+            None => Err(format!("no public key configured for session {}", code)),
+            Some(key) => decode_key(key).and_then(|key| {
+                match paragraphs.iter().find(|p| !verify_paragraph(&key, code, p)) {
+                    Some(p) => Err(format!(
+                        "bad or missing signature on \"{}\"",
+                        crate::text::truncate(&p.text, 30)
+                    )),
+                    None => Ok(()),
+                }
+            }),
+        },
+    };
+
+    let rejecting = result.is_err();
+    let changed = match state.signing.lock() {
+        Ok(mut signing) => std::mem::replace(&mut signing.rejecting, rejecting) != rejecting,
+        Err(_) => false,
+    };
+    if changed {
+        match &result {
+            Err(reason) => {
+                log::warn!("Unverified captions: {}", reason);
+                let _ = diagnostics::record(app, "unverified captions", Some(reason.clone()));
+            }
+            Ok(()) => {
+                log::info!("Caption signatures verified again");
+                let _ = diagnostics::record(app, "captions verified", None);
+            }
+        }
+    }
+
+    if rejecting {
+        rejected
+    } else {
+        Verdict::Trusted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const SESSION: &str = "ABC123";
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn signed(session_code: &str, text: &str) -> SignedParagraph {
+        let signature = signing_key().sign(format!("{}\n{}", session_code, text).as_bytes());
+        SignedParagraph {
+            text: text.to_string(),
+            signature: Some(STANDARD.encode(signature.to_bytes())),
+        }
+    }
+
+    fn verifying_key() -> VerifyingKey {
+        decode_key(&STANDARD.encode(signing_key().verifying_key().to_bytes())).unwrap()
+    }
+
+    #[test]
+    fn valid_signature_verifies() {
+        let paragraph = signed(SESSION, "Tere hommikust");
+        assert!(verify_paragraph(&verifying_key(), SESSION, &paragraph));
+    }
+
+    #[test]
+    fn whitespace_is_normalized_before_checking() {
+        let mut paragraph = signed(SESSION, "Tere hommikust");
+        paragraph.text = " Tere  \u{a0}hommikust\n".to_string();
+        assert!(verify_paragraph(&verifying_key(), SESSION, &paragraph));
+    }
+
+    #[test]
+    fn signature_from_another_session_fails() {
+        let paragraph = signed("XYZ789", "Tere hommikust");
+        assert!(!verify_paragraph(&verifying_key(), SESSION, &paragraph));
+    }
+
+    #[test]
+    fn changed_text_fails() {
+        let mut paragraph = signed(SESSION, "Tere hommikust");
+        paragraph.text = "Head aega".to_string();
+        assert!(!verify_paragraph(&verifying_key(), SESSION, &paragraph));
+    }
+
+    #[test]
+    fn missing_or_garbled_signature_fails() {
+        let mut paragraph = signed(SESSION, "Tere hommikust");
+        paragraph.signature = None;
+        assert!(!verify_paragraph(&verifying_key(), SESSION, &paragraph));
+        paragraph.signature = Some("not base64!".to_string());
+        assert!(!verify_paragraph(&verifying_key(), SESSION, &paragraph));
+    }
+
+    #[test]
+    fn key_must_be_32_bytes_of_base64() {
+        assert_eq!(
+            decode_key(&STANDARD.encode([1; 16])).err().as_deref(),
+            Some("Public key must be 32 bytes")
+        );
+        assert!(decode_key("not base64!").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+// A colour per speaker, so a panel discussion's participants can be told apart. Captions
+// name their speaker as "Name: " at the start of a paragraph (see export::split_speaker);
+// speakers get the colours of `speaker_colors.palette` in the order they first speak, and
+// keep them for the session. Caption payloads carry the colours of the speakers in them,
+// and Word and PDF exports colour the names the same way.
+
+use crate::export::split_speaker;
+use crate::{AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpeakerColor {
+    pub name: String,
+    // "#RRGGBB" from the palette
+    pub color: String,
+}
+
+#[derive(Default)]
+pub struct SpeakerColors {
+    // In the order they first spoke
+    names: Vec<String>,
+}
+
+impl SpeakerColors {
+    pub fn color<'a>(&mut self, name: &str, palette: &'a [String]) -> Option<&'a str> {
+        if palette.is_empty() {
+            return None;
+        }
+        let index = match self.names.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        };
+        // More speakers than colours share them
+        palette.get(index % palette.len()).map(String::as_str)
+    }
+}
+
+// "#RRGGBB" as red, green and blue
+pub fn rgb(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// The colours of the speakers named in a caption, empty when colour coding is off
+pub fn for_caption(app: &AppHandle, text: &str) -> Vec<SpeakerColor> {
+    let state = app.state::<AppState>();
+    let palette = match state.settings.lock() {
+        Ok(s) if s.speaker_colors.enabled => s.speaker_colors.palette.clone(),
+        _ => return Vec::new(),
+    };
+    let mut colors = match state.speaker_colors.lock() {
+        Ok(colors) => colors,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut speakers: Vec<SpeakerColor> = Vec::new();
+    for line in text.lines() {
+        let name = match split_speaker(line) {
+            (Some(name), _) => name,
+            (None, _) => continue,
+        };
+        if speakers.iter().any(|s| s.name == name) {
+            continue;
+        }
+        if let Some(color) = colors.color(name, &palette) {
+            speakers.push(SpeakerColor {
+                name: name.to_string(),
+                color: color.to_string(),
+            });
+        }
+    }
+    speakers
+}
+
+// A new session hands the colours out afresh
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut colors) = app.state::<AppState>().speaker_colors.lock() {
+        *colors = SpeakerColors::default();
+    }
+}
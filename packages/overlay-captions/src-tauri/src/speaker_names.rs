@@ -0,0 +1,108 @@
+// Names for the speakers of a session. Diarization labels captions "Speaker 1: ", which
+// means nothing to the audience, so the operator maps each label to a name ("Speaker 1" →
+// "Moderator Mari"). Captions get the names as they come in, before anything else sees the
+// speaker (see export::split_speaker), and exports rename captions from before a label was
+// mapped. The mapping belongs to the session: it is kept with the archived session and a
+// new session starts without one.
+
+use crate::export::split_speaker;
+use crate::{AppHandle, AppState};
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpeakerName {
+    // As the captions name the speaker, e.g. "Speaker 1"
+    pub label: String,
+    pub name: String,
+}
+
+fn find<'a>(names: &'a [SpeakerName], label: &str) -> Option<&'a SpeakerName> {
+    names
+        .iter()
+        .find(|n| n.label.to_lowercase() == label.to_lowercase())
+}
+
+// `text` with the speaker labels starting its lines replaced by their names
+pub fn rename(text: &str, names: &[SpeakerName]) -> String {
+    if names.is_empty() {
+        return text.to_string();
+    }
+    text.split('\n')
+        .map(|line| match split_speaker(line) {
+            (Some(label), rest) => match find(names, label) {
+                Some(mapped) => format!("{}: {}", mapped.name, rest),
+                None => line.to_string(),
+            },
+            (None, _) => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A caption with the names of the active session
+pub fn apply(app: &AppHandle, text: &str) -> String {
+    match app.state::<AppState>().speaker_names.lock() {
+        Ok(names) => rename(text, &names),
+        Err(_) => text.to_string(),
+    }
+}
+
+pub fn list(app: &AppHandle) -> Result<Vec<SpeakerName>, String> {
+    let state = app.state::<AppState>();
+    let names = state.speaker_names.lock().map_err(|e| e.to_string())?;
+    Ok(names.clone())
+}
+
+// Store the mapping with the archived session and tell the windows
+fn changed(app: &AppHandle) -> Result<Vec<SpeakerName>, String> {
+    let names = list(app)?;
+    let state = app.state::<AppState>();
+    state
+        .storage
+        .lock()
+        .map_err(|e| e.to_string())?
+        .save_speaker_names(&names)?;
+    if let Err(e) = app.emit("speaker-names-changed", &names) {
+        log::error!("Failed to emit speaker names: {}", e);
+    }
+    Ok(names)
+}
+
+pub fn set(app: &AppHandle, label: &str, name: &str) -> Result<Vec<SpeakerName>, String> {
+    let (label, name) = (label.trim(), name.trim());
+    if label.is_empty() || name.is_empty() {
+        return Err("Both the speaker label and the name are needed".to_string());
+    }
+    if name.contains(['\n', ':']) {
+        return Err("A speaker's name cannot contain a colon".to_string());
+    }
+    {
+        let state = app.state::<AppState>();
+        let mut names = state.speaker_names.lock().map_err(|e| e.to_string())?;
+        names.retain(|n| n.label.to_lowercase() != label.to_lowercase());
+        names.push(SpeakerName {
+            label: label.to_string(),
+            name: name.to_string(),
+        });
+    }
+    log::info!("Speaker {} is named {}", label, name);
+    changed(app)
+}
+
+pub fn remove(app: &AppHandle, label: &str) -> Result<Vec<SpeakerName>, String> {
+    {
+        let state = app.state::<AppState>();
+        let mut names = state.speaker_names.lock().map_err(|e| e.to_string())?;
+        names.retain(|n| n.label.to_lowercase() != label.trim().to_lowercase());
+    }
+    changed(app)
+}
+
+// A new session starts without names
+pub fn reset(app: &AppHandle) {
+    if let Ok(mut names) = app.state::<AppState>().speaker_names.lock() {
+        names.clear();
+    }
+    let _ = app.emit("speaker-names-changed", Vec::<SpeakerName>::new());
+}
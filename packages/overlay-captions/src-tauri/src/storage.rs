@@ -0,0 +1,670 @@
+// Session archive in an SQLite database in the config directory. The main window reports
+// when a session is joined and left; while a session is active every caption delivered to
+// the overlay is stored with its timing. Like the caption history, a caption that only
+// extends the previous snapshot updates that row instead of adding one.
+
+use crate::bookmarks::Bookmark;
+use crate::check_in::CheckIn;
+use crate::clock::now_millis;
+use crate::event_router::{Output, RECORD};
+use crate::settings::{self, AppSettings};
+use crate::speaker_names::SpeakerName;
+use crate::{AppHandle, AppState};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::Manager;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY,
+        code TEXT NOT NULL,
+        server_url TEXT NOT NULL,
+        language TEXT,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS captions (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        text TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS captions_session ON captions(session_id);
+    CREATE TABLE IF NOT EXISTS speaker_names (
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        label TEXT NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (session_id, label)
+    );
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        at INTEGER NOT NULL,
+        note TEXT NOT NULL,
+        tag TEXT NOT NULL DEFAULT ''
+    );
+    CREATE TABLE IF NOT EXISTS check_ins (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        prompted_at INTEGER NOT NULL,
+        answered_at INTEGER
+    );
+";
+
+// Full-text index over caption text, kept in sync with the captions table by triggers
+const SEARCH_SCHEMA: &str = "
+    CREATE VIRTUAL TABLE captions_fts USING fts5(text, content='captions', content_rowid='id');
+    CREATE TRIGGER captions_fts_insert AFTER INSERT ON captions BEGIN
+        INSERT INTO captions_fts(rowid, text) VALUES (new.id, new.text);
+    END;
+    CREATE TRIGGER captions_fts_delete AFTER DELETE ON captions BEGIN
+        INSERT INTO captions_fts(captions_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    END;
+    CREATE TRIGGER captions_fts_update AFTER UPDATE OF text ON captions BEGIN
+        INSERT INTO captions_fts(captions_fts, rowid, text) VALUES ('delete', old.id, old.text);
+        INSERT INTO captions_fts(rowid, text) VALUES (new.id, new.text);
+    END;
+    INSERT INTO captions_fts(captions_fts) VALUES ('rebuild');
+";
+
+const MAX_SEARCH_HITS: usize = 100;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedSession {
+    pub id: i64,
+    pub code: String,
+    pub server_url: String,
+    pub language: Option<String>,
+    // Milliseconds since the Unix epoch
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub caption_count: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedCaption {
+    pub started_at: u64,
+    pub updated_at: u64,
+    pub text: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTranscript {
+    pub session: ArchivedSession,
+    pub captions: Vec<ArchivedCaption>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub session_id: i64,
+    pub session_code: String,
+    pub session_started_at: u64,
+    pub caption_started_at: u64,
+    // Matching part of the caption with the matched terms in <mark> tags
+    pub snippet: String,
+}
+
+// Milliseconds since the Unix epoch, both ends inclusive
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DateRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatistics {
+    pub session_id: i64,
+    pub code: String,
+    pub started_at: u64,
+    pub duration_secs: u64,
+    pub words: u64,
+    pub average_wpm: Option<f64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStatistics {
+    pub session_count: u64,
+    pub total_hours: f64,
+    pub total_words: u64,
+    pub average_wpm: Option<f64>,
+    // Words captioned in each local hour of the day, index 0 being midnight to 1 am
+    pub words_by_hour: Vec<u64>,
+    // Up to three hours of the day with the most words, busiest first
+    pub busiest_hours: Vec<u32>,
+    // Newest first
+    pub sessions: Vec<SessionStatistics>,
+}
+
+#[derive(Default)]
+pub struct Storage {
+    // None when the database could not be opened
+    conn: Option<Connection>,
+    session_id: Option<i64>,
+    // Row id and text of the newest caption of the active session
+    last_caption: Option<(i64, String)>,
+}
+
+fn words_per_minute(words: u64, duration_secs: u64) -> Option<f64> {
+    (duration_secs > 0).then(|| words as f64 * 60.0 / duration_secs as f64)
+}
+
+// Turn free text into an FTS5 query matching captions that contain every word, so quotes
+// or operators typed by the user cannot cause syntax errors
+fn match_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Storage {
+    // The session being archived, None between sessions
+    pub fn active_session(&self) -> Option<i64> {
+        self.session_id
+    }
+
+    fn conn(&self) -> Result<&Connection, String> {
+        self.conn
+            .as_ref()
+            .ok_or_else(|| "Session archive is not available".to_string())
+    }
+
+    fn begin_session(&mut self, code: &str, server_url: &str) -> Result<(), String> {
+        self.end_session()?;
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO sessions (code, server_url, started_at) VALUES (?1, ?2, ?3)",
+            params![code, server_url, now_millis()],
+        )
+        .map_err(|e| e.to_string())?;
+        self.session_id = Some(conn.last_insert_rowid());
+        Ok(())
+    }
+
+    fn end_session(&mut self) -> Result<(), String> {
+        self.last_caption = None;
+        let id = match self.session_id.take() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        self.conn()?
+            .execute(
+                "UPDATE sessions SET ended_at = ?1 WHERE id = ?2",
+                params![now_millis(), id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn set_language(&mut self, language: Option<&str>) -> Result<(), String> {
+        let id = match self.session_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        self.conn()?
+            .execute(
+                "UPDATE sessions SET language = ?1 WHERE id = ?2",
+                params![language, id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn add_caption(&mut self, text: &str) -> Result<(), String> {
+        let session_id = match self.session_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let now = now_millis();
+        let conn = self.conn()?;
+        let id = match &self.last_caption {
+            Some((id, last)) if text.starts_with(last.as_str()) => {
+                conn.execute(
+                    "UPDATE captions SET text = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![text, now, id],
+                )
+                .map_err(|e| e.to_string())?;
+                *id
+            }
+            _ => {
+                conn.execute(
+                    "INSERT INTO captions (session_id, started_at, updated_at, text)
+                     VALUES (?1, ?2, ?2, ?3)",
+                    params![session_id, now, text],
+                )
+                .map_err(|e| e.to_string())?;
+                conn.last_insert_rowid()
+            }
+        };
+        self.last_caption = Some((id, text.to_string()));
+        Ok(())
+    }
+
+    pub fn list_sessions(&self) -> Result<Vec<ArchivedSession>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.id, s.code, s.server_url, s.language, s.started_at, s.ended_at,
+                        (SELECT COUNT(*) FROM captions c WHERE c.session_id = s.id)
+                 FROM sessions s ORDER BY s.started_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], read_session)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    pub fn transcript(&self, id: i64) -> Result<SessionTranscript, String> {
+        let conn = self.conn()?;
+        let session = conn
+            .query_row(
+                "SELECT s.id, s.code, s.server_url, s.language, s.started_at, s.ended_at,
+                        (SELECT COUNT(*) FROM captions c WHERE c.session_id = s.id)
+                 FROM sessions s WHERE s.id = ?1",
+                [id],
+                read_session,
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Unknown session: {}", id))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT started_at, updated_at, text FROM captions
+                 WHERE session_id = ?1 ORDER BY started_at, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let captions = stmt
+            .query_map([id], |row| {
+                Ok(ArchivedCaption {
+                    started_at: row.get(0)?,
+                    updated_at: row.get(1)?,
+                    text: row.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+
+        Ok(SessionTranscript { session, captions })
+    }
+
+    // Replaces the speaker names of the active session
+    pub fn save_speaker_names(&self, names: &[SpeakerName]) -> Result<(), String> {
+        let id = match self.session_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM speaker_names WHERE session_id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+        for n in names {
+            conn.execute(
+                "INSERT INTO speaker_names (session_id, label, name) VALUES (?1, ?2, ?3)",
+                params![id, n.label, n.name],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn speaker_names(&self, id: i64) -> Result<Vec<SpeakerName>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare("SELECT label, name FROM speaker_names WHERE session_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let names: Vec<SpeakerName> = stmt
+            .query_map([id], |row| {
+                Ok(SpeakerName {
+                    label: row.get(0)?,
+                    name: row.get(1)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(names)
+    }
+
+    pub fn add_bookmark(&self, bookmark: &Bookmark) -> Result<(), String> {
+        let id = match self.session_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        self.conn()?
+            .execute(
+                "INSERT INTO bookmarks (session_id, at, note, tag) VALUES (?1, ?2, ?3, ?4)",
+                params![id, bookmark.at, bookmark.note, bookmark.tag],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn bookmarks(&self, id: i64) -> Result<Vec<Bookmark>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare("SELECT at, note, tag FROM bookmarks WHERE session_id = ?1 ORDER BY at")
+            .map_err(|e| e.to_string())?;
+        let bookmarks: Vec<Bookmark> = stmt
+            .query_map([id], |row| {
+                Ok(Bookmark {
+                    at: row.get(0)?,
+                    note: row.get(1)?,
+                    tag: row.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(bookmarks)
+    }
+
+    pub fn add_check_in(&self, check_in: &CheckIn) -> Result<(), String> {
+        let id = match self.session_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        self.conn()?
+            .execute(
+                "INSERT INTO check_ins (session_id, prompted_at, answered_at) VALUES (?1, ?2, ?3)",
+                params![id, check_in.prompted_at, check_in.answered_at],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn check_ins(&self, id: i64) -> Result<Vec<CheckIn>, String> {
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT prompted_at, answered_at FROM check_ins
+                 WHERE session_id = ?1 ORDER BY prompted_at",
+            )
+            .map_err(|e| e.to_string())?;
+        let check_ins: Vec<CheckIn> = stmt
+            .query_map([id], |row| {
+                Ok(CheckIn {
+                    prompted_at: row.get(0)?,
+                    answered_at: row.get(1)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        Ok(check_ins)
+    }
+
+    pub fn delete_session(&mut self, id: i64) -> Result<(), String> {
+        if self.session_id == Some(id) {
+            return Err("Cannot delete the session that is being captioned".to_string());
+        }
+        let deleted = self
+            .conn()?
+            .execute("DELETE FROM sessions WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+        if deleted == 0 {
+            return Err(format!("Unknown session: {}", id));
+        }
+        Ok(())
+    }
+
+    pub fn search(&self, query: &str, range: &DateRange) -> Result<Vec<SearchHit>, String> {
+        let query = match_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.id, s.code, s.started_at, c.started_at,
+                        snippet(captions_fts, 0, '<mark>', '</mark>', '…', 16)
+                 FROM captions_fts
+                 JOIN captions c ON c.id = captions_fts.rowid
+                 JOIN sessions s ON s.id = c.session_id
+                 WHERE captions_fts MATCH ?1
+                   AND (?2 IS NULL OR c.started_at >= ?2)
+                   AND (?3 IS NULL OR c.started_at <= ?3)
+                 ORDER BY rank
+                 LIMIT ?4",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![query, range.from, range.to, MAX_SEARCH_HITS],
+                |row| {
+                    Ok(SearchHit {
+                        session_id: row.get(0)?,
+                        session_code: row.get(1)?,
+                        session_started_at: row.get(2)?,
+                        caption_started_at: row.get(3)?,
+                        snippet: row.get(4)?,
+                    })
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    // Captions are counted as archived, i.e. the final snapshot of each caption
+    pub fn usage_statistics(&self) -> Result<UsageStatistics, String> {
+        let conn = self.conn()?;
+        let now = now_millis();
+
+        let mut sessions: Vec<SessionStatistics> = {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, code, started_at, ended_at FROM sessions ORDER BY started_at DESC",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    let started_at: u64 = row.get(2)?;
+                    // The active session has no end yet
+                    let ended_at: Option<u64> = row.get(3)?;
+                    Ok(SessionStatistics {
+                        session_id: row.get(0)?,
+                        code: row.get(1)?,
+                        started_at,
+                        duration_secs: ended_at.unwrap_or(now).saturating_sub(started_at) / 1000,
+                        words: 0,
+                        average_wpm: None,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+
+        let mut words_by_session: HashMap<i64, u64> = HashMap::new();
+        let mut words_by_hour = vec![0u64; 24];
+        {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT session_id, text,
+                            CAST(strftime('%H', started_at / 1000, 'unixepoch', 'localtime') AS INTEGER)
+                     FROM captions",
+                )
+                .map_err(|e| e.to_string())?;
+            let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+            while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+                let session_id: i64 = row.get(0).map_err(|e| e.to_string())?;
+                let text: String = row.get(1).map_err(|e| e.to_string())?;
+                let hour: usize = row.get(2).map_err(|e| e.to_string())?;
+                let words = text.split_whitespace().count() as u64;
+                *words_by_session.entry(session_id).or_default() += words;
+                if let Some(slot) = words_by_hour.get_mut(hour) {
+                    *slot += words;
+                }
+            }
+        }
+
+        for session in &mut sessions {
+            session.words = words_by_session
+                .get(&session.session_id)
+                .copied()
+                .unwrap_or_default();
+            session.average_wpm = words_per_minute(session.words, session.duration_secs);
+        }
+
+        let total_secs: u64 = sessions.iter().map(|s| s.duration_secs).sum();
+        let total_words: u64 = sessions.iter().map(|s| s.words).sum();
+        let mut busiest_hours: Vec<u32> = (0..24)
+            .filter(|&h| words_by_hour[h] > 0)
+            .map(|h| h as u32)
+            .collect();
+        busiest_hours.sort_by(|a, b| words_by_hour[*b as usize].cmp(&words_by_hour[*a as usize]));
+        busiest_hours.truncate(3);
+
+        Ok(UsageStatistics {
+            session_count: sessions.len() as u64,
+            total_hours: total_secs as f64 / 3600.0,
+            total_words,
+            average_wpm: words_per_minute(total_words, total_secs),
+            words_by_hour,
+            busiest_hours,
+            sessions,
+        })
+    }
+}
+
+fn read_session(row: &rusqlite::Row) -> rusqlite::Result<ArchivedSession> {
+    Ok(ArchivedSession {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        server_url: row.get(2)?,
+        language: row.get(3)?,
+        started_at: row.get(4)?,
+        ended_at: row.get(5)?,
+        caption_count: row.get(6)?,
+    })
+}
+
+fn open_database() -> Result<Connection, String> {
+    let path = settings::get_config_dir().join("archive.sqlite");
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .map_err(|e| e.to_string())?;
+    conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+
+    // Archives from before search was added get their index built here
+    let has_search_index = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'captions_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .is_some();
+    if !has_search_index {
+        conn.execute_batch(SEARCH_SCHEMA)
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Bookmarks from before they could be tagged are plain ones
+    if conn.prepare("SELECT tag FROM bookmarks").is_err() {
+        conn.execute(
+            "ALTER TABLE bookmarks ADD COLUMN tag TEXT NOT NULL DEFAULT ''",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // Sessions left open by a crash end with their last caption
+    conn.execute(
+        "UPDATE sessions SET ended_at = COALESCE(
+             (SELECT MAX(updated_at) FROM captions c WHERE c.session_id = sessions.id),
+             started_at)
+         WHERE ended_at IS NULL",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+pub fn open(app: &AppHandle) {
+    let conn = match open_database() {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::error!("Failed to open session archive: {}", e);
+            return;
+        }
+    };
+    if let Ok(mut storage) = app.state::<AppState>().storage.lock() {
+        storage.conn = Some(conn);
+    }
+}
+
+// Reported by the main window: Some when a session is joined, None when it is left
+pub fn set_active_session(
+    app: &AppHandle,
+    session: Option<(String, String)>,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let language = state
+        .session_language
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+    match session {
+        Some((code, server_url)) => {
+            storage.begin_session(&code, &server_url)?;
+            storage.set_language(language.as_deref())
+        }
+        None => storage.end_session(),
+    }
+}
+
+pub fn set_language(app: &AppHandle, language: Option<&str>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+    storage.set_language(language)
+}
+
+pub struct ArchiveOutput;
+
+impl Output for ArchiveOutput {
+    fn id(&self) -> &str {
+        "archive"
+    }
+
+    fn channels(&self) -> &[&'static str] {
+        &[RECORD]
+    }
+
+    fn deliver(
+        &mut self,
+        app: &AppHandle,
+        _settings: &AppSettings,
+        _channel: &str,
+        payload: &Value,
+    ) -> Result<(), String> {
+        let text = payload
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let state = app.state::<AppState>();
+        let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+        storage.add_caption(text)
+    }
+}
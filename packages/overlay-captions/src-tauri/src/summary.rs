@@ -0,0 +1,115 @@
+// Summaries of finished sessions from a language model. When a session ends and `summary`
+// is enabled, its transcript is exported as text to the documents folder (see export.rs)
+// and sent to `summary.endpoint`, an OpenAI-compatible chat completions API such as a
+// self-hosted llama.cpp or vLLM server. The answer is saved next to the transcript as
+// "<transcript>.summary.md". Off by default, as it sends everything said to the endpoint;
+// its API key, if it needs one, is kept in the secrets store (see secrets.rs).
+
+use crate::export::{self, Alignment, Format};
+use crate::settings::SummarySettings;
+use crate::{network, secrets, AppHandle, AppState};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+const API_KEY_SECRET: &str = "summary.apiKey";
+// Models take their time with a long meeting
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub fn has_api_key() -> bool {
+    secrets::get(API_KEY_SECRET).is_some()
+}
+
+// Store the API key, or forget it with None
+pub fn set_api_key(key: Option<&str>) -> Result<(), String> {
+    secrets::set(API_KEY_SECRET, key)
+}
+
+fn request(settings: &SummarySettings, transcript: &str) -> Result<String, String> {
+    let agent = network::agent(REQUEST_TIMEOUT)?;
+    let body = json!({
+        "model": settings.model,
+        "messages": [
+            { "role": "system", "content": settings.prompt },
+            { "role": "user", "content": transcript },
+        ],
+    });
+
+    let mut request = agent
+        .post(settings.endpoint.trim())
+        .set("Content-Type", "application/json");
+    if let Some(key) = secrets::get(API_KEY_SECRET) {
+        request = request.set("Authorization", &format!("Bearer {}", key));
+    }
+    let response = match request.send_string(&body.to_string()) {
+        Ok(response) => response.into_string().map_err(|e| e.to_string())?,
+        Err(ureq::Error::Status(code, _)) => return Err(format!("HTTP {}", code)),
+        Err(e) => return Err(e.to_string()),
+    };
+    let response: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    response
+        .pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .map(|summary| summary.trim().to_string())
+        .filter(|summary| !summary.is_empty())
+        .ok_or_else(|| "The endpoint answered without a summary".to_string())
+}
+
+// Export an archived session and save its summary next to it; returns the summary's path
+pub fn summarize(app: &AppHandle, session_id: i64) -> Result<PathBuf, String> {
+    let settings = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.summary.clone()
+    };
+    if settings.endpoint.trim().is_empty() {
+        return Err("No summary endpoint".to_string());
+    }
+    network::check(app, &settings.endpoint)?;
+
+    let transcript_path = export::save(
+        app,
+        Format::Txt,
+        None,
+        Some(session_id),
+        &Alignment::FirstCaption,
+    )?;
+    let transcript = fs::read_to_string(&transcript_path).map_err(|e| e.to_string())?;
+    let summary = request(&settings, &transcript)?;
+    let path = transcript_path.with_extension("summary.md");
+    fs::write(&path, summary).map_err(|e| e.to_string())?;
+    log::info!("Session summary saved to {}", path.display());
+    Ok(path)
+}
+
+// A session was left or replaced; summarizes it in the background if enabled
+pub fn session_ended(app: &AppHandle, session_id: i64) {
+    let enabled = match app.state::<AppState>().settings.lock() {
+        Ok(s) => s.summary.enabled,
+        Err(_) => return,
+    };
+    if !enabled {
+        return;
+    }
+    let caption_count = app
+        .state::<AppState>()
+        .storage
+        .lock()
+        .ok()
+        .and_then(|storage| storage.transcript(session_id).ok())
+        .map_or(0, |transcript| transcript.captions.len());
+    // Nothing to summarize in a session that was joined and left again
+    if caption_count == 0 {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || match summarize(&app, session_id) {
+        Ok(path) => {
+            let _ = app.emit("session-summary", path.to_string_lossy().to_string());
+        }
+        Err(e) => log::error!("Failed to summarize session {}: {}", session_id, e),
+    });
+}
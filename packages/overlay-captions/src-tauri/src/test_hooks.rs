@@ -0,0 +1,111 @@
+// Commands for automated end-to-end tests of the backend, only built with the test-hooks
+// feature (`cargo build --features test-hooks`). A test runner driving the app over IPC
+// (e.g. WebDriver through tauri-driver) gets deterministic results with them:
+//   test_freeze_clock(atMs)      stop the clock (see clock.rs), null to let it run again
+//   test_advance_clock(ms)       move a frozen clock on and release reviewed captions now due
+//   test_inject_caption(text, atMs) a trusted caption timestamped atMs, as from the session
+//   test_dump_state()            everything the backend holds that a test may assert on
+// `cargo test --features test-hooks` runs them on Tauri's mock runtime too (see the tests
+// below and AppRuntime in lib.rs).
+
+use crate::commands::submit_caption;
+use crate::signatures::Verdict;
+use crate::{
+    clock, diagnostics, kiosk, remote_support, review, training, viewer, AppHandle, AppState,
+};
+use serde_json::Value;
+use tauri::Manager;
+
+#[tauri::command]
+pub fn test_freeze_clock(at_ms: Option<u64>) {
+    clock::freeze(at_ms);
+}
+
+#[tauri::command]
+pub fn test_advance_clock(app: AppHandle, ms: u64) -> Result<(), String> {
+    if !clock::is_frozen() {
+        return Err("The clock is not frozen".to_string());
+    }
+    clock::freeze(Some(clock::now_millis() + ms));
+    review::release_due(&app)
+}
+
+// The clock stands at `at_ms` while the caption goes through and is put back as it was
+// after; freeze it first to keep timers set off by the caption from running
+#[tauri::command]
+pub fn test_inject_caption(app: AppHandle, text: String, at_ms: u64) -> Result<(), String> {
+    let previous = clock::is_frozen().then(clock::now_millis);
+    clock::freeze(Some(at_ms));
+    let result = submit_caption(&app, text, Verdict::Trusted);
+    clock::freeze(previous);
+    result
+}
+
+#[tauri::command]
+pub fn test_dump_state(app: AppHandle) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let overlay_visible = *state.overlay_visible.lock().map_err(|e| e.to_string())?;
+    let presentation_mode = *state.presentation_mode.lock().map_err(|e| e.to_string())?;
+    let connected = *state.connected.lock().map_err(|e| e.to_string())?;
+    let session_code = state
+        .session_code
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let session_language = state
+        .session_language
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let history = state
+        .caption_history
+        .lock()
+        .map_err(|e| e.to_string())?
+        .entries();
+
+    Ok(serde_json::json!({
+        "clock": {
+            "frozen": clock::is_frozen(),
+            "nowMs": clock::now_millis(),
+        },
+        "settings": settings,
+        "overlayVisible": overlay_visible,
+        "presentationMode": presentation_mode,
+        "connected": connected,
+        "sessionCode": session_code,
+        "sessionLanguage": session_language,
+        "captionHistory": history,
+        "reviewQueue": review::list(&app)?,
+        "diagnostics": diagnostics::snapshot(&app)?,
+        "kiosk": kiosk::status(&app),
+        "viewer": viewer::status(&app),
+        "training": training::status(&app)?,
+        "remoteSupport": remote_support::status(&app)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_app_state;
+    use crate::settings::AppSettings;
+
+    #[test]
+    fn injected_caption_carries_its_time_and_the_clock_runs_on() {
+        let app = tauri::test::mock_builder()
+            .manage(new_app_state(AppSettings::default()))
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .expect("mock app");
+        let handle = app.handle().clone();
+
+        test_inject_caption(handle.clone(), "Tere hommikust".to_string(), 1_000_000).unwrap();
+
+        let state = test_dump_state(handle).unwrap();
+        assert_eq!(state["clock"]["frozen"], false);
+        let history = state["captionHistory"].as_array().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0]["at"], 1_000_000);
+        assert_eq!(history[0]["text"], "Tere hommikust");
+    }
+}
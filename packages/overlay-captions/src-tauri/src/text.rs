@@ -0,0 +1,81 @@
+// Script-aware text helpers. Caption text can be in any script, so lengths are counted in
+// grapheme clusters (a letter with its combining marks is one character on screen) and
+// never in bytes or chars, and the reading direction comes from the Unicode bidi rules.
+
+use unicode_bidi::Direction;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+// At most `max` grapheme clusters from the start of `text`
+pub fn truncate(text: &str, max: usize) -> &str {
+    match text.grapheme_indices(true).nth(max) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+// "ltr" or "rtl" after the first strongly directional character, None if there is none
+pub fn base_direction(text: &str) -> Option<&'static str> {
+    match unicode_bidi::get_base_direction_full(text) {
+        Direction::Ltr => Some("ltr"),
+        Direction::Rtl => Some("rtl"),
+        Direction::Mixed => None,
+    }
+}
+
+// Caption text for outputs that can't show markup: the backend's tags dropped and escaped
+// characters turned back (see processors/markup.rs)
+pub fn plain(caption: &str) -> String {
+    let mut out = String::with_capacity(caption.len());
+    let mut in_tag = false;
+    for c in caption.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// Lines of at most `width` grapheme clusters, broken at spaces where a word fits
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word = word;
+            let mut word_len = grapheme_count(word);
+            if line_len > 0 && line_len + 1 + word_len <= width {
+                line.push(' ');
+                line.push_str(word);
+                line_len += 1 + word_len;
+                continue;
+            }
+            if line_len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Words longer than a line are cut
+            while word_len > width {
+                let head = truncate(word, width);
+                lines.push(head.to_string());
+                word = &word[head.len()..];
+                word_len -= width;
+            }
+            line.push_str(word);
+            line_len = word_len;
+        }
+        if line_len > 0 {
+            lines.push(line);
+        }
+    }
+    lines
+}
@@ -0,0 +1,160 @@
+// Light or dark captions to suit the room. `settings.theme` picks "dark" (the overlay's own
+// colours), "light" (`settings.themes`), "system", which follows the OS dark mode as the
+// windows report it, or "auto": light from `day_starts` until `night_starts`, local time.
+// The theme is decided here and sent to the overlays as `theme-changed` events, so they all
+// switch together.
+
+use crate::event_router::CAPTIONS;
+use crate::settings::AppSettings;
+use crate::{clock, AppHandle, AppState};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+#[derive(Default)]
+pub struct ThemeState {
+    // Bumped to stop the running time-of-day switcher
+    generation: u64,
+    // The OS is in dark mode; None until a window has said
+    os_dark: Option<bool>,
+    // Last theme sent to the overlays
+    current: Option<&'static str>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ThemePayload {
+    // "light" or "dark"
+    pub theme: &'static str,
+}
+
+// "HH:MM" as minutes after midnight
+fn parse_minutes(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn is_day(settings: &AppSettings, now: u32) -> bool {
+    match (
+        parse_minutes(&settings.themes.day_starts),
+        parse_minutes(&settings.themes.night_starts),
+    ) {
+        (Some(day), Some(night)) if day <= night => now >= day && now < night,
+        // Day across midnight, for night shifts
+        (Some(day), Some(night)) => now >= day || now < night,
+        _ => false,
+    }
+}
+
+fn resolve(settings: &AppSettings, os_dark: Option<bool>) -> &'static str {
+    let light = match settings.theme.as_str() {
+        "light" => true,
+        "system" => os_dark == Some(false),
+        "auto" => is_day(settings, clock::local_minute_of_day()),
+        _ => false,
+    };
+    if light {
+        "light"
+    } else {
+        "dark"
+    }
+}
+
+pub fn current(app: &AppHandle) -> Result<&'static str, String> {
+    let state = app.state::<AppState>();
+    let os_dark = state.theme.lock().map_err(|e| e.to_string())?.os_dark;
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(resolve(&settings, os_dark))
+}
+
+// Text and background colour of the captions in `theme`
+pub fn caption_colors<'a>(settings: &'a AppSettings, theme: &str) -> (&'a str, &'a str) {
+    if theme == "light" {
+        return (
+            &settings.themes.light_text_color,
+            &settings.themes.light_background_color,
+        );
+    }
+    let style = settings.channel_styles.get(CAPTIONS);
+    (
+        style
+            .and_then(|s| s.color.as_deref())
+            .unwrap_or(&settings.font.color),
+        style
+            .and_then(|s| s.background_color.as_deref())
+            .unwrap_or(&settings.overlay.background_color),
+    )
+}
+
+// Tell the overlays when the theme has changed
+fn update(app: &AppHandle) -> Result<(), String> {
+    let theme = current(app)?;
+    {
+        let state = app.state::<AppState>();
+        let mut state = state.theme.lock().map_err(|e| e.to_string())?;
+        if state.current == Some(theme) {
+            return Ok(());
+        }
+        state.current = Some(theme);
+    }
+    log::info!("Caption theme: {}", theme);
+    app.emit("theme-changed", &ThemePayload { theme })
+        .map_err(|e| e.to_string())
+}
+
+fn is_current(app: &AppHandle, generation: u64) -> bool {
+    app.state::<AppState>()
+        .theme
+        .lock()
+        .map(|s| s.generation == generation)
+        .unwrap_or(false)
+}
+
+// Apply the theme in settings; "auto" keeps checking the time every minute
+pub fn restart(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let auto = match state.settings.lock() {
+        Ok(s) => s.theme == "auto",
+        Err(_) => return,
+    };
+    let generation = match state.theme.lock() {
+        Ok(mut s) => {
+            s.generation += 1;
+            s.generation
+        }
+        Err(_) => return,
+    };
+
+    if let Err(e) = update(app) {
+        log::error!("Failed to apply caption theme: {}", e);
+    }
+    if !auto {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        // Check on the minute, when the switch times fall
+        let millis = clock::now_millis();
+        std::thread::sleep(Duration::from_millis(60_000 - millis % 60_000));
+        if !is_current(&app, generation) {
+            return;
+        }
+        if let Err(e) = update(&app) {
+            log::error!("Failed to apply caption theme: {}", e);
+        }
+    });
+}
+
+// A window saw the OS switch between light and dark mode
+pub fn os_theme_changed(app: &AppHandle, dark: bool) {
+    if let Ok(mut state) = app.state::<AppState>().theme.lock() {
+        if state.os_dark == Some(dark) {
+            return;
+        }
+        state.os_dark = Some(dark);
+    }
+    if let Err(e) = update(app) {
+        log::error!("Failed to apply caption theme: {}", e);
+    }
+}
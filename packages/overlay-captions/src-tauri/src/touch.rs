@@ -0,0 +1,56 @@
+// Touch gestures on the overlays, for kiosk touchscreens without a mouse: two fingers move
+// the window, a pinch resizes it and a long press opens the context menu. The overlay page
+// recognises the gestures and reports them here; a click-through window takes no touches,
+// so none are accepted from one.
+
+use crate::window_manager::{self, ManagedWindow};
+use crate::{context_menu, drag_region, kiosk, AppHandle, WebviewWindow};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TouchGesture {
+    // Since the last report, in CSS pixels
+    Move { dx: f64, dy: f64 },
+    // Finger spread now over the spread at the last pinch reported
+    Pinch { scale: f64 },
+    LongPress,
+}
+
+pub fn handle(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    gesture: TouchGesture,
+) -> Result<(), String> {
+    let managed = match ManagedWindow::from_label(window.label()) {
+        Some(managed @ ManagedWindow::Overlay(_)) => managed,
+        _ => return Err("Touch gestures are for the overlays".to_string()),
+    };
+    if drag_region::is_click_through(app, window.label()) {
+        return Err("The overlay is click-through".to_string());
+    }
+
+    match gesture {
+        TouchGesture::Move { dx, dy } => {
+            let scale = window.scale_factor().map_err(|e| e.to_string())?;
+            window_manager::move_overlay_by(
+                app,
+                managed,
+                (dx * scale).round() as i32,
+                (dy * scale).round() as i32,
+            )
+        }
+        TouchGesture::Pinch { scale } => {
+            if !scale.is_finite() || scale <= 0.0 {
+                return Ok(());
+            }
+            window_manager::scale_overlay(app, managed, scale)
+        }
+        // The context menu acts on the public overlay
+        TouchGesture::LongPress if managed == ManagedWindow::OVERLAY => {
+            kiosk::check(app)?;
+            context_menu::popup_overlay_context_menu(app)
+        }
+        TouchGesture::LongPress => Ok(()),
+    }
+}
@@ -0,0 +1,139 @@
+// Training mode for rehearsing failure procedures with operators before real events. Started
+// with the --training command line flag; only then do the simulation commands work:
+//   simulate_disconnect - the main window drops the session connection for a while
+//   simulate_latency    - captions reach the overlay this much later, 0 to stop
+//   simulate_overlay_crash - the overlay window disappears without closing properly
+// Each simulation is recorded in the diagnostics window's events, as a real failure would
+// leave traces there too.
+
+use crate::commands::submit_caption;
+use crate::signatures::Verdict;
+use crate::window_manager::ManagedWindow;
+use crate::{diagnostics, AppHandle, AppState};
+use serde::Serialize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// Error returned by the simulation commands outside training mode
+pub const TRAINING_MODE_OFF: &str = "TrainingModeOff";
+
+const FLAG: &str = "--training";
+const DEFAULT_DISCONNECT_SECS: u64 = 10;
+
+struct DelayedCaption {
+    due: Instant,
+    text: String,
+    verdict: Verdict,
+}
+
+#[derive(Default)]
+pub struct TrainingState {
+    enabled: bool,
+    latency: Duration,
+    // Captions waiting out the simulated latency, in order
+    delayed: Option<Sender<DelayedCaption>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingStatus {
+    pub enabled: bool,
+    pub latency_ms: u64,
+}
+
+pub fn load() -> TrainingState {
+    let enabled = std::env::args().any(|arg| arg == FLAG);
+    if enabled {
+        log::info!("Training mode, failure simulations available");
+    }
+    TrainingState {
+        enabled,
+        ..TrainingState::default()
+    }
+}
+
+pub fn status(app: &AppHandle) -> Result<TrainingStatus, String> {
+    let state = app.state::<AppState>();
+    let training = state.training.lock().map_err(|e| e.to_string())?;
+    Ok(TrainingStatus {
+        enabled: training.enabled,
+        latency_ms: training.latency.as_millis() as u64,
+    })
+}
+
+fn check(app: &AppHandle) -> Result<(), String> {
+    if status(app)?.enabled {
+        Ok(())
+    } else {
+        Err(TRAINING_MODE_OFF.to_string())
+    }
+}
+
+pub fn simulate_disconnect(app: &AppHandle, seconds: Option<u64>) -> Result<(), String> {
+    check(app)?;
+    let seconds = seconds.unwrap_or(DEFAULT_DISCONNECT_SECS).max(1);
+    diagnostics::record(
+        app,
+        "training",
+        Some(format!("Simulated disconnect for {} s", seconds)),
+    )?;
+    ManagedWindow::Main.emit(app, "simulate-disconnect", seconds)
+}
+
+pub fn simulate_latency(app: &AppHandle, ms: u64) -> Result<(), String> {
+    check(app)?;
+    {
+        let state = app.state::<AppState>();
+        let mut training = state.training.lock().map_err(|e| e.to_string())?;
+        training.latency = Duration::from_millis(ms);
+        if ms > 0 && training.delayed.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            training.delayed = Some(sender);
+            let app = app.clone();
+            std::thread::spawn(move || deliver_delayed(&app, receiver));
+        }
+    }
+    diagnostics::record(
+        app,
+        "training",
+        Some(format!("Simulated latency of {} ms", ms)),
+    )
+}
+
+// Hide the overlay the way a crashed webview would: no close request, no state update
+pub fn simulate_overlay_crash(app: &AppHandle) -> Result<(), String> {
+    check(app)?;
+    diagnostics::record(app, "training", Some("Simulated overlay crash".to_string()))?;
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
+        window.destroy().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Hold a caption back for the simulated latency; false when there is none to simulate
+pub fn delay(app: &AppHandle, text: &str, verdict: Verdict) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    let training = state.training.lock().map_err(|e| e.to_string())?;
+    let sender = match &training.delayed {
+        Some(sender) if !training.latency.is_zero() => sender,
+        _ => return Ok(false),
+    };
+    sender
+        .send(DelayedCaption {
+            due: Instant::now() + training.latency,
+            text: text.to_string(),
+            verdict,
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+fn deliver_delayed(app: &AppHandle, captions: Receiver<DelayedCaption>) {
+    for caption in captions {
+        std::thread::sleep(caption.due.saturating_duration_since(Instant::now()));
+        if let Err(e) = submit_caption(app, caption.text, caption.verdict) {
+            log::error!("Failed to deliver delayed caption: {}", e);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::check_in;
+use crate::i18n::{self, tr};
+use crate::kiosk;
+use crate::presentation;
+use crate::settings::TrayActions;
+use crate::AppState;
+use crate::{AppHandle, AppRuntime};
+use std::time::{Duration, Instant};
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuItem},
+    Manager,
+};
+
+pub const TRAY_ID: &str = "main";
+// Left clicks wait this long for a second click when a double click has an action of its own;
+// Windows' default double-click time
+const DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct TrayClicks {
+    // Bumped by every click, so a left click waiting for a double click knows it was one
+    generation: u64,
+    // The button is released once more after a double click, which isn't a click of its own
+    double_clicked_at: Option<Instant>,
+}
+
+// Build the system tray menu in the current UI language
+pub fn build_menu(app: &AppHandle) -> tauri::Result<Menu<AppRuntime>> {
+    let lang = i18n::current_language(app);
+
+    let show_main_item = MenuItem::with_id(
+        app,
+        "show_main",
+        tr(&lang, "tray.show_main"),
+        true,
+        None::<&str>,
+    )?;
+    let show_overlay_item = MenuItem::with_id(
+        app,
+        "show_overlay",
+        tr(&lang, "tray.show_overlay"),
+        true,
+        None::<&str>,
+    )?;
+    let presentation_item = CheckMenuItem::with_id(
+        app,
+        "presentation_mode",
+        tr(&lang, "tray.presentation_mode"),
+        true,
+        presentation::is_active(app),
+        None::<&str>,
+    )?;
+    let quit_item = MenuItem::with_id(app, "quit", tr(&lang, "tray.quit"), true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &show_main_item,
+            &show_overlay_item,
+            &presentation_item,
+        ],
+    )?;
+
+    // Operator tools only show up in the menu once they are enabled in settings
+    let (review_enabled, operator_overlay_enabled) = app
+        .try_state::<AppState>()
+        .and_then(|state| {
+            state
+                .settings
+                .lock()
+                .ok()
+                .map(|s| (s.review.enabled, s.operator_overlay.enabled))
+        })
+        .unwrap_or_default();
+    if operator_overlay_enabled {
+        let operator_item = MenuItem::with_id(
+            app,
+            "operator_overlay",
+            tr(&lang, "tray.operator_overlay"),
+            true,
+            None::<&str>,
+        )?;
+        menu.append(&operator_item)?;
+    }
+    if review_enabled {
+        let review_item =
+            MenuItem::with_id(app, "review", tr(&lang, "tray.review"), true, None::<&str>)?;
+        menu.append(&review_item)?;
+    }
+
+    let transcript_item = MenuItem::with_id(
+        app,
+        "transcript",
+        tr(&lang, "tray.transcript"),
+        true,
+        None::<&str>,
+    )?;
+    menu.append(&transcript_item)?;
+
+    let diagnostics_item = MenuItem::with_id(
+        app,
+        "diagnostics",
+        tr(&lang, "tray.diagnostics"),
+        true,
+        None::<&str>,
+    )?;
+    menu.append(&diagnostics_item)?;
+
+    // Answers the check-in prompt while one waits (see check_in.rs)
+    if check_in::is_pending(app) {
+        let check_in_item = MenuItem::with_id(
+            app,
+            "check_in",
+            tr(&lang, "tray.check_in"),
+            true,
+            None::<&str>,
+        )?;
+        menu.insert(&check_in_item, 0)?;
+    }
+
+    // A locked kiosk can't be quit
+    if !kiosk::is_locked(app) {
+        menu.append(&quit_item)?;
+    }
+    Ok(menu)
+}
+
+// Rebuild the tray menu and tooltip, e.g. after the UI language or presentation mode changed
+pub fn refresh(app: &AppHandle) {
+    let tray = match app.tray_by_id(TRAY_ID) {
+        Some(t) => t,
+        None => return,
+    };
+
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log::error!("Failed to rebuild tray menu: {}", e),
+    }
+
+    let lang = i18n::current_language(app);
+    let _ = tray.set_tooltip(Some(tr(&lang, "tray.tooltip")));
+}
+
+// A left click on the tray icon runs its action once no double click follows. Only Windows
+// reports double clicks, so elsewhere it runs right away.
+pub fn left_click(app: &AppHandle, actions: &TrayActions) {
+    if !cfg!(windows) || actions.double_click == "none" {
+        crate::run_tray_action(app, &actions.left_click);
+        return;
+    }
+    let generation = match app.state::<AppState>().tray_clicks.lock() {
+        Ok(mut clicks) => {
+            let released = clicks
+                .double_clicked_at
+                .take()
+                .is_some_and(|at| at.elapsed() < DOUBLE_CLICK_WINDOW);
+            if released {
+                return;
+            }
+            clicks.generation += 1;
+            clicks.generation
+        }
+        Err(_) => return,
+    };
+
+    let app = app.clone();
+    let action = actions.left_click.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(DOUBLE_CLICK_WINDOW);
+        let single = app
+            .state::<AppState>()
+            .tray_clicks
+            .lock()
+            .map(|c| c.generation == generation)
+            .unwrap_or(false);
+        if single {
+            crate::run_tray_action(&app, &action);
+        }
+    });
+}
+
+// A double click cancels the left click it started with
+pub fn double_click(app: &AppHandle, actions: &TrayActions) {
+    if let Ok(mut clicks) = app.state::<AppState>().tray_clicks.lock() {
+        clicks.generation += 1;
+        clicks.double_clicked_at = Some(Instant::now());
+    }
+    crate::run_tray_action(app, &actions.double_click);
+}
@@ -0,0 +1,198 @@
+// Viewer mode: an extra, lightweight instance that only shows the overlay, with captions
+// and their styling coming from a primary instance's relay server (see relay.rs). Started
+// with
+//   --viewer                 the primary on this machine, at the relay port in its settings
+//   --viewer=<host>[:<port>] a primary elsewhere on the LAN
+//   --viewer=auto            the first primary found on the LAN (see discovery.rs)
+// A viewer never joins a session and never writes the settings file, which it shares with
+// the primary when both run on the same machine.
+
+use crate::event_router::{self, CAPTIONS};
+use crate::relay::{RelayMessage, KEEPALIVE_INTERVAL};
+use crate::{diagnostics, discovery, AppHandle, AppState};
+use serde::Serialize;
+use std::net::TcpStream;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+const FLAG: &str = "--viewer";
+const AUTO: &str = "auto";
+
+// Wait between attempts to reach the primary
+const RETRY_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+pub struct ViewerState {
+    // host:port of the primary's relay, None for a normal instance
+    primary: Option<String>,
+    connected: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerStatus {
+    pub primary: Option<String>,
+    pub connected: bool,
+}
+
+// Primary address from the command line, None when not started as a viewer
+pub fn load(relay_port: u16) -> ViewerState {
+    let primary = std::env::args().find_map(|arg| {
+        if arg == FLAG {
+            return Some(format!("127.0.0.1:{}", relay_port));
+        }
+        let address = arg.strip_prefix(FLAG)?.strip_prefix('=')?;
+        if address == AUTO || address.contains(':') {
+            Some(address.to_string())
+        } else {
+            Some(format!("{}:{}", address, relay_port))
+        }
+    });
+    if let Some(primary) = &primary {
+        log::info!("Viewer mode, primary at {}", primary);
+    }
+    ViewerState {
+        primary,
+        connected: false,
+    }
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    status(app).primary.is_some()
+}
+
+pub fn status(app: &AppHandle) -> ViewerStatus {
+    app.try_state::<AppState>()
+        .and_then(|state| {
+            state.viewer.lock().ok().map(|v| ViewerStatus {
+                primary: v.primary.clone(),
+                connected: v.connected,
+            })
+        })
+        .unwrap_or(ViewerStatus {
+            primary: None,
+            connected: false,
+        })
+}
+
+fn set_connected(app: &AppHandle, connected: bool) {
+    let changed = match app.state::<AppState>().viewer.lock() {
+        Ok(mut v) => std::mem::replace(&mut v.connected, connected) != connected,
+        Err(_) => false,
+    };
+    if changed {
+        let _ = diagnostics::record(
+            app,
+            if connected {
+                "connected to primary"
+            } else {
+                "disconnected from primary"
+            },
+            status(app).primary,
+        );
+        let _ = app.emit("viewer-status-changed", status(app));
+    }
+}
+
+// Keep a connection to the primary for as long as the app runs
+pub fn start(app: &AppHandle) {
+    let primary = match status(app).primary {
+        Some(primary) => primary,
+        None => return,
+    };
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let address = if primary == AUTO {
+            find_primary(&app)
+        } else {
+            Some(primary.clone())
+        };
+        let address = match address {
+            Some(address) => address,
+            None => {
+                log::debug!("No primary found on the LAN");
+                std::thread::sleep(RETRY_INTERVAL);
+                continue;
+            }
+        };
+        match connect(&address) {
+            Ok(socket) => {
+                log::info!("Connected to primary at {}", address);
+                set_connected(&app, true);
+                if let Err(e) = receive(&app, socket) {
+                    log::warn!("Lost connection to primary at {}: {}", address, e);
+                }
+                set_connected(&app, false);
+            }
+            Err(e) => log::debug!("Primary at {} not reachable: {}", address, e),
+        }
+        std::thread::sleep(RETRY_INTERVAL);
+    });
+}
+
+fn find_primary(app: &AppHandle) -> Option<String> {
+    let relays = discovery::discover(app, discovery::BROWSE_TIMEOUT)
+        .map_err(|e| log::warn!("Relay discovery failed: {}", e))
+        .ok()?;
+    let relay = relays.into_iter().next()?;
+    let address = relay.addresses.first()?;
+    // IPv6 addresses need brackets next to a port
+    Some(if address.contains(':') {
+        format!("[{}]:{}", address, relay.port)
+    } else {
+        format!("{}:{}", address, relay.port)
+    })
+}
+
+fn connect(primary: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+    let (socket, _) =
+        tungstenite::connect(format!("ws://{}", primary)).map_err(|e| e.to_string())?;
+    // Nothing at all, not even the relay's pings, means the primary is gone
+    if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+        stream
+            .set_read_timeout(Some(KEEPALIVE_INTERVAL * 3))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(socket)
+}
+
+fn receive(
+    app: &AppHandle,
+    mut socket: WebSocket<MaybeTlsStream<TcpStream>>,
+) -> Result<(), String> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<RelayMessage>(&text) {
+                Ok(message) => handle_message(app, message)?,
+                Err(e) => log::warn!("Invalid message from primary {}: {}", text, e),
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+fn handle_message(app: &AppHandle, message: RelayMessage) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    match message {
+        // Applied in memory only, like everything else a viewer does with its settings
+        RelayMessage::Settings { settings: shared } => {
+            let updated = {
+                let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+                shared.apply(&mut settings);
+                settings.clone()
+            };
+            app.emit("settings-changed", &updated)
+                .map_err(|e| e.to_string())
+        }
+        // Already processed by the primary, so straight to the overlay
+        RelayMessage::Caption { caption } => {
+            let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+            app.emit(event_router::event_name(&settings, CAPTIONS), caption)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Logo in a corner of the overlay, such as the accessibility service sponsor's that some
+// venues require next to the captions. The chosen image is checked and copied into the
+// config directory, so it survives the original being moved, and handed to the overlay as
+// a data URL. Each import gets a new file name, which tells the overlay to reload it.
+
+use crate::commands::apply_settings_change;
+use crate::{clock, settings, AppHandle, AppState};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+// Plenty for a logo, and little enough to send to the overlay as a data URL
+const MAX_BYTES: u64 = 2 * 1024 * 1024;
+// Imported images are named watermark-<millis>.<extension>
+const FILE_PREFIX: &str = "watermark-";
+
+// MIME type and extension from the first bytes of the file
+fn image_type(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(("image/png", "png"))
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some(("image/jpeg", "jpg"))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(("image/gif", "gif"))
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some(("image/webp", "webp"))
+    } else if String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).contains("<svg") {
+        Some(("image/svg+xml", "svg"))
+    } else {
+        None
+    }
+}
+
+fn image_path(file_name: &str) -> PathBuf {
+    settings::get_config_dir().join(file_name)
+}
+
+// Remove imported images other than `keep`
+fn remove_others(keep: Option<&str>) {
+    let entries = match fs::read_dir(settings::get_config_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(FILE_PREFIX) && Some(name.as_str()) != keep {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                log::warn!("Failed to remove old watermark {}: {}", name, e);
+            }
+        }
+    }
+}
+
+// Copy the image at `source` into the config directory and show it
+pub fn import(app: &AppHandle, source: &Path) -> Result<(), String> {
+    let size = fs::metadata(source).map_err(|e| e.to_string())?.len();
+    if size > MAX_BYTES {
+        return Err(format!(
+            "The image is {} KB, at most {} KB is allowed",
+            size / 1024,
+            MAX_BYTES / 1024
+        ));
+    }
+    let bytes = fs::read(source).map_err(|e| e.to_string())?;
+    let (_, extension) = match image_type(&bytes) {
+        Some(image_type) => image_type,
+        None => return Err("Not a PNG, JPEG, GIF, WebP or SVG image".to_string()),
+    };
+
+    let file_name = format!("{}{}.{}", FILE_PREFIX, clock::now_millis(), extension);
+    fs::write(image_path(&file_name), &bytes).map_err(|e| e.to_string())?;
+    remove_others(Some(&file_name));
+    log::info!("Imported watermark {} as {}", source.display(), file_name);
+
+    apply_settings_change(app, |settings| {
+        settings.watermark.image = file_name;
+        settings.watermark.enabled = true;
+    })?;
+    Ok(())
+}
+
+pub fn remove(app: &AppHandle) -> Result<(), String> {
+    remove_others(None);
+    apply_settings_change(app, |settings| {
+        settings.watermark.image.clear();
+        settings.watermark.enabled = false;
+    })?;
+    Ok(())
+}
+
+// The imported image as a data URL, None when there is none
+pub fn data_url(app: &AppHandle) -> Result<Option<String>, String> {
+    let file_name = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.watermark.image.clone()
+    };
+    if file_name.is_empty() {
+        return Ok(None);
+    }
+    // Only ever one of our imports, whatever the settings file says
+    if !file_name.starts_with(FILE_PREFIX) || file_name.contains(['/', '\\']) {
+        return Err(format!("{} is not an imported watermark", file_name));
+    }
+    let bytes = fs::read(image_path(&file_name)).map_err(|e| e.to_string())?;
+    let mime = match image_type(&bytes) {
+        Some((mime, _)) => mime,
+        None => return Err(format!("{} is not an image", file_name)),
+    };
+    Ok(Some(format!(
+        "data:{};base64,{}",
+        mime,
+        STANDARD.encode(&bytes)
+    )))
+}
@@ -1,27 +1,338 @@
-use crate::settings::OverlaySettings;
-use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use crate::commands::apply_settings_change;
+use crate::compositor;
+use crate::drag_region;
+use crate::settings::{OverlaySettings, WindowGeometry};
+use crate::AppHandle;
+use crate::AppState;
+use crate::WebviewWindow;
+use serde::Serialize;
+use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 
-pub fn create_overlay_window(app: &AppHandle, settings: &OverlaySettings) -> Result<(), String> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayId {
+    // The captions the audience sees
+    Public,
+    // Unreviewed captions with confidence, styled from `settings.operator_overlay`
+    Operator,
+}
+
+// Every window the app manages. Code refers to windows through this instead of label strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagedWindow {
+    Main,
+    Overlay(OverlayId),
+    Review,
+    // The caption history as it comes in, for reading back
+    Transcript,
+    Diagnostics,
+    // Pause, hide and font size buttons brought up by the hot corner
+    ControlStrip,
+}
+
+// Title and size of the ordinary decorated tool windows
+struct ToolWindowSpec {
+    title: &'static str,
+    size: (f64, f64),
+    min_size: (f64, f64),
+}
+
+impl ManagedWindow {
+    pub const OVERLAY: ManagedWindow = ManagedWindow::Overlay(OverlayId::Public);
+    pub const OPERATOR_OVERLAY: ManagedWindow = ManagedWindow::Overlay(OverlayId::Operator);
+
+    pub const ALL: [ManagedWindow; 7] = [
+        ManagedWindow::Main,
+        ManagedWindow::Overlay(OverlayId::Public),
+        ManagedWindow::Overlay(OverlayId::Operator),
+        ManagedWindow::Review,
+        ManagedWindow::Transcript,
+        ManagedWindow::Diagnostics,
+        ManagedWindow::ControlStrip,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ManagedWindow::Main => "main",
+            ManagedWindow::Overlay(OverlayId::Public) => "overlay",
+            ManagedWindow::Overlay(OverlayId::Operator) => "operator-overlay",
+            ManagedWindow::Review => "review",
+            ManagedWindow::Transcript => "transcript",
+            ManagedWindow::Diagnostics => "diagnostics",
+            ManagedWindow::ControlStrip => "control-strip",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<ManagedWindow> {
+        Self::ALL.into_iter().find(|w| w.label() == label)
+    }
+
+    fn url(self) -> &'static str {
+        match self {
+            ManagedWindow::Main => "/",
+            ManagedWindow::Overlay(OverlayId::Public) => "/overlay",
+            ManagedWindow::Overlay(OverlayId::Operator) => "/overlay?role=operator",
+            ManagedWindow::Review => "/review",
+            ManagedWindow::Transcript => "/transcript",
+            ManagedWindow::Diagnostics => "/diagnostics",
+            ManagedWindow::ControlStrip => "/control-strip",
+        }
+    }
+
+    fn tool_spec(self) -> Option<ToolWindowSpec> {
+        match self {
+            ManagedWindow::Review => Some(ToolWindowSpec {
+                title: "Review",
+                size: (520.0, 640.0),
+                min_size: (360.0, 320.0),
+            }),
+            ManagedWindow::Transcript => Some(ToolWindowSpec {
+                title: "Transcript",
+                size: (480.0, 600.0),
+                min_size: (320.0, 240.0),
+            }),
+            ManagedWindow::Diagnostics => Some(ToolWindowSpec {
+                title: "Diagnostics",
+                size: (480.0, 560.0),
+                min_size: (360.0, 320.0),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get(self, app: &AppHandle) -> Option<WebviewWindow> {
+        app.get_webview_window(self.label())
+    }
+
+    pub fn is_open(self, app: &AppHandle) -> bool {
+        self.get(app).is_some()
+    }
+
+    pub fn show(self, app: &AppHandle) -> Result<(), String> {
+        if let Some(window) = self.get(app) {
+            window.show().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn focus(self, app: &AppHandle) -> Result<(), String> {
+        if let Some(window) = self.get(app) {
+            window.show().map_err(|e| e.to_string())?;
+            window.set_focus().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn hide(self, app: &AppHandle) -> Result<(), String> {
+        if let Some(window) = self.get(app) {
+            window.hide().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    // Without a CloseRequested event, which is for closes by hand (see lib.rs); the app's own
+    // callers have checked the kiosk lock and exit PIN where they need to
+    pub fn close(self, app: &AppHandle) -> Result<(), String> {
+        if let Some(window) = self.get(app) {
+            if let Err(e) = self.remember_geometry(app) {
+                log::warn!(
+                    "Failed to save where the {} window was: {}",
+                    self.label(),
+                    e
+                );
+            }
+            window.destroy().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    // Send an event to this window only; nothing happens when it is not open
+    pub fn emit<S: Serialize + Clone>(
+        self,
+        app: &AppHandle,
+        event: &str,
+        payload: S,
+    ) -> Result<(), String> {
+        if !self.is_open(app) {
+            return Ok(());
+        }
+        app.emit_to(self.label(), event, payload)
+            .map_err(|e| e.to_string())
+    }
+
+    // Where a tool window was last closed
+    fn saved_geometry(self, app: &AppHandle) -> Option<WindowGeometry> {
+        let state = app.try_state::<AppState>()?;
+        let settings = state.settings.lock().ok()?;
+        settings.window_geometry.get(self.label()).cloned()
+    }
+
+    // Keep where an open tool window is, in logical pixels, for the next time it opens
+    pub fn remember_geometry(self, app: &AppHandle) -> Result<(), String> {
+        if self.tool_spec().is_none() {
+            return Ok(());
+        }
+        let window = match self.get(app) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let scale = window.scale_factor().map_err(|e| e.to_string())?;
+        let position = window
+            .outer_position()
+            .map_err(|e| e.to_string())?
+            .to_logical::<f64>(scale);
+        let size = window
+            .inner_size()
+            .map_err(|e| e.to_string())?
+            .to_logical::<f64>(scale);
+        let geometry = WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        };
+        if self.saved_geometry(app).as_ref() == Some(&geometry) {
+            return Ok(());
+        }
+        apply_settings_change(app, |settings| {
+            settings
+                .window_geometry
+                .insert(self.label().to_string(), geometry);
+        })?;
+        Ok(())
+    }
+
+    // Open (or bring up) a tool window such as Review or Diagnostics, where it was last
+    // closed. Overlays are created with their settings through `create_overlay_window` instead.
+    pub fn open(self, app: &AppHandle) -> Result<(), String> {
+        let spec = self
+            .tool_spec()
+            .ok_or_else(|| format!("{} is not a tool window", self.label()))?;
+
+        if self.is_open(app) {
+            return self.focus(app);
+        }
+
+        let builder =
+            WebviewWindowBuilder::new(app, self.label(), WebviewUrl::App(self.url().into()))
+                .title(spec.title)
+                .min_inner_size(spec.min_size.0, spec.min_size.1)
+                .resizable(true)
+                .visible(true);
+        let saved = self.saved_geometry(app);
+        let builder = match &saved {
+            Some(geometry) => builder
+                .inner_size(geometry.width, geometry.height)
+                .position(geometry.x, geometry.y),
+            None => builder.inner_size(spec.size.0, spec.size.1),
+        };
+        builder.build().map_err(|e| e.to_string())?;
+        // The monitor it was on may be gone
+        if saved.is_some() {
+            keep_on_screen(app, self)?;
+        }
+
+        log::info!("{} window created", spec.title);
+        Ok(())
+    }
+
+    // Open a tool window, or close it when it is already open; returns whether it is open now
+    pub fn toggle(self, app: &AppHandle) -> Result<bool, String> {
+        if self.is_open(app) {
+            self.close(app)?;
+            return Ok(false);
+        }
+        self.open(app)?;
+        Ok(true)
+    }
+}
+
+// Overlay rotations in degrees clockwise; anything else is taken as 0
+const ROTATIONS: [u32; 3] = [0, 90, 270];
+// Less of an overlay than this on any monitor counts as lost
+const MIN_VISIBLE_PX: i32 = 50;
+// Where a lost overlay goes, above the bottom of the screen like the settings' reset
+const BOTTOM_OFFSET_PX: i32 = 100;
+// Logical size of the control strip window
+pub const CONTROL_STRIP_SIZE: (f64, f64) = (208.0, 44.0);
+// Smallest overlay, as the resize handle allows; a turned overlay is the other way round
+const MIN_OVERLAY_SIZE: (u32, u32) = (400, 60);
+
+pub fn normalize_rotation(degrees: u32) -> u32 {
+    if ROTATIONS.contains(&degrees) {
+        degrees
+    } else {
+        0
+    }
+}
+
+fn is_quarter_turn(degrees: u32) -> bool {
+    normalize_rotation(degrees) % 180 == 90
+}
+
+// The overlay page lays the captions out at `settings.size` and turns them, so a quarter
+// turn swaps the width and height of the window around them
+fn window_size(settings: &OverlaySettings) -> (f64, f64) {
+    let (width, height) = (settings.size.width as f64, settings.size.height as f64);
+    if is_quarter_turn(settings.rotation) {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+// Turn the open public overlay from `from` to `to` degrees, swapping its width and height
+// when it goes between landscape and portrait
+pub fn rotate_overlay(app: &AppHandle, from: u32, to: u32) -> Result<(), String> {
+    if is_quarter_turn(from) == is_quarter_turn(to) {
+        return Ok(());
+    }
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
+        let size = window.inner_size().map_err(|e| e.to_string())?;
+        window
+            .set_size(tauri::Size::Physical(tauri::PhysicalSize {
+                width: size.height,
+                height: size.width,
+            }))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn create_overlay_window(
+    app: &AppHandle,
+    id: OverlayId,
+    settings: &OverlaySettings,
+) -> Result<(), String> {
+    let window = ManagedWindow::Overlay(id);
+    let label = window.label();
     // Check if overlay window already exists
-    if app.get_webview_window("overlay").is_some() {
-        log::info!("Overlay window already exists, skipping creation");
+    if window.is_open(app) {
+        log::info!("Overlay window {} already exists, skipping creation", label);
         return Ok(());
     }
 
-    let overlay_url = WebviewUrl::App("/overlay".into());
+    let overlay_url = WebviewUrl::App(window.url().into());
+    let (width, height) = window_size(settings);
+    // An opaque window rather than a black rectangle where nothing composites
+    let transparent = compositor::check(app);
 
-    log::info!("Creating overlay window with settings: position=({}, {}), size=({}, {}), always_on_top={}",
-        settings.position.x, settings.position.y,
-        settings.size.width, settings.size.height,
-        settings.always_on_top);
+    log::info!(
+        "Creating overlay window with settings: position=({}, {}), size=({}, {}), rotation={}, always_on_top={}",
+        settings.position.x,
+        settings.position.y,
+        width,
+        height,
+        settings.rotation,
+        settings.always_on_top
+    );
 
     #[cfg(target_os = "macos")]
-    let builder = WebviewWindowBuilder::new(app, "overlay", overlay_url)
+    let builder = WebviewWindowBuilder::new(app, label, overlay_url)
         .title("Captions")
-        .inner_size(settings.size.width as f64, settings.size.height as f64)
+        .inner_size(width, height)
         .position(settings.position.x as f64, settings.position.y as f64)
         .decorations(false)
-        .transparent(true)
+        .transparent(transparent)
         .always_on_top(true)
         .skip_taskbar(true)
         .resizable(true)
@@ -31,12 +342,12 @@ pub fn create_overlay_window(app: &AppHandle, settings: &OverlaySettings) -> Res
     // Windows: With async window creation (separate thread), we can now use transparency.
     // shadow(false) is required for WebView2 transparency to work.
     #[cfg(target_os = "windows")]
-    let builder = WebviewWindowBuilder::new(app, "overlay", overlay_url)
+    let builder = WebviewWindowBuilder::new(app, label, overlay_url)
         .title("Captions")
-        .inner_size(settings.size.width as f64, settings.size.height as f64)
+        .inner_size(width, height)
         .position(settings.position.x as f64, settings.position.y as f64)
         .decorations(false)
-        .transparent(true)
+        .transparent(transparent)
         .shadow(false)
         .always_on_top(true)
         .skip_taskbar(true)
@@ -44,12 +355,12 @@ pub fn create_overlay_window(app: &AppHandle, settings: &OverlaySettings) -> Res
         .visible(true);
 
     #[cfg(target_os = "linux")]
-    let builder = WebviewWindowBuilder::new(app, "overlay", overlay_url)
+    let builder = WebviewWindowBuilder::new(app, label, overlay_url)
         .title("Captions")
-        .inner_size(settings.size.width as f64, settings.size.height as f64)
+        .inner_size(width, height)
         .position(settings.position.x as f64, settings.position.y as f64)
         .decorations(false)
-        .transparent(true)
+        .transparent(transparent)
         .always_on_top(true)
         .skip_taskbar(true)
         .resizable(true)
@@ -58,61 +369,158 @@ pub fn create_overlay_window(app: &AppHandle, settings: &OverlaySettings) -> Res
     let window = builder.build().map_err(|e| e.to_string())?;
 
     // Explicitly set always on top after window creation (helps on some Linux WMs)
-    window
-        .set_always_on_top(true)
-        .map_err(|e| e.to_string())?;
+    window.set_always_on_top(true).map_err(|e| e.to_string())?;
 
-    log::info!("Overlay window created successfully");
+    log::info!("Overlay window {} created successfully", label);
 
     // Apply click-through if enabled
     if settings.click_through {
-        set_ignore_cursor_events(app, true)?;
+        window
+            .set_ignore_cursor_events(true)
+            .map_err(|e| e.to_string())?;
     }
+    drag_region::set_click_through(app, ManagedWindow::Overlay(id), settings.click_through);
 
     Ok(())
 }
 
-pub fn close_overlay_window(app: &AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
-        window.close().map_err(|e| e.to_string())?;
+pub fn set_ignore_cursor_events(app: &AppHandle, ignore: bool) -> Result<(), String> {
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
+        window
+            .set_ignore_cursor_events(ignore)
+            .map_err(|e| e.to_string())?;
     }
+    drag_region::set_click_through(app, ManagedWindow::OVERLAY, ignore);
     Ok(())
 }
 
-pub fn show_overlay_window(app: &AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
-        window.show().map_err(|e| e.to_string())?;
+pub fn set_overlay_position(app: &AppHandle, x: i32, y: i32) -> Result<(), String> {
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
+        window
+            .set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
-pub fn hide_overlay_window(app: &AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
-        window.hide().map_err(|e| e.to_string())?;
+// Move `window` back to the bottom of the primary monitor if no monitor shows enough of it,
+// e.g. after the one it was on went away during sleep; returns where it went
+pub fn keep_on_screen(
+    app: &AppHandle,
+    window: ManagedWindow,
+) -> Result<Option<(i32, i32)>, String> {
+    let window = match window.get(app) {
+        Some(window) => window,
+        None => return Ok(None),
+    };
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let (width, height) = (size.width as i32, size.height as i32);
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+
+    let on_screen = monitors.iter().any(|monitor| {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        let overlap_x =
+            (position.x + width).min(origin.x + extent.width as i32) - position.x.max(origin.x);
+        let overlap_y =
+            (position.y + height).min(origin.y + extent.height as i32) - position.y.max(origin.y);
+        overlap_x >= MIN_VISIBLE_PX && overlap_y >= MIN_VISIBLE_PX
+    });
+    if on_screen {
+        return Ok(None);
     }
-    Ok(())
+    let monitor = match window.primary_monitor().map_err(|e| e.to_string())? {
+        Some(monitor) => monitor,
+        None => match monitors.into_iter().next() {
+            Some(monitor) => monitor,
+            None => return Ok(None),
+        },
+    };
+
+    let (origin, extent) = (monitor.position(), monitor.size());
+    let x = origin.x + (extent.width as i32 - width) / 2;
+    let y = origin.y + extent.height as i32 - height - BOTTOM_OFFSET_PX;
+    window
+        .set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+        .map_err(|e| e.to_string())?;
+    Ok(Some((x, y)))
 }
 
-pub fn set_ignore_cursor_events(app: &AppHandle, ignore: bool) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
-        window
-            .set_ignore_cursor_events(ignore)
-            .map_err(|e| e.to_string())?;
+// The control strip, created hidden at `(x, y)` logical pixels if it isn't open yet
+pub fn create_control_strip(app: &AppHandle, x: f64, y: f64) -> Result<WebviewWindow, String> {
+    let window = ManagedWindow::ControlStrip;
+    if let Some(existing) = window.get(app) {
+        return Ok(existing);
     }
-    Ok(())
+    WebviewWindowBuilder::new(app, window.label(), WebviewUrl::App(window.url().into()))
+        .title("Controls")
+        .inner_size(CONTROL_STRIP_SIZE.0, CONTROL_STRIP_SIZE.1)
+        .position(x, y)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .visible(false)
+        .build()
+        .map_err(|e| e.to_string())
 }
 
-pub fn set_overlay_position(app: &AppHandle, x: i32, y: i32) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
+// Move an overlay by (dx, dy) physical pixels
+pub fn move_overlay_by(
+    app: &AppHandle,
+    window: ManagedWindow,
+    dx: i32,
+    dy: i32,
+) -> Result<(), String> {
+    if let Some(window) = window.get(app) {
+        let position = window.outer_position().map_err(|e| e.to_string())?;
         window
-            .set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }))
+            .set_position(tauri::Position::Physical(tauri::PhysicalPosition {
+                x: position.x + dx,
+                y: position.y + dy,
+            }))
             .map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
+// Grow or shrink an overlay by `factor` about its centre, within its monitor
+pub fn scale_overlay(app: &AppHandle, window: ManagedWindow, factor: f64) -> Result<(), String> {
+    let window = match window.get(app) {
+        Some(window) => window,
+        None => return Ok(()),
+    };
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let (min_width, min_height) = if size.height > size.width {
+        (MIN_OVERLAY_SIZE.1, MIN_OVERLAY_SIZE.0)
+    } else {
+        MIN_OVERLAY_SIZE
+    };
+    let (max_width, max_height) = match window.current_monitor().map_err(|e| e.to_string())? {
+        Some(monitor) => (monitor.size().width, monitor.size().height),
+        None => (u32::MAX, u32::MAX),
+    };
+    let scaled = |length: u32, min: u32, max: u32| {
+        ((f64::from(length) * factor).round() as u32).clamp(min, max.max(min))
+    };
+    let width = scaled(size.width, min_width, max_width);
+    let height = scaled(size.height, min_height, max_height);
+
+    window
+        .set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(tauri::Position::Physical(tauri::PhysicalPosition {
+            x: position.x + (size.width as i32 - width as i32) / 2,
+            y: position.y + (size.height as i32 - height as i32) / 2,
+        }))
+        .map_err(|e| e.to_string())
+}
+
 pub fn set_overlay_size(app: &AppHandle, width: u32, height: u32) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("overlay") {
+    if let Some(window) = ManagedWindow::OVERLAY.get(app) {
         window
             .set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }))
             .map_err(|e| e.to_string())?;
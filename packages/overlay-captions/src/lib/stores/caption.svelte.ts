@@ -13,7 +13,13 @@ const trimToMaxChars = (text: string, maxChars: number): string => {
 
 	const slice = trimmed.slice(-maxChars);
 	const firstWhitespaceIndex = slice.search(/\s/);
-	const safeSlice = firstWhitespaceIndex > 0 ? slice.slice(firstWhitespaceIndex) : slice;
+	let safeSlice = firstWhitespaceIndex > 0 ? slice.slice(firstWhitespaceIndex) : slice;
+	// Don't start inside a confidence-scored word's markup
+	const closeIndex = safeSlice.indexOf('</conf>');
+	const openIndex = safeSlice.indexOf('<conf');
+	if (closeIndex >= 0 && (openIndex < 0 || closeIndex < openIndex)) {
+		safeSlice = safeSlice.slice(closeIndex + '</conf>'.length);
+	}
 	return `${TRIM_PREFIX}${safeSlice.trimStart()}`;
 };
 
@@ -28,9 +34,13 @@ class CaptionStore {
 	private updateHandler: ((update: Uint8Array) => void) | null = null;
 
 	private emitToOverlay(text: string) {
+		// The paragraphs the text was built from, for the backend to check their signatures
+		const paragraphs = yjsStore
+			.getLastSignedParagraphs(this.displayMode === 'lastOnly' ? 1 : 3)
+			.map(({ text, signature }) => ({ text, signature }));
 		// Broadcast through Rust backend to all windows
 		console.log('[Caption] Calling broadcast_caption with text:', text.substring(0, 50));
-		invoke('broadcast_caption', { text })
+		invoke('broadcast_caption', { text, paragraphs })
 			.then(() => {
 				console.log('[Caption] broadcast_caption succeeded');
 			})
@@ -55,10 +65,16 @@ class CaptionStore {
 
 	startObserving() {
 		if (!yjsStore.ydoc) return;
+		// Rejoining a session reuses the document, don't observe it twice
+		this.stopObserving();
 
 		const xmlFrag = yjsStore.ydoc.getXmlFragment('prosemirror');
 
 		const update = () => {
+			// While catching up after a reconnect the document passes through every state we
+			// missed; only the caught-up text goes to the overlay once the sync completes
+			if (!yjsStore.synced) return;
+
 			const newText = yjsStore.extractText();
 			const newParagraphs = yjsStore.getLastParagraphs(3);
 
@@ -0,0 +1,178 @@
+import * as Y from 'yjs';
+
+// Yjs document sync over a WebRTC data channel, for sessions between two machines with no
+// server in between; offered when settings.connection.transport is "webrtc". Signaling is
+// done by hand: one side creates an invitation code, the other answers it with a code of its
+// own, and the codes are passed along any way at hand. A code holds the session description
+// with all ICE candidates, so nothing else needs to be exchanged.
+//
+// Data channel messages start with a type byte:
+//   0 - state vector; answered with the updates the other side is missing
+//   1 - document update
+//   2 - a part of a longer message, continued in the next one
+
+const SYNC_REQUEST = 0;
+const UPDATE = 1;
+const PART = 2;
+// Stays well below the message size every WebRTC implementation accepts
+const CHUNK_BYTES = 16 * 1024;
+// Transaction origin of updates that came from the peer, so they aren't sent back
+const REMOTE = 'peer';
+// Candidates gathered by then go into the code even if gathering isn't done
+const GATHER_TIMEOUT_MS = 5000;
+
+export interface PeerOptions {
+	iceServers: string[];
+	// Send this machine's microphone, e.g. the room audio for a remote captioner
+	sendAudio: boolean;
+	onStatus: (connected: boolean) => void;
+}
+
+const encode = (description: RTCSessionDescription | null): string =>
+	btoa(JSON.stringify({ type: description?.type, sdp: description?.sdp }));
+
+const decode = (code: string): RTCSessionDescriptionInit => {
+	try {
+		return JSON.parse(atob(code.trim()));
+	} catch {
+		throw new Error('Invalid peer code');
+	}
+};
+
+export class PeerLink {
+	private connection: RTCPeerConnection;
+	private channel: RTCDataChannel | null = null;
+	private microphone: MediaStream | null = null;
+	private audio: HTMLAudioElement | null = null;
+	private partial: Uint8Array[] = [];
+
+	private updateHandler = (update: Uint8Array, origin: unknown) => {
+		if (origin !== REMOTE) {
+			this.send(UPDATE, update);
+		}
+	};
+
+	constructor(
+		private ydoc: Y.Doc,
+		private options: PeerOptions
+	) {
+		this.connection = new RTCPeerConnection({
+			iceServers: options.iceServers.filter((urls) => urls.trim()).map((urls) => ({ urls }))
+		});
+		this.connection.ondatachannel = (event) => this.attach(event.channel);
+		// Audio the other side sends is played right away
+		this.connection.ontrack = (event) => {
+			this.audio ??= new Audio();
+			this.audio.srcObject = event.streams[0] ?? new MediaStream([event.track]);
+			this.audio.play().catch((e) => console.error('[Peer] Failed to play audio:', e));
+		};
+		this.connection.onconnectionstatechange = () => {
+			if (['failed', 'disconnected', 'closed'].includes(this.connection.connectionState)) {
+				this.options.onStatus(false);
+			}
+		};
+		ydoc.on('update', this.updateHandler);
+	}
+
+	// Invitation code for the other side
+	async createOffer(): Promise<string> {
+		await this.addMicrophone();
+		this.attach(this.connection.createDataChannel('yjs'));
+		await this.connection.setLocalDescription(await this.connection.createOffer());
+		return this.localCode();
+	}
+
+	// Answer code to send back for an invitation
+	async acceptOffer(code: string): Promise<string> {
+		await this.addMicrophone();
+		await this.connection.setRemoteDescription(decode(code));
+		await this.connection.setLocalDescription(await this.connection.createAnswer());
+		return this.localCode();
+	}
+
+	async acceptAnswer(code: string) {
+		await this.connection.setRemoteDescription(decode(code));
+	}
+
+	close() {
+		this.ydoc.off('update', this.updateHandler);
+		this.channel?.close();
+		this.channel = null;
+		this.microphone?.getTracks().forEach((track) => track.stop());
+		this.microphone = null;
+		if (this.audio) {
+			this.audio.pause();
+			this.audio.srcObject = null;
+			this.audio = null;
+		}
+		this.connection.close();
+	}
+
+	private async addMicrophone() {
+		if (!this.options.sendAudio) return;
+		this.microphone = await navigator.mediaDevices.getUserMedia({ audio: true });
+		for (const track of this.microphone.getAudioTracks()) {
+			this.connection.addTrack(track, this.microphone);
+		}
+	}
+
+	private async localCode(): Promise<string> {
+		if (this.connection.iceGatheringState !== 'complete') {
+			await new Promise<void>((resolve) => {
+				const timeout = setTimeout(resolve, GATHER_TIMEOUT_MS);
+				this.connection.addEventListener('icegatheringstatechange', () => {
+					if (this.connection.iceGatheringState === 'complete') {
+						clearTimeout(timeout);
+						resolve();
+					}
+				});
+			});
+		}
+		return encode(this.connection.localDescription);
+	}
+
+	private attach(channel: RTCDataChannel) {
+		channel.binaryType = 'arraybuffer';
+		channel.onopen = () => {
+			this.channel = channel;
+			this.send(SYNC_REQUEST, Y.encodeStateVector(this.ydoc));
+			this.options.onStatus(true);
+		};
+		channel.onclose = () => {
+			this.channel = null;
+			this.options.onStatus(false);
+		};
+		channel.onmessage = (event) => this.receive(new Uint8Array(event.data));
+	}
+
+	private receive(message: Uint8Array) {
+		this.partial.push(message.subarray(1));
+		if (message[0] === PART) return;
+		const body = new Uint8Array(this.partial.reduce((length, part) => length + part.length, 0));
+		let offset = 0;
+		for (const part of this.partial) {
+			body.set(part, offset);
+			offset += part.length;
+		}
+		this.partial = [];
+
+		if (message[0] === SYNC_REQUEST) {
+			this.send(UPDATE, Y.encodeStateAsUpdate(this.ydoc, body));
+		} else if (message[0] === UPDATE) {
+			Y.applyUpdate(this.ydoc, body, REMOTE);
+		}
+	}
+
+	private send(type: number, body: Uint8Array) {
+		const channel = this.channel;
+		if (channel?.readyState !== 'open') return;
+		for (let offset = 0; offset < body.length || offset === 0; offset += CHUNK_BYTES) {
+			const chunk = body.subarray(offset, offset + CHUNK_BYTES);
+			const last = offset + CHUNK_BYTES >= body.length;
+			const message = new Uint8Array(chunk.length + 1);
+			message[0] = last ? type : PART;
+			message.set(chunk, 1);
+			channel.send(message);
+		}
+	}
+}
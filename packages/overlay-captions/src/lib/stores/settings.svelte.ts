@@ -29,6 +29,7 @@ class SettingsStore {
 	}
 
 	async save(settings: Partial<AppSettings>) {
+		const previous = this.settings;
 		const merged = { ...this.settings, ...settings };
 		this.settings = merged;
 
@@ -38,6 +39,8 @@ class SettingsStore {
 		} catch (e) {
 			console.error('Failed to save settings:', e);
 			this.error = String(e);
+			// Not saved (e.g. PolicyDenied in kiosk mode), so don't show it as applied
+			this.settings = previous;
 		}
 	}
 
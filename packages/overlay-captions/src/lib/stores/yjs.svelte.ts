@@ -1,27 +1,80 @@
 import * as Y from 'yjs';
 import { WebsocketProvider } from 'y-websocket';
+import { invoke } from '@tauri-apps/api/core';
+import { PeerLink } from './peer';
 
 interface Speaker {
 	name: string;
 	color?: string;
 }
 
+// Paragraph markup to caption text. Words carrying an ASR confidence score are kept as
+// <conf v="0.42">word</conf> so the backend can apply the confidence policy.
+const toCaptionText = (content: string): string =>
+	content
+		.replace(/<(\w+)[^>]*\sconfidence="([\d.]+)"[^>]*>([^<]*)<\/\1>/g, '<conf v="$2">$3</conf>')
+		.replace(/<(?!\/?conf\b)[^>]*>/g, '')
+		.replace(/ +/g, ' ')
+		.trim();
+
+export interface ParsedParagraph {
+	caption: string;
+	text: string;
+	signature: string | null;
+}
+
 class YjsStore {
 	ydoc = $state<Y.Doc | null>(null);
 	provider = $state<WebsocketProvider | null>(null);
+	// WebRTC peer sharing the document, with or without a server (see peer.ts)
+	peer = $state<PeerLink | null>(null);
+	peerConnected = $state(false);
 	connected = $state(false);
 	connecting = $state(false);
 	sessionCode = $state<string | null>(null);
+	// False until the server has sent what the local document is missing
+	synced = $state(false);
 	speakers = $state<Map<string, Speaker>>(new Map());
 	error = $state<string | null>(null);
+	private serverUrl: string | null = null;
+	private password: string | undefined;
+
+	private reportSessionLanguage(language: string | null) {
+		// Backend switches tray and menu language to follow the session
+		invoke('set_session_language', { language }).catch((e) => {
+			console.error('[YJS] Failed to report session language:', e);
+		});
+	}
+
+	// Sessions are archived by the backend between these reports
+	private reportActiveSession(sessionCode: string | null, serverUrl: string | null) {
+		invoke('set_active_session', { code: sessionCode, serverUrl }).catch((e) => {
+			console.error('[YJS] Failed to report active session:', e);
+		});
+	}
+
+	// Feeds the diagnostics window
+	private reportConnectionEvent(kind: string, detail: string | null = null) {
+		invoke('report_connection_event', { kind, detail }).catch(() => {});
+	}
 
 	connect(sessionCode: string, serverUrl: string, password?: string) {
-		this.disconnect();
+		// Rejoining the same session keeps the local document, so the sync handshake only
+		// fetches the updates missed while offline (state vector diff), not the whole transcript
+		const resume = this.ydoc !== null && this.sessionCode === sessionCode && this.serverUrl === serverUrl;
+		if (resume) {
+			this.destroyProvider();
+		} else {
+			this.disconnect();
+		}
 		this.connecting = true;
 		this.error = null;
 
 		try {
-			this.ydoc = new Y.Doc();
+			if (!this.ydoc) {
+				this.ydoc = new Y.Doc();
+			}
+			const ydoc = this.ydoc;
 
 			// Build room URL with password parameter if provided
 			const params = new URLSearchParams();
@@ -32,15 +85,29 @@ class YjsStore {
 			const queryString = params.toString();
 			const roomWithParams = queryString ? `${sessionCode}?${queryString}` : sessionCode;
 
-			this.provider = new WebsocketProvider(serverUrl, roomWithParams, this.ydoc, {
+			this.provider = new WebsocketProvider(serverUrl, roomWithParams, ydoc, {
 				connect: true,
 				maxBackoffTime: 5000,
 				disableBc: true
 			});
 
 			this.sessionCode = sessionCode;
+			this.serverUrl = serverUrl;
+			this.password = password;
+			if (!resume) {
+				this.reportActiveSession(sessionCode, serverUrl);
+			}
+
+			this.provider.on('sync', (isSynced: boolean) => {
+				this.synced = isSynced;
+				if (isSynced) {
+					this.reportConnectionEvent('synced', resume ? 'resumed' : 'full document');
+				}
+			});
 
 			this.provider.on('status', ({ status }: { status: string }) => {
+				invoke('set_connection_status', { connected: status === 'connected' }).catch(() => {});
+				this.reportConnectionEvent(status);
 				this.connected = status === 'connected';
 				this.connecting = status === 'connecting';
 				if (status === 'connected') {
@@ -50,6 +117,7 @@ class YjsStore {
 
 			this.provider.on('connection-error', (event: Event) => {
 				console.error('[YJS] Connection error:', event);
+				this.reportConnectionEvent('error', event?.type ?? null);
 				this.error = 'Connection failed';
 				this.connected = false;
 				this.connecting = false;
@@ -58,6 +126,7 @@ class YjsStore {
 			// Listen for connection close (for password-protected sessions)
 			this.provider.on('connection-close', (event: any) => {
 				console.log('[YJS] Connection closed:', event?.code, event?.reason);
+				this.reportConnectionEvent('closed', [event?.code, event?.reason].filter(Boolean).join(' ') || null);
 				// Check if it's a password error (code 4001 from our server)
 				if (event?.code === 4001 || event?.reason?.includes('password') || event?.reason?.includes('Invalid')) {
 					// Stop auto-reconnect by disconnecting the provider
@@ -68,14 +137,9 @@ class YjsStore {
 				}
 			});
 
-			// Observe speakers map
-			const speakersMap = this.ydoc.getMap<Speaker>('speakers');
-			speakersMap.observe(() => {
-				this.speakers = new Map(speakersMap.entries());
-			});
-
-			// Load initial speakers
-			this.speakers = new Map(speakersMap.entries());
+			if (!resume) {
+				this.observeDocument(ydoc);
+			}
 
 			// Set user info
 			this.provider.awareness.setLocalStateField('user', {
@@ -90,12 +154,69 @@ class YjsStore {
 		}
 	}
 
-	disconnect() {
+	private observeDocument(ydoc: Y.Doc) {
+		// Observe speakers map
+		const speakersMap = ydoc.getMap<Speaker>('speakers');
+		speakersMap.observe(() => {
+			this.speakers = new Map(speakersMap.entries());
+		});
+
+		// Load initial speakers
+		this.speakers = new Map(speakersMap.entries());
+
+		// Observe the language declared in the session metadata
+		const metadataMap = ydoc.getMap('sessionMetadata');
+		metadataMap.observe(() => {
+			this.reportSessionLanguage((metadataMap.get('language') as string) ?? null);
+		});
+	}
+
+	// Share the session's document with a peer, or start an empty one to receive it from the
+	// peer when there is no server session
+	startPeer(iceServers: string[], sendAudio: boolean): PeerLink {
+		this.stopPeer();
+		if (!this.ydoc) {
+			this.ydoc = new Y.Doc();
+			this.observeDocument(this.ydoc);
+		}
+		this.peer = new PeerLink(this.ydoc, {
+			iceServers,
+			sendAudio,
+			onStatus: (connected) => this.peerStatusChanged(connected)
+		});
+		return this.peer;
+	}
+
+	stopPeer() {
+		this.peer?.close();
+		this.peer = null;
+		this.peerConnected = false;
+	}
+
+	private peerStatusChanged(connected: boolean) {
+		if (connected === this.peerConnected) return;
+		this.peerConnected = connected;
+		this.reportConnectionEvent(connected ? 'peer connected' : 'peer disconnected');
+		// Without a server the peer is the whole session
+		if (!this.provider) {
+			invoke('set_connection_status', { connected }).catch(() => {});
+			this.connected = connected;
+			this.connecting = false;
+		}
+	}
+
+	private destroyProvider() {
 		if (this.provider) {
 			this.provider.disconnect();
 			this.provider.destroy();
 			this.provider = null;
 		}
+		this.synced = false;
+	}
+
+	disconnect() {
+		this.stopPeer();
+		this.destroyProvider();
 		if (this.ydoc) {
 			this.ydoc.destroy();
 			this.ydoc = null;
@@ -103,7 +224,70 @@ class YjsStore {
 		this.connected = false;
 		this.connecting = false;
 		this.sessionCode = null;
+		this.serverUrl = null;
+		this.password = undefined;
 		this.speakers = new Map();
+		// End the archived session first so it keeps its language
+		this.reportActiveSession(null, null);
+		this.reportSessionLanguage(null);
+		invoke('set_connection_status', { connected: false }).catch(() => {});
+	}
+
+	// Drop the socket and connect again, keeping the document (diagnostics window button)
+	reconnect() {
+		if (!this.provider) return;
+		this.provider.disconnect();
+		this.provider.connect();
+	}
+
+	// Rejoin the session on another server after failing over, keeping the document so the
+	// backup only needs to send what it has that this one doesn't
+	switchServer(serverUrl: string) {
+		if (!this.sessionCode || !this.provider || serverUrl === this.serverUrl) return;
+		this.serverUrl = serverUrl;
+		this.connect(this.sessionCode, serverUrl, this.password);
+	}
+
+	// Drop the socket for a while as a network failure would (training mode)
+	simulateDisconnect(seconds: number) {
+		const provider = this.provider;
+		if (!provider) return;
+		provider.disconnect();
+		setTimeout(() => {
+			if (this.provider === provider) provider.connect();
+		}, seconds * 1000);
+	}
+
+	// Append a paragraph to the shared document (e.g. a caption macro)
+	appendParagraph(text: string) {
+		if (!this.ydoc) return;
+		const xmlFrag = this.ydoc.getXmlFragment('prosemirror');
+		const paragraph = new Y.XmlElement('paragraph');
+		paragraph.insert(0, [new Y.XmlText(text)]);
+		xmlFrag.push([paragraph]);
+	}
+
+	// Replace whole-word occurrences of `from` in the shared document (operator corrections)
+	replaceText(from: string, to: string) {
+		if (!this.ydoc) return;
+		const escaped = from.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+		const pattern = new RegExp(`(?<![\\p{L}\\p{N}'-])${escaped}(?![\\p{L}\\p{N}'-])`, 'gu');
+		const xmlFrag = this.ydoc.getXmlFragment('prosemirror');
+		this.ydoc.transact(() => {
+			for (const node of xmlFrag.createTreeWalker((n) => n instanceof Y.XmlText)) {
+				const text = node as Y.XmlText;
+				// Embeds take one position each, like the replacement character
+				const plain = text
+					.toDelta()
+					.map((op: { insert?: unknown }) => (typeof op.insert === 'string' ? op.insert : '\uFFFC'))
+					.join('');
+				// From the end, so earlier indices stay valid
+				for (const match of [...plain.matchAll(pattern)].reverse()) {
+					text.delete(match.index, from.length);
+					text.insert(match.index, to);
+				}
+			}
+		});
 	}
 
 	getSpeakerName(speakerId: string | null): string | null {
@@ -119,28 +303,7 @@ class YjsStore {
 			const xmlFrag = this.ydoc.getXmlFragment('prosemirror');
 			const rawText = xmlFrag.toString();
 
-			// Parse paragraphs with speaker prefixes
-			const paragraphs: string[] = [];
-			const paragraphRegex = /<paragraph([^>]*)>([\s\S]*?)<\/paragraph>/g;
-			let match;
-
-			while ((match = paragraphRegex.exec(rawText)) !== null) {
-				const attrs = match[1];
-				const content = match[2];
-
-				const speakerIdMatch = attrs.match(/speakerId="([^"]*)"/);
-				const speakerId = speakerIdMatch ? speakerIdMatch[1] : null;
-				const speakerName = this.getSpeakerName(speakerId);
-
-				const plainText = content
-					.replace(/<[^>]*>/g, '')
-					.replace(/ +/g, ' ')
-					.trim();
-
-				if (plainText) {
-					paragraphs.push(speakerName ? `${speakerName}: ${plainText}` : plainText);
-				}
-			}
+			const paragraphs = this.parseParagraphs(rawText).map((p) => p.caption);
 
 			// Fallback if regex didn't match
 			if (paragraphs.length === 0) {
@@ -160,36 +323,46 @@ class YjsStore {
 		}
 	}
 
+	// Paragraphs of the document with their caption text (speaker prefix and confidence
+	// markup included), plain text and signature (see signatures.rs in the backend)
+	private parseParagraphs(rawText: string): ParsedParagraph[] {
+		const paragraphs: ParsedParagraph[] = [];
+		const paragraphRegex = /<paragraph([^>]*)>([\s\S]*?)<\/paragraph>/g;
+		let match;
+
+		while ((match = paragraphRegex.exec(rawText)) !== null) {
+			const attrs = match[1];
+			const content = match[2];
+
+			const speakerIdMatch = attrs.match(/speakerId="([^"]*)"/);
+			const speakerId = speakerIdMatch ? speakerIdMatch[1] : null;
+			const speakerName = this.getSpeakerName(speakerId);
+			const signatureMatch = attrs.match(/signature="([^"]*)"/);
+
+			const plainText = toCaptionText(content);
+
+			if (plainText) {
+				paragraphs.push({
+					caption: speakerName ? `${speakerName}: ${plainText}` : plainText,
+					text: plainText.replace(/<\/?conf[^>]*>/g, ''),
+					signature: signatureMatch ? signatureMatch[1] : null
+				});
+			}
+		}
+
+		return paragraphs;
+	}
+
 	getLastParagraphs(count: number = 1): string[] {
+		return this.getLastSignedParagraphs(count).map((p) => p.caption);
+	}
+
+	getLastSignedParagraphs(count: number = 1): ParsedParagraph[] {
 		if (!this.ydoc) return [];
 
 		try {
 			const xmlFrag = this.ydoc.getXmlFragment('prosemirror');
-			const rawText = xmlFrag.toString();
-
-			const paragraphs: string[] = [];
-			const paragraphRegex = /<paragraph([^>]*)>([\s\S]*?)<\/paragraph>/g;
-			let match;
-
-			while ((match = paragraphRegex.exec(rawText)) !== null) {
-				const attrs = match[1];
-				const content = match[2];
-
-				const speakerIdMatch = attrs.match(/speakerId="([^"]*)"/);
-				const speakerId = speakerIdMatch ? speakerIdMatch[1] : null;
-				const speakerName = this.getSpeakerName(speakerId);
-
-				const plainText = content
-					.replace(/<[^>]*>/g, '')
-					.replace(/ +/g, ' ')
-					.trim();
-
-				if (plainText) {
-					paragraphs.push(speakerName ? `${speakerName}: ${plainText}` : plainText);
-				}
-			}
-
-			return paragraphs.slice(-count);
+			return this.parseParagraphs(xmlFrag.toString()).slice(-count);
 		} catch (err) {
 			console.error('[YJS] Error extracting paragraphs:', err);
 			return [];
@@ -8,6 +8,14 @@ export interface Size {
   height: number;
 }
 
+// Where a tool window such as Review was last closed, in logical pixels
+export interface WindowGeometry {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
 export interface OverlaySettings {
   enabled: boolean;
   position: Position;
@@ -18,6 +26,9 @@ export interface OverlaySettings {
   alwaysOnTop: boolean;
   displayMode: "lastOnly" | "multiLine";
   backgroundColor: string;
+  // Degrees clockwise the captions are turned for portrait-mounted displays; size stays
+  // that of the unturned caption box
+  rotation: 0 | 90 | 270;
 }
 
 export interface FontSettings {
@@ -27,11 +38,553 @@ export interface FontSettings {
   color: string;
   align: "left" | "center" | "right" | "justify";
   lineHeight: number;
+  // "auto" takes the direction from the caption text
+  direction: "auto" | "ltr" | "rtl";
+  // Let every line take its own direction, for sessions that mix scripts
+  autoBidi: boolean;
 }
 
 export interface ConnectionSettings {
+  // wss:// or ws://, IPv6 addresses in brackets; checked when saved
   yjsServerUrl: string;
   autoConnect: boolean;
+  // "websocket" joins sessions on the server; "webrtc" also offers direct peer connections
+  transport: "websocket" | "webrtc";
+  // Backup servers after `yjsServerUrl`, in order of preference
+  fallbackServerUrls: string[];
+  // Failed connection attempts before the next server takes over
+  failoverAfter: number;
+}
+
+export type TrayAction = "showMain" | "toggleOverlay" | "openSettings" | "fontLarger" | "fontSmaller" | "none";
+
+export interface TrayActions {
+  leftClick: TrayAction;
+  middleClick: TrayAction;
+  doubleClick: TrayAction;
+}
+
+export interface PeekSettings {
+  enabled: boolean;
+  durationSecs: number;
+}
+
+export interface PluginSettings {
+  enabled: string[];
+}
+
+export interface ScriptSettings {
+  enabled: boolean;
+}
+
+export interface TerminologySettings {
+  enabled: boolean;
+  terms: string[];
+}
+
+export interface CasingSettings {
+  languages: string[];
+}
+
+export interface NumberFormatSettings {
+  mode: "off" | "digits" | "words";
+  spellOutBelow: number;
+  units: boolean;
+  timesAndDates: boolean;
+}
+
+export interface LineBreakSettings {
+  enabled: boolean;
+  maxCharsPerLine: number;
+}
+
+export interface ReadingSpeedSettings {
+  enabled: boolean;
+  maxCharsPerSecond: number;
+}
+
+export interface ConfidenceSettings {
+  threshold: number;
+  lowConfidenceDisplay: "style" | "ellipsis";
+}
+
+export interface ReviewSettings {
+  enabled: boolean;
+  delaySecs: number;
+}
+
+export interface OperatorOverlaySettings {
+  enabled: boolean;
+  overlay: OverlaySettings;
+  font: FontSettings;
+}
+
+export interface CaptionMacro {
+  shortcut: string;
+  text: string;
+  pushToSession: boolean;
+  // An emergency message, e.g. an evacuation notice; raises a light alert
+  emergency: boolean;
+}
+
+export interface RemoteControlSettings {
+  enabled: boolean;
+  port: number;
+}
+
+export interface MidiMapping {
+  message: "note" | "cc";
+  // 1-16, null matches any channel
+  channel: number | null;
+  number: number;
+  action: "toggleOverlay" | "togglePresentation" | "macro" | "opacity" | "fontSize";
+  macroIndex: number;
+}
+
+export interface MidiSettings {
+  enabled: boolean;
+  portName: string | null;
+  mappings: MidiMapping[];
+}
+
+export interface FootswitchSettings {
+  enabled: boolean;
+  vendorId: number;
+  productId: number;
+  // Input report byte carrying the switch, null treats any set bit as pressed
+  buttonByte: number | null;
+  buttonMask: number;
+  action: "pause" | "pushToHide";
+}
+
+export interface NetworkQualitySettings {
+  enabled: boolean;
+  intervalSecs: number;
+  maxLatencyMs: number;
+  maxJitterMs: number;
+  maxLossPercent: number;
+}
+
+export interface OutputSettings {
+  // Ids of outputs that are switched off
+  disabled: string[];
+  // Channel to frontend event name overrides, e.g. "captions" -> "caption-update"
+  eventNames: Record<string, string>;
+}
+
+export interface LanguageDetectionSettings {
+  enabled: boolean;
+  // ISO 639-1 codes of the languages spoken in the session; empty allows any language
+  candidates: string[];
+  // Detections below this confidence (0.0 - 1.0) keep the previous language
+  minConfidence: number;
+}
+
+// What happens to HTML or markdown the caption source embeds
+export interface MarkupSettings {
+  policy: "strip" | "escape" | "convert";
+}
+
+export interface RelaySettings {
+  enabled: boolean;
+  // Viewer instances connect here, also from other machines
+  port: number;
+}
+
+export interface CaptionPageSettings {
+  // Chromecasts and smart TVs load the caption page from here
+  port: number;
+}
+
+// The meeting's or stream's caption URL is in the secrets store, not here
+export interface CaptionServiceSettings {
+  enabled: boolean;
+  // Caption language code sent along, e.g. "et-EE"; empty leaves it to the platform
+  language: string;
+}
+
+// The S3 access key is in the secrets store, not here
+export interface S3Settings {
+  // Empty for AWS, otherwise e.g. "https://minio.example.org:9000"
+  endpoint: string;
+  bucket: string;
+  region: string;
+  // Prepended to object names, e.g. "live/"
+  prefix: string;
+}
+
+export interface HlsCaptionSettings {
+  enabled: boolean;
+  target: "directory" | "s3";
+  directory: string;
+  s3: S3Settings;
+  segmentSeconds: number;
+  // Segments listed in the playlist; older ones are deleted
+  windowSegments: number;
+  // How far the stream runs behind the room
+  streamDelayMs: number;
+}
+
+export interface AudioMonitorSettings {
+  enabled: boolean;
+  // Device name, empty for the default one
+  device: string;
+  // Capture what an output device plays (Windows only)
+  loopback: boolean;
+  // Audio quieter than this counts as silence
+  silenceThresholdDb: number;
+  warnAfterSecs: number;
+}
+
+export interface AudioUplinkSettings {
+  enabled: boolean;
+  // ws:// or wss:// address of the recognition server's audio endpoint
+  url: string;
+  // Input device name, empty for the default one
+  device: string;
+  // Opus bitrate in bits per second
+  bitrate: number;
+}
+
+export interface WebrtcSettings {
+  // STUN/TURN server URLs used to get through NAT
+  iceServers: string[];
+  // Send the microphone to the peer, e.g. room audio for a remote captioner
+  sendAudio: boolean;
+}
+
+export interface LanSessionSettings {
+  enabled: boolean;
+  port: number;
+  // Fixed session code, empty for a new one each time the app starts
+  code: string;
+}
+
+export interface BleBroadcastSettings {
+  enabled: boolean;
+  // Local name in the advertisement
+  name: string;
+  // Longest caption tail in one notification
+  maxBytes: number;
+}
+
+export interface RemoteSupportSettings {
+  // wss:// endpoint of the support staff
+  url: string;
+  // How long streaming lasts once started
+  windowMinutes: number;
+}
+
+// Formatted transcript export. In the header and footer {title} and {date} stand for the
+// transcript's title and date and {page} for the page number.
+export interface ExportSettings {
+  header: string;
+  footer: string;
+  // Time of each caption before its text
+  timestamps: boolean;
+  // The speaker's name in bold whenever the speaker changes
+  speakerLabels: boolean;
+  // Chapter headings; the YouTube chapter list always has them
+  chapters: boolean;
+  // Captions starting with this start a chapter titled by the rest of the caption
+  chapterMarker: string;
+  // A silence this long starts a chapter, 0 for chapters only at markers
+  chapterSilenceSecs: number;
+}
+
+// Captions shown this much later to line up with a PA system or broadcast delay line
+export interface DisplayDelaySettings {
+  delayMs: number;
+  // Global shortcuts for a longer and a shorter delay, by stepMs each
+  laterShortcut: string;
+  soonerShortcut: string;
+  stepMs: number;
+}
+
+// Clock and session timer in the overlay
+export interface ClockSettings {
+  enabled: boolean;
+  showTime: boolean;
+  showTimer: boolean;
+}
+
+// Sharing the screen with other apps' caption windows
+export interface ArbitrationSettings {
+  policy: "off" | "warn" | "yield" | "share";
+  // Parts of other caption windows' titles or window classes, any case
+  windows: string[];
+  // Between the overlay and the other window when sharing the screen
+  gapPx: number;
+}
+
+// Periodic proof that the operator is at the caption station
+export interface CheckInSettings {
+  enabled: boolean;
+  // Between prompts while a session is joined
+  intervalMins: number;
+  // For answering a prompt before it counts as missed
+  graceSecs: number;
+  // Gets a JSON POST for every missed check-in, empty for none
+  webhookUrl: string;
+}
+
+// Name lookups over HTTPS for the backend's own connections, past networks that hijack DNS
+export interface DnsOverHttpsSettings {
+  enabled: boolean;
+  // Resolver with the JSON API, named by address, e.g. "https://1.1.1.1/dns-query"
+  url: string;
+}
+
+// Summaries of finished sessions from a language model; the API key is a secret
+export interface SummarySettings {
+  // Sends whole transcripts to `endpoint`, so off unless a deployment opts in
+  enabled: boolean;
+  // Chat completions URL, e.g. "http://localhost:8080/v1/chat/completions"
+  endpoint: string;
+  model: string;
+  // Instructions sent before the transcript
+  prompt: string;
+}
+
+// Rolling keyword counts for a word cloud
+export interface KeywordSettings {
+  enabled: boolean;
+  // How far back words are counted
+  windowSecs: number;
+  // How often `keywords-update` is emitted
+  intervalSecs: number;
+  // How many of the most said words are sent
+  count: number;
+  // Shorter words are left out
+  minChars: number;
+  // Left out on top of the built-in common words, e.g. the event's own name
+  stopWords: string[];
+}
+
+// Markers dropped during a session
+export interface BookmarkSettings {
+  // Global shortcut adding a bookmark without a note
+  shortcut: string;
+}
+
+// Showing the last captions again on the overlay
+export interface ReplaySettings {
+  // How far back the shortcut replays
+  seconds: number;
+  // How long the replay stays before the live caption comes back
+  showSecs: number;
+  shortcut: string;
+  // Slow motion shows the last paragraph again a word at a time
+  slowWordsPerMinute: number;
+  slowShortcut: string;
+}
+
+// Speakers whose captions stay off the public display
+export interface SpeakerMuteSettings {
+  // Names as the captions give them, e.g. "Interpreter"
+  muted: string[];
+}
+
+// A colour per speaker in the overlay and exports
+export interface SpeakerColorSettings {
+  enabled: boolean;
+  // "#RRGGBB", handed out in order of speaking
+  palette: string[];
+}
+
+// Captions forwarded by the companion browser extension
+export interface BrowserBridgeSettings {
+  enabled: boolean;
+  port: number;
+  // Extensions allowed to start the native messaging host
+  chromeExtensionIds: string[];
+  firefoxExtensionIds: string[];
+}
+
+// Sessions announced from calendar events
+export interface CalendarSettings {
+  enabled: boolean;
+  // An ICS feed: https or webcal URL, or a local file
+  icsUrl: string;
+  // How early before the start the overlay comes up
+  leadMinutes: number;
+  refreshMinutes: number;
+}
+
+// Showing captions when a meeting window appears
+export interface MeetingDetectionSettings {
+  enabled: boolean;
+  // Parts of meeting window titles, any case
+  windowTitles: string[];
+  autoJoin: boolean;
+  // The session to join; empty for the last one joined
+  sessionCode: string;
+  hideWhenEnded: boolean;
+}
+
+// The overlay attached to the focused application window
+export interface FocusFollowSettings {
+  enabled: boolean;
+  // Over the window's bottom edge or below it
+  placement: "inside" | "below";
+  offsetPx: number;
+}
+
+// The overlay shrunk to a pill with the last few words
+export interface CompactSettings {
+  words: number;
+  // Size of the pill in logical pixels
+  width: number;
+  height: number;
+  // Global shortcut for going compact and back
+  shortcut: string;
+}
+
+// A screen corner that brings up the control strip
+export interface HotCornerSettings {
+  enabled: boolean;
+  corner: "topLeft" | "topRight" | "bottomLeft" | "bottomRight";
+  // How long the strip stays after the cursor has left it
+  hideDelayMs: number;
+}
+
+export interface PowerSettings {
+  enabled: boolean;
+  warnBelowPercent: number;
+  // Fewer overlay updates and no animations while on battery
+  savePowerOnBattery: boolean;
+}
+
+export interface ThemeSettings {
+  lightTextColor: string;
+  lightBackgroundColor: string;
+  // Local times, "HH:MM"
+  dayStarts: string;
+  nightStarts: string;
+}
+
+export interface ContrastSettings {
+  enabled: boolean;
+  intervalSecs: number;
+  // WCAG contrast ratio between the caption text and what is behind it
+  minRatio: number;
+}
+
+export type LayoutElement = "captions" | "infoLine" | "lowerThird" | "watermark" | "clock";
+export type LayoutAnchor =
+  | "topLeft"
+  | "top"
+  | "topRight"
+  | "left"
+  | "center"
+  | "right"
+  | "bottomLeft"
+  | "bottom"
+  | "bottomRight";
+
+// Where one element goes in the overlay window: a cell of a 3×3 grid, higher z in front
+export interface LayoutRegion {
+  element: LayoutElement;
+  anchor: LayoutAnchor;
+  z: number;
+}
+
+// Title-safe area: percentages of the window kept clear on each side
+export interface SafeMargins {
+  top: number;
+  right: number;
+  bottom: number;
+  left: number;
+}
+
+export interface LayoutSettings {
+  // Elements moved from their default region
+  regions: LayoutRegion[];
+  safeMargins: SafeMargins;
+}
+
+// Every element's region, bottom to top, and the margins as the overlay applies them
+export interface OverlayLayout {
+  regions: LayoutRegion[];
+  safeMargins: SafeMargins;
+}
+
+// Speaker name cards in the overlay
+export interface LowerThirdSettings {
+  // How long a card stays up unless given its own duration, 0 until hidden
+  durationSecs: number;
+  // Colour of the bar beside the name
+  accentColor: string;
+}
+
+// Logo in the overlay; placed by the layout
+export interface WatermarkSettings {
+  enabled: boolean;
+  // File name of the imported image in the config directory, empty for none
+  image: string;
+  // Height of the logo in pixels
+  sizePx: number;
+  opacity: number;
+}
+
+// Style of one router channel over the overlay's font; unset fields keep the overlay's
+export interface ChannelStyle {
+  family: string | null;
+  size: number | null;
+  weight: number | null;
+  color: string | null;
+  backgroundColor: string | null;
+  italic: boolean;
+}
+
+export interface BrailleSettings {
+  enabled: boolean;
+  // "host:port" of a BRLTTY on another machine, empty for the local one
+  host: string;
+}
+
+export interface SerialDisplaySettings {
+  enabled: boolean;
+  // "COM3", "/dev/ttyUSB0"
+  port: string;
+  baudRate: number;
+  // Characters per line and lines on the display
+  width: number;
+  lines: number;
+  paging: "scroll" | "pages";
+  pageSeconds: number;
+  // Hex bytes sent before each screen, e.g. "0C" (form feed) to clear and home
+  clearSequence: string;
+  // Replace letters the display's character set likely lacks
+  asciiOnly: boolean;
+}
+
+// Bridge credentials live in the secrets store, not here
+export interface LightAlertSettings {
+  enabled: boolean;
+  // Address of the Hue bridge on the LAN
+  bridge: string;
+  // Light ids on the bridge
+  lights: string[];
+  onEmergency: boolean;
+  onDisconnect: boolean;
+}
+
+export interface ExitProtectionSettings {
+  // The PIN asked for before quitting or hiding the overlay is kept apart from the
+  // settings; set it with the set_exit_pin command
+  enabled: boolean;
+}
+
+export interface CaptionSigningSettings {
+  mode: "off" | "flag" | "block";
+  // Session code to base64 Ed25519 public key of its captioner
+  publicKeys: Record<string, string>;
+}
+
+export interface CaptionHistorySettings {
+  // Keep the history on disk so it survives a crash or restart
+  persist: boolean;
 }
 
 export interface AppSettings {
@@ -39,7 +592,80 @@ export interface AppSettings {
   font: FontSettings;
   connection: ConnectionSettings;
   lastSessionCode: string | null;
-  theme: string;
+  // Caption colours: the overlay's own ("dark"), the light theme, the OS dark mode's, or by
+  // time of day ("auto")
+  theme: "dark" | "light" | "system" | "auto";
+  trayActions: TrayActions;
+  peek: PeekSettings;
+  uiLanguage: "auto" | "et" | "en" | "fi";
+  plugins: PluginSettings;
+  scripting: ScriptSettings;
+  terminology: TerminologySettings;
+  casing: CasingSettings;
+  numbers: NumberFormatSettings;
+  lineBreaks: LineBreakSettings;
+  readingSpeed: ReadingSpeedSettings;
+  confidence: ConfidenceSettings;
+  review: ReviewSettings;
+  operatorOverlay: OperatorOverlaySettings;
+  macros: CaptionMacro[];
+  remoteControl: RemoteControlSettings;
+  midi: MidiSettings;
+  footswitch: FootswitchSettings;
+  networkQuality: NetworkQualitySettings;
+  outputs: OutputSettings;
+  captionHistory: CaptionHistorySettings;
+  languageDetection: LanguageDetectionSettings;
+  markup: MarkupSettings;
+  captionSigning: CaptionSigningSettings;
+  exitProtection: ExitProtectionSettings;
+  relay: RelaySettings;
+  captionPage: CaptionPageSettings;
+  lightAlert: LightAlertSettings;
+  serialDisplay: SerialDisplaySettings;
+  braille: BrailleSettings;
+  zoom: CaptionServiceSettings;
+  teams: CaptionServiceSettings;
+  youtube: CaptionServiceSettings;
+  hlsCaptions: HlsCaptionSettings;
+  audioMonitor: AudioMonitorSettings;
+  audioUplink: AudioUplinkSettings;
+  webrtc: WebrtcSettings;
+  lanSession: LanSessionSettings;
+  bleBroadcast: BleBroadcastSettings;
+  remoteSupport: RemoteSupportSettings;
+  export: ExportSettings;
+  displayDelay: DisplayDelaySettings;
+  clock: ClockSettings;
+  layout: LayoutSettings;
+  lowerThird: LowerThirdSettings;
+  watermark: WatermarkSettings;
+  // Channel name ("captions", "info-line", …) to its style
+  channelStyles: Record<string, ChannelStyle>;
+  contrast: ContrastSettings;
+  themes: ThemeSettings;
+  power: PowerSettings;
+  // Keep the screen on and the system awake while captioning
+  keepAwake: boolean;
+  hotCorner: HotCornerSettings;
+  compact: CompactSettings;
+  focusFollow: FocusFollowSettings;
+  meetingDetection: MeetingDetectionSettings;
+  calendar: CalendarSettings;
+  browserBridge: BrowserBridgeSettings;
+  speakerColors: SpeakerColorSettings;
+  speakerMute: SpeakerMuteSettings;
+  replay: ReplaySettings;
+  bookmarks: BookmarkSettings;
+  keywords: KeywordSettings;
+  summary: SummarySettings;
+  // Blocks every outbound integration but the caption server
+  offlineOnly: boolean;
+  dnsOverHttps: DnsOverHttpsSettings;
+  checkIn: CheckInSettings;
+  arbitration: ArbitrationSettings;
+  // Window label to where it was last closed; kept by the backend
+  windowGeometry: Record<string, WindowGeometry>;
 }
 
 export const defaultSettings: AppSettings = {
@@ -53,6 +679,7 @@ export const defaultSettings: AppSettings = {
     alwaysOnTop: true,
     displayMode: "lastOnly",
     backgroundColor: "#000000",
+    rotation: 0,
   },
   font: {
     family: "Inter, system-ui, sans-serif",
@@ -61,11 +688,355 @@ export const defaultSettings: AppSettings = {
     color: "#ffffff",
     align: "justify",
     lineHeight: 1.3,
+    direction: "auto",
+    autoBidi: true,
   },
   connection: {
     yjsServerUrl: "wss://tekstiks.ee/kk",
     autoConnect: true,
+    transport: "websocket",
+    fallbackServerUrls: [],
+    failoverAfter: 3,
   },
   lastSessionCode: null,
-  theme: "system",
+  theme: "dark",
+  trayActions: {
+    leftClick: "showMain",
+    middleClick: "toggleOverlay",
+    doubleClick: "openSettings",
+  },
+  peek: {
+    enabled: false,
+    durationSecs: 5,
+  },
+  uiLanguage: "auto",
+  plugins: {
+    enabled: [],
+  },
+  scripting: {
+    enabled: false,
+  },
+  terminology: {
+    enabled: true,
+    terms: [],
+  },
+  casing: {
+    languages: [],
+  },
+  numbers: {
+    mode: "off",
+    spellOutBelow: 10,
+    units: false,
+    timesAndDates: false,
+  },
+  lineBreaks: {
+    enabled: false,
+    maxCharsPerLine: 42,
+  },
+  readingSpeed: {
+    enabled: false,
+    maxCharsPerSecond: 17,
+  },
+  confidence: {
+    threshold: 0.5,
+    lowConfidenceDisplay: "style",
+  },
+  review: {
+    enabled: false,
+    delaySecs: 5,
+  },
+  operatorOverlay: {
+    enabled: false,
+    overlay: {
+      enabled: false,
+      position: { x: 500, y: 380 },
+      size: { width: 600, height: 160 },
+      positionPreset: "bottom",
+      opacity: 0.95,
+      clickThrough: false,
+      alwaysOnTop: true,
+      displayMode: "lastOnly",
+      backgroundColor: "#1f2937",
+      rotation: 0,
+    },
+    font: {
+      family: "Inter, system-ui, sans-serif",
+      size: 24,
+      weight: 500,
+      color: "#ffffff",
+      align: "justify",
+      lineHeight: 1.3,
+      direction: "auto",
+      autoBidi: true,
+    },
+  },
+  macros: [],
+  remoteControl: {
+    enabled: false,
+    port: 27543,
+  },
+  midi: {
+    enabled: false,
+    portName: null,
+    mappings: [],
+  },
+  footswitch: {
+    enabled: false,
+    vendorId: 0,
+    productId: 0,
+    buttonByte: null,
+    buttonMask: 0xff,
+    action: "pause",
+  },
+  networkQuality: {
+    enabled: false,
+    intervalSecs: 5,
+    maxLatencyMs: 300,
+    maxJitterMs: 100,
+    maxLossPercent: 20,
+  },
+  outputs: {
+    disabled: [],
+    eventNames: {},
+  },
+  captionHistory: {
+    persist: false,
+  },
+  languageDetection: {
+    enabled: false,
+    candidates: [],
+    minConfidence: 0.5,
+  },
+  markup: {
+    policy: "strip",
+  },
+  captionSigning: {
+    mode: "off",
+    publicKeys: {},
+  },
+  exitProtection: {
+    enabled: false,
+  },
+  relay: {
+    enabled: false,
+    port: 27544,
+  },
+  captionPage: {
+    port: 27545,
+  },
+  lightAlert: {
+    enabled: false,
+    bridge: "",
+    lights: [],
+    onEmergency: true,
+    onDisconnect: true,
+  },
+  serialDisplay: {
+    enabled: false,
+    port: "",
+    baudRate: 9600,
+    width: 20,
+    lines: 4,
+    paging: "scroll",
+    pageSeconds: 3,
+    clearSequence: "0C",
+    asciiOnly: true,
+  },
+  braille: {
+    enabled: false,
+    host: "",
+  },
+  zoom: {
+    enabled: false,
+    language: "",
+  },
+  teams: {
+    enabled: false,
+    language: "",
+  },
+  youtube: {
+    enabled: false,
+    language: "",
+  },
+  hlsCaptions: {
+    enabled: false,
+    target: "directory",
+    directory: "",
+    s3: {
+      endpoint: "",
+      bucket: "",
+      region: "",
+      prefix: "",
+    },
+    segmentSeconds: 6,
+    windowSegments: 10,
+    streamDelayMs: 0,
+  },
+  audioMonitor: {
+    enabled: false,
+    device: "",
+    loopback: false,
+    silenceThresholdDb: -50,
+    warnAfterSecs: 10,
+  },
+  audioUplink: {
+    enabled: false,
+    url: "",
+    device: "",
+    bitrate: 24000,
+  },
+  webrtc: {
+    iceServers: ["stun:stun.l.google.com:19302"],
+    sendAudio: false,
+  },
+  lanSession: {
+    enabled: false,
+    port: 27546,
+    code: "",
+  },
+  bleBroadcast: {
+    enabled: false,
+    name: "Jutukuva",
+    maxBytes: 180,
+  },
+  remoteSupport: {
+    url: "",
+    windowMinutes: 30,
+  },
+  export: {
+    header: "{title}",
+    footer: "{date} – {page}",
+    timestamps: true,
+    speakerLabels: true,
+    chapters: false,
+    chapterMarker: "##",
+    chapterSilenceSecs: 120,
+  },
+  displayDelay: {
+    delayMs: 0,
+    laterShortcut: "Ctrl+Alt+Shift+Up",
+    soonerShortcut: "Ctrl+Alt+Shift+Down",
+    stepMs: 100,
+  },
+  clock: {
+    enabled: false,
+    showTime: true,
+    showTimer: true,
+  },
+  layout: {
+    regions: [],
+    safeMargins: { top: 0, right: 0, bottom: 0, left: 0 },
+  },
+  lowerThird: {
+    durationSecs: 8,
+    accentColor: "#3b82f6",
+  },
+  watermark: {
+    enabled: false,
+    image: "",
+    sizePx: 64,
+    opacity: 0.8,
+  },
+  channelStyles: {},
+  contrast: {
+    enabled: false,
+    intervalSecs: 2,
+    minRatio: 4.5,
+  },
+  themes: {
+    lightTextColor: "#111827",
+    lightBackgroundColor: "#f9fafb",
+    dayStarts: "07:00",
+    nightStarts: "19:00",
+  },
+  power: {
+    enabled: true,
+    warnBelowPercent: 20,
+    savePowerOnBattery: false,
+  },
+  keepAwake: true,
+  hotCorner: {
+    enabled: false,
+    corner: "bottomRight",
+    hideDelayMs: 800,
+  },
+  compact: {
+    words: 6,
+    width: 320,
+    height: 48,
+    shortcut: "Ctrl+Alt+Shift+C",
+  },
+  focusFollow: {
+    enabled: false,
+    placement: "inside",
+    offsetPx: 16,
+  },
+  meetingDetection: {
+    enabled: false,
+    windowTitles: ["Zoom Meeting", "Zoom Webinar", "Meeting in ", "Meeting with "],
+    autoJoin: false,
+    sessionCode: "",
+    hideWhenEnded: true,
+  },
+  calendar: {
+    enabled: false,
+    icsUrl: "",
+    leadMinutes: 2,
+    refreshMinutes: 15,
+  },
+  browserBridge: {
+    enabled: false,
+    port: 27547,
+    chromeExtensionIds: [],
+    firefoxExtensionIds: [],
+  },
+  speakerColors: {
+    enabled: false,
+    palette: ["#FFD166", "#4CC9F0", "#06D6A0", "#F78C6B", "#C77DFF", "#A7C957"],
+  },
+  speakerMute: {
+    muted: [],
+  },
+  replay: {
+    seconds: 20,
+    showSecs: 8,
+    shortcut: "Ctrl+Alt+Shift+R",
+    slowWordsPerMinute: 60,
+    slowShortcut: "Ctrl+Alt+Shift+S",
+  },
+  bookmarks: {
+    shortcut: "Ctrl+Alt+Shift+B",
+  },
+  keywords: {
+    enabled: false,
+    windowSecs: 300,
+    intervalSecs: 10,
+    count: 30,
+    minChars: 4,
+    stopWords: [],
+  },
+  summary: {
+    enabled: false,
+    endpoint: "",
+    model: "",
+    prompt:
+      "Summarize this meeting transcript in its own language: the main topics, the decisions made and the action items with who takes them on. Use Markdown.",
+  },
+  offlineOnly: false,
+  dnsOverHttps: {
+    enabled: false,
+    url: "https://1.1.1.1/dns-query",
+  },
+  checkIn: {
+    enabled: false,
+    intervalMins: 15,
+    graceSecs: 120,
+    webhookUrl: "",
+  },
+  arbitration: {
+    policy: "off",
+    windows: ["LiveCaptionsDesktopWindow", "Live captions", "Closed Caption", "Live Transcript"],
+    gapPx: 8,
+  },
+  windowGeometry: {},
 };